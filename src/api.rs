@@ -0,0 +1,267 @@
+//! Extraction of the methods and signals a class exports to Godot.
+//!
+//! Methods are exported from `#[methods]` impl blocks with `#[export]`, whose
+//! second parameter is the owner, or with `#[method]`, whose owner parameter
+//! is marked with `#[base]`. Signals are registered on the class builder,
+//! in a method of the class or a free function taking a `&ClassBuilder` of it,
+//! usually named by `#[register_with(...)]`, either with
+//! `builder.signal("name").with_param(...).done()` or with
+//! `builder.add_signal(Signal { name: "name", args: &[...] })`.
+
+use std::collections::BTreeMap;
+
+use syn::visit::Visit;
+
+use crate::class::{Classes, Method, Signal};
+use crate::scan::ScanOptions;
+
+/// The methods and signals found in one impl block of a class.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct ClassApi {
+    /// The name of the class.
+    pub(crate) class: String,
+    pub(crate) methods: Vec<Method>,
+    pub(crate) signals: Vec<Signal>,
+}
+
+/// The methods and signals in the inherent impl block `item`, or `None` if it
+/// is not an inherent impl block or exports nothing.
+pub(crate) fn impl_api(item: &syn::ItemImpl, options: &ScanOptions) -> Option<ClassApi> {
+    if item.trait_.is_some() || !crate::cfg::enabled(&item.attrs, options) {
+        return None;
+    }
+
+    let class = match &*item.self_ty {
+        syn::Type::Path(ty) if ty.qself.is_none() => ty.path.segments.last()?.ident.to_string(),
+        _ => return None,
+    };
+
+    let exports_methods = item
+        .attrs
+        .iter()
+        .any(|attr| last_segment_is(&attr.path, "methods"));
+
+    let methods = item
+        .items
+        .iter()
+        .filter(|_| exports_methods)
+        .filter_map(|item| match item {
+            syn::ImplItem::Method(method) if crate::cfg::enabled(&method.attrs, options) => {
+                exported_method(method)
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut signals = SignalVisitor::default();
+    signals.visit_item_impl(item);
+    let signals = signals.signals.into_values().collect::<Vec<_>>();
+
+    if methods.is_empty() && signals.is_empty() {
+        return None;
+    }
+
+    Some(ClassApi {
+        class,
+        methods,
+        signals,
+    })
+}
+
+/// The signals registered in the free function `item`, which belong to `T`
+/// if it takes a `&ClassBuilder<T>`.
+pub(crate) fn fn_api(item: &syn::ItemFn, options: &ScanOptions) -> Option<ClassApi> {
+    if !crate::cfg::enabled(&item.attrs, options) {
+        return None;
+    }
+
+    let class = item.sig.inputs.iter().find_map(|input| match input {
+        syn::FnArg::Typed(param) => builder_class(&param.ty),
+        syn::FnArg::Receiver(_) => None,
+    })?;
+
+    let mut signals = SignalVisitor::default();
+    signals.visit_item_fn(item);
+    let signals = signals.signals.into_values().collect::<Vec<_>>();
+
+    if signals.is_empty() {
+        return None;
+    }
+
+    Some(ClassApi {
+        class,
+        methods: vec![],
+        signals,
+    })
+}
+
+/// The name of `T` if `ty` is `&ClassBuilder<T>`.
+fn builder_class(ty: &syn::Type) -> Option<String> {
+    let ty = match ty {
+        syn::Type::Reference(reference) => &*reference.elem,
+        _ => return None,
+    };
+
+    let segment = match ty {
+        syn::Type::Path(ty) => ty.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != "ClassBuilder" {
+        return None;
+    }
+
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => match args.args.first()? {
+            syn::GenericArgument::Type(syn::Type::Path(class)) => {
+                Some(class.path.segments.last()?.ident.to_string())
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Add the methods and signals in `apis` to the classes they belong to.
+///
+/// The methods and signals of classes that are not in `classes` are ignored,
+/// since the impl blocks they are found in may belong to other types.
+pub(crate) fn merge(classes: &mut Classes, apis: impl IntoIterator<Item = ClassApi>) {
+    for api in apis {
+        if let Some(class) = classes.get_mut(&api.class) {
+            class.methods.extend(api.methods);
+            class.signals.extend(api.signals);
+        }
+    }
+}
+
+/// The method `method` exports, if it is marked with `#[export]` or
+/// `#[method]`.
+fn exported_method(method: &syn::ImplItemMethod) -> Option<Method> {
+    let export = method
+        .attrs
+        .iter()
+        .any(|attr| last_segment_is(&attr.path, "export"));
+    let method_attr = method
+        .attrs
+        .iter()
+        .any(|attr| last_segment_is(&attr.path, "method"));
+
+    if !export && !method_attr {
+        return None;
+    }
+
+    let params = method
+        .sig
+        .inputs
+        .iter()
+        .filter_map(|input| match input {
+            syn::FnArg::Typed(param) => Some(param),
+            syn::FnArg::Receiver(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    let args = if export {
+        // The first parameter after `self` is the owner.
+        params.len().saturating_sub(1)
+    } else {
+        params
+            .iter()
+            .filter(|param| !param.attrs.iter().any(|attr| attr.path.is_ident("base")))
+            .count()
+    };
+
+    Some(Method {
+        name: method.sig.ident.to_string(),
+        args,
+    })
+}
+
+/// Collects the signals registered in a syntax tree, by the location of their
+/// name.
+#[derive(Default)]
+struct SignalVisitor {
+    signals: BTreeMap<(usize, usize), Signal>,
+}
+
+impl SignalVisitor {
+    fn insert(&mut self, name: &syn::LitStr, args: usize) {
+        let start = name.span().start();
+        let signal = self
+            .signals
+            .entry((start.line, start.column))
+            .or_insert_with(|| Signal {
+                name: name.value(),
+                args: 0,
+            });
+        signal.args = signal.args.max(args);
+    }
+}
+
+impl<'ast> Visit<'ast> for SignalVisitor {
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        // `builder.signal("name").with_param(...).done()` is nested with the
+        // last call outermost, so count the parameters down to `signal`.
+        let mut params = 0;
+        let mut current = call;
+        loop {
+            let method = current.method.to_string();
+
+            if method == "signal" {
+                if let Some(name) = current.args.first().and_then(lit_str) {
+                    self.insert(name, params);
+                }
+                break;
+            }
+
+            if method.starts_with("with_param") {
+                params += 1;
+            }
+
+            match &*current.receiver {
+                syn::Expr::MethodCall(receiver) => current = receiver,
+                _ => break,
+            }
+        }
+
+        if call.method == "add_signal" {
+            if let Some(syn::Expr::Struct(signal)) = call.args.first() {
+                let field = |name: &str| {
+                    signal.fields.iter().find_map(|field| match &field.member {
+                        syn::Member::Named(ident) if ident == name => Some(&field.expr),
+                        _ => None,
+                    })
+                };
+
+                let args = match field("args") {
+                    Some(syn::Expr::Reference(reference)) => match &*reference.expr {
+                        syn::Expr::Array(array) => array.elems.len(),
+                        _ => 0,
+                    },
+                    _ => 0,
+                };
+
+                if let Some(name) = field("name").and_then(lit_str) {
+                    self.insert(name, args);
+                }
+            }
+        }
+
+        syn::visit::visit_expr_method_call(self, call);
+    }
+}
+
+fn lit_str(expr: &syn::Expr) -> Option<&syn::LitStr> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(lit),
+            ..
+        }) => Some(lit),
+        _ => None,
+    }
+}
+
+fn last_segment_is(path: &syn::Path, name: &str) -> bool {
+    path.segments
+        .last()
+        .is_some_and(|segment| segment.ident == name)
+}
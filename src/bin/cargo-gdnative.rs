@@ -0,0 +1,14 @@
+//! The `cargo gdnative` subcommand, e.g. `cargo gdnative sync`.
+
+mod cli;
+
+fn main() -> std::process::ExitCode {
+    // cargo runs `cargo-gdnative gdnative <ARGS>`, but allow running the
+    // binary directly as well.
+    let mut args = std::env::args().skip(1).peekable();
+    if args.peek().map(String::as_str) == Some("gdnative") {
+        args.next();
+    }
+
+    cli::main("cargo gdnative", args)
+}
@@ -0,0 +1,262 @@
+//! Command line interface to generate the Godot resources of a crate outside
+//! of its build script, shared by `gdnative-project-utils` and
+//! `cargo gdnative`.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use gdnative_project_utils::*;
+
+const USAGE: &str = "\
+Generate Godot resources for the NativeScript classes of a crate
+
+Usage: {program} <COMMAND> [OPTIONS]
+
+Commands:
+  scan      Print the classes found in the crate
+  sync      Generate the library and class resources
+  generate  The same as `sync`
+  check     Check that the generated resources are up to date
+  clean     Remove the generated resources
+  watch     Generate the resources again whenever the classes change
+
+Options:
+  --manifest-path <PATH>     The Cargo.toml of the crate [default: the
+                             nearest Cargo.toml of the working directory]
+  --crate-dir <DIR>          The directory of the crate's Cargo.toml
+  --godot-project-dir <DIR>  The root of the Godot project
+  --output-dir <DIR>         The directory the resources are generated into
+  --target-dir <DIR>         The cargo target directory
+  --lib-name <NAME>          The name of the library
+  --profile <NAME>           The cargo profile the library is built with
+  --release                  Point the library entries at release builds
+  --force                    Overwrite or remove modified files
+  --json                     Print the scanned classes as JSON (`scan` only)
+  -h, --help                 Print this help
+
+The library name and target directory are found with `cargo metadata`.
+Settings not given as options are read from the crate's `gdnative.toml` or
+`[package.metadata.gdnative]` table, and the `GDNATIVE_*` environment
+variables. Generated files that were not modified by hand are overwritten,
+unless another `overwrite_policy` is configured.
+";
+
+/// The parsed command line.
+#[derive(Default)]
+struct Args {
+    command: String,
+    crate_dir: Option<PathBuf>,
+    godot_project_dir: Option<PathBuf>,
+    output_dir: Option<PathBuf>,
+    target_dir: Option<PathBuf>,
+    lib_name: Option<String>,
+    profile: Option<String>,
+    force: bool,
+    json: bool,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Args::default();
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("`{}` requires a value", arg))
+            };
+
+            match arg.as_str() {
+                "--manifest-path" => {
+                    let path = PathBuf::from(value()?);
+                    parsed.crate_dir = Some(match path.parent() {
+                        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+                        _ => PathBuf::from("."),
+                    });
+                }
+                "--crate-dir" => parsed.crate_dir = Some(value()?.into()),
+                "--godot-project-dir" => parsed.godot_project_dir = Some(value()?.into()),
+                "--output-dir" => parsed.output_dir = Some(value()?.into()),
+                "--target-dir" => parsed.target_dir = Some(value()?.into()),
+                "--lib-name" => parsed.lib_name = Some(value()?),
+                "--profile" => parsed.profile = Some(value()?),
+                "--release" => parsed.profile = Some("release".to_string()),
+                "--force" => parsed.force = true,
+                "--json" => parsed.json = true,
+                "-h" | "--help" => parsed.command = "help".to_string(),
+                command if parsed.command.is_empty() && !command.starts_with('-') => {
+                    parsed.command = command.to_string()
+                }
+                other => return Err(format!("Unexpected argument `{}`", other)),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// The generator with the settings of the crate, the environment and the
+    /// command line, in increasing precedence.
+    fn generator(&self, crate_dir: &Path) -> Result<Generator, GenerateError> {
+        // Unlike in a build script, the generator is run to bring the files
+        // up to date, e.g. with the entries of another build mode.
+        let mut generator = Generator::new()
+            .overwrite_policy(OverwritePolicy::IfGeneratedByUs)
+            .config_in(crate_dir)?
+            .env_overrides()?;
+        generator.with_cargo_metadata(true);
+
+        if let Some(dir) = &self.godot_project_dir {
+            generator.with_godot_project_dir(dir);
+        }
+        if let Some(dir) = &self.output_dir {
+            generator.with_godot_resource_output_dir(dir);
+        }
+        if let Some(dir) = &self.target_dir {
+            generator.with_target_dir(dir);
+        }
+        if let Some(name) = &self.lib_name {
+            generator.with_lib_name(name);
+        }
+        if let Some(profile) = &self.profile {
+            generator.with_build_mode(BuildMode::from_profile(profile));
+        }
+        if self.force {
+            generator.with_overwrite_policy(OverwritePolicy::IfContentDiffers);
+        }
+
+        Ok(generator)
+    }
+}
+
+/// Run the command line `args`, without the program name, as `program`.
+pub fn main(program: &str, args: impl Iterator<Item = String>) -> ExitCode {
+    let usage = USAGE.replace("{program}", program);
+
+    let args = match Args::parse(args) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, usage);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(&args, &usage) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &Args, usage: &str) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let crate_dir = match &args.crate_dir {
+        Some(dir) => dir.clone(),
+        None => find_crate_dir()?,
+    };
+
+    // Cargo tells build scripts which profile they are built with.
+    if std::env::var_os("PROFILE").is_none() {
+        std::env::set_var("PROFILE", "debug");
+    }
+
+    let scan = || scan_crate_with_report(crate_dir.join("src"));
+
+    match args.command.as_str() {
+        "scan" if args.json => print_json(&crate_dir)?,
+        "scan" => {
+            for class in scan()?.classes.iter() {
+                match (&class.source_file, &class.span) {
+                    (Some(file), Some(span)) => println!(
+                        "{} ({}:{})",
+                        class.rust_path(),
+                        file.display(),
+                        span.start_line
+                    ),
+                    _ => println!("{}", class.rust_path()),
+                }
+            }
+        }
+        "sync" | "generate" => {
+            let report = args.generator(&crate_dir)?.build(scan()?.classes)?;
+
+            for (path, action) in &report.files {
+                if *action != FileAction::Unchanged {
+                    println!("{:?}: {}", action, path.display());
+                }
+            }
+            if let Err(missing) = report.validate() {
+                eprintln!("warning: {}", missing);
+            }
+        }
+        "check" => {
+            let report = args.generator(&crate_dir)?.check(scan()?.classes)?;
+
+            if !report.is_up_to_date() {
+                print!("{}", report);
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        "clean" => {
+            for (path, action) in args.generator(&crate_dir)?.clean()? {
+                println!("{:?}: {}", action, path.display());
+            }
+        }
+        "watch" => {
+            let options = WatchOptions::new(&crate_dir, args.generator(&crate_dir)?);
+
+            watch(options, |result| {
+                match result {
+                    Ok(report) => {
+                        for (path, action) in &report.files {
+                            if *action != FileAction::Unchanged {
+                                println!("{:?}: {}", action, path.display());
+                            }
+                        }
+                    }
+                    Err(err) => eprintln!("error: {}", err),
+                }
+                true
+            })?;
+        }
+        "help" => print!("{}", usage),
+        "" => {
+            eprint!("{}", usage);
+            return Ok(ExitCode::FAILURE);
+        }
+        other => return Err(format!("Unknown command `{}`", other).into()),
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// The nearest directory containing a `Cargo.toml`, starting at the working
+/// directory, like cargo does.
+fn find_crate_dir() -> Result<PathBuf, String> {
+    let cwd = std::env::current_dir().map_err(|err| err.to_string())?;
+
+    cwd.ancestors()
+        .find(|dir| dir.join("Cargo.toml").is_file())
+        .map(Path::to_path_buf)
+        .ok_or_else(|| {
+            format!(
+                "Could not find `Cargo.toml` in `{}` or any parent directory",
+                cwd.display()
+            )
+        })
+}
+
+#[cfg(feature = "serde")]
+fn print_json(crate_dir: &Path) -> Result<(), ScanError> {
+    scan_crate_to_writer(
+        crate_dir.join("src"),
+        &ScanOptions::default(),
+        std::io::stdout(),
+    )?;
+    println!();
+    Ok(())
+}
+
+#[cfg(not(feature = "serde"))]
+fn print_json(_crate_dir: &Path) -> Result<(), String> {
+    Err("`--json` requires the `serde` feature".to_string())
+}
@@ -0,0 +1,8 @@
+//! Command line interface to generate the Godot resources of a crate outside
+//! of its build script.
+
+mod cli;
+
+fn main() -> std::process::ExitCode {
+    cli::main("gdnative-project-utils", std::env::args().skip(1))
+}
@@ -0,0 +1,96 @@
+//! Helpers for cargo build scripts.
+//!
+//! ```no_run
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     gdnative_project_utils::build_script::generate("../godot")?;
+//!     Ok(())
+//! }
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use crate::diagnostics::CargoWarnings;
+use crate::generate::{Builder, GenerateError};
+use crate::report::Report;
+use crate::scan::{scan_crate, ScanError};
+
+/// Scan the `src` directory of the crate that is being built and generate all
+/// resources into the Godot project at `godot_project_dir`.
+///
+/// Relative paths are resolved from `CARGO_MANIFEST_DIR`. The library name,
+/// build mode and target directory are found from the environment cargo sets
+/// for build scripts. The directives to re-run the build script whenever a
+/// source file changes are printed as well, and issues that do not stop the
+/// generation, like a library entry that points at a missing file or a
+/// modified `.gdns` file that is not overwritten, are shown as cargo
+/// warnings.
+pub fn generate(godot_project_dir: impl AsRef<Path>) -> Result<Report, Error> {
+    generate_with(godot_project_dir, Builder::new())
+}
+
+/// Like [`generate`], but with a `generator` that has already been configured.
+///
+/// Settings that are left unset on `generator` are found from the environment.
+pub fn generate_with(
+    godot_project_dir: impl AsRef<Path>,
+    generator: Builder,
+) -> Result<Report, Error> {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .map_err(|_| Error::MissingEnv("CARGO_MANIFEST_DIR"))?;
+
+    let classes = scan_crate(manifest_dir.join("src"))?;
+
+    let report = generator
+        .godot_project_dir(manifest_dir.join(godot_project_dir))
+        .build(classes)?;
+    report.emit_diagnostics(&mut CargoWarnings);
+
+    Ok(report)
+}
+
+/// Error type for errors that can occur in the build script helpers.
+#[derive(Debug)]
+pub enum Error {
+    /// An environment variable that cargo sets for build scripts is missing.
+    MissingEnv(&'static str),
+    /// An error was encountered when scanning the crate.
+    Scan(ScanError),
+    /// An error was encountered when generating the resources.
+    Generate(GenerateError),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::MissingEnv(var) => f.write_fmt(format_args!(
+                "Environment variable `{}` is not set, is this running in a build script?",
+                var
+            )),
+            Error::Scan(err) => f.write_fmt(format_args!("Scanning error: {}", err)),
+            Error::Generate(err) => f.write_fmt(format_args!("Generation error: {}", err)),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::MissingEnv(_) => None,
+            Error::Scan(err) => Some(err),
+            Error::Generate(err) => Some(err),
+        }
+    }
+}
+
+impl From<ScanError> for Error {
+    fn from(err: ScanError) -> Self {
+        Error::Scan(err)
+    }
+}
+
+impl From<GenerateError> for Error {
+    fn from(err: GenerateError) -> Self {
+        Error::Generate(err)
+    }
+}
@@ -0,0 +1,101 @@
+//! Reading of the build settings in cargo's configuration files.
+//!
+//! Cargo looks for `.cargo/config.toml`, or the older `.cargo/config`, in the
+//! directory of the crate and all of its ancestors, so a workspace usually
+//! keeps its configuration next to the workspace manifest. Settings in files
+//! closer to the crate take precedence, followed by the file in `CARGO_HOME`.
+//!
+//! Only the `[build]` keys the generator needs are read. Files that are not
+//! valid TOML are skipped, as if they had no settings, and left to cargo to
+//! complain about.
+
+use std::path::{Path, PathBuf};
+
+use crate::generate::GenerateError;
+
+/// The `[build]` settings of cargo's configuration.
+#[derive(Debug, Default)]
+pub(crate) struct CargoConfig {
+    /// `build.target-dir`, resolved relative to the directory containing the
+    /// `.cargo` directory it was found in.
+    pub(crate) target_dir: Option<PathBuf>,
+    /// `build.target`.
+    pub(crate) target: Option<String>,
+}
+
+impl CargoConfig {
+    /// Merge the configuration files that apply to the crate in `crate_dir`.
+    pub(crate) fn discover(crate_dir: &Path) -> Result<Self, GenerateError> {
+        let mut config_dirs = crate_dir
+            .ancestors()
+            .map(|dir| dir.join(".cargo"))
+            .collect::<Vec<_>>();
+
+        if let Some(cargo_home) = std::env::var_os("CARGO_HOME") {
+            let cargo_home = PathBuf::from(cargo_home);
+            if !config_dirs.contains(&cargo_home) {
+                config_dirs.push(cargo_home);
+            }
+        }
+
+        let mut config = CargoConfig::default();
+
+        for config_dir in config_dirs {
+            let path = match ["config.toml", "config"]
+                .iter()
+                .map(|name| config_dir.join(name))
+                .find(|path| path.is_file())
+            {
+                Some(path) => path,
+                None => continue,
+            };
+
+            // The configuration is shared with every other crate of the
+            // user, so one that is not valid TOML is left to cargo.
+            let content = std::fs::read_to_string(&path)?;
+            let cfg = match toml_document(&content) {
+                Ok(cfg) => cfg,
+                Err(error) => {
+                    debug!("Can not read {}: {}", path.display(), error);
+                    continue;
+                }
+            };
+
+            let build = match cfg.get("build").and_then(toml::Value::as_table) {
+                Some(build) => build,
+                None => continue,
+            };
+
+            if config.target_dir.is_none() {
+                let base = config_dir.parent().unwrap_or(&config_dir);
+                config.target_dir = build
+                    .get("target-dir")
+                    .and_then(toml::Value::as_str)
+                    .map(|dir| base.join(dir));
+            }
+            if config.target.is_none() {
+                config.target = build
+                    .get("target")
+                    .and_then(toml::Value::as_str)
+                    .map(String::from);
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+/// Parse a TOML document.
+pub(crate) fn toml_document(content: &str) -> Result<toml::Table, toml::de::Error> {
+    content.parse()
+}
+
+/// The items of `value` if it is an array of strings, like
+/// `["desktop", 'Android.arm64-v8a']`.
+pub(crate) fn toml_string_array(value: &toml::Value) -> Option<Vec<String>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|item| item.as_str().map(String::from))
+        .collect()
+}
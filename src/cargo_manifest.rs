@@ -0,0 +1,88 @@
+//! Reading of the library target from the crate's `Cargo.toml`.
+//!
+//! cargo names the library after `[lib] name`, or, if it is not set, after
+//! the package name with hyphens replaced by underscores. The manifest is
+//! read directly, so a renamed library is found without the `cargo_metadata`
+//! feature, and a library that Godot can not load is reported before any
+//! resources are written. A manifest that is not valid TOML is an error, as
+//! the library name it would be built with is not known.
+
+use std::path::Path;
+
+use crate::cargo_config::{toml_document, toml_string_array};
+use crate::generate::GenerateError;
+
+/// The settings of the library target in `Cargo.toml`.
+#[derive(Debug, Default)]
+pub(crate) struct CargoManifest {
+    /// `package.name`.
+    pub(crate) package_name: Option<String>,
+    /// `lib.name`.
+    pub(crate) lib_name: Option<String>,
+    /// `lib.crate-type`, `["lib"]` if it is not set, and `None` if it can
+    /// not be read.
+    pub(crate) crate_types: Option<Vec<String>>,
+}
+
+impl CargoManifest {
+    /// Read the manifest of the crate in `crate_dir`, if it has one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the manifest is not valid TOML.
+    pub(crate) fn read(crate_dir: &Path) -> Result<Option<Self>, GenerateError> {
+        let path = crate_dir.join("Cargo.toml");
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        let cfg = toml_document(&content).map_err(|error| GenerateError::InvalidToml {
+            path,
+            error: Box::new(error),
+        })?;
+        let string = |table: &str, key: &str| cfg.get(table)?.get(key)?.as_str().map(String::from);
+
+        let lib = cfg.get("lib");
+        let crate_types = match lib
+            .and_then(|lib| lib.get("crate-type"))
+            .or_else(|| lib.and_then(|lib| lib.get("crate_type")))
+        {
+            Some(value) => toml_string_array(value),
+            None => Some(vec!["lib".to_string()]),
+        };
+
+        Ok(Some(CargoManifest {
+            package_name: string("package", "name"),
+            lib_name: string("lib", "name"),
+            crate_types,
+        }))
+    }
+
+    /// The name of the library target, which the file names of the built
+    /// libraries are derived from with
+    /// [`Platform::lib_file_name`](crate::Platform::lib_file_name).
+    ///
+    /// The package name is returned as it is, like `CARGO_PKG_NAME`, so the
+    /// name of the library resource does not change.
+    pub(crate) fn target_name(&self) -> Option<String> {
+        self.lib_name.clone().or_else(|| self.package_name.clone())
+    }
+
+    /// Returns an error if the package does not build a `cdylib`, which is
+    /// the only crate type Godot can load.
+    ///
+    /// A workspace manifest without a package, and crate types that can not
+    /// be read, are not checked.
+    pub(crate) fn check_crate_type(&self) -> Result<(), GenerateError> {
+        match (&self.package_name, &self.crate_types) {
+            (Some(package), Some(crate_types)) if !crate_types.iter().any(|ty| ty == "cdylib") => {
+                Err(GenerateError::InvalidCrateType {
+                    package: package.clone(),
+                    crate_types: crate_types.clone(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! Evaluation of `cfg` predicates against the options of a scan.
+
+use std::collections::HashSet;
+
+use syn::{Lit, Meta, NestedMeta};
+
+use crate::scan::ScanOptions;
+
+/// Evaluate the `cfg` predicate `predicate`, e.g. `feature = "godot"` or
+/// `all(unix, not(feature = "editor"))`.
+///
+/// Returns `None` if the result depends on something the options do not
+/// specify.
+pub(crate) fn evaluate(predicate: &NestedMeta, options: &ScanOptions) -> Option<bool> {
+    let meta = match predicate {
+        NestedMeta::Meta(meta) => meta,
+        NestedMeta::Lit(_) => return None,
+    };
+
+    match meta {
+        Meta::NameValue(name_value) => {
+            let value = match &name_value.lit {
+                Lit::Str(value) => value.value(),
+                _ => return None,
+            };
+            let key = name_value.path.get_ident()?.to_string();
+
+            if key == "feature" {
+                options.feature_enabled(&value)
+            } else {
+                options.target_cfg(&key, Some(&value))
+            }
+        }
+        Meta::Path(path) if path.is_ident("test") => options.test_cfg(),
+        Meta::Path(path) => options.target_cfg(&path.get_ident()?.to_string(), None),
+        Meta::List(list) if list.path.is_ident("all") => {
+            let mut result = Some(true);
+            for predicate in &list.nested {
+                match evaluate(predicate, options) {
+                    Some(false) => return Some(false),
+                    Some(true) => {}
+                    None => result = None,
+                }
+            }
+            result
+        }
+        Meta::List(list) if list.path.is_ident("any") => {
+            let mut result = Some(false);
+            for predicate in &list.nested {
+                match evaluate(predicate, options) {
+                    Some(true) => return Some(true),
+                    Some(false) => {}
+                    None => result = None,
+                }
+            }
+            result
+        }
+        Meta::List(list) if list.path.is_ident("not") && list.nested.len() == 1 => {
+            evaluate(&list.nested[0], options).map(|enabled| !enabled)
+        }
+        _ => None,
+    }
+}
+
+/// Whether the `#[cfg(...)]` attributes in `attrs` all hold, counting the ones
+/// that cannot be decided as holding.
+pub(crate) fn enabled(attrs: &[syn::Attribute], options: &ScanOptions) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("cfg"))
+        .all(|attr| match attr.parse_meta() {
+            Ok(Meta::List(list)) if list.nested.len() == 1 => {
+                evaluate(&list.nested[0], options) != Some(false)
+            }
+            _ => true,
+        })
+}
+
+/// The target configuration options implied by a target triple, such as
+/// `target_os = "linux"` and `unix`.
+#[derive(Clone, Debug)]
+pub(crate) struct TargetCfg {
+    triple: String,
+    cfg: HashSet<(String, Option<String>)>,
+}
+
+impl TargetCfg {
+    /// Derive the configuration options of `triple`, e.g.
+    /// `x86_64-unknown-linux-gnu`.
+    pub(crate) fn from_triple(triple: &str) -> Self {
+        let mut parts = triple.split('-').collect::<Vec<_>>();
+        // The vendor is left out of some triples, e.g.
+        // `armv7-linux-androideabi`.
+        if parts.get(1) == Some(&"linux") {
+            parts.insert(1, "unknown");
+        }
+        let arch = parts.first().copied().unwrap_or_default();
+        let vendor = parts.get(1).copied().unwrap_or_default();
+        let rest = &parts[parts.len().min(2)..];
+
+        let target_arch = match arch {
+            "i386" | "i586" | "i686" => "x86",
+            arch if arch.starts_with("armv") || arch.starts_with("thumbv") || arch == "arm" => {
+                "arm"
+            }
+            arch => arch,
+        };
+        let pointer_width = match arch {
+            "x86_64" | "aarch64" | "wasm64" | "powerpc64" | "riscv64gc" | "s390x" => "64",
+            _ => "32",
+        };
+
+        let os = rest.first().copied().unwrap_or("none");
+        let env = rest.get(1).copied().unwrap_or_default();
+        let target_os = match (os, env) {
+            (_, env) if env.starts_with("android") => "android",
+            ("darwin", _) => "macos",
+            (os, _) => os,
+        };
+        // The suffixes of the ABI, as in `gnueabihf`, are not part of the
+        // environment, and Android has none.
+        let target_env = match env {
+            env if env.starts_with("gnu") => "gnu",
+            env if env.starts_with("musl") => "musl",
+            "msvc" => "msvc",
+            _ => "",
+        };
+
+        let mut families = vec![];
+        match target_os {
+            "windows" => families.push("windows"),
+            "none" | "unknown" => {}
+            _ => families.push("unix"),
+        }
+        if target_arch.starts_with("wasm") {
+            families.push("wasm");
+        }
+
+        let mut cfg = HashSet::new();
+        let mut insert = |key: &str, value: Option<&str>| {
+            cfg.insert((key.to_string(), value.map(str::to_string)));
+        };
+
+        insert("target_arch", Some(target_arch));
+        insert("target_vendor", Some(vendor));
+        insert("target_os", Some(target_os));
+        insert("target_env", Some(target_env));
+        insert("target_pointer_width", Some(pointer_width));
+        for family in families {
+            insert("target_family", Some(family));
+            if family != "wasm" {
+                insert(family, None);
+            }
+        }
+
+        TargetCfg {
+            triple: triple.to_string(),
+            cfg,
+        }
+    }
+
+    /// The triple the options were derived from.
+    pub(crate) fn triple(&self) -> &str {
+        &self.triple
+    }
+
+    /// Whether the option `key`, or `key = "value"`, is set, or `None` if
+    /// `key` is not a target option.
+    pub(crate) fn get(&self, key: &str, value: Option<&str>) -> Option<bool> {
+        match key {
+            "target_arch"
+            | "target_vendor"
+            | "target_os"
+            | "target_env"
+            | "target_pointer_width"
+            | "target_family"
+            | "unix"
+            | "windows" => Some(
+                self.cfg
+                    .contains(&(key.to_string(), value.map(str::to_string))),
+            ),
+            _ => None,
+        }
+    }
+}
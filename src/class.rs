@@ -0,0 +1,294 @@
+//! Classes found in the project sources.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+
+/// A type that derives `NativeClass`.
+///
+/// New fields may be added, so classes are constructed with [`Class::new`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Class {
+    /// The name of the type, which is also the name of the class in Godot.
+    pub name: String,
+    /// The modules the type is declared in, relative to the crate root, e.g.
+    /// `["enemies"]` for `crate::enemies::Slime`.
+    pub module_path: Vec<String>,
+    /// The file the type is declared in, if the class was found by scanning.
+    pub source_file: Option<PathBuf>,
+    /// The location of the type's name in `source_file`.
+    pub span: Option<SourceSpan>,
+    /// The Godot class the type extends, as given by `#[inherit(...)]` or
+    /// `type Base` in a manual implementation, e.g. `Node2D`. `None` if it is
+    /// not given, in which case the class extends `Reference`.
+    pub base: Option<String>,
+    /// The methods exported from the class's `#[methods]` impl blocks.
+    pub methods: Vec<Method>,
+    /// The signals registered by the class.
+    pub signals: Vec<Signal>,
+    /// The path of the class's `.gdns` file relative to the resource output
+    /// directory, e.g. `enemies/slime.gdns`, which replaces the path from the
+    /// layout and naming convention of the generator.
+    ///
+    /// The scanner takes it from a `gdns: <path>` line in the doc comment of
+    /// the type.
+    pub gdns_path: Option<String>,
+}
+
+impl Class {
+    /// A class called `name` in the crate root, without source information.
+    pub fn new(name: impl Into<String>) -> Self {
+        Class {
+            name: name.into(),
+            module_path: vec![],
+            source_file: None,
+            span: None,
+            base: None,
+            methods: vec![],
+            signals: vec![],
+            gdns_path: None,
+        }
+    }
+
+    /// The path of the type, e.g. `crate::enemies::Slime`.
+    pub fn rust_path(&self) -> String {
+        std::iter::once("crate")
+            .chain(self.module_path.iter().map(String::as_str))
+            .chain(std::iter::once(self.name.as_str()))
+            .collect::<Vec<_>>()
+            .join("::")
+    }
+
+    /// Returns `true` if the class matches `pattern`, in which `*` stands
+    /// for any number of characters and `?` for a single character.
+    ///
+    /// Patterns containing `::` are matched against [`rust_path`], e.g.
+    /// `crate::enemies::*`, all other patterns against the name, e.g.
+    /// `Debug*`.
+    ///
+    /// [`rust_path`]: Class::rust_path
+    pub fn matches(&self, pattern: &str) -> bool {
+        let pattern = pattern.chars().collect::<Vec<_>>();
+        if pattern.windows(2).any(|pair| pair == [':', ':']) {
+            wildcard_match(&pattern, &self.rust_path().chars().collect::<Vec<_>>())
+        } else {
+            wildcard_match(&pattern, &self.name.chars().collect::<Vec<_>>())
+        }
+    }
+}
+
+/// Whether `text` matches `pattern`, in which `*` stands for any number of
+/// characters and `?` for a single character.
+fn wildcard_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|skip| wildcard_match(rest, &text[skip..])),
+        Some((&c, rest)) => match text.split_first() {
+            Some((&t, text)) => (c == '?' || c == t) && wildcard_match(rest, text),
+            None => false,
+        },
+    }
+}
+
+/// Displays the name of the class.
+impl std::fmt::Display for Class {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+impl From<String> for Class {
+    fn from(name: String) -> Self {
+        Class::new(name)
+    }
+}
+
+impl From<&str> for Class {
+    fn from(name: &str) -> Self {
+        Class::new(name)
+    }
+}
+
+/// A method a class exports to Godot.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Method {
+    pub name: String,
+    /// The number of arguments Godot passes, not counting `self` and the
+    /// owner.
+    pub args: usize,
+}
+
+impl Method {
+    /// A method called `name` with `args` arguments.
+    pub fn new(name: impl Into<String>, args: usize) -> Self {
+        Method {
+            name: name.into(),
+            args,
+        }
+    }
+}
+
+/// A signal a class registers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Signal {
+    pub name: String,
+    /// The number of parameters of the signal.
+    pub args: usize,
+}
+
+impl Signal {
+    /// A signal called `name` with `args` parameters.
+    pub fn new(name: impl Into<String>, args: usize) -> Self {
+        Signal {
+            name: name.into(),
+            args,
+        }
+    }
+}
+
+/// A range in a source file.
+///
+/// Lines start at 1 and columns at 0.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct SourceSpan {
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl SourceSpan {
+    /// The range from `start_line` and `start_column` to `end_line` and
+    /// `end_column`.
+    pub fn new(start_line: usize, start_column: usize, end_line: usize, end_column: usize) -> Self {
+        SourceSpan {
+            start_line,
+            start_column,
+            end_line,
+            end_column,
+        }
+    }
+}
+
+impl From<proc_macro2::Span> for SourceSpan {
+    fn from(span: proc_macro2::Span) -> Self {
+        SourceSpan {
+            start_line: span.start().line,
+            start_column: span.start().column,
+            end_line: span.end().line,
+            end_column: span.end().column,
+        }
+    }
+}
+
+/// A set of classes, ordered by name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Classes(BTreeMap<String, Class>);
+
+impl Classes {
+    /// An empty set of classes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a class, replacing and returning a class of the same name.
+    pub fn insert(&mut self, class: impl Into<Class>) -> Option<Class> {
+        let class = class.into();
+        self.0.insert(class.name.clone(), class)
+    }
+
+    /// The class called `name`.
+    pub fn get(&self, name: &str) -> Option<&Class> {
+        self.0.get(name)
+    }
+
+    /// Returns `true` if the set contains a class called `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    /// The class called `name`, for adding information found elsewhere.
+    pub(crate) fn get_mut(&mut self, name: &str) -> Option<&mut Class> {
+        self.0.get_mut(name)
+    }
+
+    /// The number of classes.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the set contains no classes.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Keep only the classes for which `keep` returns `true`.
+    pub fn retain(&mut self, mut keep: impl FnMut(&Class) -> bool) {
+        self.0.retain(|_, class| keep(class));
+    }
+
+    /// Iterate over the classes in order of their names.
+    pub fn iter(&self) -> impl Iterator<Item = &Class> + '_ {
+        self.0.values()
+    }
+
+    /// Iterate over the names of the classes in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.0.keys().map(String::as_str)
+    }
+}
+
+impl<C: Into<Class>> std::iter::FromIterator<C> for Classes {
+    fn from_iter<I: IntoIterator<Item = C>>(iter: I) -> Self {
+        let mut classes = Classes::new();
+        classes.extend(iter);
+        classes
+    }
+}
+
+impl<C: Into<Class>> Extend<C> for Classes {
+    fn extend<I: IntoIterator<Item = C>>(&mut self, iter: I) {
+        for class in iter {
+            self.insert(class);
+        }
+    }
+}
+
+impl From<HashSet<String>> for Classes {
+    fn from(names: HashSet<String>) -> Self {
+        names.into_iter().collect()
+    }
+}
+
+impl IntoIterator for Classes {
+    type Item = Class;
+    type IntoIter = std::collections::btree_map::IntoValues<String, Class>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_values()
+    }
+}
+
+/// Serializes the classes as a sequence, ordered by name.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Classes {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Classes {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Vec::<Class>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
@@ -0,0 +1,57 @@
+//! Reporting issues that do not stop the generation.
+//!
+//! A scan or generation can succeed and still leave something the user should
+//! know about, like a library entry pointing at a missing file or a modified
+//! `.gdns` file that was not overwritten. [`Report::emit_diagnostics`] and
+//! [`ScanReport::emit_diagnostics`] pass them to a [`DiagnosticSink`]:
+//!
+//! ```no_run
+//! # use gdnative_project_utils::*;
+//! let scan = scan_crate_with_options("src", &ScanOptions::new().lenient(true))?;
+//! scan.emit_diagnostics(&mut CargoWarnings);
+//!
+//! let report = Generator::new()
+//!     .godot_project_dir("../godot")
+//!     .build(scan.classes)?;
+//! report.emit_diagnostics(&mut CargoWarnings);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Report::emit_diagnostics`]: crate::Report::emit_diagnostics
+//! [`ScanReport::emit_diagnostics`]: crate::ScanReport::emit_diagnostics
+
+/// Receives the warnings about issues that did not stop a scan or
+/// generation.
+///
+/// This is implemented for closures taking the message, e.g. to collect the
+/// warnings or send them to a logger.
+pub trait DiagnosticSink {
+    /// Report the warning `message`.
+    fn warning(&mut self, message: &str);
+}
+
+impl<F: FnMut(&str)> DiagnosticSink for F {
+    fn warning(&mut self, message: &str) {
+        self(message)
+    }
+}
+
+/// A [`DiagnosticSink`] that prints `cargo:warning` directives when running
+/// in a build script, so cargo shows the warnings when building the crate,
+/// and prints them to stderr otherwise.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CargoWarnings;
+
+impl DiagnosticSink for CargoWarnings {
+    fn warning(&mut self, message: &str) {
+        // cargo only sets `OUT_DIR` for build scripts.
+        if std::env::var_os("OUT_DIR").is_some() {
+            // A directive ends at the end of the line.
+            for line in message.lines() {
+                println!("cargo:warning={}", line);
+            }
+        } else {
+            eprintln!("warning: {}", message);
+        }
+    }
+}
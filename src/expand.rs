@@ -0,0 +1,183 @@
+//! Scanning of the macro-expanded crate, as printed by `cargo expand`.
+//!
+//! The expanded source is a single file with every module inlined, so it also
+//! contains the classes generated by macros, which the scanner cannot see in
+//! the original sources.
+
+use std::path::{Path, PathBuf};
+
+use crate::class::{Class, Classes};
+use crate::scan::{
+    find_classes, scan_crate_with_options, ScanError, ScanOptions, ScanReport, ScanStats, Scanner,
+};
+
+/// Set for `cargo expand`, so the build script of the crate it builds again
+/// does not expand the crate in turn.
+const NESTED_EXPAND_VAR: &str = "GDNATIVE_PROJECT_UTILS_EXPANDING";
+
+/// A [`Scanner`](crate::Scanner) that scans the macro-expanded crate, like
+/// [`scan_crate_expanded`].
+#[derive(Clone, Debug)]
+pub struct ExpandScanner {
+    crate_dir: PathBuf,
+    options: ScanOptions,
+}
+
+impl ExpandScanner {
+    /// Construct a new ExpandScanner for the crate in `crate_dir`.
+    pub fn new(crate_dir: impl AsRef<Path>) -> Self {
+        ExpandScanner {
+            crate_dir: crate_dir.as_ref().to_path_buf(),
+            options: ScanOptions::default(),
+        }
+    }
+
+    /// Set the options of the scan.
+    pub fn with_options(&mut self, options: ScanOptions) {
+        self.options = options;
+    }
+
+    /// Set the options of the scan.
+    pub fn options(mut self, options: ScanOptions) -> Self {
+        self.with_options(options);
+        self
+    }
+}
+
+/// Prints `cargo:rerun-if-changed` directives like
+/// [`scan_crate`](crate::scan_crate).
+impl Scanner for ExpandScanner {
+    fn scan(&self) -> Result<Classes, ScanError> {
+        let report = scan_crate_expanded(&self.crate_dir, &self.options)?;
+
+        if cfg!(feature = "build_script") {
+            report.print_rerun_if_changed();
+        }
+
+        Ok(report.classes)
+    }
+}
+
+/// Scan the crate in `crate_dir` like [`scan_crate_with_options`], but
+/// look at its macro-expanded source to also find classes that are generated
+/// by macros.
+///
+/// This runs [`cargo expand`](https://github.com/dtolnay/cargo-expand) on the
+/// library of the crate, which has to be installed. The features and target
+/// of `options` are passed on to it. The classes have no source information,
+/// as they are not declared in any of the crate's files. The report lists the
+/// `*.rs` files in the `src` directory of the crate.
+///
+/// `cargo expand` builds the crate again, which runs its build script again.
+/// In a build script, the crate is therefore expanded in a target directory
+/// inside `OUT_DIR`, so the build does not wait for the lock on its own
+/// target directory, and the nested build script scans the sources without
+/// expanding them.
+pub fn scan_crate_expanded(
+    crate_dir: impl AsRef<Path>,
+    options: &ScanOptions,
+) -> Result<ScanReport, ScanError> {
+    let crate_dir = crate_dir.as_ref();
+
+    if std::env::var_os(NESTED_EXPAND_VAR).is_some() {
+        return scan_crate_with_options(crate_dir.join("src"), options);
+    }
+
+    let mut command =
+        std::process::Command::new(std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+    command
+        .arg("expand")
+        .arg("--lib")
+        .arg("--manifest-path")
+        .arg(crate_dir.join("Cargo.toml"))
+        .env(NESTED_EXPAND_VAR, "1");
+
+    if let Some(out_dir) = std::env::var_os("OUT_DIR") {
+        command
+            .arg("--target-dir")
+            .arg(Path::new(&out_dir).join("expand"));
+    }
+
+    if let Some(features) = options.enabled_features() {
+        let mut features = features.iter().cloned().collect::<Vec<_>>();
+        features.sort();
+
+        if !features.iter().any(|feature| feature == "default") {
+            command.arg("--no-default-features");
+        }
+        command.arg("--features").arg(features.join(","));
+    }
+
+    if let Some(triple) = options.target_triple() {
+        command.arg("--target").arg(triple);
+    }
+
+    let output = command.output().map_err(|err| ScanError::CommandFailed {
+        command: "cargo expand".to_string(),
+        stderr: err.to_string(),
+    })?;
+
+    if !output.status.success() {
+        return Err(ScanError::CommandFailed {
+            command: "cargo expand".to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        });
+    }
+
+    let classes = scan_expanded(&String::from_utf8_lossy(&output.stdout), options)?;
+
+    let dir = crate_dir.join("src");
+    let rs_extension = std::ffi::OsString::from("rs");
+    let mut files = vec![];
+
+    for file in ignore::Walk::new(&dir) {
+        let path = file.map_err(ScanError::WalkDir)?.into_path();
+
+        if path.extension() == Some(&rs_extension) {
+            files.push(path);
+        }
+    }
+    // The directory is walked in no particular order.
+    files.sort();
+
+    let stats = ScanStats {
+        files_walked: files.len(),
+        ..ScanStats::default()
+    };
+
+    Ok(ScanReport {
+        classes,
+        dir,
+        files,
+        errors: vec![],
+        stats,
+    })
+}
+
+/// Find the classes in `source`, the macro-expanded source of a crate.
+pub fn scan_expanded(source: &str, options: &ScanOptions) -> Result<Classes, ScanError> {
+    let file = syn::parse_file(source).map_err(ScanError::Parse)?;
+
+    let (classes, apis) = find_classes(&file, options).map_err(ScanError::Parse)?;
+
+    let mut classes = classes
+        .into_iter()
+        .map(|class| Class {
+            span: None,
+            ..class
+        })
+        .try_fold(Classes::new(), |mut acc, class| {
+            if let Some(first) = acc.get(&class.name) {
+                return Err(ScanError::DuplicateClass {
+                    first: Box::new(first.clone()),
+                    second: Box::new(class),
+                });
+            }
+            acc.insert(class);
+            Ok(acc)
+        })?;
+
+    crate::api::merge(&mut classes, apis);
+
+    Ok(classes)
+}
@@ -0,0 +1,194 @@
+//! Where the generated files are read from and written to.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The files the generator reads and writes, see
+/// [`Builder::build_with_sink`](crate::Generator::build_with_sink).
+pub trait FileSink {
+    /// Returns `true` if there is a file at `path`.
+    fn exists(&self, path: &Path) -> bool;
+
+    /// Read the file at `path`.
+    fn read(&self, path: &Path) -> std::io::Result<String>;
+
+    /// Write `content` to the file at `path`, creating its parent directories
+    /// if necessary.
+    fn write(&mut self, path: &Path, content: &str) -> std::io::Result<()>;
+
+    /// Remove the file at `path`.
+    fn remove(&mut self, path: &Path) -> std::io::Result<()>;
+}
+
+/// A [`FileSink`] for the real filesystem, used by
+/// [`Builder::build`](crate::Generator::build).
+///
+/// Files are written atomically: the content is written to a hidden
+/// temporary file in the same directory, which is then renamed over the
+/// file. An interrupted build leaves the previous file in place instead of a
+/// truncated one that Godot would fail to load.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FsSink {
+    sync: bool,
+}
+
+impl FsSink {
+    /// A FsSink that does not wait for the files to reach the disk.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether every file is flushed to the disk before it replaces the
+    /// previous file, so the new content survives a crash of the system.
+    ///
+    /// Defaults to `false`.
+    pub fn with_sync(&mut self, sync: bool) {
+        self.sync = sync;
+    }
+
+    /// Set whether every file is flushed to the disk before it replaces the
+    /// previous file, so the new content survives a crash of the system.
+    ///
+    /// Defaults to `false`.
+    pub fn sync(mut self, sync: bool) -> Self {
+        self.with_sync(sync);
+        self
+    }
+}
+
+impl FileSink for FsSink {
+    fn exists(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn read(&self, path: &Path) -> std::io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    fn write(&mut self, path: &Path, content: &str) -> std::io::Result<()> {
+        write_atomic(path, content.as_bytes(), self.sync)
+    }
+
+    fn remove(&mut self, path: &Path) -> std::io::Result<()> {
+        std::fs::remove_file(path)
+    }
+}
+
+/// Write `content` to a temporary file next to `path` and rename it to
+/// `path`, creating the parent directories if necessary. With `sync` the
+/// file is flushed to the disk before it is renamed.
+pub(crate) fn write_atomic(path: &Path, content: &[u8], sync: bool) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(dir)?;
+
+    let file_name = path.file_name().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("`{}` is not a file path", path.display()),
+        )
+    })?;
+    // Hidden, so the Godot editor does not import it.
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp_path = dir.join(temp_name);
+
+    let result = std::fs::File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(content)?;
+            if sync {
+                file.sync_all()?;
+            }
+            Ok(())
+        })
+        .and_then(|_| std::fs::rename(&temp_path, path));
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result?;
+
+    // Make the rename itself durable.
+    #[cfg(unix)]
+    if sync {
+        std::fs::File::open(dir)?.sync_all()?;
+    }
+
+    Ok(())
+}
+
+/// A [`FileSink`] that keeps the files in memory, e.g. for tests.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemorySink {
+    files: BTreeMap<PathBuf, String>,
+}
+
+impl MemorySink {
+    /// An empty MemorySink.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file with `content` at `path`, replacing an existing one.
+    pub fn insert(&mut self, path: impl AsRef<Path>, content: impl Into<String>) {
+        self.files
+            .insert(path.as_ref().to_path_buf(), content.into());
+    }
+
+    /// The content of the file at `path`.
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<&str> {
+        self.files.get(path.as_ref()).map(String::as_str)
+    }
+
+    /// Iterate over the paths and contents of the files in order of their
+    /// paths.
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, &str)> + '_ {
+        self.files
+            .iter()
+            .map(|(path, content)| (path.as_path(), content.as_str()))
+    }
+
+    /// The number of files.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Returns `true` if there are no files.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+impl FileSink for MemorySink {
+    fn exists(&self, path: &Path) -> bool {
+        self.files.contains_key(path)
+    }
+
+    fn read(&self, path: &Path) -> std::io::Result<String> {
+        self.files.get(path).cloned().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("`{}` does not exist", path.display()),
+            )
+        })
+    }
+
+    fn write(&mut self, path: &Path, content: &str) -> std::io::Result<()> {
+        self.insert(path, content);
+        Ok(())
+    }
+
+    fn remove(&mut self, path: &Path) -> std::io::Result<()> {
+        self.files.remove(path).map(|_| ()).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("`{}` does not exist", path.display()),
+            )
+        })
+    }
+}
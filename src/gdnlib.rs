@@ -0,0 +1,319 @@
+//! The `.gdnlib` library resource as a typed document.
+//!
+//! A [`Gdnlib`] that was parsed keeps the document it was parsed from, like
+//! [`ConfigFile`] does, so writing it back only changes the values that were
+//! modified. Comments and the entries of platforms that are not known to this
+//! crate, e.g. `Haiku.64`, are kept as they are.
+//!
+//! ```
+//! use gdnative_project_utils::{Gdnlib, Platform};
+//!
+//! let mut lib = Gdnlib::parse(
+//!     "[entry]\n\
+//!      ; built by CI\n\
+//!      X11.64=\"res://bin/libgame.so\"\n\
+//!      Haiku.64=\"res://bin/haiku/libgame.so\"\n\
+//!      \n\
+//!      [general]\n\
+//!      \n\
+//!      singleton=false\n",
+//! )
+//! .unwrap();
+//!
+//! assert_eq!(lib.entries[&Platform::X11_64], "res://bin/libgame.so");
+//!
+//! lib.entries.insert(Platform::Windows64, "res://bin/game.dll".to_string());
+//! lib.singleton = true;
+//!
+//! assert_eq!(
+//!     lib.to_string(),
+//!     "[entry]\n\
+//!      ; built by CI\n\
+//!      X11.64=\"res://bin/libgame.so\"\n\
+//!      Haiku.64=\"res://bin/haiku/libgame.so\"\n\
+//!      Windows.64=\"res://bin/game.dll\"\n\
+//!      \n\
+//!      [general]\n\
+//!      \n\
+//!      singleton=true\n",
+//! );
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::global_classes::{Token, Tokens};
+use crate::godot_cfg::{quote, unquote, ConfigFile, ParseError};
+use crate::platform::Platform;
+
+const ENTRY: &str = "entry";
+const DEPENDENCIES: &str = "dependencies";
+const GENERAL: &str = "general";
+
+/// A `.gdnlib` library resource.
+#[derive(Clone, Debug)]
+pub struct Gdnlib {
+    /// The paths of the libraries of each platform, e.g.
+    /// `res://bin/libgame.so`, in the `[entry]` section.
+    pub entries: BTreeMap<Platform, String>,
+    /// The paths of the libraries each platform depends on, in the
+    /// `[dependencies]` section.
+    pub dependencies: BTreeMap<Platform, Vec<String>>,
+    /// Whether the library is loaded as a singleton.
+    pub singleton: bool,
+    /// Whether the library is only loaded once.
+    pub load_once: bool,
+    /// The prefix of the symbols Godot looks up in the library.
+    pub symbol_prefix: String,
+    /// Whether the library can be reloaded by the editor.
+    pub reloadable: bool,
+    /// The document the resource was parsed from.
+    document: Option<ConfigFile>,
+}
+
+impl Default for Gdnlib {
+    fn default() -> Self {
+        Gdnlib {
+            entries: BTreeMap::new(),
+            dependencies: BTreeMap::new(),
+            singleton: false,
+            load_once: true,
+            symbol_prefix: "godot_".to_string(),
+            reloadable: true,
+            document: None,
+        }
+    }
+}
+
+impl Gdnlib {
+    /// A library resource without entries and with Godot's default general
+    /// settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a `.gdnlib` library resource.
+    ///
+    /// Settings that are missing keep their defaults. Entries of unknown
+    /// platforms and unknown settings are not part of the model, but are
+    /// kept when the resource is written again.
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        let document = ConfigFile::parse(source)?;
+        let mut lib = Gdnlib::new();
+
+        for (key, value) in document.entries(ENTRY) {
+            if let Some(platform) = platform(key) {
+                let path = parse_string(value).ok_or_else(|| invalid(key))?;
+                lib.entries.insert(platform, path);
+            }
+        }
+
+        for (key, value) in document.entries(DEPENDENCIES) {
+            if let Some(platform) = platform(key) {
+                let libs = parse_string_array(value).ok_or_else(|| invalid(key))?;
+                lib.dependencies.insert(platform, libs);
+            }
+        }
+
+        let flag = |key| match document.get(GENERAL, key) {
+            Some("true") => Ok(Some(true)),
+            Some("false") => Ok(Some(false)),
+            Some(_) => Err(invalid(key)),
+            None => Ok(None),
+        };
+        lib.singleton = flag("singleton")?.unwrap_or(lib.singleton);
+        lib.load_once = flag("load_once")?.unwrap_or(lib.load_once);
+        lib.reloadable = flag("reloadable")?.unwrap_or(lib.reloadable);
+        if let Some(value) = document.get(GENERAL, "symbol_prefix") {
+            lib.symbol_prefix = parse_string(value).ok_or_else(|| invalid("symbol_prefix"))?;
+        }
+
+        lib.document = Some(document);
+        Ok(lib)
+    }
+
+    /// Add the entries and dependencies of `other`, replacing the ones of
+    /// the same platforms.
+    ///
+    /// The general settings are kept, since they are usually made in the
+    /// Godot editor.
+    pub fn merge(&mut self, other: Gdnlib) {
+        self.entries.extend(other.entries);
+        self.dependencies.extend(other.dependencies);
+    }
+
+    /// Write the model into the document it was parsed from.
+    fn update(&self, document: &mut ConfigFile) {
+        let stale = |section: &str, keep: &dyn Fn(Platform) -> bool| {
+            document
+                .entries(section)
+                .filter_map(|(key, _)| platform(key))
+                .filter(|&platform| !keep(platform))
+                .collect::<Vec<_>>()
+        };
+        let stale_entries = stale(ENTRY, &|platform| self.entries.contains_key(&platform));
+        let stale_dependencies = stale(DEPENDENCIES, &|platform| {
+            self.dependencies.contains_key(&platform)
+        });
+
+        for platform in stale_entries {
+            document.remove(ENTRY, platform.gdnlib_key());
+        }
+        for platform in stale_dependencies {
+            document.remove(DEPENDENCIES, platform.gdnlib_key());
+        }
+
+        for (platform, path) in &self.entries {
+            document.set(ENTRY, platform.gdnlib_key(), quote(path));
+        }
+        for (platform, libs) in &self.dependencies {
+            document.set(
+                DEPENDENCIES,
+                platform.gdnlib_key(),
+                dependency_array(Some(libs)),
+            );
+        }
+
+        // Settings that were left out are only added if they differ from
+        // their default.
+        let defaults = Gdnlib::new();
+        let general = [
+            (
+                "singleton",
+                self.singleton.to_string(),
+                defaults.singleton.to_string(),
+            ),
+            (
+                "load_once",
+                self.load_once.to_string(),
+                defaults.load_once.to_string(),
+            ),
+            (
+                "symbol_prefix",
+                quote(&self.symbol_prefix),
+                quote(&defaults.symbol_prefix),
+            ),
+            (
+                "reloadable",
+                self.reloadable.to_string(),
+                defaults.reloadable.to_string(),
+            ),
+        ];
+        for (key, value, default) in general {
+            if document.get(GENERAL, key).is_some() || value != default {
+                document.set(GENERAL, key, value);
+            }
+        }
+    }
+
+    /// Render the resource the way Godot writes new ones.
+    fn render(&self) -> String {
+        let mut content = String::from("[entry]\n");
+
+        for (platform, path) in &self.entries {
+            content.push_str(&format!("{}={}\n", platform.gdnlib_key(), quote(path)));
+        }
+
+        content.push_str("\n[dependencies]\n\n");
+
+        for platform in self.entries.keys() {
+            content.push_str(&format!(
+                "{}={}\n",
+                platform.gdnlib_key(),
+                dependency_array(self.dependencies.get(platform))
+            ));
+        }
+
+        content.push_str(&format!(
+            r#"
+[general]
+
+singleton={singleton}
+load_once={load_once}
+symbol_prefix={symbol_prefix}
+reloadable={reloadable}"#,
+            singleton = self.singleton,
+            load_once = self.load_once,
+            symbol_prefix = quote(&self.symbol_prefix),
+            reloadable = self.reloadable,
+        ));
+
+        content
+    }
+}
+
+impl std::str::FromStr for Gdnlib {
+    type Err = ParseError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Gdnlib::parse(source)
+    }
+}
+
+/// Serializes the resource. A parsed resource reproduces the lines of the
+/// values that were not modified verbatim.
+impl std::fmt::Display for Gdnlib {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.document {
+            Some(document) => {
+                let mut document = document.clone();
+                self.update(&mut document);
+                document.fmt(f)
+            }
+            None => f.write_str(&self.render()),
+        }
+    }
+}
+
+/// The dependencies of a platform as an array of the `.gdnlib` and `.tres`
+/// library resources, formatted like Godot does.
+pub(crate) fn dependency_array(libs: Option<&Vec<String>>) -> String {
+    match libs {
+        Some(libs) if !libs.is_empty() => format!(
+            "[ {} ]",
+            libs.iter()
+                .map(|lib| quote(lib))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        _ => "[  ]".to_string(),
+    }
+}
+
+fn platform(key: &str) -> Option<Platform> {
+    Platform::ALL
+        .iter()
+        .copied()
+        .find(|platform| platform.gdnlib_key() == key)
+}
+
+fn parse_string(value: &str) -> Option<String> {
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        Some(unquote(value))
+    } else {
+        None
+    }
+}
+
+/// Parse an array of strings, like `[ "res://a.so", "res://b.so" ]`.
+fn parse_string_array(value: &str) -> Option<Vec<String>> {
+    let mut tokens = Tokens::new(value);
+    let mut strings = vec![];
+
+    tokens.expect(Token::Punct('['))?;
+    loop {
+        match tokens.next()? {
+            Token::Punct(']') => break,
+            Token::Punct(',') => {}
+            Token::Str(string) => strings.push(string),
+            Token::Punct(_) => return None,
+        }
+    }
+
+    Some(strings)
+}
+
+fn invalid(key: &str) -> ParseError {
+    ParseError::InvalidValue {
+        key: key.to_string(),
+    }
+}
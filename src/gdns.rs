@@ -0,0 +1,222 @@
+//! The `.gdns` script resource as a typed document.
+//!
+//! Like [`Gdnlib`](crate::Gdnlib), a [`Gdns`] that was parsed keeps the
+//! resource it was parsed from, so writing it back only changes the values
+//! that were modified:
+//!
+//! ```
+//! use gdnative_project_utils::Gdns;
+//!
+//! let mut gdns = Gdns::parse(
+//!     r#"[gd_resource type="NativeScript" load_steps=3 format=2]
+//!
+//! [ext_resource path="res://native/game.gdnlib" type="GDNativeLibrary" id=1]
+//! [ext_resource path="res://icons/player.svg" type="Texture" id=2]
+//!
+//! [resource]
+//! class_name = "Player"
+//! library = ExtResource( 1 )
+//! script_class_icon = ExtResource( 2 )
+//! "#,
+//! )
+//! .unwrap();
+//!
+//! assert_eq!(gdns.class_name, "Player");
+//! assert_eq!(gdns.library, "res://native/game.gdnlib");
+//!
+//! // the library was moved
+//! gdns.library = "res://bin/game.gdnlib".to_string();
+//!
+//! assert!(gdns.to_string().contains(
+//!     r#"[ext_resource path="res://bin/game.gdnlib" type="GDNativeLibrary" id=1]"#
+//! ));
+//! ```
+
+use crate::godot_cfg::{quote, unquote, ParseError};
+use crate::godot_resource::TextResource;
+
+const RESOURCE: &str = "resource";
+
+/// A `.gdns` script resource.
+#[derive(Clone, Debug)]
+pub struct Gdns {
+    /// The name of the class in the library.
+    pub class_name: String,
+    /// The name the class is registered with in GDScript, if it is
+    /// registered.
+    pub script_class_name: Option<String>,
+    /// The path of the library resource, e.g. `res://native/game.gdnlib`.
+    pub library: String,
+    /// The other properties of the script resource, with their raw,
+    /// unparsed values, e.g. `("script_class_icon", "ExtResource( 2 )")`.
+    pub properties: Vec<(String, String)>,
+    /// The resource it was parsed from, and the id of the library in it.
+    document: Option<(TextResource, String)>,
+}
+
+impl Gdns {
+    /// A script resource for the class `class_name` in the library at
+    /// `library`, registered in GDScript with the same name.
+    pub fn new(class_name: impl Into<String>, library: impl Into<String>) -> Self {
+        let class_name = class_name.into();
+
+        Gdns {
+            script_class_name: Some(class_name.clone()),
+            class_name,
+            library: library.into(),
+            properties: vec![],
+            document: None,
+        }
+    }
+
+    /// Parse a `.gdns` script resource.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the resource can not be parsed, or if the class
+    /// name or the library is missing or has an invalid value.
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        let document = TextResource::parse(source)?;
+
+        let string = |key| {
+            document
+                .property(RESOURCE, key)
+                .map(|value| parse_string(value).ok_or_else(|| invalid(key)))
+                .transpose()
+        };
+        let class_name = string("class_name")?.ok_or_else(|| invalid("class_name"))?;
+        let script_class_name = string("script_class_name")?;
+
+        let library_id = document
+            .property(RESOURCE, "library")
+            .and_then(ext_resource_id)
+            .ok_or_else(|| invalid("library"))?;
+        let library = document
+            .ext_resources()
+            .find(|ext| ext.id == library_id)
+            .ok_or_else(|| invalid("library"))?
+            .path;
+
+        let properties = document
+            .properties(RESOURCE)
+            .filter(|(key, _)| !is_known(key))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+
+        Ok(Gdns {
+            class_name,
+            script_class_name,
+            library,
+            properties,
+            document: Some((document, library_id)),
+        })
+    }
+
+    /// Write the model into the resource it was parsed from.
+    fn update(&self, document: &mut TextResource, library_id: &str) {
+        document.set_ext_resource_path(library_id, &self.library);
+
+        document.set_property(RESOURCE, "class_name", quote(&self.class_name));
+        match &self.script_class_name {
+            Some(name) => {
+                document.set_property(RESOURCE, "script_class_name", quote(name));
+            }
+            None => {
+                document.remove_property(RESOURCE, "script_class_name");
+            }
+        }
+
+        let stale = document
+            .properties(RESOURCE)
+            .map(|(key, _)| key.to_string())
+            .filter(|key| !is_known(key) && !self.properties.iter().any(|(k, _)| k == key))
+            .collect::<Vec<_>>();
+        for key in stale {
+            document.remove_property(RESOURCE, &key);
+        }
+
+        for (key, value) in &self.properties {
+            document.set_property(RESOURCE, key, value.as_str());
+        }
+    }
+
+    /// Render the resource the way Godot writes new ones.
+    fn render(&self) -> String {
+        let mut content = format!(
+            r#"[gd_resource type="NativeScript" load_steps=2 format=2]
+
+[ext_resource path={library} type="GDNativeLibrary" id=1]
+
+[resource]
+class_name = {name}
+"#,
+            library = quote(&self.library),
+            name = quote(&self.class_name),
+        );
+
+        if let Some(name) = &self.script_class_name {
+            content.push_str(&format!("script_class_name = {}\n", quote(name)));
+        }
+        content.push_str("library = ExtResource( 1 )\n");
+        for (key, value) in &self.properties {
+            content.push_str(&format!("{} = {}\n", key, value));
+        }
+
+        content
+    }
+}
+
+impl std::str::FromStr for Gdns {
+    type Err = ParseError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Gdns::parse(source)
+    }
+}
+
+/// Serializes the resource. A parsed resource reproduces the lines of the
+/// values that were not modified verbatim.
+impl std::fmt::Display for Gdns {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.document {
+            Some((document, library_id)) => {
+                let mut document = document.clone();
+                self.update(&mut document, library_id);
+                document.fmt(f)
+            }
+            None => f.write_str(&self.render()),
+        }
+    }
+}
+
+/// Whether `key` is one of the properties that are part of the model.
+fn is_known(key: &str) -> bool {
+    matches!(key, "class_name" | "script_class_name" | "library")
+}
+
+fn parse_string(value: &str) -> Option<String> {
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        Some(unquote(value))
+    } else {
+        None
+    }
+}
+
+/// The id in `ExtResource( 1 )`, or in `ExtResource("1_x2k4d")` as written by
+/// Godot 4.
+fn ext_resource_id(value: &str) -> Option<String> {
+    let id = value
+        .strip_prefix("ExtResource")?
+        .trim_start()
+        .strip_prefix('(')?
+        .strip_suffix(')')?
+        .trim();
+
+    Some(unquote(id))
+}
+
+fn invalid(key: &str) -> ParseError {
+    ParseError::InvalidValue {
+        key: key.to_string(),
+    }
+}
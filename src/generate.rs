@@ -1,11 +1,63 @@
 use path_slash::PathExt;
-use std::path::{Path, PathBuf};
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use crate::class::{Class, Classes};
+use crate::file_sink::{FileSink, FsSink};
+use crate::gdns::Gdns;
+use crate::godot_cfg::{ConfigFile, ParseError};
+use crate::godot_version::GodotVersion;
+use crate::manifest::Manifest;
+use crate::platform::{Platform, Platforms};
+use crate::report::{CheckIssue, CheckReport, FileAction, Report};
+use crate::scan::{ScanError, Scanner};
+
+mod gdns;
+mod lib_resource;
+mod plan;
+mod staging;
+
+use self::gdns::{gdns_file_name, stub_file_name};
+pub use self::gdns::{render_gdns, GdnsConfig};
+pub use self::lib_resource::{render_gdnlib, GdnlibConfig};
+use self::plan::{plan_user_file, text_resource_files, Plan};
+use self::staging::{lipo, remove_old_versions, stage_artifact};
 
 /// Build mode of the crate
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum BuildMode {
     Debug,
     Release,
+    /// A custom cargo profile, whose artifacts are placed in
+    /// `target/{name}`.
+    Custom(String),
+}
+
+impl BuildMode {
+    /// The build mode for the cargo profile called `name`.
+    ///
+    /// The built-in profiles are mapped to the directory cargo uses for them,
+    /// so `dev` and `test` are [`BuildMode::Debug`] and `bench` is
+    /// [`BuildMode::Release`].
+    pub fn from_profile(name: &str) -> Self {
+        match name {
+            "debug" | "dev" | "test" => BuildMode::Debug,
+            "release" | "bench" => BuildMode::Release,
+            _ => BuildMode::Custom(name.to_string()),
+        }
+    }
+
+    /// The name of the directory inside the target directory that holds the
+    /// artifacts of this build mode.
+    pub fn dir_name(&self) -> &str {
+        match self {
+            BuildMode::Debug => "debug",
+            BuildMode::Release => "release",
+            BuildMode::Custom(name) => name,
+        }
+    }
 }
 
 /// The filetype of the GDNativeLibrary
@@ -15,16 +67,359 @@ pub enum LibFormat {
     Tres,
 }
 
+/// How the iOS libraries are packaged.
+#[derive(Copy, Clone, Debug)]
+pub enum IosLibLayout {
+    /// One static library per architecture, `lib{name}.a` inside the target
+    /// triple directories.
+    StaticLib,
+    /// One static library per architecture built for the simulator:
+    /// `iOS.arm64` points at the `aarch64-apple-ios-sim` build instead of the
+    /// device build. `iOS.armv7` has no simulator and keeps the device build.
+    Simulator,
+    /// A single `{name}.xcframework` bundle inside the build mode directory,
+    /// shared by all architectures.
+    XcFramework,
+    /// Like [`IosLibLayout::XcFramework`], but the bundle is created with
+    /// `xcodebuild -create-xcframework` from the `aarch64-apple-ios` build
+    /// and the `aarch64-apple-ios-sim` and `x86_64-apple-ios` simulator
+    /// builds that exist, when the resources are generated.
+    XcFrameworkCreate,
+}
+
+/// Where the Android libraries are found.
+#[derive(Clone, Debug)]
+pub enum AndroidLibLayout {
+    /// `lib{name}.so` inside the target triple directories, as built by
+    /// `cargo build --target aarch64-linux-android`.
+    TargetTriple,
+    /// `lib{name}.so` inside one directory per ABI, like
+    /// `jniLibs/arm64-v8a`, as written by `cargo ndk -o jniLibs`.
+    ///
+    /// A relative path is resolved from the Godot project directory.
+    AbiDirectories(PathBuf),
+}
+
+/// How the macOS libraries are packaged.
+#[derive(Copy, Clone, Debug)]
+pub enum MacosLibLayout {
+    /// One dynamic library per architecture: `OSX.64` points at the host
+    /// build and `OSX.arm64` at the `aarch64-apple-darwin` build.
+    PerArchitecture,
+    /// A single universal `lib{name}.dylib` inside
+    /// `universal2-apple-darwin/{mode}`, shared by all architectures, as
+    /// created by e.g. `cargo zigbuild --target universal2-apple-darwin`.
+    Universal,
+    /// Like [`MacosLibLayout::Universal`], but the universal library is
+    /// created with `lipo` from the `x86_64-apple-darwin` and
+    /// `aarch64-apple-darwin` builds when the resources are generated.
+    UniversalLipo,
+}
+
+/// Where [`Builder::repair`] looks for `.gdns` files.
+#[derive(Copy, Clone, Debug)]
+pub enum RepairScope {
+    /// Only the resource output directory.
+    OutputDir,
+    /// The whole Godot project, except for hidden directories and the target
+    /// directory.
+    Project,
+}
+
+/// What to do when a file that would be generated already exists.
+#[derive(Copy, Clone, Debug)]
+pub enum OverwritePolicy {
+    /// Never touch existing files.
+    Never,
+    /// Overwrite files that were created by the generator and have not been
+    /// modified since, according to the content hash in the manifest.
+    /// Hand-edited and hand-made files are left untouched.
+    IfGeneratedByUs,
+    /// Overwrite every file whose content differs from the generated content.
+    IfContentDiffers,
+}
+
+/// Where the `.gdns` files are placed inside the resource output directory.
+#[derive(Copy, Clone, Debug)]
+pub enum OutputLayout {
+    /// All files directly inside the resource output directory.
+    Flat,
+    /// One subdirectory per Rust module, e.g. `enemies/Slime.gdns` for
+    /// `crate::enemies::Slime`.
+    ModuleTree,
+}
+
+/// How the file names of `.gdns` files are derived from class names.
+#[derive(Clone)]
+pub enum NamingConvention {
+    /// The class name as is, e.g. `PlayerController.gdns`.
+    PascalCase,
+    /// The class name in snake case, e.g. `player_controller.gdns`.
+    SnakeCase,
+    /// Another convention with a prefix and suffix added, see
+    /// [`with_prefix`](Self::with_prefix) and
+    /// [`with_suffix`](Self::with_suffix).
+    Affixed {
+        convention: Box<NamingConvention>,
+        prefix: String,
+        suffix: String,
+    },
+    /// A function from the class name to the file name, without the `.gdns`
+    /// extension, see [`custom`](Self::custom).
+    Custom(Arc<dyn Fn(&str) -> String + Send + Sync>),
+}
+
+impl NamingConvention {
+    /// A function from the class name to the file name, without the `.gdns`
+    /// extension.
+    pub fn custom(f: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        NamingConvention::Custom(Arc::new(f))
+    }
+
+    /// Put `prefix` in front of every file name.
+    ///
+    /// ```
+    /// # use gdnative_project_utils::NamingConvention;
+    /// let naming = NamingConvention::SnakeCase.with_prefix("rs_");
+    /// assert_eq!(naming.file_stem("PlayerController"), "rs_player_controller");
+    /// ```
+    pub fn with_prefix(self, prefix: impl Into<String>) -> Self {
+        match self {
+            NamingConvention::Affixed {
+                convention, suffix, ..
+            } => NamingConvention::Affixed {
+                convention,
+                prefix: prefix.into(),
+                suffix,
+            },
+            convention => NamingConvention::Affixed {
+                convention: Box::new(convention),
+                prefix: prefix.into(),
+                suffix: String::new(),
+            },
+        }
+    }
+
+    /// Put `suffix` after every file name, before the extension.
+    pub fn with_suffix(self, suffix: impl Into<String>) -> Self {
+        match self {
+            NamingConvention::Affixed {
+                convention, prefix, ..
+            } => NamingConvention::Affixed {
+                convention,
+                prefix,
+                suffix: suffix.into(),
+            },
+            convention => NamingConvention::Affixed {
+                convention: Box::new(convention),
+                prefix: String::new(),
+                suffix: suffix.into(),
+            },
+        }
+    }
+
+    /// The file name for the class `class_name`, without the extension.
+    pub fn file_stem(&self, class_name: &str) -> String {
+        match self {
+            NamingConvention::PascalCase => class_name.to_string(),
+            NamingConvention::SnakeCase => snake_case(class_name),
+            NamingConvention::Affixed {
+                convention,
+                prefix,
+                suffix,
+            } => format!("{}{}{}", prefix, convention.file_stem(class_name), suffix),
+            NamingConvention::Custom(f) => f(class_name),
+        }
+    }
+}
+
+impl std::fmt::Debug for NamingConvention {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NamingConvention::PascalCase => f.write_str("PascalCase"),
+            NamingConvention::SnakeCase => f.write_str("SnakeCase"),
+            NamingConvention::Affixed {
+                convention,
+                prefix,
+                suffix,
+            } => f
+                .debug_struct("Affixed")
+                .field("convention", convention)
+                .field("prefix", prefix)
+                .field("suffix", suffix)
+                .finish(),
+            NamingConvention::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// How built libraries are placed into the staging directory.
+#[derive(Copy, Clone, Debug)]
+pub enum StagingMode {
+    /// Copy the libraries.
+    Copy,
+    /// Symlink the libraries, so a rebuilt library is visible to a running
+    /// Godot editor without a copy step. Falls back to copying where symlinks
+    /// can not be created, e.g. on Windows without the required privilege.
+    Symlink,
+    /// Copy the libraries under a name that includes a hash of their
+    /// modification time and size, e.g. `my_crate_0123456789abcdef.dll`, and
+    /// remove the copies of previous builds.
+    ///
+    /// The Godot editor keeps a loaded library locked on Windows, so a
+    /// rebuilt library can not be copied over it. With a new name for every
+    /// build, the library resource changes and Godot reloads the library
+    /// without restarting the editor. Copies that are still locked are
+    /// removed by a later build.
+    ///
+    /// Unless an [`OverwritePolicy`] is set, this uses
+    /// [`OverwritePolicy::IfGeneratedByUs`], so the library resource is
+    /// updated with the new names.
+    Versioned,
+}
+
+/// The kind of native library resource to generate.
+#[derive(Copy, Clone, Debug)]
+pub enum OutputFormat {
+    /// Godot 3 GDNative: a `GDNativeLibrary` resource plus one `.gdns`
+    /// NativeScript resource per class.
+    Gdnative,
+    /// Godot 4 GDExtension: a single `.gdextension` file. Classes are
+    /// registered by the library itself, so no script resources are created.
+    GdExtension,
+    /// Both, for a crate that targets Godot 3 and Godot 4: the GDNative
+    /// library resource and script resources, and next to them a
+    /// `.gdextension` file pointing at the same libraries.
+    Dual,
+}
+
+/// How the paths of the libraries and the library resource are written into
+/// the generated resources.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PathStyle {
+    /// A `res://` path for files inside the Godot project, an absolute path
+    /// otherwise.
+    Auto,
+    /// Always a `res://` path. Generating fails for files outside the Godot
+    /// project.
+    ForceRes,
+    /// Always an absolute path, even for files inside the Godot project.
+    ForceAbsolute,
+}
+
+/// How the directories given to the generator are resolved before they are
+/// compared.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PathResolution {
+    /// Resolve symlinks, so every directory is compared by its real
+    /// location.
+    Canonical,
+    /// Only make the paths absolute and remove `.` and `..` components,
+    /// keeping symlinks. A target directory that is a symlink inside the
+    /// Godot project is inside the project, wherever it points to.
+    Logical,
+}
+
+impl PathResolution {
+    /// Resolve `path`, which does not need to exist yet as long as one of
+    /// its ancestors does.
+    fn resolve(self, path: PathBuf) -> Result<PathBuf, GenerateError> {
+        match self {
+            PathResolution::Canonical => resolve(path),
+            PathResolution::Logical => {
+                resolve(path.clone())?;
+                absolute(&path)
+            }
+        }
+    }
+
+    /// Resolve `path`, which has to exist.
+    fn canonicalize(self, path: PathBuf) -> Result<PathBuf, GenerateError> {
+        match self {
+            PathResolution::Canonical => canonicalize(path),
+            PathResolution::Logical => {
+                canonicalize(path.clone())?;
+                absolute(&path)
+            }
+        }
+    }
+}
+
+/// A callback of [`Builder::on_file_written`].
+type FileWrittenHook = Arc<dyn Fn(&Path, FileAction) + Send + Sync>;
+
+/// A callback of [`Builder::on_skip`].
+type SkipHook = Arc<dyn Fn(&Path) + Send + Sync>;
+
+/// A filter of [`Builder::filter_classes`].
+type ClassFilter = Arc<dyn Fn(&Class) -> bool + Send + Sync>;
+
 /// A builder type that holds all necessary information about the project to
 /// generate files in all the right places.
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct Builder {
     godot_project_dir: Option<PathBuf>,
+    auto_detect_project: bool,
     godot_resource_output_dir: Option<PathBuf>,
+    crate_dir: Option<PathBuf>,
+    #[cfg(feature = "cargo_metadata")]
+    cargo_metadata: bool,
     target_dir: Option<PathBuf>,
+    /// Whether `target_dir` was found by [`Builder::finish`] and is used as
+    /// it is, since cargo may not have created it yet.
+    target_dir_resolved: bool,
+    target_triple: Option<String>,
     lib_name: Option<String>,
     build_mode: Option<BuildMode>,
     lib_format: Option<LibFormat>,
+    output_format: Option<OutputFormat>,
+    godot_version: Option<GodotVersion>,
+    entry_symbol: Option<String>,
+    compatibility_minimum: Option<String>,
+    ios_lib_layout: Option<IosLibLayout>,
+    android_lib_layout: Option<AndroidLibLayout>,
+    android_abis: Option<Platforms>,
+    macos_lib_layout: Option<MacosLibLayout>,
+    platforms: Option<Platforms>,
+    html5: Option<bool>,
+    general: GeneralOverrides,
+    general_for: Vec<(BuildMode, GeneralOverrides)>,
+    gdnlib_template: Option<String>,
+    gdns_template: Option<String>,
+    update_existing_lib: bool,
+    prune_stale: bool,
+    layout: Option<OutputLayout>,
+    naming: Option<NamingConvention>,
+    /// Per-class files of other libraries generated into the same directory,
+    /// which are never pruned.
+    other_class_files: HashSet<String>,
+    gdscript_stubs: bool,
+    scene_templates: bool,
+    editor_plugin: Option<String>,
+    class_icons: BTreeMap<String, PathBuf>,
+    global_script_classes: bool,
+    migrate_renames: bool,
+    update_export_presets: bool,
+    gdignore: bool,
+    backups: Option<bool>,
+    sync_writes: bool,
+    overwrite_policy: Option<OverwritePolicy>,
+    platform_build_modes: BTreeMap<Platform, BuildMode>,
+    binary_overrides: BTreeMap<Platform, PathBuf>,
+    dependencies: BTreeMap<Platform, Vec<PathBuf>>,
+    path_style: Option<PathStyle>,
+    path_resolution: Option<PathResolution>,
+    staging_dir: Option<PathBuf>,
+    staging_mode: Option<StagingMode>,
+    include_debug_symbols: bool,
+    stage_dependencies: bool,
+    host_only: bool,
+    installed_targets: Option<InstalledTargets>,
+    on_file_written: Vec<FileWrittenHook>,
+    on_skip: Vec<SkipHook>,
+    class_filters: Vec<ClassFilter>,
+    include_classes: Vec<String>,
+    exclude_classes: Vec<String>,
 }
 
 impl Builder {
@@ -33,19 +428,46 @@ impl Builder {
         Self::default()
     }
 
-    /// **REQUIRED** Set the path to the root of the Godot project.
+    /// **REQUIRED** Set the path to the root of the Godot project, unless it
+    /// is found with [`auto_detect_project`](Self::auto_detect_project).
     pub fn with_godot_project_dir(&mut self, dir: impl AsRef<Path>) {
         let dir = dir.as_ref().to_path_buf();
 
         self.godot_project_dir = Some(dir);
     }
 
-    /// **REQUIRED** Set the path to the root of the Godot project.
+    /// **REQUIRED** Set the path to the root of the Godot project, unless it
+    /// is found with [`auto_detect_project`](Self::auto_detect_project).
     pub fn godot_project_dir(mut self, dir: impl AsRef<Path>) -> Self {
         self.with_godot_project_dir(dir);
         self
     }
 
+    /// Set whether the Godot project directory is searched for if it is not
+    /// given.
+    ///
+    /// The nearest ancestor of the crate directory with a `project.godot`
+    /// file is used, or else the only directory one or two levels below the
+    /// crate directory or its parent that has one, e.g. `../godot`. If
+    /// several directories are found, generating fails with
+    /// [`GenerateError::AmbiguousProject`].
+    pub fn with_auto_detect_project(&mut self, detect: bool) {
+        self.auto_detect_project = detect;
+    }
+
+    /// Set whether the Godot project directory is searched for if it is not
+    /// given.
+    ///
+    /// The nearest ancestor of the crate directory with a `project.godot`
+    /// file is used, or else the only directory one or two levels below the
+    /// crate directory or its parent that has one, e.g. `../godot`. If
+    /// several directories are found, generating fails with
+    /// [`GenerateError::AmbiguousProject`].
+    pub fn auto_detect_project(mut self, detect: bool) -> Self {
+        self.with_auto_detect_project(detect);
+        self
+    }
+
     /// Set the path to the directory inside the Godot project to which the
     /// generates files should be saved.
     pub fn with_godot_resource_output_dir(&mut self, dir: impl AsRef<Path>) {
@@ -61,21 +483,99 @@ impl Builder {
         self
     }
 
+    /// Set the path to the root of the crate, the directory containing its
+    /// `Cargo.toml`.
+    ///
+    /// The target directory and target triple configured in the
+    /// `.cargo/config.toml` files of the crate and its workspace are used
+    /// when they are not set otherwise. Defaults to `CARGO_MANIFEST_DIR`.
+    pub fn with_crate_dir(&mut self, dir: impl AsRef<Path>) {
+        self.crate_dir = Some(dir.as_ref().to_path_buf());
+    }
+
+    /// Set the path to the root of the crate, the directory containing its
+    /// `Cargo.toml`.
+    ///
+    /// The target directory and target triple configured in the
+    /// `.cargo/config.toml` files of the crate and its workspace are used
+    /// when they are not set otherwise. Defaults to `CARGO_MANIFEST_DIR`.
+    pub fn crate_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.with_crate_dir(dir);
+        self
+    }
+
+    /// Set whether `cargo metadata` is run for the crate to find its library
+    /// name and target directory, and to verify it builds a `cdylib`.
+    ///
+    /// This is more reliable than guessing from the environment, but runs
+    /// cargo. Explicitly set values still take precedence. Defaults to
+    /// `false`.
+    #[cfg(feature = "cargo_metadata")]
+    pub fn with_cargo_metadata(&mut self, enabled: bool) {
+        self.cargo_metadata = enabled;
+    }
+
+    /// Set whether `cargo metadata` is run for the crate to find its library
+    /// name and target directory, and to verify it builds a `cdylib`.
+    ///
+    /// This is more reliable than guessing from the environment, but runs
+    /// cargo. Explicitly set values still take precedence. Defaults to
+    /// `false`.
+    #[cfg(feature = "cargo_metadata")]
+    pub fn cargo_metadata(mut self, enabled: bool) -> Self {
+        self.with_cargo_metadata(enabled);
+        self
+    }
+
     /// Set the path to the `target` directory in which cargo creates build
     /// artefacts.
+    ///
+    /// Defaults to `CARGO_TARGET_DIR`, the `build.target-dir` of cargo's
+    /// configuration, or the target directory the build script runs in.
+    /// Outside of a build script, the first `target` directory found in the
+    /// crate directory or one of its ancestors is used.
     pub fn with_target_dir(&mut self, dir: impl AsRef<Path>) {
         let dir = dir.as_ref().to_path_buf();
 
         self.target_dir = Some(dir);
+        self.target_dir_resolved = false;
     }
 
     /// Set the path to the `target` directory in which cargo creates build
     /// artefacts.
+    ///
+    /// Defaults to `CARGO_TARGET_DIR`, the `build.target-dir` of cargo's
+    /// configuration, or the target directory the build script runs in.
+    /// Outside of a build script, the first `target` directory found in the
+    /// crate directory or one of its ancestors is used.
     pub fn target_dir(mut self, dir: impl AsRef<Path>) -> Self {
         self.with_target_dir(dir);
         self
     }
 
+    /// Set the target triple the crate is built for with `cargo build
+    /// --target`, whose artifacts cargo places in `target/{triple}/{mode}`.
+    ///
+    /// The entry of the platform matching the triple points into that
+    /// directory, the entries of all other platforms are unchanged. Defaults
+    /// to the `CARGO_BUILD_TARGET` environment variable or the `build.target`
+    /// setting of cargo's configuration, if either is set.
+    pub fn with_target_triple(&mut self, triple: impl AsRef<str>) {
+        self.target_triple = Some(triple.as_ref().to_string());
+    }
+
+    /// Set the target triple the crate is built for with `cargo build
+    /// --target`, whose artifacts cargo places in `target/{triple}/{mode}`.
+    ///
+    /// The entry of the platform matching the triple points into that
+    /// directory, the entries of all other platforms are unchanged. Defaults
+    /// to the `CARGO_BUILD_TARGET` environment variable or the `build.target`
+    /// setting of cargo's configuration, if either is set.
+    pub fn target_triple(mut self, triple: impl AsRef<str>) -> Self {
+        self.with_target_triple(triple);
+        self
+    }
+
     /// Set the type of the GDNativeLibrary Format
     pub fn with_lib_format(&mut self, lib_format: LibFormat) {
         self.lib_format = Some(lib_format);
@@ -87,276 +587,2312 @@ impl Builder {
         self
     }
 
-    /// Set the name of the crate.
-    pub fn with_lib_name(&mut self, name: impl AsRef<str>) {
-        let name = name.as_ref().to_string();
+    /// Set the build mode of the crate for some `platforms`, overriding the
+    /// general [`build_mode`](Self::build_mode) for their entries.
+    pub fn with_build_mode_for(&mut self, platforms: impl Into<Platforms>, mode: BuildMode) {
+        for platform in platforms.into() {
+            self.platform_build_modes.insert(platform, mode.clone());
+        }
+    }
 
-        self.lib_name = Some(name);
+    /// Set the build mode of the crate for some `platforms`, overriding the
+    /// general [`build_mode`](Self::build_mode) for their entries.
+    ///
+    /// ```
+    /// # use gdnative_project_utils::*;
+    /// // iterate with debug builds on desktop, but ship release builds to Android
+    /// let generator = Generator::new()
+    ///     .build_mode(BuildMode::Debug)
+    ///     .build_mode_for(Platforms::android(), BuildMode::Release);
+    /// ```
+    pub fn build_mode_for(mut self, platforms: impl Into<Platforms>, mode: BuildMode) -> Self {
+        self.with_build_mode_for(platforms, mode);
+        self
     }
 
-    /// Set the name of the crate.
-    pub fn lib_name(mut self, name: impl AsRef<str>) -> Self {
-        self.with_lib_name(name);
+    /// Point the entry of `platform` at the library at `path` instead of the
+    /// one in the target directory, selecting `platform` if it is not
+    /// selected already.
+    ///
+    /// Relative paths are resolved from the Godot project directory. The
+    /// entries of all other platforms are still generated as usual.
+    pub fn with_override_binary(&mut self, platform: Platform, path: impl AsRef<Path>) {
+        self.binary_overrides
+            .insert(platform, path.as_ref().to_path_buf());
+    }
+
+    /// Point the entry of `platform` at the library at `path` instead of the
+    /// one in the target directory, selecting `platform` if it is not
+    /// selected already.
+    ///
+    /// Relative paths are resolved from the Godot project directory. The
+    /// entries of all other platforms are still generated as usual.
+    ///
+    /// ```
+    /// # use gdnative_project_utils::*;
+    /// // use a prebuilt library from a vendor directory inside the project
+    /// let generator = Generator::new()
+    ///     .platforms(Platforms::desktop())
+    ///     .override_binary(Platform::X11Arm64, "vendor/linux-arm64/libgame.so");
+    /// ```
+    pub fn override_binary(mut self, platform: Platform, path: impl AsRef<Path>) -> Self {
+        self.with_override_binary(platform, path);
         self
     }
 
-    /// Set the build mode of the crate.
+    /// Add the library at `path` to the dependencies of `platform`, which
+    /// Godot loads before the library itself, e.g. a third-party library the
+    /// crate links against.
     ///
-    /// This will affect the path the `gdnlib` resource points to.
-    pub fn with_build_mode(&mut self, mode: BuildMode) {
-        self.build_mode = Some(mode);
+    /// Relative paths are resolved from the Godot project directory. The
+    /// dependencies are written into the `[dependencies]` section of the
+    /// library resource, and are ignored for platforms that are not selected.
+    /// Dependencies outside the Godot project can be copied into it with
+    /// [`stage_dependencies`](Self::stage_dependencies).
+    pub fn with_dependency(&mut self, platform: Platform, path: impl AsRef<Path>) {
+        self.dependencies
+            .entry(platform)
+            .or_default()
+            .push(path.as_ref().to_path_buf());
     }
 
-    /// Set the build mode of the crate.
+    /// Add the library at `path` to the dependencies of `platform`, which
+    /// Godot loads before the library itself, e.g. a third-party library the
+    /// crate links against.
     ///
-    /// This will affect the path the `gdnlib` resource points to.
-    pub fn build_mode(mut self, mode: BuildMode) -> Self {
-        self.with_build_mode(mode);
+    /// Relative paths are resolved from the Godot project directory. The
+    /// dependencies are written into the `[dependencies]` section of the
+    /// library resource, and are ignored for platforms that are not selected.
+    /// Dependencies outside the Godot project can be copied into it with
+    /// [`stage_dependencies`](Self::stage_dependencies).
+    ///
+    /// ```
+    /// # use gdnative_project_utils::*;
+    /// let generator = Generator::new()
+    ///     .dependency(Platform::X11_64, "bin/linux/libsteam_api.so")
+    ///     .dependency(Platform::Windows64, "bin/windows/steam_api64.dll");
+    /// ```
+    pub fn dependency(mut self, platform: Platform, path: impl AsRef<Path>) -> Self {
+        self.with_dependency(platform, path);
         self
     }
 
-    /// Build and generate files for the crate and all `classes`.
-    ///
-    /// # Panics
-    ///
-    /// This function panics if the `godot_project_dir` has not been set.
-    pub fn build(self, classes: crate::scan::Classes) -> Result<(), std::io::Error> {
-        let lib_name = self
-            .lib_name
-            .or_else(|| std::env::var("CARGO_PKG_NAME").ok())
-            .expect("Package name not given and unable to find");
-        let godot_project_dir = self
-            .godot_project_dir
-            .and_then(|path| dunce::canonicalize(path).ok())
-            .expect("Godot project dir not given");
-        let godot_resource_output_dir = self
-            .godot_resource_output_dir
-            .or_else(|| Some(godot_project_dir.join("native")))
-            .and_then(|path| std::fs::create_dir_all(&path).ok().map(|_| path))
-            .and_then(|path| dunce::canonicalize(path).ok())
-            .expect("Unable to create godot_resource_output_dir");
-        let target_dir = self
-            .target_dir
-            .and_then(|path| dunce::canonicalize(path).ok())
-            .or_else(|| {
-                let dir = std::env::var("CARGO_TARGET_DIR").ok()?;
-                dunce::canonicalize(PathBuf::from(dir)).ok()
-            })
-            .or_else(|| {
-                let dir = std::env::var("OUT_DIR").ok()?;
-                let out_path = PathBuf::from(&dir);
+    /// Set whether the library entries and the path of the library resource
+    /// are written as `res://` or absolute paths.
+    ///
+    /// Defaults to [`PathStyle::Auto`].
+    pub fn with_path_style(&mut self, style: PathStyle) {
+        self.path_style = Some(style);
+    }
 
-                // target/{debug/release}/build/{crate}/out
-                dunce::canonicalize(out_path.join("../../../../")).ok()
-            })
-            .expect("Target dir not given and unable to find");
-        let build_mode = self
-            .build_mode
-            .or_else(|| {
-                let profile = std::env::var("PROFILE").ok()?;
-                match profile.as_str() {
-                    "release" => Some(BuildMode::Release),
-                    "debug" => Some(BuildMode::Debug),
-                    _ => None,
-                }
-            })
-            .expect("Build mode not given and unable to find");
+    /// Set whether the library entries and the path of the library resource
+    /// are written as `res://` or absolute paths.
+    ///
+    /// Defaults to [`PathStyle::Auto`].
+    pub fn path_style(mut self, style: PathStyle) -> Self {
+        self.with_path_style(style);
+        self
+    }
 
-        let lib_ext = match self.lib_format {
-            Some(LibFormat::Gdnlib) | None => "gdnlib",
-            Some(LibFormat::Tres) => "tres",
-        };
-        let gdnlib_path = godot_resource_output_dir.join(format!("{}.{}", lib_name, lib_ext));
+    /// Set how the project, resource output, target and staging directories
+    /// are resolved before deciding which files are inside the Godot
+    /// project.
+    ///
+    /// With [`PathResolution::Logical`], the layout as it is seen through
+    /// symlinks decides, so a target directory that is a symlink inside the
+    /// project, e.g. into a ramdisk, gets `res://` library entries instead of
+    /// absolute paths that only work on one machine. The paths in the
+    /// [`Report`] are the logical paths as well.
+    ///
+    /// Defaults to [`PathResolution::Canonical`].
+    pub fn with_path_resolution(&mut self, resolution: PathResolution) {
+        self.path_resolution = Some(resolution);
+    }
 
-        {
-            let target_base_path = target_dir;
+    /// Set how the project, resource output, target and staging directories
+    /// are resolved before deciding which files are inside the Godot
+    /// project.
+    ///
+    /// With [`PathResolution::Logical`], the layout as it is seen through
+    /// symlinks decides, so a target directory that is a symlink inside the
+    /// project, e.g. into a ramdisk, gets `res://` library entries instead of
+    /// absolute paths that only work on one machine. The paths in the
+    /// [`Report`] are the logical paths as well.
+    ///
+    /// Defaults to [`PathResolution::Canonical`].
+    pub fn path_resolution(mut self, resolution: PathResolution) -> Self {
+        self.with_path_resolution(resolution);
+        self
+    }
 
-            let target_rel_path = pathdiff::diff_paths(&target_base_path, &godot_project_dir)
-                .expect("Unable to create relative path between Godot project and library output");
+    /// Copy the built libraries into `dir` and point the library entries at
+    /// the copies instead of the cargo target directory.
+    ///
+    /// Relative paths are resolved from the Godot project directory. Every
+    /// platform gets its own subdirectory, named after its lower-cased
+    /// `.gdnlib` key, e.g. `native/bin/x11.64/libmy_crate.so`. Libraries that
+    /// have not been built are skipped. Since the paths no longer depend on
+    /// the build mode, the library resource stays the same between debug and
+    /// release builds, and exports are self-contained.
+    pub fn with_stage_artifacts(&mut self, dir: impl AsRef<Path>) {
+        self.staging_dir = Some(dir.as_ref().to_path_buf());
+    }
 
-            let prefix;
-            let output_path;
+    /// Copy the built libraries into `dir` and point the library entries at
+    /// the copies instead of the cargo target directory.
+    ///
+    /// Relative paths are resolved from the Godot project directory. Every
+    /// platform gets its own subdirectory, named after its lower-cased
+    /// `.gdnlib` key, e.g. `native/bin/x11.64/libmy_crate.so`. Libraries that
+    /// have not been built are skipped. Since the paths no longer depend on
+    /// the build mode, the library resource stays the same between debug and
+    /// release builds, and exports are self-contained.
+    pub fn stage_artifacts(mut self, dir: impl AsRef<Path>) -> Self {
+        self.with_stage_artifacts(dir);
+        self
+    }
 
-            if target_rel_path.starts_with("../") {
-                // not in the project folder, use an absolute path
-                prefix = "";
-                output_path = target_base_path;
-            } else {
-                // output paths are inside the project folder, use a `res://` path
-                prefix = "res://";
-                output_path = target_rel_path;
-            };
+    /// Set how libraries are placed into the staging directory.
+    ///
+    /// Defaults to [`StagingMode::Copy`]. Only used together with
+    /// [`stage_artifacts`](Self::stage_artifacts).
+    pub fn with_staging_mode(&mut self, mode: StagingMode) {
+        self.staging_mode = Some(mode);
+    }
+
+    /// Set how libraries are placed into the staging directory.
+    ///
+    /// Defaults to [`StagingMode::Copy`]. Only used together with
+    /// [`stage_artifacts`](Self::stage_artifacts).
+    pub fn staging_mode(mut self, mode: StagingMode) -> Self {
+        self.with_staging_mode(mode);
+        self
+    }
+
+    /// Set whether the debug symbols of the libraries are staged with them.
+    ///
+    /// These are the `.pdb` files of Windows libraries and the `.dSYM`
+    /// bundles of macOS libraries, which crash dumps need to be
+    /// symbolicated. Symbols that have not been built are skipped. Defaults
+    /// to `false`. Only used together with
+    /// [`stage_artifacts`](Self::stage_artifacts).
+    pub fn with_include_debug_symbols(&mut self, include: bool) {
+        self.include_debug_symbols = include;
+    }
 
-            let binaries = common_binary_outputs(&output_path, build_mode, &lib_name);
+    /// Set whether the debug symbols of the libraries are staged with them.
+    ///
+    /// These are the `.pdb` files of Windows libraries and the `.dSYM`
+    /// bundles of macOS libraries, which crash dumps need to be
+    /// symbolicated. Symbols that have not been built are skipped. Defaults
+    /// to `false`. Only used together with
+    /// [`stage_artifacts`](Self::stage_artifacts).
+    pub fn include_debug_symbols(mut self, include: bool) -> Self {
+        self.with_include_debug_symbols(include);
+        self
+    }
 
-            let file_exists = gdnlib_path.exists() && gdnlib_path.is_file();
+    /// Set whether the [dependencies](Self::dependency) are staged with the
+    /// libraries.
+    ///
+    /// Each dependency is copied or linked next to the library of its
+    /// platform, keeping its file name, and the `[dependencies]` section
+    /// points at the copy, so editor runs and exports find it inside the
+    /// project. Defaults to `false`. Only used together with
+    /// [`stage_artifacts`](Self::stage_artifacts).
+    pub fn with_stage_dependencies(&mut self, stage: bool) {
+        self.stage_dependencies = stage;
+    }
 
-            if !file_exists {
-                let content = match self.lib_format {
-                    Some(LibFormat::Gdnlib) | None => generate_gdnlib(prefix, binaries),
-                    Some(LibFormat::Tres) => generate_tres(prefix, binaries),
-                };
-                std::fs::write(&gdnlib_path, content)?;
-            }
-        }
+    /// Set whether the [dependencies](Self::dependency) are staged with the
+    /// libraries.
+    ///
+    /// Each dependency is copied or linked next to the library of its
+    /// platform, keeping its file name, and the `[dependencies]` section
+    /// points at the copy, so editor runs and exports find it inside the
+    /// project. Defaults to `false`. Only used together with
+    /// [`stage_artifacts`](Self::stage_artifacts).
+    pub fn stage_dependencies(mut self, stage: bool) -> Self {
+        self.with_stage_dependencies(stage);
+        self
+    }
+
+    /// Set the kind of resources to generate.
+    ///
+    /// Defaults to the format the Godot version loads, see
+    /// [`godot_version`](Self::godot_version), or to [`OutputFormat::Gdnative`]
+    /// if the version is unknown.
+    pub fn with_output_format(&mut self, format: OutputFormat) {
+        self.output_format = Some(format);
+    }
 
-        let rel_gdnlib_path = pathdiff::diff_paths(&gdnlib_path, &godot_project_dir)
-            .expect("Unable to create relative path between Godot project and library output");
+    /// Set the kind of resources to generate.
+    ///
+    /// Defaults to the format the Godot version loads, see
+    /// [`godot_version`](Self::godot_version), or to [`OutputFormat::Gdnative`]
+    /// if the version is unknown.
+    pub fn output_format(mut self, format: OutputFormat) -> Self {
+        self.with_output_format(format);
+        self
+    }
 
-        let prefix;
-        let output_path;
+    /// Set the Godot version of the project.
+    ///
+    /// By default it is detected from `project.godot` in the Godot project
+    /// directory with [`GodotVersion::from_project`]. It selects the output
+    /// format unless one is set, and the `format` of generated scenes.
+    pub fn with_godot_version(&mut self, version: GodotVersion) {
+        self.godot_version = Some(version);
+    }
 
-        if rel_gdnlib_path.starts_with("../") {
-            // not in the project folder, use an absolute path
-            prefix = "";
-            output_path = &gdnlib_path;
-        } else {
-            // output paths are inside the project folder, use a `res://` path
-            prefix = "res://";
-            output_path = &rel_gdnlib_path;
-        };
+    /// Set the Godot version of the project.
+    ///
+    /// By default it is detected from `project.godot` in the Godot project
+    /// directory with [`GodotVersion::from_project`]. It selects the output
+    /// format unless one is set, and the `format` of generated scenes.
+    pub fn godot_version(mut self, version: GodotVersion) -> Self {
+        self.with_godot_version(version);
+        self
+    }
 
-        for name in classes {
-            let path = godot_resource_output_dir.join(format!("{}.gdns", &name));
+    /// Set the `entry_symbol` of the `.gdextension` file.
+    ///
+    /// Defaults to `gdext_rust_init`. Only used with [`OutputFormat::GdExtension`] and
+    /// [`OutputFormat::Dual`].
+    pub fn with_entry_symbol(&mut self, symbol: impl AsRef<str>) {
+        self.entry_symbol = Some(symbol.as_ref().to_string());
+    }
 
-            let file_exists = path.exists() && path.is_file();
+    /// Set the `entry_symbol` of the `.gdextension` file.
+    ///
+    /// Defaults to `gdext_rust_init`. Only used with [`OutputFormat::GdExtension`] and
+    /// [`OutputFormat::Dual`].
+    pub fn entry_symbol(mut self, symbol: impl AsRef<str>) -> Self {
+        self.with_entry_symbol(symbol);
+        self
+    }
 
-            if !file_exists {
-                let content = generate_gdns(&prefix, &output_path, &name);
-                std::fs::write(&path, content)?;
-            }
+    /// Set the `compatibility_minimum` Godot version of the `.gdextension` file.
+    ///
+    /// Defaults to `4.1`. Only used with [`OutputFormat::GdExtension`] and
+    /// [`OutputFormat::Dual`].
+    pub fn with_compatibility_minimum(&mut self, version: impl AsRef<str>) {
+        self.compatibility_minimum = Some(version.as_ref().to_string());
+    }
+
+    /// Set the `compatibility_minimum` Godot version of the `.gdextension` file.
+    ///
+    /// Defaults to `4.1`. Only used with [`OutputFormat::GdExtension`] and
+    /// [`OutputFormat::Dual`].
+    pub fn compatibility_minimum(mut self, version: impl AsRef<str>) -> Self {
+        self.with_compatibility_minimum(version);
+        self
+    }
+
+    /// Set the platforms for which library entries are generated.
+    ///
+    /// Defaults to [`Platforms::common()`].
+    pub fn with_platforms(&mut self, platforms: impl Into<Platforms>) {
+        self.platforms = Some(platforms.into());
+    }
+
+    /// Set the platforms for which library entries are generated.
+    ///
+    /// Defaults to [`Platforms::common()`].
+    pub fn platforms(mut self, platforms: impl Into<Platforms>) -> Self {
+        self.with_platforms(platforms);
+        self
+    }
+
+    /// Set whether only the entry of the platform being built for is
+    /// generated, for fast iteration on a single machine.
+    ///
+    /// The platform is the one of the target triple if one is set, or else
+    /// [`Platform::host`]. The selected [`platforms`](Self::platforms) and
+    /// overridden binaries of other platforms are ignored. Together with
+    /// [`update_existing_lib`](Self::update_existing_lib), the entries of
+    /// the other platforms in an existing library resource are kept as they
+    /// are. Defaults to `false`.
+    pub fn with_host_only(&mut self, host_only: bool) {
+        self.host_only = host_only;
+    }
+
+    /// Set whether only the entry of the platform being built for is
+    /// generated, for fast iteration on a single machine.
+    ///
+    /// The platform is the one of the target triple if one is set, or else
+    /// [`Platform::host`]. The selected [`platforms`](Self::platforms) and
+    /// overridden binaries of other platforms are ignored. Together with
+    /// [`update_existing_lib`](Self::update_existing_lib), the entries of
+    /// the other platforms in an existing library resource are kept as they
+    /// are. Defaults to `false`.
+    pub fn host_only(mut self, host_only: bool) -> Self {
+        self.with_host_only(host_only);
+        self
+    }
+
+    /// Set whether only entries for platforms whose target is installed
+    /// are generated, as listed by `rustup target list --installed`.
+    ///
+    /// The entries of the other platforms would always point at missing
+    /// libraries, they are left out with a warning. Overridden binaries are
+    /// kept. Fails the build if `rustup` can't be run. Defaults to `false`.
+    pub fn with_detect_installed_targets(&mut self, detect: bool) {
+        if detect {
+            self.installed_targets = Some(InstalledTargets::Detect);
+        } else if let Some(InstalledTargets::Detect) = self.installed_targets {
+            self.installed_targets = None;
         }
+    }
+
+    /// Set whether only entries for platforms whose target is installed
+    /// are generated, as listed by `rustup target list --installed`.
+    ///
+    /// The entries of the other platforms would always point at missing
+    /// libraries, they are left out with a warning. Overridden binaries are
+    /// kept. Fails the build if `rustup` can't be run. Defaults to `false`.
+    pub fn detect_installed_targets(mut self, detect: bool) -> Self {
+        self.with_detect_installed_targets(detect);
+        self
+    }
+
+    /// Only generate entries for platforms whose target is in `targets`,
+    /// instead of asking `rustup` which targets are installed.
+    ///
+    /// The entries of the other platforms are left out with a warning, see
+    /// [`detect_installed_targets`](Self::detect_installed_targets).
+    pub fn with_installed_targets(&mut self, targets: impl IntoIterator<Item = impl Into<String>>) {
+        let targets = targets.into_iter().map(Into::into).collect();
+        self.installed_targets = Some(InstalledTargets::Listed(targets));
+    }
+
+    /// Only generate entries for platforms whose target is in `targets`,
+    /// instead of asking `rustup` which targets are installed.
+    ///
+    /// The entries of the other platforms are left out with a warning, see
+    /// [`detect_installed_targets`](Self::detect_installed_targets).
+    pub fn installed_targets(
+        mut self,
+        targets: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.with_installed_targets(targets);
+        self
+    }
+
+    /// Set whether the `HTML5.wasm32` entry for web exports is generated,
+    /// regardless of the selected platforms.
+    ///
+    /// Defaults to generating it only if [`Platform::Html5Wasm32`] is selected.
+    pub fn with_html5(&mut self, html5: bool) {
+        self.html5 = Some(html5);
+    }
+
+    /// Set whether the `HTML5.wasm32` entry for web exports is generated,
+    /// regardless of the selected platforms.
+    ///
+    /// Defaults to generating it only if [`Platform::Html5Wasm32`] is selected.
+    pub fn html5(mut self, html5: bool) -> Self {
+        self.with_html5(html5);
+        self
+    }
+
+    /// Set how the iOS libraries are packaged.
+    ///
+    /// Defaults to [`IosLibLayout::StaticLib`].
+    pub fn with_ios_lib_layout(&mut self, layout: IosLibLayout) {
+        self.ios_lib_layout = Some(layout);
+    }
+
+    /// Set how the iOS libraries are packaged.
+    ///
+    /// Defaults to [`IosLibLayout::StaticLib`].
+    pub fn ios_lib_layout(mut self, layout: IosLibLayout) -> Self {
+        self.with_ios_lib_layout(layout);
+        self
+    }
+
+    /// Set where the Android libraries are found.
+    ///
+    /// Defaults to [`AndroidLibLayout::TargetTriple`].
+    pub fn with_android_lib_layout(&mut self, layout: AndroidLibLayout) {
+        self.android_lib_layout = Some(layout);
+    }
+
+    /// Set where the Android libraries are found.
+    ///
+    /// Defaults to [`AndroidLibLayout::TargetTriple`].
+    ///
+    /// ```
+    /// # use gdnative_project_utils::*;
+    /// // libraries built with `cargo ndk -o ../godot/android/jniLibs build`
+    /// let generator = Generator::new()
+    ///     .android_lib_layout(AndroidLibLayout::AbiDirectories("android/jniLibs".into()));
+    /// ```
+    pub fn android_lib_layout(mut self, layout: AndroidLibLayout) -> Self {
+        self.with_android_lib_layout(layout);
+        self
+    }
+
+    /// Set the Android ABIs for which entries are generated, replacing the
+    /// Android platforms among the selected [`platforms`](Self::platforms).
+    ///
+    /// Platforms in `abis` that are not Android platforms are ignored.
+    /// Defaults to the Android platforms that are selected.
+    pub fn with_android_abis(&mut self, abis: impl Into<Platforms>) {
+        self.android_abis = Some(abis.into());
+    }
+
+    /// Set the Android ABIs for which entries are generated, replacing the
+    /// Android platforms among the selected [`platforms`](Self::platforms).
+    ///
+    /// Platforms in `abis` that are not Android platforms are ignored.
+    /// Defaults to the Android platforms that are selected.
+    ///
+    /// ```
+    /// # use gdnative_project_utils::*;
+    /// // only the 64 bit ARM devices
+    /// let generator = Generator::new()
+    ///     .platforms(Platforms::common())
+    ///     .android_abis(Platform::AndroidAarch64);
+    /// ```
+    pub fn android_abis(mut self, abis: impl Into<Platforms>) -> Self {
+        self.with_android_abis(abis);
+        self
+    }
+
+    /// Set how the macOS libraries are packaged.
+    ///
+    /// Defaults to [`MacosLibLayout::PerArchitecture`].
+    pub fn with_macos_lib_layout(&mut self, layout: MacosLibLayout) {
+        self.macos_lib_layout = Some(layout);
+    }
+
+    /// Set how the macOS libraries are packaged.
+    ///
+    /// Defaults to [`MacosLibLayout::PerArchitecture`].
+    pub fn macos_lib_layout(mut self, layout: MacosLibLayout) -> Self {
+        self.with_macos_lib_layout(layout);
+        self
+    }
+
+    /// Set whether the library is loaded as a singleton.
+    ///
+    /// Defaults to `false`.
+    pub fn with_singleton(&mut self, singleton: bool) {
+        self.general.singleton = Some(singleton);
+    }
+
+    /// Set whether the library is loaded as a singleton.
+    ///
+    /// Defaults to `false`.
+    pub fn singleton(mut self, singleton: bool) -> Self {
+        self.with_singleton(singleton);
+        self
+    }
+
+    /// Set whether the library is only loaded once for all scripts.
+    ///
+    /// Defaults to `true`.
+    pub fn with_load_once(&mut self, load_once: bool) {
+        self.general.load_once = Some(load_once);
+    }
+
+    /// Set whether the library is only loaded once for all scripts.
+    ///
+    /// Defaults to `true`.
+    pub fn load_once(mut self, load_once: bool) -> Self {
+        self.with_load_once(load_once);
+        self
+    }
+
+    /// Set the prefix of the init and terminate symbols of the library.
+    ///
+    /// Defaults to `godot_`.
+    pub fn with_symbol_prefix(&mut self, prefix: impl AsRef<str>) {
+        self.general.symbol_prefix = Some(prefix.as_ref().to_string());
+    }
+
+    /// Set the prefix of the init and terminate symbols of the library.
+    ///
+    /// Defaults to `godot_`.
+    pub fn symbol_prefix(mut self, prefix: impl AsRef<str>) -> Self {
+        self.with_symbol_prefix(prefix);
+        self
+    }
+
+    /// Set whether the library can be reloaded when the editor regains focus.
+    ///
+    /// Defaults to `true`.
+    pub fn with_reloadable(&mut self, reloadable: bool) {
+        self.general.reloadable = Some(reloadable);
+    }
+
+    /// Set whether the library can be reloaded when the editor regains focus.
+    ///
+    /// Defaults to `true`.
+    pub fn reloadable(mut self, reloadable: bool) -> Self {
+        self.with_reloadable(reloadable);
+        self
+    }
+
+    /// Set whether the library is loaded as a singleton when the crate is
+    /// built in `mode`, overriding [`singleton`](Self::singleton).
+    ///
+    /// An existing `.gdnlib` file only follows the build mode if it may be
+    /// overwritten or [updated](Self::update_existing_lib).
+    pub fn with_singleton_for(&mut self, mode: BuildMode, singleton: bool) {
+        self.general_overrides_mut(mode).singleton = Some(singleton);
+    }
+
+    /// Set whether the library is loaded as a singleton when the crate is
+    /// built in `mode`, overriding [`singleton`](Self::singleton).
+    ///
+    /// An existing `.gdnlib` file only follows the build mode if it may be
+    /// overwritten or [updated](Self::update_existing_lib).
+    pub fn singleton_for(mut self, mode: BuildMode, singleton: bool) -> Self {
+        self.with_singleton_for(mode, singleton);
+        self
+    }
+
+    /// Set whether the library is only loaded once for all scripts when the
+    /// crate is built in `mode`, overriding [`load_once`](Self::load_once).
+    ///
+    /// An existing `.gdnlib` file only follows the build mode if it may be
+    /// overwritten or [updated](Self::update_existing_lib).
+    pub fn with_load_once_for(&mut self, mode: BuildMode, load_once: bool) {
+        self.general_overrides_mut(mode).load_once = Some(load_once);
+    }
+
+    /// Set whether the library is only loaded once for all scripts when the
+    /// crate is built in `mode`, overriding [`load_once`](Self::load_once).
+    ///
+    /// An existing `.gdnlib` file only follows the build mode if it may be
+    /// overwritten or [updated](Self::update_existing_lib).
+    pub fn load_once_for(mut self, mode: BuildMode, load_once: bool) -> Self {
+        self.with_load_once_for(mode, load_once);
+        self
+    }
+
+    /// Set whether the library can be reloaded when the editor regains focus
+    /// when the crate is built in `mode`, overriding
+    /// [`reloadable`](Self::reloadable).
+    ///
+    /// An existing `.gdnlib` file only follows the build mode if it may be
+    /// overwritten or [updated](Self::update_existing_lib).
+    pub fn with_reloadable_for(&mut self, mode: BuildMode, reloadable: bool) {
+        self.general_overrides_mut(mode).reloadable = Some(reloadable);
+    }
+
+    /// Set whether the library can be reloaded when the editor regains focus
+    /// when the crate is built in `mode`, overriding
+    /// [`reloadable`](Self::reloadable).
+    ///
+    /// An existing `.gdnlib` file only follows the build mode if it may be
+    /// overwritten or [updated](Self::update_existing_lib).
+    ///
+    /// ```
+    /// # use gdnative_project_utils::*;
+    /// // hot reload while iterating, but load release builds only once
+    /// let generator = Generator::new()
+    ///     .reloadable(true)
+    ///     .reloadable_for(BuildMode::Release, false)
+    ///     .load_once_for(BuildMode::Release, true);
+    /// ```
+    pub fn reloadable_for(mut self, mode: BuildMode, reloadable: bool) -> Self {
+        self.with_reloadable_for(mode, reloadable);
+        self
+    }
+
+    /// The `[general]` settings that differ for `mode`.
+    fn general_overrides_mut(&mut self, mode: BuildMode) -> &mut GeneralOverrides {
+        let index = match self.general_for.iter().position(|(m, _)| *m == mode) {
+            Some(index) => index,
+            None => {
+                self.general_for.push((mode, GeneralOverrides::default()));
+                self.general_for.len() - 1
+            }
+        };
+        &mut self.general_for[index].1
+    }
+
+    /// Set a template that replaces the default content of the `.gdnlib`
+    /// library resource.
+    ///
+    /// The `{placeholders}` in the template are replaced by:
+    ///
+    /// - `{lib_name}`: the name of the library
+    /// - `{entries}`: the `key="path"` lines of the `[entry]` section
+    /// - `{dependencies}`: the `key=[ "path" ]` lines of the `[dependencies]`
+    ///   section
+    /// - `{entry.<key>}`: the path of the library of one platform, e.g.
+    ///   `{entry.X11.64}`, or nothing if the platform has no entry
+    /// - `{singleton}`, `{load_once}`, `{symbol_prefix}`, `{reloadable}`: the
+    ///   settings of the `[general]` section
+    ///
+    /// The paths and `{symbol_prefix}` are escaped to go between the quotes
+    /// of a string, like `"{entry.X11.64}"`.
+    ///
+    /// `{{` and `}}` stand for literal braces. The template is not used for
+    /// `.tres` and `.gdextension` library resources, or when an existing
+    /// resource is [updated](Self::update_existing_lib).
+    pub fn with_gdnlib_template(&mut self, template: impl AsRef<str>) {
+        self.gdnlib_template = Some(template.as_ref().to_string());
+    }
+
+    /// Set a template that replaces the default content of the `.gdnlib`
+    /// library resource.
+    ///
+    /// The `{placeholders}` in the template are replaced by:
+    ///
+    /// - `{lib_name}`: the name of the library
+    /// - `{entries}`: the `key="path"` lines of the `[entry]` section
+    /// - `{dependencies}`: the `key=[ "path" ]` lines of the `[dependencies]`
+    ///   section
+    /// - `{entry.<key>}`: the path of the library of one platform, e.g.
+    ///   `{entry.X11.64}`, or nothing if the platform has no entry
+    /// - `{singleton}`, `{load_once}`, `{symbol_prefix}`, `{reloadable}`: the
+    ///   settings of the `[general]` section
+    ///
+    /// The paths and `{symbol_prefix}` are escaped to go between the quotes
+    /// of a string, like `"{entry.X11.64}"`.
+    ///
+    /// `{{` and `}}` stand for literal braces. The template is not used for
+    /// `.tres` and `.gdextension` library resources, or when an existing
+    /// resource is [updated](Self::update_existing_lib).
+    pub fn gdnlib_template(mut self, template: impl AsRef<str>) -> Self {
+        self.with_gdnlib_template(template);
+        self
+    }
+
+    /// Set a template that replaces the default content of the `.gdns`
+    /// script resources.
+    ///
+    /// The `{placeholders}` in the template are replaced by:
+    ///
+    /// - `{class_name}`: the name of the class
+    /// - `{base}`: the base class, `Reference` if it is not known
+    /// - `{lib_name}`: the name of the library
+    /// - `{gdnlib_path}`: the path of the library resource, e.g.
+    ///   `res://native/my_crate.gdnlib`, escaped to go between the quotes of
+    ///   a string
+    ///
+    /// `{{` and `}}` stand for literal braces.
+    pub fn with_gdns_template(&mut self, template: impl AsRef<str>) {
+        self.gdns_template = Some(template.as_ref().to_string());
+    }
+
+    /// Set a template that replaces the default content of the `.gdns`
+    /// script resources.
+    ///
+    /// The `{placeholders}` in the template are replaced by:
+    ///
+    /// - `{class_name}`: the name of the class
+    /// - `{base}`: the base class, `Reference` if it is not known
+    /// - `{lib_name}`: the name of the library
+    /// - `{gdnlib_path}`: the path of the library resource, e.g.
+    ///   `res://native/my_crate.gdnlib`, escaped to go between the quotes of
+    ///   a string
+    ///
+    /// `{{` and `}}` stand for literal braces.
+    pub fn gdns_template(mut self, template: impl AsRef<str>) -> Self {
+        self.with_gdns_template(template);
+        self
+    }
+
+    /// Set whether an existing library resource is updated.
+    ///
+    /// By default an existing library resource is left untouched. When
+    /// enabled, the entries of the selected platforms are rewritten to point at
+    /// the current library paths and the general settings set on this builder
+    /// are written, while all other sections and keys, such as hand-added
+    /// dependencies, are preserved.
+    pub fn with_update_existing_lib(&mut self, update: bool) {
+        self.update_existing_lib = update;
+    }
+
+    /// Set whether an existing library resource is updated.
+    ///
+    /// By default an existing library resource is left untouched. When
+    /// enabled, the entries of the selected platforms are rewritten to point at
+    /// the current library paths and the general settings set on this builder
+    /// are written, while all other sections and keys, such as hand-added
+    /// dependencies, are preserved.
+    pub fn update_existing_lib(mut self, update: bool) -> Self {
+        self.with_update_existing_lib(update);
+        self
+    }
+
+    /// Set where the `.gdns` files are placed inside the resource output
+    /// directory.
+    ///
+    /// Defaults to [`OutputLayout::Flat`].
+    pub fn with_layout(&mut self, layout: OutputLayout) {
+        self.layout = Some(layout);
+    }
+
+    /// Set where the `.gdns` files are placed inside the resource output
+    /// directory.
+    ///
+    /// Defaults to [`OutputLayout::Flat`].
+    pub fn layout(mut self, layout: OutputLayout) -> Self {
+        self.with_layout(layout);
+        self
+    }
+
+    /// Set how the file names of `.gdns` files are derived from class names.
+    ///
+    /// Defaults to [`NamingConvention::PascalCase`].
+    pub fn with_naming_convention(&mut self, naming: NamingConvention) {
+        self.naming = Some(naming);
+    }
+
+    /// Set how the file names of `.gdns` files are derived from class names.
+    ///
+    /// Defaults to [`NamingConvention::PascalCase`].
+    pub fn naming_convention(mut self, naming: NamingConvention) -> Self {
+        self.with_naming_convention(naming);
+        self
+    }
+
+    /// The naming convention that has been set, if any.
+    pub(crate) fn current_naming_convention(&self) -> Option<&NamingConvention> {
+        self.naming.as_ref()
+    }
+
+    /// Set whether `.gdns` files of classes that no longer exist are removed.
+    ///
+    /// Only files that were created by the generator are removed. They are
+    /// tracked in a hidden manifest file in the resource output directory, so
+    /// hand-made `.gdns` files are never touched.
+    pub fn with_prune_stale(&mut self, prune: bool) {
+        self.prune_stale = prune;
+    }
+
+    /// Set whether `.gdns` files of classes that no longer exist are removed.
+    ///
+    /// Only files that were created by the generator are removed. They are
+    /// tracked in a hidden manifest file in the resource output directory, so
+    /// hand-made `.gdns` files are never touched.
+    pub fn prune_stale(mut self, prune: bool) -> Self {
+        self.with_prune_stale(prune);
+        self
+    }
+
+    /// Set whether a GDScript stub is generated next to the `.gdns` file of
+    /// each class.
+    ///
+    /// The stub `Name.stub.gd` extends the base class and declares the
+    /// exported methods and signals of the class with empty bodies, so the
+    /// Godot editor can offer them for autocompletion. Stubs are tracked like
+    /// `.gdns` files and are pruned with them. Unless they were modified by
+    /// hand, they are updated when the class changes, even with
+    /// [`OverwritePolicy::Never`].
+    pub fn with_gdscript_stubs(&mut self, stubs: bool) {
+        self.gdscript_stubs = stubs;
+    }
+
+    /// Set whether a GDScript stub is generated next to the `.gdns` file of
+    /// each class.
+    ///
+    /// The stub `Name.stub.gd` extends the base class and declares the
+    /// exported methods and signals of the class with empty bodies, so the
+    /// Godot editor can offer them for autocompletion. Stubs are tracked like
+    /// `.gdns` files and are pruned with them. Unless they were modified by
+    /// hand, they are updated when the class changes, even with
+    /// [`OverwritePolicy::Never`].
+    pub fn gdscript_stubs(mut self, stubs: bool) -> Self {
+        self.with_gdscript_stubs(stubs);
+        self
+    }
+
+    /// Set whether a scene is generated next to the `.gdns` file of each
+    /// class with a known base class.
+    ///
+    /// The scene `Name.tscn` has a single node of the base class, named after
+    /// the class, with the `.gdns` script attached. With
+    /// [`OutputFormat::GdExtension`] the node is of the class itself, which
+    /// Godot 4 knows as a native type, and the scene is generated where the
+    /// `.gdns` file would be. Scenes are only created if they do not exist
+    /// yet, regardless of the overwrite policy, and are never pruned, since
+    /// they are meant to be edited.
+    pub fn with_scene_templates(&mut self, templates: bool) {
+        self.scene_templates = templates;
+    }
+
+    /// Set whether a scene is generated next to the `.gdns` file of each
+    /// class with a known base class.
+    ///
+    /// The scene `Name.tscn` has a single node of the base class, named after
+    /// the class, with the `.gdns` script attached. With
+    /// [`OutputFormat::GdExtension`] the node is of the class itself, which
+    /// Godot 4 knows as a native type, and the scene is generated where the
+    /// `.gdns` file would be. Scenes are only created if they do not exist
+    /// yet, regardless of the overwrite policy, and are never pruned, since
+    /// they are meant to be edited.
+    pub fn scene_templates(mut self, templates: bool) -> Self {
+        self.with_scene_templates(templates);
+        self
+    }
+
+    /// Generate an editor plugin in `addons/<name>` of the Godot project that
+    /// registers every class with a known base class as a custom type.
+    ///
+    /// Once the plugin is enabled in the project settings, the classes appear
+    /// in the "Create New Node" and "New Resource" dialogs of the editor. The
+    /// plugin files are tracked in the manifest like the other generated
+    /// files, and follow the classes unless they were modified by hand, even
+    /// with [`OverwritePolicy::Never`].
+    pub fn with_editor_plugin(&mut self, name: impl AsRef<str>) {
+        self.editor_plugin = Some(name.as_ref().to_string());
+    }
+
+    /// Generate an editor plugin in `addons/<name>` of the Godot project that
+    /// registers every class with a known base class as a custom type.
+    ///
+    /// Once the plugin is enabled in the project settings, the classes appear
+    /// in the "Create New Node" and "New Resource" dialogs of the editor. The
+    /// plugin files are tracked in the manifest like the other generated
+    /// files, and follow the classes unless they were modified by hand, even
+    /// with [`OverwritePolicy::Never`].
+    pub fn editor_plugin(mut self, name: impl AsRef<str>) -> Self {
+        self.with_editor_plugin(name);
+        self
+    }
+
+    /// Set the icon of the class `class` in the editor plugin and in
+    /// `project.godot`, as a path relative to the Godot project directory.
+    pub fn with_class_icon(&mut self, class: impl AsRef<str>, icon: impl AsRef<Path>) {
+        self.class_icons
+            .insert(class.as_ref().to_string(), icon.as_ref().to_path_buf());
+    }
+
+    /// Set the icon of the class `class` in the editor plugin and in
+    /// `project.godot`, as a path relative to the Godot project directory.
+    pub fn class_icon(mut self, class: impl AsRef<str>, icon: impl AsRef<Path>) -> Self {
+        self.with_class_icon(class, icon);
+        self
+    }
+
+    /// Set whether the classes are registered in the `_global_script_classes`
+    /// of `project.godot`, so GDScript can refer to them by name without
+    /// preloading their `.gdns` files.
+    ///
+    /// The entries of other scripts are kept. Entries of classes that were
+    /// generated before but no longer exist are removed. A changed
+    /// `project.godot` is backed up first. Nothing is changed with
+    /// [`OverwritePolicy::Never`], the default, and with
+    /// [`OverwritePolicy::IfGeneratedByUs`] the file is left alone once it was
+    /// changed by hand after the classes were last registered. Generating
+    /// fails if the Godot project has no `project.godot`.
+    pub fn with_global_script_classes(&mut self, register: bool) {
+        self.global_script_classes = register;
+    }
+
+    /// Set whether the classes are registered in the `_global_script_classes`
+    /// of `project.godot`, so GDScript can refer to them by name without
+    /// preloading their `.gdns` files.
+    ///
+    /// The entries of other scripts are kept. Entries of classes that were
+    /// generated before but no longer exist are removed. A changed
+    /// `project.godot` is backed up first. Nothing is changed with
+    /// [`OverwritePolicy::Never`], the default, and with
+    /// [`OverwritePolicy::IfGeneratedByUs`] the file is left alone once it was
+    /// changed by hand after the classes were last registered. Generating
+    /// fails if the Godot project has no `project.godot`.
+    pub fn global_script_classes(mut self, register: bool) -> Self {
+        self.with_global_script_classes(register);
+        self
+    }
+
+    /// Set whether renamed classes are migrated.
+    ///
+    /// A class is considered renamed if the `.gdns` file of a class generated
+    /// before is gone and a new class appeared in the same source file, on the
+    /// same line if there are several. Renames are always listed in
+    /// [`Report::renames`](crate::Report::renames). When migrating, the old
+    /// `.gdns` file is removed and every `.tscn` and `.tres` file in the Godot
+    /// project that refers to it is changed to refer to the new one. The
+    /// changed scenes and resources, and a modified old `.gdns` file, are
+    /// backed up first. Nothing is migrated with
+    /// [`OverwritePolicy::Never`], the default.
+    pub fn with_migrate_renames(&mut self, migrate: bool) {
+        self.migrate_renames = migrate;
+    }
+
+    /// Set whether renamed classes are migrated.
+    ///
+    /// A class is considered renamed if the `.gdns` file of a class generated
+    /// before is gone and a new class appeared in the same source file, on the
+    /// same line if there are several. Renames are always listed in
+    /// [`Report::renames`](crate::Report::renames). When migrating, the old
+    /// `.gdns` file is removed and every `.tscn` and `.tres` file in the Godot
+    /// project that refers to it is changed to refer to the new one. The
+    /// changed scenes and resources, and a modified old `.gdns` file, are
+    /// backed up first. Nothing is migrated with
+    /// [`OverwritePolicy::Never`], the default.
+    pub fn migrate_renames(mut self, migrate: bool) -> Self {
+        self.with_migrate_renames(migrate);
+        self
+    }
+
+    /// Set whether the `include_filter` of every preset in
+    /// `export_presets.cfg` is extended with the native libraries.
+    ///
+    /// Godot does not export the libraries on its own, so exported games fail
+    /// to load them. The staging directory is added as `dir/*` if
+    /// [`stage_artifacts`](Self::stage_artifacts) is used, otherwise each
+    /// library inside the Godot project is added. Existing filters are kept,
+    /// and a changed `export_presets.cfg` is backed up first. Nothing is
+    /// changed with [`OverwritePolicy::Never`], the default, and with
+    /// [`OverwritePolicy::IfGeneratedByUs`] the file is left alone once it was
+    /// changed by hand after it was last updated.
+    pub fn with_update_export_presets(&mut self, update: bool) {
+        self.update_export_presets = update;
+    }
+
+    /// Set whether the `include_filter` of every preset in
+    /// `export_presets.cfg` is extended with the native libraries.
+    ///
+    /// Godot does not export the libraries on its own, so exported games fail
+    /// to load them. The staging directory is added as `dir/*` if
+    /// [`stage_artifacts`](Self::stage_artifacts) is used, otherwise each
+    /// library inside the Godot project is added. Existing filters are kept,
+    /// and a changed `export_presets.cfg` is backed up first. Nothing is
+    /// changed with [`OverwritePolicy::Never`], the default, and with
+    /// [`OverwritePolicy::IfGeneratedByUs`] the file is left alone once it was
+    /// changed by hand after it was last updated.
+    pub fn update_export_presets(mut self, update: bool) -> Self {
+        self.with_update_export_presets(update);
+        self
+    }
+
+    /// Set whether an empty `.gdignore` file is created in the target
+    /// directory and the staging directory, if they are inside the Godot
+    /// project.
+    ///
+    /// This stops the Godot editor from importing build artifacts. An
+    /// existing `.gdignore` is left alone.
+    pub fn with_gdignore(&mut self, gdignore: bool) {
+        self.gdignore = gdignore;
+    }
+
+    /// Set whether an empty `.gdignore` file is created in the target
+    /// directory and the staging directory, if they are inside the Godot
+    /// project.
+    ///
+    /// This stops the Godot editor from importing build artifacts. An
+    /// existing `.gdignore` is left alone.
+    pub fn gdignore(mut self, gdignore: bool) -> Self {
+        self.with_gdignore(gdignore);
+        self
+    }
+
+    /// Set whether files that were modified by hand are backed up before
+    /// they are overwritten or pruned.
+    ///
+    /// The backup is a copy next to the file with `.bak` appended to its
+    /// name, e.g. `Player.gdns.bak`, which the Godot editor does not import.
+    /// An existing backup is replaced. Files are only overwritten despite
+    /// modifications with [`OverwritePolicy::IfContentDiffers`]. Defaults to
+    /// `true`.
+    pub fn with_backups(&mut self, backups: bool) {
+        self.backups = Some(backups);
+    }
+
+    /// Set whether files that were modified by hand are backed up before
+    /// they are overwritten or pruned.
+    ///
+    /// The backup is a copy next to the file with `.bak` appended to its
+    /// name, e.g. `Player.gdns.bak`, which the Godot editor does not import.
+    /// An existing backup is replaced. Files are only overwritten despite
+    /// modifications with [`OverwritePolicy::IfContentDiffers`]. Defaults to
+    /// `true`.
+    pub fn backups(mut self, backups: bool) -> Self {
+        self.with_backups(backups);
+        self
+    }
+
+    /// Set whether every generated file is flushed to the disk before it
+    /// replaces the previous file.
+    ///
+    /// Files are always written to a temporary file first and renamed into
+    /// place, so an interrupted build never leaves a truncated file behind.
+    /// Flushing also keeps the new content if the system crashes, at the cost
+    /// of slower builds. Defaults to `false`.
+    pub fn with_sync_writes(&mut self, sync: bool) {
+        self.sync_writes = sync;
+    }
+
+    /// Set whether every generated file is flushed to the disk before it
+    /// replaces the previous file.
+    ///
+    /// Files are always written to a temporary file first and renamed into
+    /// place, so an interrupted build never leaves a truncated file behind.
+    /// Flushing also keeps the new content if the system crashes, at the cost
+    /// of slower builds. Defaults to `false`.
+    pub fn sync_writes(mut self, sync: bool) -> Self {
+        self.with_sync_writes(sync);
+        self
+    }
+
+    /// Set what happens to existing files.
+    ///
+    /// Defaults to [`OverwritePolicy::Never`], or to
+    /// [`OverwritePolicy::IfGeneratedByUs`] with [`StagingMode::Versioned`].
+    /// An existing library resource is updated instead of overwritten if
+    /// [`update_existing_lib`](Self::update_existing_lib) is enabled.
+    pub fn with_overwrite_policy(&mut self, policy: OverwritePolicy) {
+        self.overwrite_policy = Some(policy);
+    }
+
+    /// Set what happens to existing files.
+    ///
+    /// Defaults to [`OverwritePolicy::Never`], or to
+    /// [`OverwritePolicy::IfGeneratedByUs`] with [`StagingMode::Versioned`].
+    /// An existing library resource is updated instead of overwritten if
+    /// [`update_existing_lib`](Self::update_existing_lib) is enabled.
+    pub fn overwrite_policy(mut self, policy: OverwritePolicy) -> Self {
+        self.with_overwrite_policy(policy);
+        self
+    }
+
+    /// Set the name of the crate.
+    ///
+    /// Defaults to the name of the library target in the `Cargo.toml` of the
+    /// [crate directory](Self::crate_dir), which is `[lib] name` if it is
+    /// set, or to `CARGO_PKG_NAME`. If the name is taken from `Cargo.toml`,
+    /// generating fails with [`GenerateError::InvalidCrateType`] unless the
+    /// crate builds a `cdylib`.
+    pub fn with_lib_name(&mut self, name: impl AsRef<str>) {
+        let name = name.as_ref().to_string();
+
+        self.lib_name = Some(name);
+    }
+
+    /// Set the name of the crate.
+    ///
+    /// Defaults to the name of the library target in the `Cargo.toml` of the
+    /// [crate directory](Self::crate_dir), which is `[lib] name` if it is
+    /// set, or to `CARGO_PKG_NAME`. If the name is taken from `Cargo.toml`,
+    /// generating fails with [`GenerateError::InvalidCrateType`] unless the
+    /// crate builds a `cdylib`.
+    pub fn lib_name(mut self, name: impl AsRef<str>) -> Self {
+        self.with_lib_name(name);
+        self
+    }
+
+    /// Set the build mode of the crate.
+    ///
+    /// This will affect the path the `gdnlib` resource points to.
+    pub fn with_build_mode(&mut self, mode: BuildMode) {
+        self.build_mode = Some(mode);
+    }
+
+    /// Set the build mode of the crate.
+    ///
+    /// This will affect the path the `gdnlib` resource points to.
+    pub fn build_mode(mut self, mode: BuildMode) -> Self {
+        self.with_build_mode(mode);
+        self
+    }
+
+    /// Add a callback that is called with the path of every file the
+    /// generator creates, updates or removes, and what happened to it.
+    ///
+    /// The callbacks run right after the file is written, e.g. to log it,
+    /// touch related files or ask a running Godot editor to rescan its
+    /// filesystem. Files that already had the generated content are not
+    /// passed to the callbacks.
+    pub fn with_on_file_written(&mut self, f: impl Fn(&Path, FileAction) + Send + Sync + 'static) {
+        self.on_file_written.push(Arc::new(f));
+    }
+
+    /// Add a callback that is called with the path of every file the
+    /// generator creates, updates or removes, and what happened to it.
+    ///
+    /// The callbacks run right after the file is written, e.g. to log it,
+    /// touch related files or ask a running Godot editor to rescan its
+    /// filesystem. Files that already had the generated content are not
+    /// passed to the callbacks.
+    pub fn on_file_written(
+        mut self,
+        f: impl Fn(&Path, FileAction) + Send + Sync + 'static,
+    ) -> Self {
+        self.with_on_file_written(f);
+        self
+    }
+
+    /// Add a callback that is called with the path of every file the
+    /// generator did not overwrite because the overwrite policy forbids it,
+    /// usually because it was modified by hand.
+    pub fn with_on_skip(&mut self, f: impl Fn(&Path) + Send + Sync + 'static) {
+        self.on_skip.push(Arc::new(f));
+    }
+
+    /// Add a callback that is called with the path of every file the
+    /// generator did not overwrite because the overwrite policy forbids it,
+    /// usually because it was modified by hand.
+    pub fn on_skip(mut self, f: impl Fn(&Path) + Send + Sync + 'static) -> Self {
+        self.with_on_skip(f);
+        self
+    }
+
+    /// Add a filter deciding which classes the generator handles. Classes
+    /// for which any filter returns `false` are left out, as if they did not
+    /// exist, so their generated `.gdns` files are treated as stale.
+    pub fn with_filter_classes(&mut self, f: impl Fn(&Class) -> bool + Send + Sync + 'static) {
+        self.class_filters.push(Arc::new(f));
+    }
+
+    /// Add a filter deciding which classes the generator handles. Classes
+    /// for which any filter returns `false` are left out, as if they did not
+    /// exist, so their generated `.gdns` files are treated as stale.
+    pub fn filter_classes(mut self, f: impl Fn(&Class) -> bool + Send + Sync + 'static) -> Self {
+        self.with_filter_classes(f);
+        self
+    }
+
+    /// Only handle the classes matching one of `patterns`, see
+    /// [`Class::matches`] for the syntax. By default all classes are handled.
+    pub fn with_include_classes<S: AsRef<str>>(&mut self, patterns: impl IntoIterator<Item = S>) {
+        self.include_classes.extend(
+            patterns
+                .into_iter()
+                .map(|pattern| pattern.as_ref().to_string()),
+        );
+    }
+
+    /// Only handle the classes matching one of `patterns`, see
+    /// [`Class::matches`] for the syntax. By default all classes are handled.
+    pub fn include_classes<S: AsRef<str>>(mut self, patterns: impl IntoIterator<Item = S>) -> Self {
+        self.with_include_classes(patterns);
+        self
+    }
+
+    /// Leave out the classes matching one of `patterns`, even if they match
+    /// an include pattern. See [`Class::matches`] for the syntax.
+    pub fn with_exclude_classes<S: AsRef<str>>(&mut self, patterns: impl IntoIterator<Item = S>) {
+        self.exclude_classes.extend(
+            patterns
+                .into_iter()
+                .map(|pattern| pattern.as_ref().to_string()),
+        );
+    }
+
+    /// Leave out the classes matching one of `patterns`, even if they match
+    /// an include pattern. See [`Class::matches`] for the syntax.
+    ///
+    /// ```
+    /// # use gdnative_project_utils::Generator;
+    /// let generator = Generator::new().exclude_classes(["Debug*", "crate::tests::*"]);
+    /// ```
+    pub fn exclude_classes<S: AsRef<str>>(mut self, patterns: impl IntoIterator<Item = S>) -> Self {
+        self.with_exclude_classes(patterns);
+        self
+    }
+
+    /// Whether the class passes the include and exclude patterns and the
+    /// filters.
+    fn keeps_class(&self, class: &Class) -> bool {
+        (self.include_classes.is_empty()
+            || self
+                .include_classes
+                .iter()
+                .any(|pattern| class.matches(pattern)))
+            && !self
+                .exclude_classes
+                .iter()
+                .any(|pattern| class.matches(pattern))
+            && self.class_filters.iter().all(|filter| filter(class))
+    }
+
+    /// Check the configuration and return a [`Configured`] Builder with the
+    /// settings that were found from the environment set explicitly.
+    ///
+    /// The required settings are resolved like [`build`](Self::build) does,
+    /// without reading the Godot project or writing anything, so a
+    /// misconfigured build script fails before any work is done, with an
+    /// error that names the problem, e.g. [`GenerateError::MissingField`]
+    /// with `"godot_project_dir"`. The library name, build mode, target
+    /// directory and target triple, and the Godot project directory if it was
+    /// [detected](Self::auto_detect_project), are kept, so later builds no
+    /// longer depend on the environment. The target directory does not have
+    /// to exist yet, as cargo creates it on the first build. A Builder can
+    /// still be built without `finish`, in which case the same errors are
+    /// returned by `build`.
+    ///
+    /// ```no_run
+    /// # use gdnative_project_utils::*;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let generator = Generator::new().godot_project_dir("../godot").finish()?;
+    /// let report = generator.build_with_scanner(SynScanner::new("src"))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`build`](Self::build) that are caused by a
+    /// required setting that is missing or can not be resolved.
+    pub fn finish(mut self) -> Result<Configured, GenerateError> {
+        let settings = self.settings()?;
+
+        if self.godot_project_dir.is_none() {
+            self.godot_project_dir = Some(settings.godot_project_dir);
+        }
+        if self.target_dir.is_none() {
+            self.target_dir = Some(settings.target_dir);
+            self.target_dir_resolved = true;
+        }
+        if self.target_triple.is_none() {
+            self.target_triple = settings.target_triple;
+        }
+        self.lib_name = Some(settings.lib_name);
+        self.build_mode = Some(settings.build_mode);
+
+        Ok(Configured { builder: self })
+    }
+
+    /// Build and generate files for the crate and all `classes`, and return a
+    /// summary of what was done.
+    ///
+    /// Every file that is created is recorded together with a hash of its
+    /// content in a hidden manifest file (`.gdnative-utils.cfg`) inside the
+    /// resource output directory.
+    ///
+    /// With [`OutputFormat::GdExtension`] only the `.gdextension` file is
+    /// generated and `classes` is not used. With [`OutputFormat::Dual`] the
+    /// `.gdextension` file is generated in addition to the GDNative files.
+    ///
+    /// The output does not depend on the order the classes were found in:
+    /// classes are processed by name and library entries by platform, so
+    /// repeated builds produce byte-identical files and reports.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a required setting has not been given and could
+    /// not be found from the environment, if a path could not be resolved or
+    /// if writing a file failed.
+    pub fn build(self, classes: Classes) -> Result<Report, GenerateError> {
+        let mut sink = FsSink::new().sync(self.sync_writes);
+        self.build_with_sink(classes, &mut sink)
+    }
+
+    /// Generate files for the crate and all `classes` like
+    /// [`build`](Self::build), but read and write the generated files and the
+    /// manifest through `sink` instead of the filesystem.
+    ///
+    /// ```
+    /// # use gdnative_project_utils::*;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let godot_project_dir = tempfile::tempdir()?;
+    /// # let target_dir = tempfile::tempdir()?;
+    /// let mut sink = MemorySink::new();
+    ///
+    /// let report = Generator::new()
+    ///     .godot_project_dir(&godot_project_dir)
+    ///     .target_dir(&target_dir)
+    ///     .lib_name("game")
+    ///     .build_mode(BuildMode::Debug)
+    ///     .build_with_sink(vec!["Player".to_string()].into_iter().collect(), &mut sink)?;
+    ///
+    /// assert!(sink.get(report.godot_resource_output_dir.join("Player.gdns")).is_some());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// The directories are still resolved on the filesystem, so the Godot
+    /// project directory has to exist. Staged libraries are copied on the
+    /// filesystem as well.
+    pub fn build_with_sink(
+        self,
+        classes: Classes,
+        sink: &mut impl FileSink,
+    ) -> Result<Report, GenerateError> {
+        let staging_mode = self.staging_mode.unwrap_or(StagingMode::Copy);
+        let on_file_written = self.on_file_written.clone();
+        let on_skip = self.on_skip.clone();
+
+        let Plan {
+            mut report,
+            writes,
+            manifest,
+            artifacts,
+            dependency_artifacts,
+            universal_lib,
+            xcframework,
+        } = self.plan(classes, sink)?;
+
+        // The universal library has to exist before it can be staged.
+        if let Some((output, inputs)) = universal_lib {
+            lipo(&output, &inputs)?;
+        }
+        if let Some(xcframework) = xcframework {
+            xcframework.create()?;
+        }
+
+        for (source, destination) in artifacts {
+            if stage_artifact(&source, &destination, staging_mode)? {
+                info!(
+                    "Staged `{}` as `{}`",
+                    source.display(),
+                    destination.display()
+                );
+                if let StagingMode::Versioned = staging_mode {
+                    remove_old_versions(&destination);
+                }
+                report.staged.push((source, destination));
+            }
+        }
+
+        let dependency_staging_mode = match staging_mode {
+            StagingMode::Versioned => StagingMode::Copy,
+            mode => mode,
+        };
+        for (source, destination) in dependency_artifacts {
+            if stage_artifact(&source, &destination, dependency_staging_mode)? {
+                info!(
+                    "Staged `{}` as `{}`",
+                    source.display(),
+                    destination.display()
+                );
+                report.staged.push((source, destination));
+            }
+        }
+
+        report.files = apply_writes(
+            writes,
+            sink,
+            &on_file_written,
+            &on_skip,
+            &mut report.backups,
+        )?;
+
+        manifest.save(&report.godot_resource_output_dir, sink)?;
+
+        Ok(report)
+    }
+
+    /// Find the classes with `scanner` and generate files for them like
+    /// [`build`](Self::build).
+    ///
+    /// ```no_run
+    /// # use gdnative_project_utils::*;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let report = Generator::new()
+    ///     .godot_project_dir("../godot")
+    ///     .build_with_scanner(SynScanner::new("src"))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if scanning fails, or any error of
+    /// [`build`](Self::build).
+    pub fn build_with_scanner(self, scanner: impl Scanner) -> Result<Report, GenerateError> {
+        let classes = scanner.scan()?;
+        self.build(classes)
+    }
+
+    /// Generate the resources of several native libraries, given by name
+    /// together with their classes, into the same directory like
+    /// [`build`](Self::build).
+    ///
+    /// Every library gets its own library resource, and the `.gdns` file of
+    /// each class points at the library that defines it. Pruning only removes
+    /// `.gdns` files of classes that are in none of the libraries.
+    ///
+    /// ```no_run
+    /// # use gdnative_project_utils::*;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let reports = Generator::new()
+    ///     .godot_project_dir("../godot")
+    ///     .build_libraries(vec![
+    ///         ("game", scan_crate("../game/src")?),
+    ///         ("editor_tools", scan_crate("../editor_tools/src")?),
+    ///     ])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of [`build`](Self::build). The resources of
+    /// the libraries before it have already been generated.
+    pub fn build_libraries<S: Into<String>>(
+        self,
+        libraries: impl IntoIterator<Item = (S, Classes)>,
+    ) -> Result<Vec<Report>, GenerateError> {
+        let libraries = libraries
+            .into_iter()
+            .map(|(lib_name, classes)| (lib_name.into(), classes))
+            .collect::<Vec<(String, _)>>();
+
+        let layout = self.layout.unwrap_or(OutputLayout::Flat);
+        let naming = self.naming.clone().unwrap_or(NamingConvention::PascalCase);
+        let gdscript_stubs = self.gdscript_stubs;
+        let all_class_files = libraries
+            .iter()
+            .flat_map(|(_, classes)| classes.iter())
+            .flat_map(|class| {
+                let gdns = gdns_file_name(layout, &naming, class);
+                let stub = Some(stub_file_name(&gdns)).filter(|_| gdscript_stubs);
+                std::iter::once(gdns).chain(stub)
+            })
+            .collect::<HashSet<_>>();
+
+        libraries
+            .into_iter()
+            .map(|(lib_name, classes)| {
+                let mut builder = self.clone();
+                builder.with_lib_name(lib_name);
+                builder.other_class_files = all_class_files.clone();
+                builder.build(classes)
+            })
+            .collect()
+    }
+
+    /// Resolve all paths and render all files like [`build`](Self::build),
+    /// but return the planned file operations instead of performing them.
+    ///
+    /// Existing files are read to decide what would happen to them, but
+    /// nothing is written, created or removed.
+    pub fn dry_run(self, classes: Classes) -> Result<Vec<PlannedWrite>, GenerateError> {
+        Ok(self.plan(classes, &FsSink::new())?.writes)
+    }
+
+    /// Render all files like [`build`](Self::build), but return their paths
+    /// and contents, ordered by path, instead of writing them.
+    ///
+    /// Every generated file is included, regardless of the overwrite policy
+    /// and of what is on disk, so the output can be tested, post-processed
+    /// or written through another IO layer. Stale files that `build` would
+    /// remove are not included. Libraries are not staged and the manifest is
+    /// not updated.
+    pub fn build_to_map(
+        mut self,
+        classes: Classes,
+    ) -> Result<BTreeMap<PathBuf, String>, GenerateError> {
+        self.overwrite_policy = Some(OverwritePolicy::IfContentDiffers);
+
+        Ok(self
+            .plan(classes, &FsSink::new())?
+            .writes
+            .into_iter()
+            .filter(|write| write.action != FileAction::Removed)
+            .map(|write| (write.path, write.content))
+            .collect())
+    }
+
+    /// Compare the files that would be generated with the files on disk,
+    /// without modifying anything.
+    ///
+    /// This reports files that are missing, files whose content is out of
+    /// date, for example because the library paths changed, and generated
+    /// `.gdns` files of classes that no longer exist. The overwrite policy is
+    /// not taken into account, every difference is reported.
+    pub fn check(mut self, classes: Classes) -> Result<CheckReport, GenerateError> {
+        self.overwrite_policy = Some(OverwritePolicy::IfContentDiffers);
+        self.prune_stale = true;
+
+        let writes = self.plan(classes, &FsSink::new())?.writes;
+
+        let mut issues = vec![];
+
+        for write in writes {
+            let issue = match write.action {
+                FileAction::Created => CheckIssue::Missing(write.path),
+                FileAction::Updated => CheckIssue::OutOfDate {
+                    actual: std::fs::read_to_string(&write.path)?,
+                    expected: write.content,
+                    path: write.path,
+                },
+                FileAction::Removed => CheckIssue::Orphaned(write.path),
+                FileAction::Unchanged | FileAction::Skipped => continue,
+            };
+
+            issues.push(issue);
+        }
+
+        Ok(CheckReport { issues })
+    }
+
+    /// Remove the files the generator created in the resource output
+    /// directory, and the manifest recording them.
+    ///
+    /// Only the files listed in the manifest are removed, and only if they
+    /// still have the generated content, unless the overwrite policy is
+    /// [`OverwritePolicy::IfContentDiffers`]. Modified files are kept and
+    /// listed as [`FileAction::Skipped`]. The entries of removed `.gdns`
+    /// files in the `_global_script_classes` of `project.godot` are removed
+    /// too, unless it was changed by hand since the classes were registered,
+    /// but staged libraries and other user-owned files are left untouched.
+    /// Only the Godot project directory and the resource output directory
+    /// have to be known.
+    ///
+    /// Files are written, removed and backed up like [`build`](Self::build)
+    /// does, and the [`on_file_written`](Self::on_file_written) and
+    /// [`on_skip`](Self::on_skip) callbacks are called for them.
+    pub fn clean(self) -> Result<Vec<(PathBuf, FileAction)>, GenerateError> {
+        let resolution = self.path_resolution.unwrap_or(PathResolution::Canonical);
+        let resolve = |path| resolution.resolve(path);
+        let canonicalize = |path| resolution.canonicalize(path);
+
+        let godot_project_dir = match self.godot_project_dir {
+            Some(dir) => dir,
+            None if self.auto_detect_project => {
+                let crate_dir = self
+                    .crate_dir
+                    .or_else(|| std::env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from))
+                    .ok_or(GenerateError::MissingField("godot_project_dir"))
+                    .and_then(canonicalize)?;
+                crate::project_dir::find(&crate_dir)?
+            }
+            None => return Err(GenerateError::MissingField("godot_project_dir")),
+        };
+        let godot_project_dir = canonicalize(godot_project_dir)?;
+        let godot_resource_output_dir = resolve(
+            self.godot_resource_output_dir
+                .unwrap_or_else(|| godot_project_dir.join("native")),
+        )?;
+        let force = matches!(
+            self.overwrite_policy,
+            Some(OverwritePolicy::IfContentDiffers)
+        );
+        let mut sink = FsSink::new().sync(self.sync_writes);
+
+        let mut manifest = Manifest::load(&godot_resource_output_dir, &sink)?;
+        let generated = manifest
+            .paths(&godot_resource_output_dir)
+            .map(|(file, path)| (file.to_string(), path))
+            .collect::<Vec<_>>();
+
+        let mut writes = vec![];
+        for (file, path) in generated {
+            // Files outside the Godot project, like a `.gdignore` in a target
+            // directory that moved, are not removed.
+            if !absolute(&path)?.starts_with(&godot_project_dir) {
+                continue;
+            }
+            if !sink.exists(&path) {
+                // Already removed by hand.
+                manifest.remove(&file);
+                continue;
+            }
+
+            let modified = !manifest.is_unmodified(&file, &sink.read(&path)?);
+            let action = if force || !modified {
+                manifest.remove(&file);
+                FileAction::Removed
+            } else {
+                FileAction::Skipped
+            };
+            writes.push(PlannedWrite {
+                backup: Some(backup_path(&path))
+                    .filter(|_| action == FileAction::Removed && modified),
+                path,
+                content: String::new(),
+                action,
+            });
+        }
+
+        // Godot fails to resolve the registered classes of removed scripts.
+        let removed_scripts = writes
+            .iter()
+            .filter(|write| {
+                write.action == FileAction::Removed
+                    && write.path.extension() == Some("gdns".as_ref())
+            })
+            .map(|write| resource_path(&write.path, &godot_project_dir))
+            .collect::<Result<HashSet<_>, _>>()?;
+        let project_path = godot_project_dir.join("project.godot");
+
+        if !removed_scripts.is_empty() {
+            let policy = if force {
+                OverwritePolicy::IfContentDiffers
+            } else {
+                OverwritePolicy::IfGeneratedByUs
+            };
+            let write = plan_user_file(
+                project_path.clone(),
+                &godot_resource_output_dir,
+                policy,
+                &mut manifest,
+                &sink,
+                |existing| {
+                    let invalid_file = |error| GenerateError::InvalidFile {
+                        path: project_path.clone(),
+                        error,
+                    };
+                    let mut project = ConfigFile::parse(existing).map_err(invalid_file)?;
+                    crate::global_classes::update(&mut project, &[], &removed_scripts)
+                        .map_err(invalid_file)?;
+                    Ok(Some(project.to_string()))
+                },
+            )?;
+            writes.extend(write.filter(|write| write.action != FileAction::Unchanged));
+        }
+
+        if !self.backups.unwrap_or(true) {
+            for write in &mut writes {
+                write.backup = None;
+            }
+        }
+        let files = apply_writes(
+            writes,
+            &mut sink,
+            &self.on_file_written,
+            &self.on_skip,
+            &mut vec![],
+        )?;
+
+        if manifest.is_empty() {
+            let path = godot_resource_output_dir.join(crate::manifest::MANIFEST_FILE_NAME);
+            if sink.exists(&path) {
+                sink.remove(&path)?;
+            }
+        } else {
+            manifest.save(&godot_resource_output_dir, &mut sink)?;
+        }
+
+        Ok(files)
+    }
+
+    /// Point the `.gdns` files that refer to an old location of the library
+    /// resource at its current location, after the resource output directory
+    /// or the name of the library changed.
+    ///
+    /// A `.gdns` file in `scope` is retargeted if its library is not the
+    /// current one, and either has the same file name and no longer exists or
+    /// was generated into a previous resource output directory, as after
+    /// moving the resource output directory, or no longer exists and the
+    /// `.gdns` file is in the resource output directory, as after renaming
+    /// the library. Other `.gdns` files, like the ones of addons, and files
+    /// Godot can not read either, are left alone.
+    ///
+    /// Files are written and backed up like [`build`](Self::build) does, and
+    /// the [`on_file_written`](Self::on_file_written) callbacks are called
+    /// for them. Returns the files that were updated. Nothing is generated,
+    /// and GDExtension libraries have no `.gdns` files to repair.
+    pub fn repair(self, scope: RepairScope) -> Result<Vec<(PathBuf, FileAction)>, GenerateError> {
+        let mut sink = FsSink::new().sync(self.sync_writes);
+        let backups = self.backups.unwrap_or(true);
+        let on_file_written = self.on_file_written.clone();
+
+        let report = self.plan(Classes::new(), &sink)?.report;
+        let library = match &report.lib_resource_path {
+            Some(library) => library,
+            None => return Ok(vec![]),
+        };
+        let output_dir = &report.godot_resource_output_dir;
+        let lib_file_name = report.lib_path.file_name();
+
+        let files = match scope {
+            RepairScope::OutputDir => {
+                text_resource_files(output_dir, &report.target_dir, &["gdns"])
+            }
+            RepairScope::Project => {
+                text_resource_files(&report.godot_project_dir, &report.target_dir, &["gdns"])
+            }
+        };
+
+        let mut manifests = BTreeMap::new();
+        let mut repaired = vec![];
+
+        for path in files {
+            let existing = sink.read(&path)?;
+            let mut gdns = match Gdns::parse(&existing) {
+                Ok(gdns) => gdns,
+                Err(_) => continue,
+            };
+            if gdns.library == *library {
+                continue;
+            }
+
+            let old_path = match gdns.library.strip_prefix("res://") {
+                Some(rel_path) => report.godot_project_dir.join(rel_path),
+                None => PathBuf::from(&gdns.library),
+            };
+            // A library of the same name that still exists may belong to
+            // someone else, e.g. an addon.
+            let moved = old_path.file_name() == lib_file_name
+                && (!sink.exists(&old_path) || was_generated(&old_path, &sink)?);
+            let renamed = !sink.exists(&old_path) && path.starts_with(output_dir);
+            if !moved && !renamed {
+                continue;
+            }
+
+            info!(
+                "Pointing `{}` at `{}` instead of `{}`",
+                path.display(),
+                library,
+                gdns.library
+            );
+            gdns.library = library.clone();
+            let content = gdns.to_string();
+
+            // Generated files stay recognized as unmodified, also in the
+            // manifest of a previous resource output directory.
+            let manifest_dir = path
+                .ancestors()
+                .skip(1)
+                .take_while(|dir| dir.starts_with(&report.godot_project_dir))
+                .find(|dir| sink.exists(&dir.join(crate::manifest::MANIFEST_FILE_NAME)));
+            let mut modified = true;
+            if let Some(dir) = manifest_dir {
+                let manifest = match manifests.entry(dir.to_path_buf()) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => entry.insert(Manifest::load(dir, &sink)?),
+                };
+                let file = path.strip_prefix(dir).unwrap_or(&path).to_slash_lossy();
+                if manifest.is_unmodified(&file, &existing) {
+                    manifest.insert(file, &content);
+                    modified = false;
+                }
+            }
+
+            if modified && backups {
+                let backup = backup_path(&path);
+                sink.write(&backup, &existing)?;
+                info!("Backed up `{}` as `{}`", path.display(), backup.display());
+            }
+            sink.write(&path, &content)?;
+            for f in &on_file_written {
+                f(&path, FileAction::Updated);
+            }
+
+            repaired.push((path, FileAction::Updated));
+        }
+
+        for (dir, manifest) in manifests {
+            manifest.save(&dir, &mut sink)?;
+        }
+
+        Ok(repaired)
+    }
+}
+
+/// Perform the planned `writes` through `sink`, backing up files first and
+/// calling the callbacks, and return the files with what was done to them.
+///
+/// The backups that were made are added to `backups`.
+fn apply_writes(
+    writes: Vec<PlannedWrite>,
+    sink: &mut dyn FileSink,
+    on_file_written: &[FileWrittenHook],
+    on_skip: &[SkipHook],
+    backups: &mut Vec<(PathBuf, PathBuf)>,
+) -> Result<Vec<(PathBuf, FileAction)>, GenerateError> {
+    let mut files = vec![];
+
+    for write in writes {
+        if let Some(backup) = &write.backup {
+            sink.write(backup, &sink.read(&write.path)?)?;
+            info!(
+                "Backed up `{}` as `{}`",
+                write.path.display(),
+                backup.display()
+            );
+            backups.push((write.path.clone(), backup.clone()));
+        }
+
+        match write.action {
+            FileAction::Created | FileAction::Updated => sink.write(&write.path, &write.content)?,
+            FileAction::Removed => sink.remove(&write.path)?,
+            FileAction::Unchanged | FileAction::Skipped => {}
+        }
+
+        match write.action {
+            FileAction::Created | FileAction::Updated | FileAction::Removed => {
+                info!("{:?}: {}", write.action, write.path.display());
+                for f in on_file_written {
+                    f(&write.path, write.action);
+                }
+            }
+            FileAction::Skipped => {
+                warn!(
+                    "Skipped `{}`, it was modified and the overwrite policy forbids \
+                     overwriting it",
+                    write.path.display()
+                );
+                for f in on_skip {
+                    f(&write.path);
+                }
+            }
+            FileAction::Unchanged => trace!("Unchanged: {}", write.path.display()),
+        }
+
+        files.push((write.path, write.action));
+    }
+
+    Ok(files)
+}
+
+/// A [`Builder`] whose configuration was checked by
+/// [`Builder::finish`], with the settings found from the environment set
+/// explicitly.
+///
+/// It can only build, so code that takes a `Configured` can not be handed a
+/// Builder that misses a required setting. Use
+/// [`into_builder`](Self::into_builder) to change settings or for the other
+/// operations of the Builder.
+#[derive(Clone)]
+pub struct Configured {
+    builder: Builder,
+}
+
+impl Configured {
+    /// Build and generate files for the crate and all `classes`, see
+    /// [`Builder::build`].
+    pub fn build(self, classes: Classes) -> Result<Report, GenerateError> {
+        self.builder.build(classes)
+    }
+
+    /// Generate files for the crate and all `classes` through `sink`, see
+    /// [`Builder::build_with_sink`].
+    pub fn build_with_sink(
+        self,
+        classes: Classes,
+        sink: &mut impl FileSink,
+    ) -> Result<Report, GenerateError> {
+        self.builder.build_with_sink(classes, sink)
+    }
+
+    /// Find the classes with `scanner` and generate files for them, see
+    /// [`Builder::build_with_scanner`].
+    pub fn build_with_scanner(self, scanner: impl Scanner) -> Result<Report, GenerateError> {
+        self.builder.build_with_scanner(scanner)
+    }
+
+    /// The Builder with the checked settings.
+    pub fn into_builder(self) -> Builder {
+        self.builder
+    }
+}
+
+/// A file operation planned by [`Builder::dry_run`].
+#[derive(Clone, Debug)]
+pub struct PlannedWrite {
+    /// The path of the file.
+    pub path: PathBuf,
+    /// The generated content of the file, empty for removed files.
+    pub content: String,
+    /// What would happen to the file.
+    pub action: FileAction,
+    /// Where the existing file is copied to before it is overwritten or
+    /// removed, because it was modified by hand.
+    pub backup: Option<PathBuf>,
+}
+
+/// Error type for errors that can occur during generation.
+///
+/// Some variants only exist with the features that can cause them, so the
+/// enum is not exhaustive.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum GenerateError {
+    /// A required setting was not given and could not be found from the
+    /// environment.
+    MissingField(&'static str),
+    /// A path could not be resolved, usually because it does not exist.
+    InvalidPath {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    /// An existing file could not be parsed.
+    InvalidFile { path: PathBuf, error: ParseError },
+    /// A TOML file, like `Cargo.toml` or `gdnative.toml`, could not be
+    /// parsed.
+    InvalidToml {
+        path: PathBuf,
+        error: Box<toml::de::Error>,
+    },
+    /// No relative path could be created from `base` to `path`.
+    DiffFailure { path: PathBuf, base: PathBuf },
+    /// The crate does not build a `cdylib`, so Godot can not load it.
+    InvalidCrateType {
+        package: String,
+        crate_types: Vec<String>,
+    },
+    /// Running `cargo metadata` failed.
+    #[cfg(feature = "cargo_metadata")]
+    Metadata(cargo_metadata::Error),
+    /// The built library does not export the symbols Godot looks for.
+    #[cfg(feature = "object")]
+    Symbols(crate::symbols::SymbolError),
+    /// The sources could not be watched.
+    #[cfg(feature = "notify")]
+    Watch(notify::Error),
+    /// A file has to be referred to with a `res://` path because of
+    /// [`PathStyle::ForceRes`], but is outside the Godot project.
+    OutsideProject { path: PathBuf },
+    /// The Godot project directory was searched for, but several were
+    /// found.
+    AmbiguousProject { candidates: Vec<PathBuf> },
+    /// The template set with `template` has an unknown or unclosed
+    /// placeholder.
+    InvalidTemplate {
+        template: &'static str,
+        placeholder: String,
+    },
+    /// The environment variable `var` has an invalid value.
+    InvalidEnvVar { var: String, value: String },
+    /// An external command exited unsuccessfully.
+    CommandFailed { command: String, stderr: String },
+    /// The classes could not be found.
+    Scan(ScanError),
+    /// An error was encountered when creating a directory or writing a file.
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenerateError::MissingField(field) => f.write_fmt(format_args!(
+                "`{}` was not given and could not be found from the environment",
+                field
+            )),
+            GenerateError::InvalidPath { path, error } => {
+                f.write_fmt(format_args!("Invalid path `{}`: {}", path.display(), error))
+            }
+            GenerateError::InvalidFile { path, error } => f.write_fmt(format_args!(
+                "Unable to parse `{}`: {}",
+                path.display(),
+                error
+            )),
+            GenerateError::InvalidToml { path, error } => f.write_fmt(format_args!(
+                "Unable to parse `{}`: {}",
+                path.display(),
+                error
+            )),
+            GenerateError::DiffFailure { path, base } => f.write_fmt(format_args!(
+                "Unable to create relative path from `{}` to `{}`",
+                base.display(),
+                path.display()
+            )),
+            GenerateError::InvalidCrateType {
+                package,
+                crate_types,
+            } => f.write_fmt(format_args!(
+                "`{}` must have the crate type `cdylib` to be loaded by Godot, but has {:?}. \
+                 Add `crate-type = [\"cdylib\"]` to the `[lib]` section of its Cargo.toml",
+                package, crate_types
+            )),
+            #[cfg(feature = "cargo_metadata")]
+            GenerateError::Metadata(err) => {
+                f.write_fmt(format_args!("Unable to run `cargo metadata`: {}", err))
+            }
+            #[cfg(feature = "object")]
+            GenerateError::Symbols(err) => f.write_fmt(format_args!("Invalid library: {}", err)),
+            #[cfg(feature = "notify")]
+            GenerateError::Watch(err) => {
+                f.write_fmt(format_args!("Unable to watch the sources: {}", err))
+            }
+            GenerateError::OutsideProject { path } => f.write_fmt(format_args!(
+                "`{}` is outside the Godot project and has no `res://` path",
+                path.display()
+            )),
+            GenerateError::AmbiguousProject { candidates } => f.write_fmt(format_args!(
+                "Found several Godot projects, set `godot_project_dir` to one of {}",
+                candidates
+                    .iter()
+                    .map(|dir| format!("`{}`", dir.display()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+            GenerateError::InvalidTemplate {
+                template,
+                placeholder,
+            } => f.write_fmt(format_args!(
+                "Unknown placeholder `{{{}}}` in `{}`",
+                placeholder, template
+            )),
+            GenerateError::InvalidEnvVar { var, value } => f.write_fmt(format_args!(
+                "Invalid value `{}` of the environment variable `{}`",
+                value, var
+            )),
+            GenerateError::CommandFailed { command, stderr } => {
+                f.write_fmt(format_args!("`{}` failed: {}", command, stderr.trim()))
+            }
+            GenerateError::Scan(err) => f.write_fmt(format_args!("Scanning error: {}", err)),
+            GenerateError::Io(err) => f.write_fmt(format_args!("IO error: {}", err)),
+        }
+    }
+}
+
+impl std::error::Error for GenerateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GenerateError::InvalidPath { error, .. } => Some(error),
+            GenerateError::InvalidFile { error, .. } => Some(error),
+            GenerateError::InvalidToml { error, .. } => Some(error.as_ref()),
+            GenerateError::Scan(err) => Some(err),
+            GenerateError::Io(err) => Some(err),
+            #[cfg(feature = "cargo_metadata")]
+            GenerateError::Metadata(err) => Some(err),
+            #[cfg(feature = "object")]
+            GenerateError::Symbols(err) => Some(err),
+            #[cfg(feature = "notify")]
+            GenerateError::Watch(err) => Some(err),
+            GenerateError::MissingField(_)
+            | GenerateError::InvalidCrateType { .. }
+            | GenerateError::DiffFailure { .. }
+            | GenerateError::OutsideProject { .. }
+            | GenerateError::AmbiguousProject { .. }
+            | GenerateError::InvalidTemplate { .. }
+            | GenerateError::InvalidEnvVar { .. }
+            | GenerateError::CommandFailed { .. } => None,
+        }
+    }
+}
+
+#[cfg(feature = "cargo_metadata")]
+impl From<cargo_metadata::Error> for GenerateError {
+    fn from(err: cargo_metadata::Error) -> Self {
+        GenerateError::Metadata(err)
+    }
+}
+
+#[cfg(feature = "object")]
+impl From<crate::symbols::SymbolError> for GenerateError {
+    fn from(err: crate::symbols::SymbolError) -> Self {
+        GenerateError::Symbols(err)
+    }
+}
+
+#[cfg(feature = "notify")]
+impl From<notify::Error> for GenerateError {
+    fn from(err: notify::Error) -> Self {
+        GenerateError::Watch(err)
+    }
+}
+
+impl From<ScanError> for GenerateError {
+    fn from(err: ScanError) -> Self {
+        GenerateError::Scan(err)
+    }
+}
+
+impl From<std::io::Error> for GenerateError {
+    fn from(err: std::io::Error) -> Self {
+        GenerateError::Io(err)
+    }
+}
+
+/// Convert a `PascalCase` name to `snake_case`.
+///
+/// Acronyms stay together, so `HTTPServer` becomes `http_server`, and digits
+/// stay attached to the word before them, so `Node2D` becomes `node2d`.
+fn snake_case(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut snake = String::with_capacity(name.len() + 4);
+
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lowercase = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+
+            let word_start = prev.is_lowercase()
+                || (prev.is_uppercase() && next_lowercase)
+                || (prev.is_ascii_digit() && next_lowercase);
+
+            if word_start && !snake.ends_with('_') {
+                snake.push('_');
+            }
+        }
+
+        snake.extend(c.to_lowercase());
+    }
+
+    snake
+}
+
+/// Whether the file at `path` is recorded in the manifest of its directory,
+/// i.e. whether it was generated into a resource output directory.
+fn was_generated(path: &Path, sink: &dyn FileSink) -> Result<bool, GenerateError> {
+    match (path.parent(), path.file_name()) {
+        (Some(dir), Some(file_name))
+            if sink.exists(&dir.join(crate::manifest::MANIFEST_FILE_NAME)) =>
+        {
+            Ok(Manifest::load(dir, sink)?.contains(&file_name.to_string_lossy()))
+        }
+        _ => Ok(false),
+    }
+}
+
+/// The path of the backup of the file at `path`.
+fn backup_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".bak");
+    path.with_file_name(file_name)
+}
+
+/// Canonicalize `path`, which does not need to exist yet as long as one of
+/// its ancestors does.
+fn resolve(path: PathBuf) -> Result<PathBuf, GenerateError> {
+    if path.exists() {
+        return canonicalize(path);
+    }
+
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => {
+            Ok(resolve(parent.to_path_buf())?.join(name))
+        }
+        _ => canonicalize(path),
+    }
+}
+
+fn canonicalize(path: PathBuf) -> Result<PathBuf, GenerateError> {
+    dunce::canonicalize(&path).map_err(|error| GenerateError::InvalidPath { path, error })
+}
+
+/// The relative path from `base` to `path`.
+///
+/// A verbatim (`\\?\C:\...`) and a regular path to the same directory have
+/// different prefixes, so both are simplified first. Otherwise the difference
+/// would climb up to the root and append the verbatim path.
+fn diff_paths(path: &Path, base: &Path) -> Result<PathBuf, GenerateError> {
+    pathdiff::diff_paths(dunce::simplified(path), dunce::simplified(base)).ok_or_else(|| {
+        GenerateError::DiffFailure {
+            path: path.to_path_buf(),
+            base: base.to_path_buf(),
+        }
+    })
+}
+
+/// Writes the paths of the libraries and the library resource in the
+/// [`PathStyle`] of the generator.
+struct ResourcePaths {
+    style: PathStyle,
+    godot_project_dir: PathBuf,
+}
+
+impl ResourcePaths {
+    /// The path of the file at the resolved `path` as it is written into a
+    /// resource.
+    fn resource_path(&self, path: &Path) -> Result<String, GenerateError> {
+        let rel_path = Some(diff_paths(path, &self.godot_project_dir)?)
+            .filter(|rel_path| !rel_path.starts_with(".."));
+
+        match (self.style, rel_path) {
+            (PathStyle::ForceAbsolute, _) | (PathStyle::Auto, None) => {
+                Ok(absolute_resource_path(path))
+            }
+            (_, Some(rel_path)) => Ok(format!("res://{}", rel_path.to_slash_lossy())),
+            (PathStyle::ForceRes, None) => Err(GenerateError::OutsideProject {
+                path: path.to_path_buf(),
+            }),
+        }
+    }
+}
+
+/// `path` made absolute without resolving symlinks, with `.` and `..`
+/// components removed.
+fn absolute(path: &Path) -> Result<PathBuf, GenerateError> {
+    let mut absolute = PathBuf::new();
+    for component in std::env::current_dir()?.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                absolute.pop();
+            }
+            component => absolute.push(component),
+        }
+    }
+    Ok(dunce::simplified(&absolute).to_path_buf())
+}
+
+/// The `[general]` section of a `.gdnlib` file.
+#[derive(Clone, Debug)]
+struct GeneralSettings {
+    singleton: bool,
+    load_once: bool,
+    symbol_prefix: String,
+    reloadable: bool,
+}
+
+impl Default for GeneralSettings {
+    fn default() -> Self {
+        GeneralSettings {
+            singleton: false,
+            load_once: true,
+            symbol_prefix: "godot_".to_string(),
+            reloadable: true,
+        }
+    }
+}
+
+impl GeneralSettings {
+    /// Replace the settings that are overridden.
+    fn apply(&mut self, overrides: &GeneralOverrides) {
+        self.singleton = overrides.singleton.unwrap_or(self.singleton);
+        self.load_once = overrides.load_once.unwrap_or(self.load_once);
+        self.reloadable = overrides.reloadable.unwrap_or(self.reloadable);
+        if let Some(prefix) = &overrides.symbol_prefix {
+            self.symbol_prefix = prefix.clone();
+        }
+    }
+}
+
+/// Where the installed targets that entries are generated for come from.
+#[derive(Clone, Debug)]
+enum InstalledTargets {
+    Detect,
+    Listed(Vec<String>),
+}
+
+/// `[general]` settings of a `.gdnlib` file that were set, for every build
+/// mode or for one of them.
+#[derive(Clone, Debug, Default)]
+struct GeneralOverrides {
+    singleton: Option<bool>,
+    load_once: Option<bool>,
+    symbol_prefix: Option<String>,
+    reloadable: Option<bool>,
+}
+
+/// The libraries the entries of a library resource point at.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Binaries(BTreeMap<Platform, PathBuf>);
+
+impl Binaries {
+    /// The path of the library of `platform`, if it has an entry.
+    pub fn get(&self, platform: Platform) -> Option<&Path> {
+        self.0.get(&platform).map(PathBuf::as_path)
+    }
+
+    /// Returns `true` if there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterate over the platforms and their libraries in generation order.
+    pub fn iter(&self) -> impl Iterator<Item = (Platform, &Path)> + '_ {
+        self.0
+            .iter()
+            .map(|(platform, path)| (*platform, path.as_path()))
+    }
+}
+
+/// The path of the library at `path` as it is written into the library
+/// resource: a `res://` path inside the Godot project, an absolute path
+/// otherwise.
+fn resource_path(path: &Path, godot_project_dir: &Path) -> Result<String, GenerateError> {
+    let rel_path = diff_paths(path, godot_project_dir)?;
+
+    if rel_path.starts_with("../") {
+        Ok(absolute_resource_path(path))
+    } else {
+        Ok(format!("res://{}", rel_path.to_slash_lossy()))
+    }
+}
 
-        Ok(())
-    }
-}
-
-struct Binaries {
-    x11: PathBuf,
-    osx: PathBuf,
-    // TODO
-    // ios: PathBuf,
-    windows: PathBuf,
-    android_aarch64: PathBuf,
-    android_armv7: PathBuf,
-    android_x86: PathBuf,
-    android_x86_64: PathBuf,
-}
-
-fn common_binary_outputs(target: &Path, mode: BuildMode, name: &str) -> Binaries {
-    let mode_path = match mode {
-        BuildMode::Debug => "debug",
-        BuildMode::Release => "release",
-    };
-
-    // NOTE: If a crate has a hyphen in the name, at least on Linux the resulting library
-    // will have it replaced with an underscore. I assume other platforms do the same?
-    let name = name.replace("-", "_");
-
-    Binaries {
-        x11: target.join(mode_path).join(format!("lib{}.so", name)),
-        osx: target.join(mode_path).join(format!("lib{}.dylib", name)),
-
-        windows: target.join(mode_path).join(format!("{}.dll", name)),
-        android_armv7: target
-            .join("armv7-linux-androideabi")
-            .join(mode_path)
-            .join(format!("lib{}.so", name)),
-        android_aarch64: target
-            .join("aarch64-linux-android")
-            .join(mode_path)
-            .join(format!("lib{}.so", name)),
-        android_x86: target
-            .join("i686-linux-android")
-            .join(mode_path)
-            .join(format!("lib{}.so", name)),
-        android_x86_64: target
-            .join("x86_64-linux-android")
-            .join(mode_path)
-            .join(format!("lib{}.so", name)),
-    }
-}
-
-fn generate_tres(path_prefix: &str, binaries: Binaries) -> String {
-    format!(
-        r#"[gd_resource type="GDNativeLibrary" format=2]
-
-[resource]
-entry/Android.armeabi-v7a="{prefix}{android_armv7}"
-entry/Android.arm64-v8a="{prefix}{android_aarch64}"
-entry/Android.x86="{prefix}{android_x86}"
-entry/Android.x86_64="{prefix}{android_x86_64}"
-entry/X11.64="{prefix}{x11}"
-entry/OSX.64="{prefix}{osx}"
-entry/Windows.64="{prefix}{win}"
-dependency/Android.armeabi-v7a=[  ]
-dependency/Android.arm64-v8a=[  ]
-dependency/Android.x86=[  ]
-dependency/Android.x86_64=[  ]
-dependency/X11.64=[  ]
-dependency/OSX.64=[  ]
-"#,
-        prefix = path_prefix,
-        android_armv7 = binaries.android_armv7.to_slash_lossy(),
-        android_aarch64 = binaries.android_aarch64.to_slash_lossy(),
-        android_x86 = binaries.android_x86.to_slash_lossy(),
-        android_x86_64 = binaries.android_x86_64.to_slash_lossy(),
-        x11 = binaries.x11.to_slash_lossy(),
-        osx = binaries.osx.to_slash_lossy(),
-        win = binaries.windows.to_slash_lossy(),
-    )
-}
-
-fn generate_gdnlib(path_prefix: &str, binaries: Binaries) -> String {
-    format!(
-        r#"[entry]
-Android.armeabi-v7a="{prefix}{android_armv7}"
-Android.arm64-v8a="{prefix}{android_aarch64}"
-Android.x86="{prefix}{android_x86}"
-Android.x86_64="{prefix}{android_x86_64}"
-X11.64="{prefix}{x11}"
-OSX.64="{prefix}{osx}"
-Windows.64="{prefix}{win}"
-
-[dependencies]
-
-Android.armeabi-v7a=[  ]
-Android.arm64-v8a=[  ]
-Android.x86=[  ]
-Android.x86_64=[  ]
-X11.64=[  ]
-OSX.64=[  ]
-
-[general]
-
-singleton=false
-load_once=true
-symbol_prefix="godot_"
-reloadable=true"#,
-        prefix = path_prefix,
-        android_armv7 = binaries.android_armv7.to_slash_lossy(),
-        android_aarch64 = binaries.android_aarch64.to_slash_lossy(),
-        android_x86 = binaries.android_x86.to_slash_lossy(),
-        android_x86_64 = binaries.android_x86_64.to_slash_lossy(),
-        x11 = binaries.x11.to_slash_lossy(),
-        osx = binaries.osx.to_slash_lossy(),
-        win = binaries.windows.to_slash_lossy(),
-    )
-}
-
-fn generate_gdns(path_prefix: &str, gdnlib_path: &Path, name: &str) -> String {
-    format!(
-        r#"[gd_resource type="NativeScript" load_steps=2 format=2]
-
-[ext_resource path="{prefix}{gdnlib}" type="GDNativeLibrary" id=1]
-
-[resource]
-class_name = "{name}"
-script_class_name = "{name}"
-library = ExtResource( 1 )
-"#,
-        prefix = path_prefix,
-        gdnlib = gdnlib_path.to_slash_lossy(),
-        name = name,
-    )
+/// The absolute `path` as it is written into a resource.
+///
+/// `canonicalize` returns verbatim paths on Windows, which Godot can't open,
+/// so the `\\?\` prefix is removed wherever that keeps the meaning of the
+/// path.
+fn absolute_resource_path(path: &Path) -> String {
+    dunce::simplified(path).to_slash_lossy()
 }
@@ -0,0 +1,311 @@
+//! The `.gdns` script resources of the classes and the files generated next
+//! to them.
+
+use path_slash::PathExt;
+use std::collections::HashSet;
+
+use crate::class::{Class, Classes};
+use crate::gdns::Gdns;
+use crate::global_classes::GlobalClass;
+use crate::godot_cfg::{escape, quote};
+use crate::godot_version::GodotVersion;
+use crate::manifest::ClassRecord;
+use crate::report::FileAction;
+
+use super::plan::{plan_write, CustomType, Plan, PlanContext};
+use super::{
+    resource_path, Builder, GenerateError, NamingConvention, OutputLayout, OverwritePolicy,
+};
+
+impl Builder {
+    /// Plan the scene templates of the GDExtension `classes` that have a
+    /// base class.
+    pub(super) fn plan_gdextension_scenes(
+        &self,
+        ctx: &PlanContext,
+        classes: &Classes,
+        plan: &mut Plan,
+    ) -> Result<(), GenerateError> {
+        let layout = self.layout.unwrap_or(OutputLayout::Flat);
+        let naming = self.naming.clone().unwrap_or(NamingConvention::PascalCase);
+
+        for class in classes.iter().filter(|class| class.base.is_some()) {
+            plan.writes.push(plan_write(
+                ctx.godot_resource_output_dir,
+                &scene_file_name(&gdns_file_name(layout, &naming, class)),
+                generate_gdextension_tscn(class),
+                OverwritePolicy::Never,
+                &mut plan.manifest,
+                ctx.sink,
+            )?);
+        }
+
+        Ok(())
+    }
+
+    /// Plan the `.gdns` files of `classes` pointing at the library resource
+    /// at `library`, and their GDScript stubs and scene templates.
+    pub(super) fn plan_classes(
+        &self,
+        ctx: &PlanContext,
+        classes: Classes,
+        library: &str,
+        policy: OverwritePolicy,
+        generated_policy: OverwritePolicy,
+        plan: &mut Plan,
+    ) -> Result<ClassFiles, GenerateError> {
+        let layout = self.layout.unwrap_or(OutputLayout::Flat);
+        let naming = self.naming.clone().unwrap_or(NamingConvention::PascalCase);
+        let mut class_files = ClassFiles::default();
+
+        for class in classes {
+            let file_name = gdns_file_name(layout, &naming, &class);
+
+            if self.gdscript_stubs {
+                let stub_name = stub_file_name(&file_name);
+                plan.writes.push(plan_write(
+                    ctx.godot_resource_output_dir,
+                    &stub_name,
+                    generate_gdscript_stub(&class),
+                    generated_policy,
+                    &mut plan.manifest,
+                    ctx.sink,
+                )?);
+
+                class_files.files.insert(stub_name);
+            }
+
+            let write = plan_write(
+                ctx.godot_resource_output_dir,
+                &file_name,
+                self.gdns(&class, ctx.lib_name, library)?,
+                policy,
+                &mut plan.manifest,
+                ctx.sink,
+            )?;
+
+            if plan.manifest.contains(&file_name) {
+                let record = ClassRecord {
+                    name: class.name.clone(),
+                    source_file: class.source_file.as_ref().map(|file| file.to_slash_lossy()),
+                    line: class.span.map(|span| span.start_line),
+                };
+
+                if let FileAction::Created = write.action {
+                    class_files
+                        .new_classes
+                        .push((file_name.clone(), record.clone()));
+                }
+                plan.manifest.set_class(file_name.clone(), record);
+            }
+            plan.writes.push(write);
+
+            let gdns_resource_path = resource_path(
+                &ctx.godot_resource_output_dir.join(&file_name),
+                ctx.godot_project_dir,
+            )?;
+
+            if self.scene_templates && class.base.is_some() {
+                // Scenes are edited by hand once created, so they are never
+                // overwritten.
+                plan.writes.push(plan_write(
+                    ctx.godot_resource_output_dir,
+                    &scene_file_name(&file_name),
+                    generate_tscn(&class, &gdns_resource_path),
+                    OverwritePolicy::Never,
+                    &mut plan.manifest,
+                    ctx.sink,
+                )?);
+            }
+
+            let icon = self
+                .class_icons
+                .get(&class.name)
+                .map(|icon| {
+                    let icon = ctx.resolution.resolve(ctx.godot_project_dir.join(icon))?;
+                    resource_path(&icon, ctx.godot_project_dir)
+                })
+                .transpose()?;
+
+            if self.global_script_classes {
+                class_files.global_classes.push(GlobalClass {
+                    name: class.name.clone(),
+                    base: class
+                        .base
+                        .clone()
+                        .unwrap_or_else(|| "Reference".to_string()),
+                    language: "NativeScript".to_string(),
+                    path: gdns_resource_path.clone(),
+                    icon: icon.clone().unwrap_or_default(),
+                });
+            }
+
+            if let Some(base) = &class.base {
+                class_files.custom_types.push(CustomType {
+                    name: class.name.clone(),
+                    base: base.clone(),
+                    script: gdns_resource_path,
+                    icon,
+                });
+            }
+
+            class_files.files.insert(file_name);
+        }
+
+        Ok(class_files)
+    }
+
+    /// The content of the `.gdns` file of `class`, from the
+    /// [`gdns_template`](Self::gdns_template) if there is one.
+    fn gdns(&self, class: &Class, lib_name: &str, library: &str) -> Result<String, GenerateError> {
+        match &self.gdns_template {
+            Some(template) => crate::template::render(template, |name| match name {
+                "class_name" => Some(class.name.clone()),
+                "base" => Some(class.base.as_deref().unwrap_or("Reference").to_string()),
+                "lib_name" => Some(lib_name.to_string()),
+                "gdnlib_path" => Some(escape(library)),
+                _ => None,
+            })
+            .map_err(|placeholder| GenerateError::InvalidTemplate {
+                template: "gdns_template",
+                placeholder,
+            }),
+            None => Ok(render_gdns(&GdnsConfig {
+                class_name: class.name.clone(),
+                library: library.to_string(),
+            })),
+        }
+    }
+}
+
+/// The files planned for the classes and what is registered for them.
+#[derive(Default)]
+pub(super) struct ClassFiles {
+    /// The `.gdns` files and GDScript stubs, relative to the resource output
+    /// directory.
+    pub(super) files: HashSet<String>,
+    pub(super) custom_types: Vec<CustomType>,
+    pub(super) global_classes: Vec<GlobalClass>,
+    /// The classes whose `.gdns` file is created, with the file.
+    pub(super) new_classes: Vec<(String, ClassRecord)>,
+}
+
+/// The path of the `.gdns` file of `class`, relative to the resource output
+/// directory.
+pub(super) fn gdns_file_name(
+    layout: OutputLayout,
+    naming: &NamingConvention,
+    class: &Class,
+) -> String {
+    if let Some(path) = &class.gdns_path {
+        return path.clone();
+    }
+
+    let file_name = format!("{}.gdns", naming.file_stem(&class.name));
+
+    match layout {
+        OutputLayout::Flat => file_name,
+        OutputLayout::ModuleTree => class
+            .module_path
+            .iter()
+            .map(String::as_str)
+            .chain(std::iter::once(file_name.as_str()))
+            .collect::<Vec<_>>()
+            .join("/"),
+    }
+}
+
+/// The path of the GDScript stub of the class whose `.gdns` file is at
+/// `gdns_file_name`.
+pub(super) fn stub_file_name(gdns_file_name: &str) -> String {
+    let stem = gdns_file_name
+        .strip_suffix(".gdns")
+        .unwrap_or(gdns_file_name);
+    format!("{}.stub.gd", stem)
+}
+
+/// The path of the scene template of the class whose `.gdns` file is at
+/// `gdns_file_name`.
+fn scene_file_name(gdns_file_name: &str) -> String {
+    let stem = gdns_file_name
+        .strip_suffix(".gdns")
+        .unwrap_or(gdns_file_name);
+    format!("{}.tscn", stem)
+}
+
+/// The content of a `.gdns` script resource, for [`render_gdns`].
+#[derive(Clone, Debug)]
+pub struct GdnsConfig {
+    /// The name of the class.
+    pub class_name: String,
+    /// The path of the library resource as it is written into the script
+    /// resource, e.g. `res://native/game.gdnlib`.
+    pub library: String,
+}
+
+/// Render a `.gdns` script resource the way [`Builder::build`] generates it.
+pub fn render_gdns(config: &GdnsConfig) -> String {
+    Gdns::new(config.class_name.clone(), config.library.clone()).to_string()
+}
+
+fn generate_tscn(class: &Class, gdns_path: &str) -> String {
+    format!(
+        r#"[gd_scene load_steps=2 format=2]
+
+[ext_resource path={gdns} type="Script" id=1]
+
+[node name={name} type={base}]
+script = ExtResource( 1 )
+"#,
+        gdns = quote(gdns_path),
+        name = quote(&class.name),
+        base = quote(class.base.as_deref().unwrap_or("Reference")),
+    )
+}
+
+fn generate_gdextension_tscn(class: &Class) -> String {
+    format!(
+        "[gd_scene format={format}]\n\n[node name={name} type={name}]\n",
+        format = GodotVersion::Godot4.resource_format(),
+        name = quote(&class.name),
+    )
+}
+
+fn generate_gdscript_stub(class: &Class) -> String {
+    let args = |count: usize| {
+        (0..count)
+            .map(|i| format!("arg{}", i))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let mut content = format!(
+        "# Generated by gdnative-project-utils, do not edit.\n\
+         # API of the native class `{name}` (`{path}`), for autocompletion only.\n\
+         extends {base}\n",
+        name = class.name,
+        path = class.rust_path(),
+        base = class.base.as_deref().unwrap_or("Reference"),
+    );
+
+    if !class.signals.is_empty() {
+        content.push('\n');
+    }
+    for signal in &class.signals {
+        if signal.args == 0 {
+            content.push_str(&format!("signal {}\n", signal.name));
+        } else {
+            content.push_str(&format!("signal {}({})\n", signal.name, args(signal.args)));
+        }
+    }
+
+    for method in &class.methods {
+        content.push_str(&format!(
+            "\nfunc {}({}):\n\tpass\n",
+            method.name,
+            args(method.args)
+        ));
+    }
+
+    content
+}
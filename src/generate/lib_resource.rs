@@ -0,0 +1,499 @@
+//! The `.gdnlib`, `.tres` and `.gdextension` library resources.
+
+use std::collections::BTreeMap;
+
+use crate::gdnlib::{dependency_array, Gdnlib};
+use crate::godot_cfg::{escape, quote, ConfigFile, ParseError};
+use crate::platform::{Platform, Platforms};
+use crate::report::FileAction;
+
+use super::plan::{plan_write, Plan, PlanContext};
+use super::{
+    backup_path, Builder, GeneralOverrides, GeneralSettings, GenerateError, LibFormat,
+    OutputFormat, OverwritePolicy, PlannedWrite,
+};
+
+impl Builder {
+    /// The library resources to generate, as their format and file name.
+    ///
+    /// With [`OutputFormat::Dual`] the `.gdextension` file is added to the
+    /// GDNative resource, which comes first and stays the one the `.gdns`
+    /// files point at.
+    pub(super) fn lib_resources(
+        &self,
+        output_format: OutputFormat,
+        lib_name: &str,
+    ) -> Vec<(OutputFormat, String)> {
+        let lib_file_name = |output_format, lib_format| {
+            let lib_ext = match (output_format, lib_format) {
+                (OutputFormat::GdExtension, _) => "gdextension",
+                (OutputFormat::Gdnative | OutputFormat::Dual, Some(LibFormat::Gdnlib) | None) => {
+                    "gdnlib"
+                }
+                (OutputFormat::Gdnative | OutputFormat::Dual, Some(LibFormat::Tres)) => "tres",
+            };
+            format!("{}.{}", lib_name, lib_ext)
+        };
+
+        let mut lib_resources =
+            vec![(output_format, lib_file_name(output_format, self.lib_format))];
+        if let OutputFormat::Dual = output_format {
+            lib_resources.push((
+                OutputFormat::GdExtension,
+                lib_file_name(OutputFormat::GdExtension, None),
+            ));
+        }
+        lib_resources
+    }
+
+    /// Plan writing the `lib_resources` with `content`, or updating the
+    /// existing ones if [`update_existing_lib`](Self::update_existing_lib)
+    /// is set.
+    pub(super) fn plan_lib_resources(
+        &self,
+        ctx: &PlanContext,
+        lib_resources: &[(OutputFormat, String)],
+        content: &LibContent,
+        policy: OverwritePolicy,
+        plan: &mut Plan,
+    ) -> Result<(), GenerateError> {
+        for (lib_format, lib_file_name) in lib_resources {
+            let lib_path = ctx.godot_resource_output_dir.join(lib_file_name);
+
+            if !(ctx.sink.exists(&lib_path) && self.update_existing_lib) {
+                let content = self.lib_resource(*lib_format, ctx.lib_name, content)?;
+                plan.writes.push(plan_write(
+                    ctx.godot_resource_output_dir,
+                    lib_file_name,
+                    content,
+                    policy,
+                    &mut plan.manifest,
+                    ctx.sink,
+                )?);
+                continue;
+            }
+
+            let existing = ctx.sink.read(&lib_path)?;
+            let content = self
+                .updated_lib_resource(*lib_format, &existing, content)
+                .map_err(|error| GenerateError::InvalidFile {
+                    path: lib_path.clone(),
+                    error,
+                })?;
+
+            // Edits the merge keeps are still backed up, in case it changed
+            // something the user relied on.
+            let modified = !plan.manifest.is_unmodified(lib_file_name, &existing);
+            let action = if content != existing {
+                plan.manifest.insert(lib_file_name.clone(), &content);
+                FileAction::Updated
+            } else {
+                FileAction::Unchanged
+            };
+            plan.writes.push(PlannedWrite {
+                backup: Some(backup_path(&lib_path))
+                    .filter(|_| modified && action == FileAction::Updated),
+                path: lib_path,
+                content,
+                action,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// The content of a new library resource in `lib_format`.
+    fn lib_resource(
+        &self,
+        lib_format: OutputFormat,
+        lib_name: &str,
+        content: &LibContent,
+    ) -> Result<String, GenerateError> {
+        let LibContent {
+            entries,
+            dependencies,
+            general,
+        } = *content;
+
+        Ok(match (lib_format, self.lib_format) {
+            (OutputFormat::GdExtension, _) => generate_gdextension(
+                entries,
+                dependencies,
+                self.entry_symbol.as_deref().unwrap_or("gdext_rust_init"),
+                self.compatibility_minimum.as_deref().unwrap_or("4.1"),
+            ),
+            (OutputFormat::Gdnative | OutputFormat::Dual, Some(LibFormat::Gdnlib) | None) => {
+                match &self.gdnlib_template {
+                    Some(template) => {
+                        render_gdnlib_template(template, lib_name, entries, dependencies, general)?
+                    }
+                    None => gdnlib(entries, dependencies, general).to_string(),
+                }
+            }
+            (OutputFormat::Gdnative | OutputFormat::Dual, Some(LibFormat::Tres)) => {
+                generate_tres(entries, dependencies, general)
+            }
+        })
+    }
+
+    /// The content of the `existing` library resource in `lib_format` with
+    /// the entries and dependencies of `content`, keeping everything else.
+    fn updated_lib_resource(
+        &self,
+        lib_format: OutputFormat,
+        existing: &str,
+        content: &LibContent,
+    ) -> Result<String, ParseError> {
+        let LibContent {
+            entries,
+            dependencies,
+            general,
+        } = *content;
+
+        match (lib_format, self.lib_format) {
+            (OutputFormat::Gdnative | OutputFormat::Dual, Some(LibFormat::Gdnlib) | None) => {
+                let mut lib = Gdnlib::parse(existing)?;
+                lib.merge(gdnlib(entries, dependencies, general));
+
+                // Settings made with the Builder, for every build mode or
+                // for one of them, follow it, the others are left to the
+                // editor.
+                let set = |setting: fn(&GeneralOverrides) -> bool| {
+                    setting(&self.general)
+                        || self
+                            .general_for
+                            .iter()
+                            .any(|(_, overrides)| setting(overrides))
+                };
+                if set(|overrides| overrides.singleton.is_some()) {
+                    lib.singleton = general.singleton;
+                }
+                if set(|overrides| overrides.load_once.is_some()) {
+                    lib.load_once = general.load_once;
+                }
+                if set(|overrides| overrides.symbol_prefix.is_some()) {
+                    lib.symbol_prefix = general.symbol_prefix.clone();
+                }
+                if set(|overrides| overrides.reloadable.is_some()) {
+                    lib.reloadable = general.reloadable;
+                }
+                Ok(lib.to_string())
+            }
+            _ => {
+                let mut lib = ConfigFile::parse(existing)?;
+
+                let lib_entries = lib_entries(lib_format, self.lib_format, entries, dependencies);
+                for (section, key, value) in lib_entries {
+                    lib.set(section, &key, value);
+                }
+
+                Ok(lib.to_string())
+            }
+        }
+    }
+}
+
+/// What the library resources hold, whatever their format.
+#[derive(Clone, Copy)]
+pub(super) struct LibContent<'a> {
+    pub(super) entries: &'a BTreeMap<Platform, String>,
+    pub(super) dependencies: &'a BTreeMap<Platform, Vec<String>>,
+    pub(super) general: &'a GeneralSettings,
+}
+
+/// The sets of symbols Godot looks up in a library of `output_format`, one of
+/// which it has to export.
+pub(super) fn required_symbols(
+    output_format: OutputFormat,
+    entry_symbol: Option<&str>,
+    symbol_prefix: &str,
+) -> Vec<Vec<String>> {
+    let gdextension = || vec![entry_symbol.unwrap_or("gdext_rust_init").to_string()];
+    let gdnative = || {
+        ["gdnative_init", "gdnative_terminate", "nativescript_init"]
+            .iter()
+            .map(|symbol| format!("{}{}", symbol_prefix, symbol))
+            .collect()
+    };
+
+    match output_format {
+        OutputFormat::GdExtension => vec![gdextension()],
+        OutputFormat::Gdnative => vec![gdnative()],
+        // The library is built for one of the Godot versions at a time.
+        OutputFormat::Dual => vec![gdnative(), gdextension()],
+    }
+}
+
+/// The sections, keys and values of the library entries and of the
+/// dependencies that were given, as they are stored in the library resource.
+fn lib_entries(
+    output_format: OutputFormat,
+    lib_format: Option<LibFormat>,
+    entries: &BTreeMap<Platform, String>,
+    dependencies: &BTreeMap<Platform, Vec<String>>,
+) -> Vec<(&'static str, String, String)> {
+    let value = |path: &String| quote(path);
+
+    match (output_format, lib_format) {
+        (OutputFormat::GdExtension, _) => entries
+            .iter()
+            .filter_map(|(platform, path)| {
+                Some((
+                    "libraries",
+                    platform.gdextension_key()?.to_string(),
+                    value(path),
+                ))
+            })
+            .chain(dependencies.iter().filter_map(|(platform, libs)| {
+                Some((
+                    "dependencies",
+                    platform.gdextension_key()?.to_string(),
+                    gdextension_dependency_map(libs),
+                ))
+            }))
+            .collect(),
+        (OutputFormat::Gdnative | OutputFormat::Dual, Some(LibFormat::Gdnlib) | None) => entries
+            .iter()
+            .map(|(platform, path)| ("entry", platform.gdnlib_key().to_string(), value(path)))
+            .chain(dependencies.iter().map(|(platform, libs)| {
+                (
+                    "dependencies",
+                    platform.gdnlib_key().to_string(),
+                    dependency_array(Some(libs)),
+                )
+            }))
+            .collect(),
+        (OutputFormat::Gdnative | OutputFormat::Dual, Some(LibFormat::Tres)) => entries
+            .iter()
+            .map(|(platform, path)| {
+                (
+                    "resource",
+                    format!("entry/{}", platform.gdnlib_key()),
+                    value(path),
+                )
+            })
+            .chain(dependencies.iter().map(|(platform, libs)| {
+                (
+                    "resource",
+                    format!("dependency/{}", platform.gdnlib_key()),
+                    dependency_array(Some(libs)),
+                )
+            }))
+            .collect(),
+    }
+}
+
+/// The dependencies of a platform as a dictionary of the `.gdextension` file,
+/// which maps each library to the directory it is exported to, empty for the
+/// directory of the library itself.
+fn gdextension_dependency_map(libs: &[String]) -> String {
+    format!(
+        "{{ {} }}",
+        libs.iter()
+            .map(|lib| format!("{}: \"\"", quote(lib)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+fn generate_tres(
+    entries: &BTreeMap<Platform, String>,
+    dependencies: &BTreeMap<Platform, Vec<String>>,
+    general: &GeneralSettings,
+) -> String {
+    let mut content =
+        String::from("[gd_resource type=\"GDNativeLibrary\" format=2]\n\n[resource]\n");
+
+    for (platform, path) in entries {
+        content.push_str(&format!(
+            "entry/{}={}\n",
+            platform.gdnlib_key(),
+            quote(path)
+        ));
+    }
+
+    for platform in entries.keys() {
+        content.push_str(&format!(
+            "dependency/{}={}\n",
+            platform.gdnlib_key(),
+            dependency_array(dependencies.get(platform))
+        ));
+    }
+
+    // Like Godot itself, only store properties that differ from their default value.
+    let defaults = GeneralSettings::default();
+    if general.singleton != defaults.singleton {
+        content.push_str(&format!("singleton = {}\n", general.singleton));
+    }
+    if general.load_once != defaults.load_once {
+        content.push_str(&format!("load_once = {}\n", general.load_once));
+    }
+    if general.symbol_prefix != defaults.symbol_prefix {
+        content.push_str(&format!(
+            "symbol_prefix = {}\n",
+            quote(&general.symbol_prefix)
+        ));
+    }
+    if general.reloadable != defaults.reloadable {
+        content.push_str(&format!("reloadable = {}\n", general.reloadable));
+    }
+
+    content
+}
+
+/// Render the gdnlib `template` for the library `lib_name`.
+fn render_gdnlib_template(
+    template: &str,
+    lib_name: &str,
+    entries: &BTreeMap<Platform, String>,
+    dependencies: &BTreeMap<Platform, Vec<String>>,
+    general: &GeneralSettings,
+) -> Result<String, GenerateError> {
+    crate::template::render(template, |name| match name {
+        "lib_name" => Some(lib_name.to_string()),
+        "entries" => Some(
+            entries
+                .iter()
+                .map(|(platform, path)| format!("{}={}\n", platform.gdnlib_key(), quote(path)))
+                .collect(),
+        ),
+        "dependencies" => Some(
+            entries
+                .keys()
+                .map(|platform| {
+                    format!(
+                        "{}={}\n",
+                        platform.gdnlib_key(),
+                        dependency_array(dependencies.get(platform))
+                    )
+                })
+                .collect(),
+        ),
+        "singleton" => Some(general.singleton.to_string()),
+        "load_once" => Some(general.load_once.to_string()),
+        "symbol_prefix" => Some(escape(&general.symbol_prefix)),
+        "reloadable" => Some(general.reloadable.to_string()),
+        _ => {
+            let key = name.strip_prefix("entry.")?;
+            let platform = Platforms::all()
+                .iter()
+                .find(|platform| platform.gdnlib_key() == key)?;
+            Some(
+                entries
+                    .get(&platform)
+                    .map(|path| escape(path))
+                    .unwrap_or_default(),
+            )
+        }
+    })
+    .map_err(|placeholder| GenerateError::InvalidTemplate {
+        template: "gdnlib_template",
+        placeholder,
+    })
+}
+
+/// The `.gdnlib` library resource with `entries`, `dependencies` and the
+/// `general` settings.
+fn gdnlib(
+    entries: &BTreeMap<Platform, String>,
+    dependencies: &BTreeMap<Platform, Vec<String>>,
+    general: &GeneralSettings,
+) -> Gdnlib {
+    let mut lib = Gdnlib::new();
+    lib.entries = entries.clone();
+    lib.dependencies = dependencies.clone();
+    lib.singleton = general.singleton;
+    lib.load_once = general.load_once;
+    lib.symbol_prefix = general.symbol_prefix.clone();
+    lib.reloadable = general.reloadable;
+    lib
+}
+
+fn generate_gdextension(
+    entries: &BTreeMap<Platform, String>,
+    dependencies: &BTreeMap<Platform, Vec<String>>,
+    entry_symbol: &str,
+    compatibility_minimum: &str,
+) -> String {
+    let mut content = format!(
+        r#"[configuration]
+entry_symbol = {entry_symbol}
+compatibility_minimum = {compatibility_minimum}
+
+[libraries]
+"#,
+        entry_symbol = quote(entry_symbol),
+        compatibility_minimum = quote(compatibility_minimum),
+    );
+
+    for (platform, path) in entries {
+        if let Some(key) = platform.gdextension_key() {
+            content.push_str(&format!("{} = {}\n", key, quote(path)));
+        }
+    }
+
+    if dependencies
+        .keys()
+        .any(|platform| platform.gdextension_key().is_some())
+    {
+        content.push_str("\n[dependencies]\n");
+
+        for (platform, libs) in dependencies {
+            if let Some(key) = platform.gdextension_key() {
+                content.push_str(&format!("{} = {}\n", key, gdextension_dependency_map(libs)));
+            }
+        }
+    }
+
+    content
+}
+
+/// The content of a `.gdnlib` library resource, for [`render_gdnlib`].
+#[derive(Clone, Debug)]
+pub struct GdnlibConfig {
+    /// The paths of the libraries of each platform as they are written into
+    /// the resource, e.g. `res://bin/libgame.so`.
+    pub entries: BTreeMap<Platform, String>,
+    /// The paths of the libraries each platform depends on, written into the
+    /// `[dependencies]` section.
+    pub dependencies: BTreeMap<Platform, Vec<String>>,
+    /// Whether the library is loaded as a singleton.
+    pub singleton: bool,
+    /// Whether the library is only loaded once.
+    pub load_once: bool,
+    /// The prefix of the symbols Godot looks up in the library.
+    pub symbol_prefix: String,
+    /// Whether the library can be reloaded by the editor.
+    pub reloadable: bool,
+}
+
+impl Default for GdnlibConfig {
+    fn default() -> Self {
+        let general = GeneralSettings::default();
+
+        GdnlibConfig {
+            entries: BTreeMap::new(),
+            dependencies: BTreeMap::new(),
+            singleton: general.singleton,
+            load_once: general.load_once,
+            symbol_prefix: general.symbol_prefix,
+            reloadable: general.reloadable,
+        }
+    }
+}
+
+/// Render a `.gdnlib` library resource the way [`Builder::build`] generates
+/// it.
+pub fn render_gdnlib(config: &GdnlibConfig) -> String {
+    gdnlib(
+        &config.entries,
+        &config.dependencies,
+        &GeneralSettings {
+            singleton: config.singleton,
+            load_once: config.load_once,
+            symbol_prefix: config.symbol_prefix.clone(),
+            reloadable: config.reloadable,
+        },
+    )
+    .to_string()
+}
@@ -0,0 +1,1207 @@
+//! Deciding on the file operations of a build.
+
+use path_slash::PathExt;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::cargo_config::CargoConfig;
+use crate::cargo_manifest::CargoManifest;
+use crate::class::Classes;
+use crate::file_sink::FileSink;
+use crate::global_classes::GlobalClass;
+use crate::godot_cfg::{escape, quote, unquote, ConfigFile};
+use crate::godot_resource::TextResource;
+use crate::godot_version::GodotVersion;
+use crate::manifest::{ClassRecord, Manifest};
+use crate::platform::{Platform, Platforms};
+use crate::report::{ClassRename, FileAction, Report};
+
+use super::gdns::ClassFiles;
+use super::lib_resource::{required_symbols, LibContent};
+use super::staging::XcFramework;
+use super::{
+    backup_path, diff_paths, resource_path, Binaries, BuildMode, Builder, GeneralSettings,
+    GenerateError, InstalledTargets, OutputFormat, OverwritePolicy, PathResolution, PathStyle,
+    PlannedWrite, ResourcePaths, StagingMode,
+};
+
+impl Builder {
+    /// Resolve the settings every plan needs, from the Builder or else the
+    /// environment.
+    pub(super) fn settings(&self) -> Result<Settings, GenerateError> {
+        let resolution = self.path_resolution.unwrap_or(PathResolution::Canonical);
+        let resolve = |path| resolution.resolve(path);
+        let canonicalize = |path| resolution.canonicalize(path);
+
+        let crate_dir = self
+            .crate_dir
+            .clone()
+            .or_else(|| std::env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from))
+            .map(canonicalize)
+            .transpose()?;
+        trace!("Crate directory: {:?}", crate_dir);
+
+        #[cfg(feature = "cargo_metadata")]
+        let metadata = match (self.cargo_metadata, &crate_dir) {
+            (false, _) => None,
+            (true, Some(crate_dir)) => {
+                let metadata = crate::metadata::CrateMetadata::query(crate_dir)?;
+                Some((metadata.lib_name, metadata.target_dir))
+            }
+            (true, None) => return Err(GenerateError::MissingField("crate_dir")),
+        };
+        #[cfg(not(feature = "cargo_metadata"))]
+        let metadata: Option<(String, PathBuf)> = None;
+        let (metadata_lib_name, metadata_target_dir) = metadata.unzip();
+        // `[lib] name` can differ from the package name in `CARGO_PKG_NAME`.
+        let manifest = match (&self.lib_name, &metadata_lib_name, &crate_dir) {
+            (None, None, Some(crate_dir)) => CargoManifest::read(crate_dir)?,
+            _ => None,
+        };
+        if let Some(manifest) = &manifest {
+            // Otherwise the library entries point at files that are never
+            // built.
+            manifest.check_crate_type()?;
+        }
+        let manifest_lib_name = manifest.and_then(|manifest| manifest.target_name());
+
+        let lib_name = self
+            .lib_name
+            .clone()
+            .inspect(|_| trace!("Library name given to the generator"))
+            .or_else(|| metadata_lib_name.inspect(|_| trace!("Library name from cargo metadata")))
+            .or_else(|| manifest_lib_name.inspect(|_| trace!("Library name from `Cargo.toml`")))
+            .or_else(|| {
+                let name = std::env::var("CARGO_PKG_NAME").ok()?;
+                trace!("Library name from `CARGO_PKG_NAME`");
+                Some(name)
+            })
+            .ok_or(GenerateError::MissingField("lib_name"))?;
+        debug!("Library name: {}", lib_name);
+        let godot_project_dir = match (self.godot_project_dir.clone(), &crate_dir) {
+            (Some(dir), _) => dir,
+            (None, Some(crate_dir)) if self.auto_detect_project => {
+                let dir = crate::project_dir::find(crate_dir)?;
+                trace!("Godot project directory found from the crate directory");
+                dir
+            }
+            (None, _) => return Err(GenerateError::MissingField("godot_project_dir")),
+        };
+        let godot_project_dir = canonicalize(godot_project_dir)?;
+        let godot_resource_output_dir = self
+            .godot_resource_output_dir
+            .clone()
+            .unwrap_or_else(|| godot_project_dir.join("native"));
+        // The output directory is only created when the plan is carried out.
+        let godot_resource_output_dir = resolve(godot_resource_output_dir)?;
+        debug!("Godot project directory: {}", godot_project_dir.display());
+        debug!(
+            "Resource output directory: {}",
+            godot_resource_output_dir.display()
+        );
+        // Only read when needed, as every ancestor and `CARGO_HOME` is looked
+        // at.
+        let cargo_config = match &crate_dir {
+            Some(crate_dir) if self.target_dir.is_none() || self.target_triple.is_none() => {
+                CargoConfig::discover(crate_dir)?
+            }
+            _ => CargoConfig::default(),
+        };
+        let target_dir = match (self.target_dir.clone(), metadata_target_dir) {
+            // found by `finish`, before cargo may have created it
+            (Some(path), _) if self.target_dir_resolved => path,
+            (Some(path), _) => {
+                trace!("Target directory given to the generator");
+                canonicalize(path)?
+            }
+            // cargo creates the target directory on the first build
+            (None, Some(path)) => {
+                trace!("Target directory from cargo metadata");
+                resolve(path)?
+            }
+            (None, None) => std::env::var("CARGO_TARGET_DIR")
+                .ok()
+                .and_then(|dir| canonicalize(PathBuf::from(dir)).ok())
+                .inspect(|_| trace!("Target directory from `CARGO_TARGET_DIR`"))
+                .or_else(|| {
+                    let dir = resolve(cargo_config.target_dir.clone()?).ok()?;
+                    trace!("Target directory from `build.target-dir` of the cargo config");
+                    Some(dir)
+                })
+                .or_else(|| {
+                    let dir = std::env::var("OUT_DIR").ok()?;
+                    let out_path = PathBuf::from(&dir);
+
+                    // target/{debug/release}/build/{crate}/out
+                    let target_dir = canonicalize(out_path.join("../../../../")).ok()?;
+                    debug!(
+                        "Target directory guessed from `OUT_DIR` (`{}`), set it explicitly if \
+                         the crate is built with a different layout",
+                        dir
+                    );
+                    Some(target_dir)
+                })
+                .or_else(|| {
+                    // The target directory of the crate or its workspace.
+                    let dir = crate_dir
+                        .as_ref()?
+                        .ancestors()
+                        .map(|dir| dir.join("target"))
+                        .find(|dir| dir.is_dir())?;
+                    debug!("Target directory guessed from the crate directory");
+                    Some(dir)
+                })
+                .ok_or(GenerateError::MissingField("target_dir"))?,
+        };
+        debug!("Target directory: {}", target_dir.display());
+        let build_mode = self
+            .build_mode
+            .clone()
+            .or_else(|| {
+                let profile = std::env::var("PROFILE").ok()?;
+                Some(BuildMode::from_profile(&profile))
+            })
+            .ok_or(GenerateError::MissingField("build_mode"))?;
+        debug!("Build mode: {:?}", build_mode);
+
+        let target_triple = self
+            .target_triple
+            .clone()
+            .or_else(|| std::env::var("CARGO_BUILD_TARGET").ok())
+            .or(cargo_config.target)
+            .filter(|triple| !triple.is_empty());
+        debug!("Target triple: {:?}", target_triple);
+
+        Ok(Settings {
+            lib_name,
+            godot_project_dir,
+            godot_resource_output_dir,
+            target_dir,
+            target_triple,
+            build_mode,
+        })
+    }
+
+    /// Decide on all file operations, reading existing files from `sink`.
+    pub(super) fn plan(
+        self,
+        mut classes: Classes,
+        sink: &dyn FileSink,
+    ) -> Result<Plan, GenerateError> {
+        classes.retain(|class| {
+            let keep = self.keeps_class(class);
+            if !keep {
+                debug!("Leaving out filtered class {}", class.name);
+            }
+            keep
+        });
+
+        let resolution = self.path_resolution.unwrap_or(PathResolution::Canonical);
+
+        let Settings {
+            lib_name,
+            godot_project_dir,
+            godot_resource_output_dir,
+            target_dir,
+            target_triple,
+            build_mode,
+        } = self.settings()?;
+        let paths = ResourcePaths {
+            style: self.path_style.unwrap_or(PathStyle::Auto),
+            godot_project_dir: godot_project_dir.clone(),
+        };
+
+        let mut general = GeneralSettings::default();
+        general.apply(&self.general);
+        if let Some((_, overrides)) = self
+            .general_for
+            .iter()
+            .find(|(mode, _)| *mode == build_mode)
+        {
+            general.apply(overrides);
+        }
+
+        let godot_version = match self.godot_version {
+            Some(version) => Some(version),
+            None => detect_godot_version(&godot_project_dir, sink)?,
+        };
+        debug!("Godot version: {:?}", godot_version);
+
+        let output_format = self
+            .output_format
+            .or_else(|| godot_version.map(GodotVersion::output_format))
+            .unwrap_or(OutputFormat::Gdnative);
+        debug!("Output format: {:?}", output_format);
+
+        let lib_resources = self.lib_resources(output_format, &lib_name);
+        let gdnlib_path = godot_resource_output_dir.join(&lib_resources[0].1);
+        let gdextension_path = lib_resources
+            .iter()
+            .find(|(format, _)| matches!(format, OutputFormat::GdExtension))
+            .map(|(_, file_name)| godot_resource_output_dir.join(file_name));
+
+        let overwrite_policy = self.overwrite_policy.unwrap_or(match self.staging_mode {
+            // The library resource has to follow the names of the copies.
+            Some(StagingMode::Versioned) => OverwritePolicy::IfGeneratedByUs,
+            _ => OverwritePolicy::Never,
+        });
+        // Files that only exist because they are generated follow the
+        // classes, as long as they were not modified by hand.
+        let generated_policy = match overwrite_policy {
+            OverwritePolicy::IfContentDiffers => OverwritePolicy::IfContentDiffers,
+            OverwritePolicy::Never | OverwritePolicy::IfGeneratedByUs => {
+                OverwritePolicy::IfGeneratedByUs
+            }
+        };
+
+        let report = Report {
+            godot_project_dir: godot_project_dir.clone(),
+            godot_resource_output_dir: godot_resource_output_dir.clone(),
+            target_dir: target_dir.clone(),
+            target_triple: target_triple.clone(),
+            lib_name: lib_name.clone(),
+            build_mode: build_mode.clone(),
+            godot_version,
+            output_format,
+            lib_path: gdnlib_path.clone(),
+            gdextension_path,
+            binary_path_prefix: String::new(),
+            binaries: Binaries::default(),
+            lib_resource_path: None,
+            files: vec![],
+            staged: vec![],
+            backups: vec![],
+            renames: vec![],
+            uninstalled_platforms: vec![],
+            required_symbols: required_symbols(
+                output_format,
+                self.entry_symbol.as_deref(),
+                &general.symbol_prefix,
+            ),
+        };
+        let mut plan = Plan {
+            report,
+            writes: vec![],
+            manifest: Manifest::load(&godot_resource_output_dir, sink)?,
+            artifacts: vec![],
+            dependency_artifacts: vec![],
+            universal_lib: None,
+            xcframework: None,
+        };
+
+        let ctx = PlanContext {
+            godot_project_dir: &godot_project_dir,
+            godot_resource_output_dir: &godot_resource_output_dir,
+            target_dir: &target_dir,
+            paths: &paths,
+            lib_name: &lib_name,
+            resolution,
+            sink,
+        };
+
+        let staging_dir = match &self.staging_dir {
+            Some(dir) => Some(resolution.resolve(godot_project_dir.join(dir))?),
+            None => None,
+        };
+
+        let platforms = self.entry_platforms(target_triple.as_deref(), &mut plan.report)?;
+        let mut binaries = self.plan_binaries(
+            &ctx,
+            &platforms,
+            &build_mode,
+            target_triple.as_deref(),
+            &mut plan,
+        )?;
+        if let Some(staging_dir) = &staging_dir {
+            self.stage_binaries(staging_dir, &mut binaries, &mut plan)?;
+        }
+
+        let target_base_path = staging_dir.as_ref().unwrap_or(&target_dir);
+        plan.report.binary_path_prefix = match paths.resource_path(target_base_path) {
+            Ok(path) if path.starts_with("res://") => "res://".to_string(),
+            _ => String::new(),
+        };
+
+        let entries = binaries
+            .0
+            .iter()
+            .map(|(platform, path)| Ok((*platform, paths.resource_path(path)?)))
+            .collect::<Result<BTreeMap<_, _>, GenerateError>>()?;
+        let dependencies =
+            self.plan_dependencies(&ctx, &entries, staging_dir.as_deref(), &mut plan)?;
+
+        self.plan_lib_resources(
+            &ctx,
+            &lib_resources,
+            &LibContent {
+                entries: &entries,
+                dependencies: &dependencies,
+                general: &general,
+            },
+            overwrite_policy,
+            &mut plan,
+        )?;
+        plan.report.binaries = binaries;
+
+        if self.gdignore {
+            plan_gdignore(&ctx, staging_dir.as_deref(), &mut plan)?;
+        }
+
+        if self.update_export_presets {
+            let libraries = match &staging_dir {
+                Some(staging_dir) => vec![staging_dir.join("*")],
+                None => plan.report.binaries.0.values().cloned().collect(),
+            };
+            plan_export_presets(
+                &ctx,
+                &libraries,
+                overwrite_policy,
+                &mut plan.manifest,
+                &mut plan.writes,
+            )?;
+        }
+
+        if let OutputFormat::GdExtension = output_format {
+            if self.scene_templates {
+                self.plan_gdextension_scenes(&ctx, &classes, &mut plan)?;
+            }
+
+            // GDExtension classes register themselves, there are no script resources.
+            return Ok(plan);
+        }
+
+        let library = paths.resource_path(&gdnlib_path)?;
+        plan.report.lib_resource_path = Some(library.clone());
+
+        let class_files = self.plan_classes(
+            &ctx,
+            classes,
+            &library,
+            overwrite_policy,
+            generated_policy,
+            &mut plan,
+        )?;
+
+        let other_class_files = &self.other_class_files;
+        let renamed_files = detect_class_renames(&ctx, &class_files, other_class_files, &mut plan);
+
+        if self.migrate_renames && !plan.report.renames.is_empty() {
+            let old_files = renamed_files.iter().map(String::as_str).collect::<Vec<_>>();
+            // Scenes and resources belong to the user, so they are only
+            // changed if files may be overwritten at all.
+            plan_rename_migration(
+                &ctx,
+                &plan.report.renames,
+                &old_files,
+                overwrite_policy,
+                &mut plan.manifest,
+                &mut plan.writes,
+            )?;
+        }
+
+        if let Some(plugin_name) = &self.editor_plugin {
+            plan_editor_plugin(
+                &ctx,
+                plugin_name,
+                &lib_name,
+                &class_files.custom_types,
+                generated_policy,
+                &mut plan.manifest,
+                &mut plan.writes,
+            )?;
+        }
+
+        if self.global_script_classes {
+            // Entries of `.gdns` files generated before belong to classes
+            // that may have been renamed or removed since, unless they belong
+            // to the other libraries built together with this one.
+            let replaced_scripts = plan
+                .manifest
+                .paths(&godot_resource_output_dir)
+                .filter(|(file, _)| {
+                    file.ends_with(".gdns")
+                        && (class_files.files.contains(*file) || !other_class_files.contains(*file))
+                })
+                .map(|(_, path)| resource_path(&path, &godot_project_dir))
+                .collect::<Result<HashSet<_>, _>>()?;
+
+            plan_global_classes(
+                &ctx,
+                &class_files.global_classes,
+                &replaced_scripts,
+                overwrite_policy,
+                &mut plan.manifest,
+                &mut plan.writes,
+            )?;
+        }
+
+        if self.prune_stale {
+            plan_prune_stale(&ctx, &class_files.files, other_class_files, &mut plan)?;
+        }
+
+        if !self.backups.unwrap_or(true) {
+            for write in &mut plan.writes {
+                write.backup = None;
+            }
+        }
+
+        Ok(plan)
+    }
+
+    /// The platforms entries are generated for, leaving out those without an
+    /// installed target, which are recorded in `report`.
+    fn entry_platforms(
+        &self,
+        target_triple: Option<&str>,
+        report: &mut Report,
+    ) -> Result<Platforms, GenerateError> {
+        let mut platforms = self.platforms.clone().unwrap_or_else(Platforms::common);
+        match self.html5 {
+            Some(true) => platforms.insert(Platform::Html5Wasm32),
+            Some(false) => platforms.remove(Platform::Html5Wasm32),
+            None => {}
+        }
+        if let Some(abis) = &self.android_abis {
+            for platform in Platforms::android() {
+                match abis.contains(platform) {
+                    true => platforms.insert(platform),
+                    false => platforms.remove(platform),
+                }
+            }
+        }
+        if self.host_only {
+            let host = match target_triple {
+                Some(triple) => Platform::from_target_triple(triple),
+                None => Platform::host(),
+            };
+            if host.is_none() {
+                warn!("The platform being built for has no library entry in Godot");
+            }
+            platforms = host.into_iter().collect();
+        }
+        if let Some(installed_targets) = &self.installed_targets {
+            let targets = match installed_targets {
+                InstalledTargets::Detect => rustup_installed_targets()?,
+                InstalledTargets::Listed(targets) => targets.clone(),
+            };
+            let buildable = targets
+                .iter()
+                .filter_map(|triple| Platform::from_target_triple(triple))
+                .collect::<Platforms>();
+
+            let (installed, uninstalled): (Vec<_>, Vec<_>) =
+                platforms.iter().partition(|&platform| {
+                    let built_as = match platform {
+                        Platform::Server64 => Platform::X11_64,
+                        platform => platform,
+                    };
+                    buildable.contains(built_as)
+                });
+            // Overridden binaries are not built, so they keep their entries.
+            let binary_overrides = &self.binary_overrides;
+            let uninstalled = uninstalled
+                .into_iter()
+                .filter(|platform| !binary_overrides.contains_key(platform))
+                .collect::<Vec<_>>();
+            for platform in &uninstalled {
+                warn!(
+                    "Leaving out the {} entry, no target for it is installed",
+                    platform.gdnlib_key()
+                );
+            }
+            platforms = installed.into_iter().collect();
+            report.uninstalled_platforms = uninstalled;
+        }
+
+        Ok(platforms)
+    }
+}
+
+/// The settings of [`Builder`] that every plan needs, given explicitly or
+/// found from the environment.
+pub(super) struct Settings {
+    pub(super) lib_name: String,
+    pub(super) godot_project_dir: PathBuf,
+    pub(super) godot_resource_output_dir: PathBuf,
+    pub(super) target_dir: PathBuf,
+    pub(super) target_triple: Option<String>,
+    pub(super) build_mode: BuildMode,
+}
+
+/// Everything [`Builder::build`] is going to do.
+pub(super) struct Plan {
+    pub(super) report: Report,
+    pub(super) writes: Vec<PlannedWrite>,
+    /// The state of the manifest after the writes are performed.
+    pub(super) manifest: Manifest,
+    /// Libraries to copy into the staging directory, as source and
+    /// destination.
+    pub(super) artifacts: Vec<(PathBuf, PathBuf)>,
+    /// Dependencies to copy into the staging directory, as source and
+    /// destination. They keep their names, since the libraries that link
+    /// against them look them up by name.
+    pub(super) dependency_artifacts: Vec<(PathBuf, PathBuf)>,
+    /// The universal macOS library to create with `lipo`, and the
+    /// single-architecture libraries it is created from.
+    pub(super) universal_lib: Option<(PathBuf, Vec<PathBuf>)>,
+    /// The iOS xcframework to create with `xcodebuild`.
+    pub(super) xcframework: Option<XcFramework>,
+}
+
+/// The directories and the library of a plan, shared by the features that
+/// change files of the Godot project.
+pub(super) struct PlanContext<'a> {
+    pub(super) godot_project_dir: &'a Path,
+    pub(super) godot_resource_output_dir: &'a Path,
+    pub(super) target_dir: &'a Path,
+    pub(super) paths: &'a ResourcePaths,
+    pub(super) lib_name: &'a str,
+    pub(super) resolution: PathResolution,
+    pub(super) sink: &'a dyn FileSink,
+}
+
+/// The targets listed by `rustup target list --installed`.
+fn rustup_installed_targets() -> Result<Vec<String>, GenerateError> {
+    let result = std::process::Command::new("rustup")
+        .args(["target", "list", "--installed"])
+        .output()
+        .map_err(|err| GenerateError::CommandFailed {
+            command: "rustup".to_string(),
+            stderr: err.to_string(),
+        })?;
+
+    if !result.status.success() {
+        return Err(GenerateError::CommandFailed {
+            command: "rustup".to_string(),
+            stderr: String::from_utf8_lossy(&result.stderr).into_owned(),
+        });
+    }
+
+    Ok(String::from_utf8_lossy(&result.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Pair the classes whose `.gdns` files are gone with the new classes they
+/// were probably renamed to, as indices into `stale` and `new`.
+///
+/// Classes are only paired within the same source file, even if just one
+/// class was replaced, so an unrelated class is never taken for a rename.
+fn detect_renames(
+    stale: &[(String, ClassRecord)],
+    new: &[(String, ClassRecord)],
+) -> Vec<(usize, usize)> {
+    let in_same_file = |record: &ClassRecord, others: &[(String, ClassRecord)]| {
+        others
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, other))| {
+                record.source_file.is_some() && other.source_file == record.source_file
+            })
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>()
+    };
+
+    // A pair is only certain enough if neither class has another candidate.
+    let unique_match =
+        |record: &ClassRecord, candidates: Vec<usize>, others: &[(String, ClassRecord)]| {
+            match candidates.as_slice() {
+                [idx] => Some(*idx),
+                _ => {
+                    let mut same_line = candidates
+                        .into_iter()
+                        .filter(|idx| record.line.is_some() && others[*idx].1.line == record.line);
+                    match (same_line.next(), same_line.next()) {
+                        (Some(idx), None) => Some(idx),
+                        _ => None,
+                    }
+                }
+            }
+        };
+
+    stale
+        .iter()
+        .enumerate()
+        .filter_map(|(old, (_, record))| {
+            let new_idx = unique_match(record, in_same_file(record, new), new)?;
+            let back = unique_match(&new[new_idx].1, in_same_file(&new[new_idx].1, stale), stale)?;
+            Some((old, new_idx)).filter(|_| back == old)
+        })
+        .collect()
+}
+
+/// The files with one of the `extensions` below `dir`, e.g. the `.tscn` and
+/// `.tres` files of the Godot project, skipping hidden directories and the
+/// cargo target directory.
+pub(super) fn text_resource_files(
+    dir: &Path,
+    target_dir: &Path,
+    extensions: &[&str],
+) -> Vec<PathBuf> {
+    let target_dir = target_dir.to_path_buf();
+
+    let mut files = ignore::WalkBuilder::new(dir)
+        .standard_filters(false)
+        .hidden(true)
+        .filter_entry(move |entry| entry.path() != target_dir)
+        .build()
+        .filter_map(Result::ok)
+        .map(ignore::DirEntry::into_path)
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| extensions.iter().any(|e| ext == *e))
+        })
+        .collect::<Vec<_>>();
+
+    files.sort();
+    files
+}
+
+/// Decide whether `content` is written to the file `file_name` inside `dir`,
+/// which is read from `sink`.
+///
+/// The file is not written if it already has this content or `policy`
+/// forbids overwriting it.
+pub(super) fn plan_write(
+    dir: &Path,
+    file_name: &str,
+    content: String,
+    policy: OverwritePolicy,
+    manifest: &mut Manifest,
+    sink: &dyn FileSink,
+) -> Result<PlannedWrite, GenerateError> {
+    plan_write_at(
+        dir.join(file_name),
+        file_name,
+        content,
+        policy,
+        manifest,
+        sink,
+    )
+}
+
+/// Decide whether `content` is written to the file at `path`, which is
+/// recorded in the manifest as `file_name`.
+fn plan_write_at(
+    path: PathBuf,
+    file_name: &str,
+    content: String,
+    policy: OverwritePolicy,
+    manifest: &mut Manifest,
+    sink: &dyn FileSink,
+) -> Result<PlannedWrite, GenerateError> {
+    let mut backup = None;
+
+    let action = if sink.exists(&path) {
+        let existing = sink.read(&path)?;
+        let modified = !manifest.is_unmodified(file_name, &existing);
+
+        let overwrite = match policy {
+            OverwritePolicy::Never => false,
+            OverwritePolicy::IfGeneratedByUs => !modified,
+            OverwritePolicy::IfContentDiffers => true,
+        };
+
+        // Rewriting identical content would only bump the modification time
+        // and make the Godot editor re-import the resource.
+        if existing == content {
+            FileAction::Unchanged
+        } else if overwrite {
+            if modified {
+                backup = Some(backup_path(&path));
+            }
+            FileAction::Updated
+        } else {
+            FileAction::Skipped
+        }
+    } else {
+        FileAction::Created
+    };
+
+    if let FileAction::Created | FileAction::Updated = action {
+        manifest.insert(file_name, &content);
+    }
+
+    Ok(PlannedWrite {
+        path,
+        content,
+        action,
+        backup,
+    })
+}
+
+/// Decide whether the file at `path`, which belongs to the user, is changed
+/// to the content `update` returns for its current content. It is recorded in
+/// the manifest relative to `godot_resource_output_dir`.
+///
+/// Returns `None` if the file does not exist or `update` leaves it alone. The
+/// file is skipped with [`OverwritePolicy::Never`], and with
+/// [`OverwritePolicy::IfGeneratedByUs`] if it was changed by hand since the
+/// generator last changed it. Otherwise the changed file is backed up first,
+/// unless it is still what the generator wrote.
+pub(super) fn plan_user_file(
+    path: PathBuf,
+    godot_resource_output_dir: &Path,
+    policy: OverwritePolicy,
+    manifest: &mut Manifest,
+    sink: &dyn FileSink,
+    update: impl FnOnce(&str) -> Result<Option<String>, GenerateError>,
+) -> Result<Option<PlannedWrite>, GenerateError> {
+    if !sink.exists(&path) {
+        return Ok(None);
+    }
+
+    let existing = sink.read(&path)?;
+    let content = match update(&existing)? {
+        Some(content) => content,
+        None => return Ok(None),
+    };
+
+    let key = diff_paths(&path, godot_resource_output_dir)?.to_slash_lossy();
+    let modified = manifest.is_user_file_modified(&key, &existing);
+    let overwrite = match policy {
+        OverwritePolicy::Never => false,
+        OverwritePolicy::IfGeneratedByUs => !modified,
+        OverwritePolicy::IfContentDiffers => true,
+    };
+
+    let mut backup = None;
+    let action = if content == existing {
+        FileAction::Unchanged
+    } else if overwrite {
+        if !manifest.is_user_file_unmodified(&key, &existing) {
+            backup = Some(backup_path(&path));
+        }
+        manifest.insert_user_file(key, &content);
+        FileAction::Updated
+    } else {
+        FileAction::Skipped
+    };
+
+    Ok(Some(PlannedWrite {
+        path,
+        content,
+        action,
+        backup,
+    }))
+}
+
+/// Plan adding the `libraries` inside the Godot project to the
+/// `include_filter` of every preset in `export_presets.cfg`.
+fn plan_export_presets(
+    ctx: &PlanContext,
+    libraries: &[PathBuf],
+    policy: OverwritePolicy,
+    manifest: &mut Manifest,
+    writes: &mut Vec<PlannedWrite>,
+) -> Result<(), GenerateError> {
+    let patterns = libraries
+        .iter()
+        .filter_map(|path| {
+            let rel_path = diff_paths(path, ctx.godot_project_dir).ok()?;
+            Some(rel_path.to_slash_lossy()).filter(|path| !path.starts_with("../"))
+        })
+        .collect::<Vec<_>>();
+
+    // Without presets there is nothing to export yet.
+    let path = ctx.godot_project_dir.join("export_presets.cfg");
+    let write = plan_user_file(
+        path.clone(),
+        ctx.godot_resource_output_dir,
+        policy,
+        manifest,
+        ctx.sink,
+        |existing| {
+            let mut presets =
+                ConfigFile::parse(existing).map_err(|error| GenerateError::InvalidFile {
+                    path: path.clone(),
+                    error,
+                })?;
+
+            let preset_sections = presets
+                .section_names()
+                .filter(|section| section.starts_with("preset.") && !section.ends_with(".options"))
+                .map(String::from)
+                .collect::<Vec<_>>();
+
+            for section in preset_sections {
+                let filter = unquote(presets.get(&section, "include_filter").unwrap_or_default());
+                let mut filters = filter
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|filter| !filter.is_empty())
+                    .map(String::from)
+                    .collect::<Vec<_>>();
+
+                for pattern in &patterns {
+                    if !filters.contains(pattern) {
+                        filters.push(pattern.clone());
+                    }
+                }
+
+                presets.set(&section, "include_filter", quote(&filters.join(", ")));
+            }
+
+            Ok(Some(presets.to_string()))
+        },
+    )?;
+
+    writes.extend(write);
+    Ok(())
+}
+
+/// Plan removing the old `.gdns` files of `renames`, recorded in the manifest
+/// as `old_files`, and pointing the scenes and resources of the Godot project
+/// at the new ones.
+///
+/// Modified `.gdns` files are backed up. With [`OverwritePolicy::Never`],
+/// every file is skipped.
+fn plan_rename_migration(
+    ctx: &PlanContext,
+    renames: &[ClassRename],
+    old_files: &[&str],
+    policy: OverwritePolicy,
+    manifest: &mut Manifest,
+    writes: &mut Vec<PlannedWrite>,
+) -> Result<(), GenerateError> {
+    let migrate = !matches!(policy, OverwritePolicy::Never);
+    let renamed_paths = renames
+        .iter()
+        .map(|rename| {
+            Ok((
+                resource_path(&rename.old_file, ctx.godot_project_dir)?,
+                resource_path(&rename.new_file, ctx.godot_project_dir)?,
+            ))
+        })
+        .collect::<Result<Vec<_>, GenerateError>>()?;
+
+    for path in text_resource_files(ctx.godot_project_dir, ctx.target_dir, &["tscn", "tres"]) {
+        let write = plan_user_file(
+            path,
+            ctx.godot_resource_output_dir,
+            policy,
+            manifest,
+            ctx.sink,
+            |existing| {
+                // Files Godot can not read either are left alone.
+                let mut resource = match TextResource::parse(existing) {
+                    Ok(resource) => resource,
+                    Err(_) => return Ok(None),
+                };
+
+                let rewritten = renamed_paths
+                    .iter()
+                    .map(|(old, new)| resource.rewrite_ext_resource_paths(old, new))
+                    .sum::<usize>();
+
+                Ok(Some(resource.to_string()).filter(|_| rewritten > 0))
+            },
+        )?;
+        writes.extend(write);
+    }
+
+    for (rename, old_file) in renames.iter().zip(old_files) {
+        if ctx.sink.exists(&rename.old_file) {
+            let modified = !manifest.is_unmodified(old_file, &ctx.sink.read(&rename.old_file)?);
+            writes.push(PlannedWrite {
+                path: rename.old_file.clone(),
+                content: String::new(),
+                action: if migrate {
+                    FileAction::Removed
+                } else {
+                    FileAction::Skipped
+                },
+                backup: Some(backup_path(&rename.old_file)).filter(|_| migrate && modified),
+            });
+        }
+        if migrate {
+            manifest.remove(old_file);
+        }
+    }
+
+    Ok(())
+}
+
+/// Plan the `plugin.cfg` and `plugin.gd` of the editor plugin `plugin_name`,
+/// which registers the `custom_types`.
+fn plan_editor_plugin(
+    ctx: &PlanContext,
+    plugin_name: &str,
+    lib_name: &str,
+    custom_types: &[CustomType],
+    policy: OverwritePolicy,
+    manifest: &mut Manifest,
+    writes: &mut Vec<PlannedWrite>,
+) -> Result<(), GenerateError> {
+    let plugin_dir = ctx.godot_project_dir.join("addons").join(plugin_name);
+
+    let files = [
+        ("plugin.cfg", generate_plugin_cfg(plugin_name, lib_name)),
+        ("plugin.gd", generate_plugin_gd(custom_types)),
+    ];
+    for (file_name, content) in files {
+        let path = ctx.resolution.resolve(plugin_dir.join(file_name))?;
+        let key = diff_paths(&path, ctx.godot_resource_output_dir)?.to_slash_lossy();
+        writes.push(plan_write_at(
+            path, &key, content, policy, manifest, ctx.sink,
+        )?);
+    }
+
+    Ok(())
+}
+
+/// Plan registering `classes` in the `_global_script_classes` of
+/// `project.godot`, replacing the entries of the `replaced_scripts`.
+fn plan_global_classes(
+    ctx: &PlanContext,
+    classes: &[GlobalClass],
+    replaced_scripts: &HashSet<String>,
+    policy: OverwritePolicy,
+    manifest: &mut Manifest,
+    writes: &mut Vec<PlannedWrite>,
+) -> Result<(), GenerateError> {
+    let path = ctx.godot_project_dir.join("project.godot");
+    let invalid_file = |error| GenerateError::InvalidFile {
+        path: path.clone(),
+        error,
+    };
+
+    let write = plan_user_file(
+        path.clone(),
+        ctx.godot_resource_output_dir,
+        policy,
+        manifest,
+        ctx.sink,
+        |existing| {
+            let mut project = ConfigFile::parse(existing).map_err(invalid_file)?;
+            crate::global_classes::update(&mut project, classes, replaced_scripts)
+                .map_err(invalid_file)?;
+            Ok(Some(project.to_string()))
+        },
+    )?;
+
+    match write {
+        Some(write) => writes.push(write),
+        None => {
+            return Err(GenerateError::InvalidPath {
+                path,
+                error: std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "The classes can not be registered without `project.godot`",
+                ),
+            })
+        }
+    }
+
+    Ok(())
+}
+
+/// The Godot version of the project in `godot_project_dir`, found from its
+/// `project.godot`, if there is one.
+fn detect_godot_version(
+    godot_project_dir: &Path,
+    sink: &dyn FileSink,
+) -> Result<Option<GodotVersion>, GenerateError> {
+    let project_path = godot_project_dir.join("project.godot");
+    if !sink.exists(&project_path) {
+        return Ok(None);
+    }
+
+    let project = ConfigFile::parse(&sink.read(&project_path)?).map_err(|error| {
+        GenerateError::InvalidFile {
+            path: project_path,
+            error,
+        }
+    })?;
+    Ok(GodotVersion::from_project(&project))
+}
+
+/// Record the stale classes in the manifest that were probably renamed to one
+/// of the new classes in the report of `plan`, and return the `.gdns` files
+/// of the old classes.
+///
+/// Classes are stale if their `.gdns` file is neither one of the
+/// `class_files` nor one of the `other_class_files`.
+fn detect_class_renames(
+    ctx: &PlanContext,
+    class_files: &ClassFiles,
+    other_class_files: &HashSet<String>,
+    plan: &mut Plan,
+) -> Vec<String> {
+    let stale_classes = plan
+        .manifest
+        .paths(ctx.godot_resource_output_dir)
+        .filter(|(file, _)| {
+            file.ends_with(".gdns")
+                && !class_files.files.contains(*file)
+                && !other_class_files.contains(*file)
+        })
+        .filter_map(|(file, _)| Some((file.to_string(), plan.manifest.class(file)?.clone())))
+        .collect::<Vec<_>>();
+
+    let renames = detect_renames(&stale_classes, &class_files.new_classes);
+
+    for &(old, new) in &renames {
+        let (old_file, old_record) = &stale_classes[old];
+        let (new_file, new_record) = &class_files.new_classes[new];
+
+        info!(
+            "`{}` looks like it was renamed to `{}`",
+            old_record.name, new_record.name
+        );
+        plan.report.renames.push(ClassRename {
+            old_class: old_record.name.clone(),
+            new_class: new_record.name.clone(),
+            old_file: ctx.godot_resource_output_dir.join(old_file),
+            new_file: ctx.godot_resource_output_dir.join(new_file),
+        });
+    }
+
+    renames
+        .into_iter()
+        .map(|(old, _)| stale_classes[old].0.clone())
+        .collect()
+}
+
+/// Plan a `.gdignore` file in the target directory and `staging_dir` if they
+/// are inside the Godot project, so the editor does not import the build
+/// artifacts.
+fn plan_gdignore(
+    ctx: &PlanContext,
+    staging_dir: Option<&Path>,
+    plan: &mut Plan,
+) -> Result<(), GenerateError> {
+    for dir in std::iter::once(ctx.target_dir).chain(staging_dir) {
+        let in_project = diff_paths(dir, ctx.godot_project_dir)
+            .is_ok_and(|rel_path| !rel_path.starts_with("../"));
+        if !in_project {
+            continue;
+        }
+
+        // An existing `.gdignore` may hold anything, it only has to exist,
+        // and one the user created is not ours to track.
+        let path = dir.join(".gdignore");
+        if ctx.sink.exists(&path) {
+            continue;
+        }
+
+        let key = diff_paths(&path, ctx.godot_resource_output_dir)?.to_slash_lossy();
+        plan.writes.push(plan_write_at(
+            path,
+            &key,
+            String::new(),
+            OverwritePolicy::Never,
+            &mut plan.manifest,
+            ctx.sink,
+        )?);
+    }
+
+    Ok(())
+}
+
+/// Plan removing the `.gdns` files and GDScript stubs in the manifest that
+/// are neither `class_files` nor `other_class_files`, backing up the modified
+/// ones.
+fn plan_prune_stale(
+    ctx: &PlanContext,
+    class_files: &HashSet<String>,
+    other_class_files: &HashSet<String>,
+    plan: &mut Plan,
+) -> Result<(), GenerateError> {
+    let stale = plan
+        .manifest
+        .paths(ctx.godot_resource_output_dir)
+        .filter(|(file, _)| {
+            (file.ends_with(".gdns") || file.ends_with(".stub.gd"))
+                && !class_files.contains(*file)
+                && !other_class_files.contains(*file)
+        })
+        .map(|(file, path)| (file.to_string(), path))
+        .collect::<Vec<_>>();
+
+    for (file, path) in stale {
+        if ctx.sink.exists(&path) {
+            let modified = !plan.manifest.is_unmodified(&file, &ctx.sink.read(&path)?);
+            plan.writes.push(PlannedWrite {
+                backup: Some(backup_path(&path)).filter(|_| modified),
+                path,
+                content: String::new(),
+                action: FileAction::Removed,
+            });
+        }
+        plan.manifest.remove(&file);
+    }
+
+    Ok(())
+}
+
+/// A class registered by the editor plugin.
+pub(super) struct CustomType {
+    pub(super) name: String,
+    pub(super) base: String,
+    /// The resource path of the `.gdns` file.
+    pub(super) script: String,
+    /// The resource path of the icon.
+    pub(super) icon: Option<String>,
+}
+
+fn generate_plugin_cfg(name: &str, lib_name: &str) -> String {
+    format!(
+        r#"[plugin]
+
+name={name}
+description="Registers the native classes of {lib_name} as custom types."
+author="gdnative-project-utils"
+version="1.0"
+script="plugin.gd"
+"#,
+        name = quote(name),
+        lib_name = escape(lib_name),
+    )
+}
+
+fn generate_plugin_gd(custom_types: &[CustomType]) -> String {
+    let mut enter_tree = String::new();
+    let mut exit_tree = String::new();
+
+    for custom_type in custom_types {
+        let icon = match &custom_type.icon {
+            Some(icon) => format!("preload({})", quote(icon)),
+            None => "null".to_string(),
+        };
+        enter_tree.push_str(&format!(
+            "\tadd_custom_type({}, {}, preload({}), {})\n",
+            quote(&custom_type.name),
+            quote(&custom_type.base),
+            quote(&custom_type.script),
+            icon
+        ));
+        exit_tree.push_str(&format!(
+            "\tremove_custom_type({})\n",
+            quote(&custom_type.name)
+        ));
+    }
+
+    // A function body must not be empty.
+    if custom_types.is_empty() {
+        enter_tree.push_str("\tpass\n");
+        exit_tree.push_str("\tpass\n");
+    }
+
+    format!(
+        "# Generated by gdnative-project-utils, do not edit.\n\
+         tool\n\
+         extends EditorPlugin\n\
+         \n\
+         \n\
+         func _enter_tree():\n\
+         {}\n\
+         \n\
+         func _exit_tree():\n\
+         {}",
+        enter_tree, exit_tree
+    )
+}
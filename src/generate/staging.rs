@@ -0,0 +1,555 @@
+//! Finding the libraries of the platforms and staging them into the Godot
+//! project.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::platform::{Platform, Platforms};
+
+use super::plan::{Plan, PlanContext};
+use super::{
+    AndroidLibLayout, Binaries, BuildMode, Builder, GenerateError, IosLibLayout, MacosLibLayout,
+    StagingMode,
+};
+
+impl Builder {
+    /// The libraries the entries of `platforms` point at before they are
+    /// staged, planning to create the universal macOS library and the iOS
+    /// xcframework if their layouts ask for it.
+    pub(super) fn plan_binaries(
+        &self,
+        ctx: &PlanContext,
+        platforms: &Platforms,
+        build_mode: &BuildMode,
+        target_triple: Option<&str>,
+        plan: &mut Plan,
+    ) -> Result<Binaries, GenerateError> {
+        let platform_build_modes = &self.platform_build_modes;
+        let mode = |platform| platform_build_modes.get(&platform).unwrap_or(build_mode);
+        let macos_layout = self
+            .macos_lib_layout
+            .unwrap_or(MacosLibLayout::PerArchitecture);
+
+        let ios_layout = self.ios_lib_layout.unwrap_or(IosLibLayout::StaticLib);
+        let target_dir = ctx.target_dir;
+        let lib_name = ctx.lib_name;
+
+        let mut binaries = common_binary_outputs(
+            target_dir,
+            mode,
+            platforms,
+            ios_layout,
+            macos_layout,
+            target_triple,
+            lib_name,
+        );
+
+        if let MacosLibLayout::UniversalLipo = macos_layout {
+            let macos = binaries
+                .0
+                .iter()
+                .find(|(platform, _)| matches!(platform, Platform::OsxArm64 | Platform::Osx64));
+
+            if let Some((&platform, output)) = macos {
+                let mode_path = mode(platform).dir_name();
+                let file_name = platform.lib_file_name(lib_name);
+                let inputs = ["x86_64-apple-darwin", "aarch64-apple-darwin"]
+                    .iter()
+                    .map(|triple| target_dir.join(triple).join(mode_path).join(&file_name))
+                    .collect();
+
+                plan.universal_lib = Some((output.clone(), inputs));
+            }
+        }
+
+        if let IosLibLayout::XcFrameworkCreate = ios_layout {
+            let ios = binaries.0.iter().find(|(platform, _)| {
+                matches!(
+                    platform,
+                    Platform::IosArmv7 | Platform::IosArm64 | Platform::IosSimulatorX86_64
+                )
+            });
+
+            if let Some((&platform, output)) = ios {
+                let mode_path = mode(platform).dir_name();
+                let file_name = Platform::IosArm64.lib_file_name(lib_name);
+                let lib = |triple: &str| target_dir.join(triple).join(mode_path).join(&file_name);
+
+                plan.xcframework = Some(XcFramework {
+                    output: output.clone(),
+                    device: lib("aarch64-apple-ios"),
+                    simulators: vec![lib("aarch64-apple-ios-sim"), lib("x86_64-apple-ios")],
+                    universal_simulator: lib("universal-apple-ios-sim"),
+                });
+            }
+        }
+
+        if let Some(AndroidLibLayout::AbiDirectories(dir)) = &self.android_lib_layout {
+            let dir = ctx.resolution.resolve(ctx.godot_project_dir.join(dir))?;
+            for (platform, path) in binaries.0.iter_mut() {
+                if let Some(abi) = platform.android_abi() {
+                    *path = dir.join(abi).join(platform.lib_file_name(lib_name));
+                }
+            }
+        }
+
+        for (&platform, path) in &self.binary_overrides {
+            if self.host_only && !platforms.contains(platform) {
+                continue;
+            }
+            let path = ctx.resolution.resolve(ctx.godot_project_dir.join(path))?;
+            binaries.0.insert(platform, path);
+        }
+
+        Ok(binaries)
+    }
+
+    /// Point the `binaries` at their copies in `staging_dir`, and plan
+    /// copying the libraries and their debug symbols there.
+    pub(super) fn stage_binaries(
+        &self,
+        staging_dir: &Path,
+        binaries: &mut Binaries,
+        plan: &mut Plan,
+    ) -> Result<(), GenerateError> {
+        let versioned = matches!(self.staging_mode, Some(StagingMode::Versioned));
+
+        for (platform, path) in binaries.0.iter_mut() {
+            let file_name = path.file_name().expect("Artifacts have a file name");
+            let file_name = match versioned {
+                true => versioned_file_name(path)?,
+                false => None,
+            }
+            .unwrap_or_else(|| file_name.to_os_string());
+            let staged = staging_dir
+                .join(staging_dir_name(*platform))
+                .join(file_name);
+
+            let source = std::mem::replace(path, staged.clone());
+
+            if self.include_debug_symbols {
+                if let Some(symbols) = debug_symbols_file_name(*platform, &source) {
+                    plan.artifacts.push((
+                        source.with_file_name(&symbols),
+                        staged.with_file_name(&symbols),
+                    ));
+                }
+            }
+
+            plan.artifacts.push((source, staged));
+        }
+
+        Ok(())
+    }
+
+    /// The resource paths of the dependencies of the platforms with
+    /// `entries`, planning to stage them into `staging_dir` if dependencies
+    /// are staged.
+    pub(super) fn plan_dependencies(
+        &self,
+        ctx: &PlanContext,
+        entries: &BTreeMap<Platform, String>,
+        staging_dir: Option<&Path>,
+        plan: &mut Plan,
+    ) -> Result<BTreeMap<Platform, Vec<String>>, GenerateError> {
+        let mut dependencies = BTreeMap::new();
+        for (platform, libs) in &self.dependencies {
+            if !entries.contains_key(platform) {
+                continue;
+            }
+
+            let mut lib_paths = vec![];
+            for lib in libs {
+                let mut lib = ctx.resolution.resolve(ctx.godot_project_dir.join(lib))?;
+
+                if let (true, Some(staging_dir)) = (self.stage_dependencies, staging_dir) {
+                    let file_name = lib.file_name().expect("Libraries have a file name");
+                    let staged = staging_dir
+                        .join(staging_dir_name(*platform))
+                        .join(file_name);
+                    let source = std::mem::replace(&mut lib, staged.clone());
+                    plan.dependency_artifacts.push((source, staged));
+                }
+
+                lib_paths.push(ctx.paths.resource_path(&lib)?);
+            }
+            dependencies.insert(*platform, lib_paths);
+        }
+
+        Ok(dependencies)
+    }
+}
+
+/// An xcframework bundle holding the iOS device and simulator libraries.
+pub(super) struct XcFramework {
+    output: PathBuf,
+    device: PathBuf,
+    /// The simulator libraries of the single architectures, which are
+    /// combined into `universal_simulator` if more than one exists, since an
+    /// xcframework holds only one library per platform.
+    simulators: Vec<PathBuf>,
+    universal_simulator: PathBuf,
+}
+
+impl XcFramework {
+    /// Create the bundle, unless the device library is missing or the
+    /// bundle is newer than all libraries.
+    pub(super) fn create(&self) -> Result<(), GenerateError> {
+        let device_modified = match std::fs::metadata(&self.device) {
+            Ok(meta) => meta.modified()?,
+            Err(_) => return Ok(()),
+        };
+
+        let simulators = self
+            .simulators
+            .iter()
+            .filter(|path| path.is_file())
+            .cloned()
+            .collect::<Vec<_>>();
+        let simulator = match simulators.as_slice() {
+            [] => None,
+            [simulator] => Some(simulator.clone()),
+            _ => {
+                lipo(&self.universal_simulator, &simulators)?;
+                Some(self.universal_simulator.clone())
+            }
+        };
+
+        let mut input_modified = vec![device_modified];
+        if let Some(simulator) = &simulator {
+            input_modified.push(std::fs::metadata(simulator)?.modified()?);
+        }
+        if let Ok(output_meta) = std::fs::metadata(&self.output) {
+            let output_modified = output_meta.modified()?;
+            if input_modified.iter().all(|m| *m <= output_modified) {
+                return Ok(());
+            }
+            // `xcodebuild` refuses to overwrite an existing bundle.
+            std::fs::remove_dir_all(&self.output)?;
+        }
+
+        if let Some(parent) = self.output.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut command = std::process::Command::new("xcodebuild");
+        command.arg("-create-xcframework");
+        for library in std::iter::once(&self.device).chain(&simulator) {
+            command.arg("-library").arg(library);
+        }
+        let result = command.arg("-output").arg(&self.output).output()?;
+
+        if !result.status.success() {
+            return Err(GenerateError::CommandFailed {
+                command: "xcodebuild".to_string(),
+                stderr: String::from_utf8_lossy(&result.stderr).into_owned(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// The name of the subdirectory of the staging directory for `platform`.
+fn staging_dir_name(platform: Platform) -> String {
+    platform.gdnlib_key().to_lowercase()
+}
+
+/// The name of the file or bundle holding the debug symbols of the library
+/// at `path` for `platform`, if they are kept separately from the library.
+fn debug_symbols_file_name(platform: Platform, path: &Path) -> Option<std::ffi::OsString> {
+    let file_name = Path::new(path.file_name()?);
+
+    match platform {
+        // `my_crate.dll` -> `my_crate.pdb`
+        Platform::Windows64 | Platform::Windows32 | Platform::WindowsArm64 => {
+            Some(file_name.with_extension("pdb").into_os_string())
+        }
+        // `libmy_crate.dylib` -> `libmy_crate.dylib.dSYM`
+        Platform::Osx64 | Platform::OsxArm64
+            if file_name.extension().is_some_and(|ext| ext == "dylib") =>
+        {
+            let mut symbols = file_name.as_os_str().to_os_string();
+            symbols.push(".dSYM");
+            Some(symbols)
+        }
+        _ => None,
+    }
+}
+
+/// The name of the versioned copy of the library at `path`, or `None` if the
+/// library has not been built or is a directory.
+///
+/// The name is derived from the modification time and size of the library,
+/// so planning does not read the whole library every time.
+fn versioned_file_name(path: &Path) -> std::io::Result<Option<std::ffi::OsString>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let metadata = std::fs::metadata(path)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let hash =
+        crate::manifest::fnv1a64(format!("{}:{}", modified.as_nanos(), metadata.len()).as_bytes());
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+
+    let file_name = match path.extension() {
+        Some(ext) => format!("{}_{:016x}.{}", stem, hash, ext.to_string_lossy()),
+        None => format!("{}_{:016x}", stem, hash),
+    };
+
+    Ok(Some(file_name.into()))
+}
+
+/// Remove the versioned copies next to the versioned library `current`,
+/// except for `current` itself.
+///
+/// Copies that can not be removed, because Godot still has them loaded, are
+/// left for a later build to remove.
+pub(super) fn remove_old_versions(current: &Path) {
+    let (dir, file_name) = match (current.parent(), current.file_name()) {
+        (Some(dir), Some(file_name)) => (dir, file_name.to_string_lossy()),
+        _ => return,
+    };
+
+    // `my_crate_0123456789abcdef.dll` -> `my_crate_`, `.dll`
+    let (prefix, suffix) = match file_name.rfind('.') {
+        Some(dot) => file_name.split_at(dot),
+        None => (&*file_name, ""),
+    };
+    let prefix = match prefix.len().checked_sub(16) {
+        Some(len) => &prefix[..len],
+        None => return,
+    };
+
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+
+        let is_old_version = name != file_name
+            && name
+                .strip_prefix(prefix)
+                .and_then(|rest| rest.strip_suffix(suffix))
+                .is_some_and(|hash| {
+                    hash.len() == 16 && hash.chars().all(|c| c.is_ascii_hexdigit())
+                });
+
+        if is_old_version {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// Copy or link the library at `source` to `destination`, unless it has not
+/// been built or `destination` is already up to date.
+///
+/// Returns whether the library was staged.
+pub(super) fn stage_artifact(
+    source: &Path,
+    destination: &Path,
+    mode: StagingMode,
+) -> Result<bool, GenerateError> {
+    let source_meta = match std::fs::metadata(source) {
+        Ok(meta) => meta,
+        Err(_) => return Ok(false),
+    };
+
+    if let Ok(destination_meta) = std::fs::symlink_metadata(destination) {
+        let up_to_date = match (mode, destination_meta.file_type().is_symlink()) {
+            (StagingMode::Symlink, true) => std::fs::read_link(destination)? == source,
+            // The modification time of a `.dSYM` bundle does not change when
+            // the files inside it are rebuilt.
+            (StagingMode::Copy, false) | (StagingMode::Versioned, false)
+                if source_meta.is_dir() =>
+            {
+                newest_modified(destination)? >= newest_modified(source)?
+            }
+            (StagingMode::Copy, false) | (StagingMode::Versioned, false) => {
+                destination_meta.len() == source_meta.len()
+                    && destination_meta.modified()? >= source_meta.modified()?
+            }
+            _ => false,
+        };
+        if up_to_date {
+            return Ok(false);
+        }
+
+        if destination_meta.is_dir() {
+            std::fs::remove_dir_all(destination)?;
+        } else {
+            std::fs::remove_file(destination)?;
+        }
+    }
+
+    if let Some(parent) = destination.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    if let StagingMode::Symlink = mode {
+        if symlink(source, destination).is_ok() {
+            return Ok(true);
+        }
+    }
+
+    copy_recursively(source, destination)?;
+
+    Ok(true)
+}
+
+/// Combine the single-architecture macOS libraries `inputs` into the
+/// universal library `output`, unless one of them has not been built or
+/// `output` is newer than all of them.
+pub(super) fn lipo(output: &Path, inputs: &[PathBuf]) -> Result<(), GenerateError> {
+    let mut input_modified = vec![];
+    for input in inputs {
+        match std::fs::metadata(input) {
+            Ok(meta) => input_modified.push(meta.modified()?),
+            Err(_) => return Ok(()),
+        }
+    }
+
+    if let Ok(output_meta) = std::fs::metadata(output) {
+        let output_modified = output_meta.modified()?;
+        if input_modified.iter().all(|m| *m <= output_modified) {
+            return Ok(());
+        }
+    }
+
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let result = std::process::Command::new("lipo")
+        .arg("-create")
+        .arg("-output")
+        .arg(output)
+        .args(inputs)
+        .output()?;
+
+    if !result.status.success() {
+        return Err(GenerateError::CommandFailed {
+            command: "lipo".to_string(),
+            stderr: String::from_utf8_lossy(&result.stderr).into_owned(),
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink(source: &Path, destination: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(source, destination)
+}
+
+#[cfg(windows)]
+fn symlink(source: &Path, destination: &Path) -> std::io::Result<()> {
+    if source.is_dir() {
+        std::os::windows::fs::symlink_dir(source, destination)
+    } else {
+        std::os::windows::fs::symlink_file(source, destination)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn symlink(_source: &Path, _destination: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        "Symlinks are not supported on this platform",
+    ))
+}
+
+/// The newest modification time of the file at `path`, or of the files in
+/// the directory at `path` and its subdirectories.
+fn newest_modified(path: &Path) -> std::io::Result<std::time::SystemTime> {
+    let mut newest = std::fs::metadata(path)?.modified()?;
+
+    if path.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            newest = newest.max(newest_modified(&entry?.path())?);
+        }
+    }
+
+    Ok(newest)
+}
+
+/// Copy a file, or a directory such as an `.xcframework` bundle.
+fn copy_recursively(source: &Path, destination: &Path) -> std::io::Result<()> {
+    if source.is_dir() {
+        std::fs::create_dir_all(destination)?;
+
+        for entry in std::fs::read_dir(source)? {
+            let entry = entry?;
+            copy_recursively(&entry.path(), &destination.join(entry.file_name()))?;
+        }
+    } else {
+        std::fs::copy(source, destination)?;
+    }
+
+    Ok(())
+}
+
+fn common_binary_outputs<'a>(
+    target: &Path,
+    mode: impl Fn(Platform) -> &'a BuildMode,
+    platforms: &Platforms,
+    ios_layout: IosLibLayout,
+    macos_layout: MacosLibLayout,
+    target_triple: Option<&str>,
+    name: &str,
+) -> Binaries {
+    let target_platform = target_triple.and_then(Platform::from_target_triple);
+
+    let binaries = platforms
+        .iter()
+        .map(|platform| {
+            let mode_path = mode(platform).dir_name();
+
+            let universal = !matches!(macos_layout, MacosLibLayout::PerArchitecture);
+
+            // The headless server uses the Linux library.
+            let library_platform = match platform {
+                Platform::Server64 => Platform::X11_64,
+                platform => platform,
+            };
+            let triple = match target_platform {
+                Some(target_platform) if target_platform == library_platform => target_triple,
+                _ => platform.target_triple(),
+            };
+
+            let path = match (platform, ios_layout, triple) {
+                (
+                    Platform::IosArmv7 | Platform::IosArm64 | Platform::IosSimulatorX86_64,
+                    IosLibLayout::XcFramework | IosLibLayout::XcFrameworkCreate,
+                    _,
+                ) => target
+                    .join(mode_path)
+                    .join(format!("{}.xcframework", name.replace('-', "_"))),
+                (Platform::IosArm64, IosLibLayout::Simulator, _) => target
+                    .join("aarch64-apple-ios-sim")
+                    .join(mode_path)
+                    .join(platform.lib_file_name(name)),
+                (Platform::OsxArm64 | Platform::Osx64, _, _) if universal => target
+                    .join("universal2-apple-darwin")
+                    .join(mode_path)
+                    .join(platform.lib_file_name(name)),
+                (_, _, Some(triple)) => target
+                    .join(triple)
+                    .join(mode_path)
+                    .join(platform.lib_file_name(name)),
+                (_, _, None) => target.join(mode_path).join(platform.lib_file_name(name)),
+            };
+
+            (platform, path)
+        })
+        .collect();
+
+    Binaries(binaries)
+}
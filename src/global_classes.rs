@@ -0,0 +1,228 @@
+//! Registration of classes in the `_global_script_classes` of `project.godot`.
+//!
+//! Godot 3 lists every script with a `class_name` in two entries before the
+//! first section of `project.godot`:
+//!
+//! ```ini
+//! _global_script_classes=[ {
+//! "base": "KinematicBody2D",
+//! "class": "Player",
+//! "language": "NativeScript",
+//! "path": "res://native/Player.gdns"
+//! } ]
+//! _global_script_class_icons={
+//! "Player": ""
+//! }
+//! ```
+//!
+//! The entries of GDScript classes and other libraries are kept as they are.
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::godot_cfg::{quote, unescape, ConfigFile, ParseError};
+
+const CLASSES_KEY: &str = "_global_script_classes";
+const ICONS_KEY: &str = "_global_script_class_icons";
+
+/// A class registered in `project.godot`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct GlobalClass {
+    pub(crate) name: String,
+    pub(crate) base: String,
+    pub(crate) language: String,
+    /// The resource path of the script.
+    pub(crate) path: String,
+    /// The resource path of the icon, or an empty string.
+    pub(crate) icon: String,
+}
+
+/// Register `classes` in the `project.godot` document `project`.
+///
+/// Existing entries of classes with the same name, and of NativeScript classes
+/// whose script is in `replaced_scripts`, are removed first.
+pub(crate) fn update(
+    project: &mut ConfigFile,
+    classes: &[GlobalClass],
+    replaced_scripts: &HashSet<String>,
+) -> Result<(), ParseError> {
+    let mut existing = match project.entries("").find(|(key, _)| *key == CLASSES_KEY) {
+        Some((_, value)) => parse_classes(value).ok_or_else(|| invalid(CLASSES_KEY))?,
+        None => vec![],
+    };
+    let mut icons = match project.entries("").find(|(key, _)| *key == ICONS_KEY) {
+        Some((_, value)) => {
+            parse_dictionary(&mut Tokens::new(value)).ok_or_else(|| invalid(ICONS_KEY))?
+        }
+        None => BTreeMap::new(),
+    };
+
+    let names = classes
+        .iter()
+        .map(|class| class.name.as_str())
+        .collect::<HashSet<_>>();
+
+    existing.retain(|entry| {
+        let field = |key: &str| entry.get(key).map(String::as_str).unwrap_or_default();
+
+        let replaced = names.contains(field("class"))
+            || (field("language") == "NativeScript" && replaced_scripts.contains(field("path")));
+        if replaced {
+            icons.remove(field("class"));
+        }
+        !replaced
+    });
+
+    for class in classes {
+        let mut entry = BTreeMap::new();
+        entry.insert("base".to_string(), class.base.clone());
+        entry.insert("class".to_string(), class.name.clone());
+        entry.insert("language".to_string(), class.language.clone());
+        entry.insert("path".to_string(), class.path.clone());
+        existing.push(entry);
+
+        icons.insert(class.name.clone(), class.icon.clone());
+    }
+
+    // Godot keeps the classes sorted by name.
+    existing.sort_by(|a, b| a.get("class").cmp(&b.get("class")));
+
+    project.set("", CLASSES_KEY, render_classes(&existing));
+    project.set("", ICONS_KEY, render_dictionary(&icons));
+
+    Ok(())
+}
+
+fn invalid(key: &str) -> ParseError {
+    ParseError::InvalidValue {
+        key: key.to_string(),
+    }
+}
+
+type Dictionary = BTreeMap<String, String>;
+
+fn parse_classes(value: &str) -> Option<Vec<Dictionary>> {
+    let mut tokens = Tokens::new(value);
+    let mut classes = vec![];
+
+    tokens.expect(Token::Punct('['))?;
+    loop {
+        match tokens.peek()? {
+            Token::Punct(']') => break,
+            Token::Punct(',') => {
+                tokens.next();
+            }
+            _ => classes.push(parse_dictionary(&mut tokens)?),
+        }
+    }
+
+    Some(classes)
+}
+
+/// Parse a dictionary of string keys and values.
+fn parse_dictionary(tokens: &mut Tokens) -> Option<Dictionary> {
+    let mut entries = Dictionary::new();
+
+    tokens.expect(Token::Punct('{'))?;
+    loop {
+        match tokens.next()? {
+            Token::Punct('}') => break,
+            Token::Punct(',') => {}
+            Token::Str(key) => {
+                tokens.expect(Token::Punct(':'))?;
+                match tokens.next()? {
+                    Token::Str(value) => {
+                        entries.insert(key, value);
+                    }
+                    Token::Punct(_) => return None,
+                }
+            }
+            Token::Punct(_) => return None,
+        }
+    }
+
+    Some(entries)
+}
+
+fn render_classes(classes: &[Dictionary]) -> String {
+    if classes.is_empty() {
+        return "[  ]".to_string();
+    }
+
+    let classes = classes.iter().map(render_dictionary).collect::<Vec<_>>();
+
+    format!("[ {} ]", classes.join(", "))
+}
+
+fn render_dictionary(entries: &Dictionary) -> String {
+    if entries.is_empty() {
+        return "{\n}".to_string();
+    }
+
+    let entries = entries
+        .iter()
+        .map(|(key, value)| format!("{}: {}", quote(key), quote(value)))
+        .collect::<Vec<_>>();
+
+    format!("{{\n{}\n}}", entries.join(",\n"))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Token {
+    Str(String),
+    Punct(char),
+}
+
+/// The strings and punctuation of a Godot variant, skipping whitespace.
+pub(crate) struct Tokens<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    peeked: Option<Token>,
+}
+
+impl<'a> Tokens<'a> {
+    pub(crate) fn new(value: &'a str) -> Self {
+        Tokens {
+            chars: value.chars().peekable(),
+            peeked: None,
+        }
+    }
+
+    pub(crate) fn peek(&mut self) -> Option<&Token> {
+        if self.peeked.is_none() {
+            self.peeked = self.lex();
+        }
+        self.peeked.as_ref()
+    }
+
+    pub(crate) fn next(&mut self) -> Option<Token> {
+        self.peeked.take().or_else(|| self.lex())
+    }
+
+    pub(crate) fn expect(&mut self, token: Token) -> Option<()> {
+        if self.next()? == token {
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn lex(&mut self) -> Option<Token> {
+        while self.chars.peek()?.is_whitespace() {
+            self.chars.next();
+        }
+
+        match self.chars.next()? {
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match self.chars.next()? {
+                        '"' => break,
+                        '\\' => string.push(unescape(self.chars.next()?)),
+                        c => string.push(c),
+                    }
+                }
+                Some(Token::Str(string))
+            }
+            c => Some(Token::Punct(c)),
+        }
+    }
+}
@@ -0,0 +1,417 @@
+//! Reading and writing of Godot's INI-like `ConfigFile` format.
+//!
+//! The document keeps every line it was parsed from, so serializing it again
+//! reproduces the input byte for byte. Only entries that are modified through
+//! [`ConfigFile::set`] are re-rendered. This makes it safe to edit files like
+//! `project.godot`, `export_presets.cfg` or `.gdnlib` resources that are also
+//! edited by Godot and by hand.
+//!
+//! Values are kept as raw, unparsed text, e.g. `"res://icon.png"` including
+//! the quotes, or a whole multi-line array.
+//!
+//! ```
+//! use gdnative_project_utils::godot_cfg::ConfigFile;
+//!
+//! let mut project: ConfigFile = "config_version=4\n\n[application]\n\nconfig/name=\"Game\"\n"
+//!     .parse()
+//!     .unwrap();
+//!
+//! assert_eq!(project.get("application", "config/name"), Some("\"Game\""));
+//!
+//! project.set("application", "run/main_scene", "\"res://Main.tscn\"");
+//! project.remove("", "config_version");
+//!
+//! assert_eq!(
+//!     project.to_string(),
+//!     "\n[application]\n\nconfig/name=\"Game\"\nrun/main_scene=\"res://Main.tscn\"\n"
+//! );
+//! ```
+
+/// A parsed `ConfigFile` document.
+#[derive(Clone, Debug)]
+pub struct ConfigFile {
+    /// The first section has no header and holds the keys before the first
+    /// `[section]` line.
+    sections: Vec<Section>,
+}
+
+#[derive(Clone, Debug)]
+struct Section {
+    name: String,
+    lines: Vec<Line>,
+}
+
+#[derive(Clone, Debug)]
+enum Line {
+    /// A section header, blank line, comment, or anything else that is kept
+    /// verbatim.
+    Verbatim(String),
+    Entry(Entry),
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    key: String,
+    /// The text between the key and the value, e.g. `=` or ` = `.
+    separator: String,
+    value: String,
+    /// The original text of the entry, dropped once the value is modified.
+    raw: Option<String>,
+}
+
+impl Entry {
+    fn render(&self) -> String {
+        match &self.raw {
+            Some(raw) => raw.clone(),
+            None => format!("{}{}{}", self.key, self.separator, self.value),
+        }
+    }
+}
+
+impl ConfigFile {
+    /// Parse a `ConfigFile` document.
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        let mut sections = vec![Section {
+            name: String::new(),
+            lines: vec![],
+        }];
+
+        let mut lines = source.split('\n').enumerate();
+
+        while let Some((line_no, line)) = lines.next() {
+            let trimmed = line.trim();
+
+            if trimmed.starts_with('[') {
+                let name = trimmed
+                    .strip_suffix(']')
+                    .map(|header| header[1..].to_string())
+                    .ok_or(ParseError::InvalidSection { line: line_no + 1 })?;
+
+                sections.push(Section {
+                    name,
+                    lines: vec![Line::Verbatim(line.to_string())],
+                });
+                continue;
+            }
+
+            let section = sections.last_mut().expect("There is always a section");
+
+            let eq = match line.find('=') {
+                Some(eq) if !trimmed.starts_with(';') && !trimmed.starts_with('#') => eq,
+                _ => {
+                    section.lines.push(Line::Verbatim(line.to_string()));
+                    continue;
+                }
+            };
+
+            let mut raw = line.to_string();
+
+            // Arrays and dictionaries may span multiple lines.
+            while nesting_depth(&raw[eq + 1..]) > 0 {
+                match lines.next() {
+                    Some((_, next)) => {
+                        raw.push('\n');
+                        raw.push_str(next);
+                    }
+                    None => return Err(ParseError::UnterminatedValue { line: line_no + 1 }),
+                }
+            }
+
+            let key = raw[..eq].trim();
+            let value_start = eq + 1 + (raw[eq + 1..].len() - raw[eq + 1..].trim_start().len());
+            let key_end = raw[..eq].trim_end().len();
+
+            section.lines.push(Line::Entry(Entry {
+                key: key.to_string(),
+                separator: raw[key_end..value_start].to_string(),
+                value: raw[value_start..].trim_end().to_string(),
+                raw: Some(raw.clone()),
+            }));
+        }
+
+        Ok(ConfigFile { sections })
+    }
+
+    /// Iterate over the keys and raw, unparsed values in `section`.
+    pub fn entries<'a>(&'a self, section: &str) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+        self.section(section)
+            .into_iter()
+            .flat_map(|section| section.lines.iter())
+            .filter_map(|line| match line {
+                Line::Entry(entry) => Some((entry.key.as_str(), entry.value.as_str())),
+                Line::Verbatim(_) => None,
+            })
+    }
+
+    /// The raw value of `key` in `section`.
+    ///
+    /// Keys before the first section header are in the section `""`. If the
+    /// key appears more than once, the last value is returned, like Godot
+    /// does.
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        self.entries(section)
+            .filter(|(k, _)| *k == key)
+            .last()
+            .map(|(_, value)| value)
+    }
+
+    /// Remove every entry of `key` in `section`, and return the raw value of
+    /// the last one.
+    pub fn remove(&mut self, section: &str, key: &str) -> Option<String> {
+        let section = self.sections.iter_mut().find(|s| s.name == section)?;
+
+        let mut removed = None;
+        section.lines.retain(|line| match line {
+            Line::Entry(entry) if entry.key == key => {
+                removed = Some(entry.value.clone());
+                false
+            }
+            _ => true,
+        });
+
+        removed
+    }
+
+    /// Iterate over the names of the sections, starting with `""` for the keys
+    /// before the first section header.
+    pub fn section_names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.sections.iter().map(|section| section.name.as_str())
+    }
+
+    /// Set the raw value of `key` in `section`.
+    ///
+    /// Keys before the first section header are in the section `""`.
+    ///
+    /// Existing entries are updated in place. New entries are added after the
+    /// last entry of the section, and new sections are added at the end of the
+    /// document.
+    pub fn set(&mut self, section: &str, key: &str, value: impl Into<String>) {
+        let value = value.into();
+
+        if self.section(section).is_none() {
+            self.add_section(section);
+        }
+
+        let section = self
+            .sections
+            .iter_mut()
+            .find(|s| s.name == section)
+            .expect("Section was just added");
+
+        let existing = section.lines.iter_mut().rev().find_map(|line| match line {
+            Line::Entry(entry) if entry.key == key => Some(entry),
+            _ => None,
+        });
+
+        if let Some(entry) = existing {
+            if entry.value != value {
+                entry.value = value;
+                entry.raw = None;
+            }
+            return;
+        }
+
+        let last_entry = section
+            .lines
+            .iter()
+            .rposition(|line| matches!(line, Line::Entry(_)));
+
+        let separator = last_entry
+            .and_then(|idx| match &section.lines[idx] {
+                Line::Entry(entry) => Some(entry.separator.clone()),
+                Line::Verbatim(_) => None,
+            })
+            .unwrap_or_else(|| "=".to_string());
+
+        // Without entries, add the key right after the header.
+        let idx = match last_entry {
+            Some(idx) => idx + 1,
+            None => usize::from(!section.name.is_empty()),
+        };
+
+        section.lines.insert(
+            idx,
+            Line::Entry(Entry {
+                key: key.to_string(),
+                separator,
+                value,
+                raw: None,
+            }),
+        );
+    }
+
+    fn section(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|s| s.name == name)
+    }
+
+    fn add_section(&mut self, name: &str) {
+        let document_empty = self.sections.iter().all(|s| {
+            s.lines
+                .iter()
+                .all(|l| matches!(l, Line::Verbatim(l) if l.is_empty()))
+        });
+
+        let previous = self.sections.last_mut().expect("There is always a section");
+
+        // Keep the document's trailing newline at the very end.
+        let trailing_newline =
+            matches!(previous.lines.last(), Some(Line::Verbatim(l)) if l.is_empty());
+        if trailing_newline {
+            previous.lines.pop();
+        }
+
+        if !document_empty {
+            previous.lines.push(Line::Verbatim(String::new()));
+        }
+
+        let mut lines = vec![Line::Verbatim(format!("[{}]", name))];
+        if trailing_newline {
+            lines.push(Line::Verbatim(String::new()));
+        }
+
+        self.sections.push(Section {
+            name: name.to_string(),
+            lines,
+        });
+    }
+}
+
+impl std::str::FromStr for ConfigFile {
+    type Err = ParseError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        ConfigFile::parse(source)
+    }
+}
+
+/// Serializes the document, reproducing unmodified lines verbatim.
+impl std::fmt::Display for ConfigFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lines = self
+            .sections
+            .iter()
+            .flat_map(|section| section.lines.iter())
+            .map(|line| match line {
+                Line::Verbatim(text) => text.clone(),
+                Line::Entry(entry) => entry.render(),
+            })
+            .collect::<Vec<_>>();
+
+        f.write_str(&lines.join("\n"))
+    }
+}
+
+/// Error type for errors that can occur when parsing a `ConfigFile`.
+#[derive(Debug)]
+pub enum ParseError {
+    /// A section header is not closed.
+    InvalidSection { line: usize },
+    /// An array, dictionary or string is not closed before the end of the file.
+    UnterminatedValue { line: usize },
+    /// The value of `key` does not have the expected form.
+    InvalidValue { key: String },
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::InvalidSection { line } => {
+                f.write_fmt(format_args!("Invalid section header on line {}", line))
+            }
+            ParseError::UnterminatedValue { line } => {
+                f.write_fmt(format_args!("Unterminated value starting on line {}", line))
+            }
+            ParseError::InvalidValue { key } => {
+                f.write_fmt(format_args!("Invalid value of `{}`", key))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// The number of unclosed brackets and strings in `value`.
+pub(crate) fn nesting_depth(value: &str) -> usize {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for c in value.chars() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '[' | '{' | '(' => depth += 1,
+            ']' | '}' | ')' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    depth + usize::from(in_string)
+}
+
+/// `value` as a string literal of Godot's text formats and of GDScript.
+pub(crate) fn quote(value: &str) -> String {
+    format!("\"{}\"", escape(value))
+}
+
+/// Escape the characters of `value` that end or break a string literal.
+///
+/// Everything else, including non-ASCII characters, is kept as it is, since
+/// the files are read as UTF-8.
+pub(crate) fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The string the literal `value` stands for, or `value` itself if it is not
+/// quoted.
+pub(crate) fn unquote(value: &str) -> String {
+    let inner = match value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    {
+        Some(inner) => inner,
+        None => return value.to_string(),
+    };
+
+    let mut unquoted = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unquoted.extend(chars.next().map(unescape)),
+            c => unquoted.push(c),
+        }
+    }
+    unquoted
+}
+
+/// The character the escape sequence `\c` stands for.
+pub(crate) fn unescape(c: char) -> char {
+    match c {
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        'b' => '\u{8}',
+        'f' => '\u{c}',
+        c => c,
+    }
+}
@@ -0,0 +1,452 @@
+//! Reading and rewriting of Godot's text resource format (`.tres`, `.tscn`).
+//!
+//! Like [`ConfigFile`](crate::godot_cfg::ConfigFile), a [`TextResource`]
+//! keeps every line it was parsed from and only re-renders the headings and
+//! properties that are modified. The `[heading]` lines are understood, which is
+//! enough to find and rewrite the external resources a scene or resource refers
+//! to, and the properties below them are kept as raw, unparsed values:
+//!
+//! ```
+//! use gdnative_project_utils::godot_resource::TextResource;
+//!
+//! let mut scene: TextResource = r#"[gd_scene load_steps=2 format=2]
+//!
+//! [ext_resource path="res://native/Player.gdns" type="Script" id=1]
+//!
+//! [node name="Player" type="KinematicBody2D"]
+//! script = ExtResource( 1 )
+//! "#
+//! .parse()
+//! .unwrap();
+//!
+//! let ext = scene.ext_resources().next().unwrap();
+//! assert_eq!(ext.path, "res://native/Player.gdns");
+//! assert_eq!(ext.type_name, "Script");
+//! assert_eq!(ext.id, "1");
+//!
+//! let rewritten =
+//!     scene.rewrite_ext_resource_paths("res://native/Player.gdns", "res://native/Hero.gdns");
+//! assert_eq!(rewritten, 1);
+//! assert!(scene
+//!     .to_string()
+//!     .contains(r#"[ext_resource path="res://native/Hero.gdns" type="Script" id=1]"#));
+//! ```
+
+use crate::godot_cfg::{nesting_depth, quote, unquote, ParseError};
+
+/// A parsed text resource or scene.
+#[derive(Clone, Debug)]
+pub struct TextResource {
+    lines: Vec<Line>,
+}
+
+#[derive(Clone, Debug)]
+enum Line {
+    /// A blank line, comment, or anything else that is kept verbatim.
+    Verbatim(String),
+    Heading(Heading),
+    Property(Property),
+}
+
+#[derive(Clone, Debug)]
+struct Heading {
+    /// The kind of the heading, e.g. `ext_resource` or `node`.
+    kind: String,
+    /// The attributes with their raw values, e.g. `("path", "\"res://a.gd\"")`.
+    attributes: Vec<(String, String)>,
+    /// The original text of the heading, dropped once it is modified.
+    raw: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+struct Property {
+    key: String,
+    /// The text between the key and the value, e.g. ` = `.
+    separator: String,
+    value: String,
+    /// The original text of the property, dropped once the value is modified.
+    raw: Option<String>,
+}
+
+/// An `[ext_resource]` a text resource refers to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtResource {
+    /// The path of the resource, usually a `res://` path.
+    pub path: String,
+    /// The type of the resource, e.g. `Script` or `PackedScene`.
+    pub type_name: String,
+    /// The id the resource is referred to with in `ExtResource( id )`.
+    pub id: String,
+}
+
+impl TextResource {
+    /// Parse a text resource or scene.
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        let mut lines = vec![];
+        let mut source_lines = source.split('\n').enumerate();
+
+        while let Some((line_no, line)) = source_lines.next() {
+            let trimmed = line.trim();
+
+            if trimmed.starts_with('[') {
+                let heading = Heading::parse(trimmed)
+                    .ok_or(ParseError::InvalidSection { line: line_no + 1 })?;
+                lines.push(Line::Heading(Heading {
+                    raw: Some(line.to_string()),
+                    ..heading
+                }));
+                continue;
+            }
+
+            let eq = match line.find('=') {
+                Some(eq) if !trimmed.starts_with(';') => eq,
+                _ => {
+                    lines.push(Line::Verbatim(line.to_string()));
+                    continue;
+                }
+            };
+
+            let mut raw = line.to_string();
+
+            // Property values may span multiple lines.
+            while nesting_depth(&raw[eq + 1..]) > 0 {
+                match source_lines.next() {
+                    Some((_, next)) => {
+                        raw.push('\n');
+                        raw.push_str(next);
+                    }
+                    None => return Err(ParseError::UnterminatedValue { line: line_no + 1 }),
+                }
+            }
+
+            let value_start = eq + 1 + (raw[eq + 1..].len() - raw[eq + 1..].trim_start().len());
+            let key_end = raw[..eq].trim_end().len();
+
+            lines.push(Line::Property(Property {
+                key: raw[..eq].trim().to_string(),
+                separator: raw[key_end..value_start].to_string(),
+                value: raw[value_start..].trim_end().to_string(),
+                raw: Some(raw.clone()),
+            }));
+        }
+
+        Ok(TextResource { lines })
+    }
+
+    /// Iterate over the external resources, in the order they are declared.
+    pub fn ext_resources(&self) -> impl Iterator<Item = ExtResource> + '_ {
+        self.headings()
+            .filter(|heading| heading.kind == "ext_resource")
+            .map(|heading| ExtResource {
+                path: heading.value("path").unwrap_or_default(),
+                type_name: heading.value("type").unwrap_or_default(),
+                id: heading.value("id").unwrap_or_default(),
+            })
+    }
+
+    /// Set the path of the external resource with the id `id`, and return
+    /// whether it exists.
+    pub fn set_ext_resource_path(&mut self, id: &str, path: &str) -> bool {
+        let heading = self.headings_mut().find(|heading| {
+            heading.kind == "ext_resource" && heading.value("id").as_deref() == Some(id)
+        });
+
+        match heading {
+            Some(heading) => {
+                heading.set("path", &quote(path));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Point every external resource at `old_path` to `new_path` instead, and
+    /// return how many were changed.
+    pub fn rewrite_ext_resource_paths(&mut self, old_path: &str, new_path: &str) -> usize {
+        let mut count = 0;
+
+        for heading in self.headings_mut() {
+            if heading.kind == "ext_resource" && heading.value("path").as_deref() == Some(old_path)
+            {
+                heading.set("path", &quote(new_path));
+                count += 1;
+            }
+        }
+
+        count
+    }
+
+    /// Iterate over the keys and raw, unparsed values of the properties
+    /// below the first heading of the kind `kind`, e.g. `resource` or `node`.
+    pub fn properties<'a>(&'a self, kind: &str) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+        let lines = match self.heading_lines(kind) {
+            Some(range) => &self.lines[range],
+            None => &[],
+        };
+
+        lines.iter().filter_map(|line| match line {
+            Line::Property(property) => Some((property.key.as_str(), property.value.as_str())),
+            Line::Verbatim(_) | Line::Heading(_) => None,
+        })
+    }
+
+    /// The raw value of the property `key` below the first heading of the
+    /// kind `kind`.
+    pub fn property(&self, kind: &str, key: &str) -> Option<&str> {
+        self.properties(kind)
+            .filter(|(k, _)| *k == key)
+            .last()
+            .map(|(_, value)| value)
+    }
+
+    /// Set the raw value of the property `key` below the first heading of the
+    /// kind `kind`, and return whether the heading exists.
+    ///
+    /// Existing properties are updated in place. New properties are added
+    /// after the last property of the heading.
+    pub fn set_property(&mut self, kind: &str, key: &str, value: impl Into<String>) -> bool {
+        let value = value.into();
+        let range = match self.heading_lines(kind) {
+            Some(range) => range,
+            None => return false,
+        };
+
+        let existing = self.lines[range.clone()]
+            .iter_mut()
+            .rev()
+            .find_map(|line| match line {
+                Line::Property(property) if property.key == key => Some(property),
+                _ => None,
+            });
+
+        if let Some(property) = existing {
+            if property.value != value {
+                property.value = value;
+                property.raw = None;
+            }
+            return true;
+        }
+
+        let last_property = self.lines[range.clone()]
+            .iter()
+            .rposition(|line| matches!(line, Line::Property(_)));
+
+        let separator = last_property
+            .and_then(|idx| match &self.lines[range.start + idx] {
+                Line::Property(property) => Some(property.separator.clone()),
+                Line::Verbatim(_) | Line::Heading(_) => None,
+            })
+            .unwrap_or_else(|| " = ".to_string());
+
+        let idx = match last_property {
+            Some(idx) => range.start + idx + 1,
+            None => range.start,
+        };
+
+        self.lines.insert(
+            idx,
+            Line::Property(Property {
+                key: key.to_string(),
+                separator,
+                value,
+                raw: None,
+            }),
+        );
+
+        true
+    }
+
+    /// Remove every property `key` below the first heading of the kind
+    /// `kind`, and return the raw value of the last one.
+    pub fn remove_property(&mut self, kind: &str, key: &str) -> Option<String> {
+        let range = self.heading_lines(kind)?;
+
+        let mut removed = None;
+        let mut idx = range.start;
+        let mut end = range.end;
+        while idx < end {
+            match &self.lines[idx] {
+                Line::Property(property) if property.key == key => {
+                    removed = Some(property.value.clone());
+                    self.lines.remove(idx);
+                    end -= 1;
+                }
+                _ => idx += 1,
+            }
+        }
+
+        removed
+    }
+
+    /// The lines below the first heading of the kind `kind`, up to the next
+    /// heading.
+    fn heading_lines(&self, kind: &str) -> Option<std::ops::Range<usize>> {
+        let start = self
+            .lines
+            .iter()
+            .position(|line| matches!(line, Line::Heading(heading) if heading.kind == kind))?
+            + 1;
+        let end = self.lines[start..]
+            .iter()
+            .position(|line| matches!(line, Line::Heading(_)))
+            .map_or(self.lines.len(), |len| start + len);
+
+        Some(start..end)
+    }
+
+    fn headings(&self) -> impl Iterator<Item = &Heading> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Heading(heading) => Some(heading),
+            Line::Verbatim(_) | Line::Property(_) => None,
+        })
+    }
+
+    fn headings_mut(&mut self) -> impl Iterator<Item = &mut Heading> {
+        self.lines.iter_mut().filter_map(|line| match line {
+            Line::Heading(heading) => Some(heading),
+            Line::Verbatim(_) | Line::Property(_) => None,
+        })
+    }
+}
+
+impl Heading {
+    /// Parse a heading like `[ext_resource path="res://a.gd" type="Script" id=1]`.
+    fn parse(text: &str) -> Option<Self> {
+        let inner = text.strip_prefix('[')?.strip_suffix(']')?;
+        let mut chars = inner.chars().peekable();
+
+        let kind = take_while(&mut chars, |c| !c.is_whitespace());
+        if kind.is_empty() {
+            return None;
+        }
+
+        let mut attributes = vec![];
+        loop {
+            while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                chars.next();
+            }
+            if chars.peek().is_none() {
+                break;
+            }
+
+            let key = take_while(&mut chars, |c| c != '=' && !c.is_whitespace());
+            if chars.next() != Some('=') || key.is_empty() {
+                return None;
+            }
+
+            // Values are strings or bare values like `1` or
+            // `ExtResource( 2 )`, which may contain spaces in parentheses.
+            let mut value = String::new();
+            let mut in_string = false;
+            let mut escaped = false;
+            let mut depth = 0usize;
+            while let Some(&c) = chars.peek() {
+                if !in_string && depth == 0 && c.is_whitespace() {
+                    break;
+                }
+                chars.next();
+                value.push(c);
+
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' if in_string => escaped = true,
+                    '"' => in_string = !in_string,
+                    '(' | '[' | '{' if !in_string => depth += 1,
+                    ')' | ']' | '}' if !in_string => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
+            if in_string || depth > 0 {
+                return None;
+            }
+
+            attributes.push((key, value));
+        }
+
+        Some(Heading {
+            kind,
+            attributes,
+            raw: None,
+        })
+    }
+
+    /// The value of the attribute `key`, without quotes.
+    fn value(&self, key: &str) -> Option<String> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| unquote(value))
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        match self.attributes.iter_mut().find(|(k, _)| k == key) {
+            Some((_, existing)) if existing == value => return,
+            Some((_, existing)) => *existing = value.to_string(),
+            None => self.attributes.push((key.to_string(), value.to_string())),
+        }
+        self.raw = None;
+    }
+
+    fn render(&self) -> String {
+        match &self.raw {
+            Some(raw) => raw.clone(),
+            None => {
+                let mut text = format!("[{}", self.kind);
+                for (key, value) in &self.attributes {
+                    text.push_str(&format!(" {}={}", key, value));
+                }
+                text.push(']');
+                text
+            }
+        }
+    }
+}
+
+impl Property {
+    fn render(&self) -> String {
+        match &self.raw {
+            Some(raw) => raw.clone(),
+            None => format!("{}{}{}", self.key, self.separator, self.value),
+        }
+    }
+}
+
+impl std::str::FromStr for TextResource {
+    type Err = ParseError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        TextResource::parse(source)
+    }
+}
+
+/// Serializes the resource, reproducing unmodified lines verbatim.
+impl std::fmt::Display for TextResource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lines = self
+            .lines
+            .iter()
+            .map(|line| match line {
+                Line::Verbatim(text) => text.clone(),
+                Line::Heading(heading) => heading.render(),
+                Line::Property(property) => property.render(),
+            })
+            .collect::<Vec<_>>();
+
+        f.write_str(&lines.join("\n"))
+    }
+}
+
+fn take_while(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+    predicate: impl Fn(char) -> bool,
+) -> String {
+    let mut taken = String::new();
+    while let Some(&c) = chars.peek() {
+        if !predicate(c) {
+            break;
+        }
+        taken.push(c);
+        chars.next();
+    }
+    taken
+}
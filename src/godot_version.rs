@@ -0,0 +1,67 @@
+//! Detection of the Godot version a project is made with.
+//!
+//! Godot 3 writes `config_version=4` at the top of `project.godot`, Godot 4
+//! writes `config_version=5`. Godot 4 also lists its minor version in
+//! `config/features`, e.g. `PackedStringArray("4.2", "Forward Plus")`, which
+//! is used if the config version is missing.
+
+use crate::generate::OutputFormat;
+use crate::godot_cfg::{unquote, ConfigFile};
+
+/// The major version of Godot a project is made with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GodotVersion {
+    /// Godot 3, which loads GDNative libraries.
+    Godot3,
+    /// Godot 4, which loads GDExtension libraries.
+    Godot4,
+}
+
+impl GodotVersion {
+    /// Detect the version from the content of a `project.godot` file, or
+    /// `None` if it is not recognized.
+    ///
+    /// ```
+    /// # use gdnative_project_utils::{ConfigFile, GodotVersion};
+    /// let project: ConfigFile = "config_version=5\n\n[application]\n".parse().unwrap();
+    /// assert_eq!(GodotVersion::from_project(&project), Some(GodotVersion::Godot4));
+    /// ```
+    pub fn from_project(project: &ConfigFile) -> Option<Self> {
+        let from_config_version = match project.get("", "config_version").map(str::trim) {
+            Some("3" | "4") => Some(GodotVersion::Godot3),
+            Some("5") => Some(GodotVersion::Godot4),
+            _ => None,
+        };
+
+        from_config_version.or_else(|| {
+            let features = project.get("application", "config/features")?;
+            let arguments = features.split_once('(')?.1.trim_end().strip_suffix(')')?;
+
+            arguments
+                .split(',')
+                .map(|feature| unquote(feature.trim()))
+                .find_map(|feature| match feature.split('.').next()? {
+                    "3" => Some(GodotVersion::Godot3),
+                    "4" => Some(GodotVersion::Godot4),
+                    _ => None,
+                })
+        })
+    }
+
+    /// The `format` number of the text resources and scenes of this version,
+    /// `2` for Godot 3 and `3` for Godot 4.
+    pub fn resource_format(self) -> u32 {
+        match self {
+            GodotVersion::Godot3 => 2,
+            GodotVersion::Godot4 => 3,
+        }
+    }
+
+    /// The kind of native library resource this version loads.
+    pub fn output_format(self) -> OutputFormat {
+        match self {
+            GodotVersion::Godot3 => OutputFormat::Gdnative,
+            GodotVersion::Godot4 => OutputFormat::GdExtension,
+        }
+    }
+}
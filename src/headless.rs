@@ -0,0 +1,227 @@
+//! Loading the generated resources in a headless Godot.
+//!
+//! Godot only reports broken library paths when a resource is loaded, which
+//! makes regressions easy to miss. A [`HeadlessCheck`] runs a small script in
+//! the project that loads every generated resource, so they can be caught in
+//! CI:
+//!
+//! ```no_run
+//! # use gdnative_project_utils::*;
+//! let report = Generator::new()
+//!     .godot_project_dir("../godot")
+//!     .build(Classes::new())?;
+//!
+//! HeadlessCheck::new("godot").run(&report)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use crate::godot_cfg::quote;
+use crate::report::{FileAction, Report};
+
+/// Prefix of the lines the check script prints for resources that failed to
+/// load.
+const FAILURE_PREFIX: &str = "gdnative-project-utils: failed to load ";
+
+/// Loads the resources of a [`Report`] in a Godot binary without a window.
+#[derive(Clone, Debug)]
+pub struct HeadlessCheck {
+    godot: PathBuf,
+    args: Vec<OsString>,
+}
+
+impl HeadlessCheck {
+    /// Construct a new HeadlessCheck that runs the Godot binary `godot`.
+    pub fn new(godot: impl AsRef<Path>) -> Self {
+        HeadlessCheck {
+            godot: godot.as_ref().to_path_buf(),
+            args: vec![],
+        }
+    }
+
+    /// Add an argument that is passed to Godot, in addition to the ones
+    /// selecting the project and the check script.
+    ///
+    /// Godot is run with `--headless` if it is Godot 4 or newer, and with
+    /// `--no-window` otherwise. Godot 3 can only run without a display
+    /// server in its server or headless builds.
+    pub fn with_arg(&mut self, arg: impl Into<OsString>) {
+        self.args.push(arg.into());
+    }
+
+    /// Add an argument that is passed to Godot, in addition to the ones
+    /// selecting the project and the check script.
+    ///
+    /// Godot is run with `--headless` if it is Godot 4 or newer, and with
+    /// `--no-window` otherwise. Godot 3 can only run without a display
+    /// server in its server or headless builds.
+    pub fn arg(mut self, arg: impl Into<OsString>) -> Self {
+        self.with_arg(arg);
+        self
+    }
+
+    /// Load the library and script resources of `report` in Godot.
+    ///
+    /// The resources are the `.gdnlib`, `.gdns` and `.gdextension` files of
+    /// the report inside its Godot project. A `.gdns` resource only counts as
+    /// loaded if its class can be instanced, which requires the library to
+    /// load and register the class.
+    pub fn run(&self, report: &Report) -> Result<(), HeadlessCheckError> {
+        let resources = report
+            .files
+            .iter()
+            .filter(|(_, action)| *action != FileAction::Removed)
+            .map(|(path, _)| path)
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext == "gdnlib" || ext == "gdns" || ext == "gdextension")
+            })
+            .filter_map(|path| path.strip_prefix(&report.godot_project_dir).ok())
+            .map(|path| {
+                let path = path
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>();
+                format!("res://{}", path.join("/"))
+            })
+            .collect::<Vec<_>>();
+
+        let major_version = self.major_version()?;
+
+        let script_path = std::env::temp_dir().join(format!(
+            "gdnative-project-utils-check-{}.gd",
+            std::process::id()
+        ));
+        std::fs::write(&script_path, check_script(&resources, major_version))
+            .map_err(HeadlessCheckError::Io)?;
+
+        let output = std::process::Command::new(&self.godot)
+            .arg(if major_version >= 4 {
+                "--headless"
+            } else {
+                "--no-window"
+            })
+            .args(&self.args)
+            .arg("--path")
+            .arg(&report.godot_project_dir)
+            .arg("-s")
+            .arg(&script_path)
+            .output();
+        let _ = std::fs::remove_file(&script_path);
+        let output = output.map_err(HeadlessCheckError::Io)?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let failed = stdout
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix(FAILURE_PREFIX))
+            .map(String::from)
+            .collect::<Vec<_>>();
+
+        if output.status.success() && failed.is_empty() {
+            Ok(())
+        } else {
+            Err(HeadlessCheckError::Failed {
+                resources: failed,
+                output: format!("{}{}", stdout, String::from_utf8_lossy(&output.stderr)),
+            })
+        }
+    }
+
+    /// The major version of Godot, from `godot --version`.
+    fn major_version(&self) -> Result<u32, HeadlessCheckError> {
+        let output = std::process::Command::new(&self.godot)
+            .arg("--version")
+            .output()
+            .map_err(HeadlessCheckError::Io)?;
+        let version = String::from_utf8_lossy(&output.stdout);
+
+        version
+            .trim()
+            .split('.')
+            .next()
+            .and_then(|major| major.parse().ok())
+            .ok_or_else(|| HeadlessCheckError::UnknownVersion(version.trim().to_string()))
+    }
+}
+
+/// A GDScript that loads `resources` and quits with a non-zero exit code if
+/// any of them fail to load.
+fn check_script(resources: &[String], major_version: u32) -> String {
+    let can_instance = if major_version >= 4 {
+        "can_instantiate"
+    } else {
+        "can_instance"
+    };
+
+    let resources = resources
+        .iter()
+        .map(|resource| quote(resource))
+        .collect::<Vec<_>>();
+
+    format!(
+        r#"extends SceneTree
+
+func _init():
+	var failed = 0
+	for path in [{resources}]:
+		var resource = load(path)
+		if resource == null or (resource is Script and not resource.{can_instance}()):
+			print("{prefix}" + path)
+			failed += 1
+	quit(1 if failed > 0 else 0)
+"#,
+        resources = resources.join(", "),
+        can_instance = can_instance,
+        prefix = FAILURE_PREFIX,
+    )
+}
+
+/// Error type for errors that can occur when running a [`HeadlessCheck`].
+#[derive(Debug)]
+pub enum HeadlessCheckError {
+    /// Godot could not be run, or the check script could not be written.
+    Io(std::io::Error),
+    /// `godot --version` did not print a version.
+    UnknownVersion(String),
+    /// Godot failed to load `resources`, or exited unsuccessfully.
+    Failed {
+        resources: Vec<String>,
+        /// What Godot printed.
+        output: String,
+    },
+}
+
+impl std::fmt::Display for HeadlessCheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeadlessCheckError::Io(err) => {
+                f.write_fmt(format_args!("Unable to run Godot: {}", err))
+            }
+            HeadlessCheckError::UnknownVersion(version) => {
+                f.write_fmt(format_args!("Unknown Godot version `{}`", version))
+            }
+            HeadlessCheckError::Failed { resources, output } if resources.is_empty() => {
+                f.write_fmt(format_args!("Godot failed: {}", output.trim()))
+            }
+            HeadlessCheckError::Failed { resources, .. } => f.write_fmt(format_args!(
+                "Godot failed to load {}",
+                resources
+                    .iter()
+                    .map(|resource| format!("`{}`", resource))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+impl std::error::Error for HeadlessCheckError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HeadlessCheckError::Io(err) => Some(err),
+            HeadlessCheckError::UnknownVersion(_) | HeadlessCheckError::Failed { .. } => None,
+        }
+    }
+}
@@ -1,14 +1,81 @@
 //! Utilities for working with GDNative projects.
 //!
 //! This crate can be used in cargo build scripts to automatically generate
-//! `.gdnlib` and `.gdns` files for the current project.
+//! `.gdnlib` and `.gdns` files for the current project, or a `.gdextension`
+//! file for Godot 4 projects.
 //!
 //! It currently does this by scanning the project sources for types that derive
 //! `NativeClass` and generates one `.gdns` file for each type.
 
+#[macro_use]
+mod logging;
+
+mod api;
+#[cfg(feature = "build_script")]
+pub mod build_script;
+mod cargo_config;
+mod cargo_manifest;
+mod cfg;
+mod class;
+mod diagnostics;
+#[cfg(feature = "cargo_expand")]
+mod expand;
+mod file_sink;
+mod gdnlib;
+mod gdns;
 mod generate;
+mod global_classes;
+pub mod godot_cfg;
+pub mod godot_resource;
+mod godot_version;
+mod headless;
+mod manifest;
+#[cfg(feature = "cargo_metadata")]
+mod metadata;
+mod platform;
+mod project_config;
+mod project_dir;
+mod report;
 mod scan;
+mod scan_cache;
+#[cfg(feature = "object")]
+mod symbols;
+mod template;
+#[cfg(feature = "notify")]
+mod watch;
+#[cfg(feature = "cargo_metadata")]
+mod workspace;
 
+pub use class::{Class, Classes, Method, Signal, SourceSpan};
+pub use diagnostics::{CargoWarnings, DiagnosticSink};
+#[cfg(feature = "cargo_expand")]
+pub use expand::{scan_crate_expanded, scan_expanded, ExpandScanner};
+pub use file_sink::{FileSink, FsSink, MemorySink};
+pub use gdnlib::Gdnlib;
+pub use gdns::Gdns;
 pub use generate::Builder as Generator;
-pub use generate::{BuildMode, LibFormat};
-pub use scan::{scan_crate, Classes, ScanError};
+pub use generate::{
+    render_gdnlib, render_gdns, AndroidLibLayout, Binaries, BuildMode, Configured, GdnlibConfig,
+    GdnsConfig, GenerateError, IosLibLayout, LibFormat, MacosLibLayout, NamingConvention,
+    OutputFormat, OutputLayout, OverwritePolicy, PathResolution, PathStyle, PlannedWrite,
+    RepairScope, StagingMode,
+};
+pub use godot_cfg::ConfigFile;
+pub use godot_cfg::ParseError as ConfigParseError;
+pub use godot_resource::{ExtResource, TextResource};
+pub use godot_version::GodotVersion;
+pub use headless::{HeadlessCheck, HeadlessCheckError};
+pub use platform::{Platform, Platforms};
+pub use report::{CheckIssue, CheckReport, ClassRename, FileAction, MissingLibrary, Report};
+#[cfg(feature = "serde")]
+pub use scan::scan_crate_to_writer;
+pub use scan::{
+    scan_crate, scan_crate_with_options, scan_crate_with_report, FileScanError, ScanError,
+    ScanOptions, ScanReport, ScanStats, Scanner, SynScanner,
+};
+#[cfg(feature = "object")]
+pub use symbols::{check_exported_symbols, SymbolError};
+#[cfg(feature = "notify")]
+pub use watch::{watch, WatchOptions};
+#[cfg(feature = "cargo_metadata")]
+pub use workspace::{WorkspaceError, WorkspaceGenerator};
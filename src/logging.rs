@@ -0,0 +1,31 @@
+//! Logging through the `log` crate if the `log` feature is enabled.
+//!
+//! Without the feature the macros compile to nothing, but still check their
+//! arguments, so no code has to be gated on the feature.
+
+macro_rules! log_at {
+    ($level:ident, $($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        log::$level!($($arg)+);
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = format!($($arg)+);
+        }
+    }};
+}
+
+macro_rules! trace {
+    ($($arg:tt)+) => { log_at!(trace, $($arg)+) };
+}
+
+macro_rules! debug {
+    ($($arg:tt)+) => { log_at!(debug, $($arg)+) };
+}
+
+macro_rules! info {
+    ($($arg:tt)+) => { log_at!(info, $($arg)+) };
+}
+
+macro_rules! warn {
+    ($($arg:tt)+) => { log_at!(warn, $($arg)+) };
+}
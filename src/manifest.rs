@@ -0,0 +1,220 @@
+//! Tracking of the files created by the generator.
+//!
+//! The manifest is stored as a hidden `ConfigFile` next to the generated
+//! resources, so the Godot editor does not pick it up. It maps the path of
+//! every generated file, relative to the resource output directory, to a hash
+//! of the content that was written:
+//!
+//! ```ini
+//! ; Files generated by gdnative-project-utils, do not edit.
+//!
+//! [files]
+//!
+//! my_crate.gdnlib="fnv1a64:8c1f2a3b4c5d6e7f"
+//! Player.gdns="fnv1a64:0123456789abcdef"
+//!
+//! [classes]
+//!
+//! Player.gdns="Player|src/player.rs|12"
+//! ```
+//!
+//! The `[classes]` section records which class each `.gdns` file was generated
+//! for and where it was declared, so renamed classes can be recognized. The
+//! `[user_files]` section records the files of the user the generator changed,
+//! like `project.godot`, so later changes by hand are noticed. They are never
+//! removed by the generator.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::file_sink::FileSink;
+use crate::generate::GenerateError;
+use crate::godot_cfg::ConfigFile;
+
+/// File name of the manifest inside the resource output directory.
+pub(crate) const MANIFEST_FILE_NAME: &str = ".gdnative-utils.cfg";
+
+/// The files created by the generator, relative to the resource output
+/// directory, and the hashes of their content.
+#[derive(Debug, Default)]
+pub(crate) struct Manifest {
+    files: BTreeMap<String, String>,
+    classes: BTreeMap<String, ClassRecord>,
+    user_files: BTreeMap<String, String>,
+}
+
+/// The class a `.gdns` file was generated for.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct ClassRecord {
+    pub(crate) name: String,
+    /// The file the class was declared in, if it is known.
+    pub(crate) source_file: Option<String>,
+    /// The line the class was declared on, if it is known.
+    pub(crate) line: Option<usize>,
+}
+
+impl Manifest {
+    /// Load the manifest from `dir`, or return an empty manifest if none
+    /// exists yet.
+    pub(crate) fn load(dir: &Path, sink: &dyn FileSink) -> Result<Self, GenerateError> {
+        let path = dir.join(MANIFEST_FILE_NAME);
+
+        if !sink.exists(&path) {
+            return Ok(Manifest::default());
+        }
+
+        let content = sink.read(&path)?;
+        let cfg = ConfigFile::parse(&content)
+            .map_err(|error| GenerateError::InvalidFile { path, error })?;
+
+        let files = cfg
+            .entries("files")
+            .map(|(file, hash)| (file.to_string(), hash.trim_matches('"').to_string()))
+            .collect();
+
+        let classes = cfg
+            .entries("classes")
+            .map(|(file, record)| {
+                let mut parts = record.trim_matches('"').split('|');
+                let record = ClassRecord {
+                    name: parts.next().unwrap_or_default().to_string(),
+                    source_file: parts
+                        .next()
+                        .filter(|file| !file.is_empty())
+                        .map(String::from),
+                    line: parts.next().and_then(|line| line.parse().ok()),
+                };
+                (file.to_string(), record)
+            })
+            .collect();
+
+        let user_files = cfg
+            .entries("user_files")
+            .map(|(file, hash)| (file.to_string(), hash.trim_matches('"').to_string()))
+            .collect();
+
+        Ok(Manifest {
+            files,
+            classes,
+            user_files,
+        })
+    }
+
+    /// Write the manifest to `dir`.
+    pub(crate) fn save(&self, dir: &Path, sink: &mut dyn FileSink) -> std::io::Result<()> {
+        let mut content = String::from(
+            "; Files generated by gdnative-project-utils, do not edit.\n\n[files]\n\n",
+        );
+
+        for (file, hash) in &self.files {
+            content.push_str(&format!("{}=\"{}\"\n", file, hash));
+        }
+
+        if !self.classes.is_empty() {
+            content.push_str("\n[classes]\n\n");
+        }
+        for (file, record) in &self.classes {
+            content.push_str(&format!(
+                "{}=\"{}|{}|{}\"\n",
+                file,
+                record.name,
+                record.source_file.as_deref().unwrap_or_default(),
+                record.line.map(|line| line.to_string()).unwrap_or_default()
+            ));
+        }
+
+        if !self.user_files.is_empty() {
+            content.push_str("\n[user_files]\n\n");
+        }
+        for (file, hash) in &self.user_files {
+            content.push_str(&format!("{}=\"{}\"\n", file, hash));
+        }
+
+        let path = dir.join(MANIFEST_FILE_NAME);
+        if sink.read(&path).ok().as_ref() == Some(&content) {
+            return Ok(());
+        }
+
+        sink.write(&path, &content)
+    }
+
+    /// Record that `file` was written with `content`.
+    pub(crate) fn insert(&mut self, file: impl Into<String>, content: &str) {
+        self.files.insert(file.into(), content_hash(content));
+    }
+
+    /// Returns `true` if `file` was created by the generator and `content` is
+    /// still what was written.
+    pub(crate) fn is_unmodified(&self, file: &str, content: &str) -> bool {
+        self.files.get(file) == Some(&content_hash(content))
+    }
+
+    /// Record that the file `file` of the user was changed to `content`.
+    pub(crate) fn insert_user_file(&mut self, file: impl Into<String>, content: &str) {
+        self.user_files.insert(file.into(), content_hash(content));
+    }
+
+    /// Returns `true` if the file `file` of the user was changed by the
+    /// generator before and `content` is no longer what was written.
+    pub(crate) fn is_user_file_modified(&self, file: &str, content: &str) -> bool {
+        self.user_files
+            .get(file)
+            .is_some_and(|hash| *hash != content_hash(content))
+    }
+
+    /// Returns `true` if the file `file` of the user was changed by the
+    /// generator before and `content` is still what was written.
+    pub(crate) fn is_user_file_unmodified(&self, file: &str, content: &str) -> bool {
+        self.user_files.get(file) == Some(&content_hash(content))
+    }
+
+    pub(crate) fn remove(&mut self, file: &str) {
+        self.files.remove(file);
+        self.classes.remove(file);
+    }
+
+    /// Record that the `.gdns` file `file` belongs to the class `record`.
+    pub(crate) fn set_class(&mut self, file: impl Into<String>, record: ClassRecord) {
+        self.classes.insert(file.into(), record);
+    }
+
+    /// The class the `.gdns` file `file` was generated for.
+    pub(crate) fn class(&self, file: &str) -> Option<&ClassRecord> {
+        self.classes.get(file)
+    }
+
+    /// Returns `true` if `file` was created by the generator.
+    pub(crate) fn contains(&self, file: &str) -> bool {
+        self.files.contains_key(file)
+    }
+
+    /// Returns `true` if no generated files are recorded.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// The generated files, as paths inside `dir`.
+    pub(crate) fn paths<'a>(
+        &'a self,
+        dir: &'a Path,
+    ) -> impl Iterator<Item = (&'a str, PathBuf)> + 'a {
+        self.files
+            .keys()
+            .map(move |file| (file.as_str(), dir.join(file)))
+    }
+}
+
+/// A stable hash of `content`.
+///
+/// This uses 64 bit FNV-1a, which is good enough to detect modifications and
+/// does not change between Rust versions like the std hashers may.
+pub(crate) fn content_hash(content: &str) -> String {
+    format!("fnv1a64:{:016x}", fnv1a64(content.as_bytes()))
+}
+
+/// The 64 bit FNV-1a hash of `bytes`.
+pub(crate) fn fnv1a64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
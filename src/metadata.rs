@@ -0,0 +1,68 @@
+//! Querying cargo for the layout of the crate, instead of guessing it from
+//! the environment.
+
+use std::path::{Path, PathBuf};
+
+use cargo_metadata::MetadataCommand;
+
+use crate::generate::GenerateError;
+
+/// What `cargo metadata` reports about the crate the resources are generated
+/// for.
+pub(crate) struct CrateMetadata {
+    /// The name of the library target, which may differ from the package
+    /// name through `[lib] name`.
+    pub(crate) lib_name: String,
+    /// The target directory of the crate's workspace.
+    pub(crate) target_dir: PathBuf,
+}
+
+impl CrateMetadata {
+    /// Run `cargo metadata` for the crate in `crate_dir`.
+    ///
+    /// Returns an error if the crate does not build a `cdylib`, which is the
+    /// only crate type Godot can load.
+    pub(crate) fn query(crate_dir: &Path) -> Result<Self, GenerateError> {
+        let manifest_path = crate_dir.join("Cargo.toml");
+
+        let metadata = MetadataCommand::new()
+            .manifest_path(&manifest_path)
+            .no_deps()
+            .exec()?;
+
+        // `crate_dir` may be reached through a symlink.
+        let canonical_manifest_path = dunce::canonicalize(&manifest_path)?;
+        let package = metadata
+            .packages
+            .iter()
+            .find(|package| {
+                dunce::canonicalize(&package.manifest_path).ok().as_ref()
+                    == Some(&canonical_manifest_path)
+            })
+            .ok_or_else(|| GenerateError::InvalidPath {
+                path: manifest_path.clone(),
+                error: std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "The manifest is not part of the workspace",
+                ),
+            })?;
+
+        let lib = package.targets.iter().find(|target| {
+            target
+                .kind
+                .iter()
+                .any(|kind| kind.ends_with("lib") || kind == "proc-macro")
+        });
+
+        match lib {
+            Some(lib) if lib.crate_types.iter().any(|ty| ty == "cdylib") => Ok(CrateMetadata {
+                lib_name: lib.name.clone(),
+                target_dir: metadata.target_directory.into_std_path_buf(),
+            }),
+            lib => Err(GenerateError::InvalidCrateType {
+                package: package.name.clone(),
+                crate_types: lib.map(|lib| lib.crate_types.clone()).unwrap_or_default(),
+            }),
+        }
+    }
+}
@@ -0,0 +1,448 @@
+//! Platforms for which library entries can be generated.
+
+use std::collections::BTreeSet;
+
+/// A single platform entry of a native library resource.
+///
+/// The variants are declared in the order in which their entries are written
+/// to generated files.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Platform {
+    /// `Android.armeabi-v7a`, built for `armv7-linux-androideabi`.
+    AndroidArmv7,
+    /// `Android.arm64-v8a`, built for `aarch64-linux-android`.
+    AndroidAarch64,
+    /// `Android.x86`, built for `i686-linux-android`.
+    AndroidX86,
+    /// `Android.x86_64`, built for `x86_64-linux-android`.
+    AndroidX86_64,
+    /// `X11.armv7`, built for `armv7-unknown-linux-gnueabihf`.
+    ///
+    /// The ARM entries are declared before [`Platform::X11_32`] and
+    /// [`Platform::X11_64`], so Godot picks them on ARM, where both match.
+    X11Armv7,
+    /// `X11.arm64`, built for `aarch64-unknown-linux-gnu`.
+    X11Arm64,
+    /// `X11.32`, built for `i686-unknown-linux-gnu`.
+    X11_32,
+    /// `X11.64`, built for the host.
+    X11_64,
+    /// `Server.64`, the headless Linux server, using the same library as
+    /// [`Platform::X11_64`].
+    Server64,
+    /// `OSX.arm64`, built for `aarch64-apple-darwin`.
+    ///
+    /// Declared before [`Platform::Osx64`], so Godot picks this entry on Apple
+    /// Silicon, where both match.
+    OsxArm64,
+    /// `OSX.64`, built for the host.
+    Osx64,
+    /// `iOS.armv7`, built for `armv7-apple-ios`.
+    IosArmv7,
+    /// `iOS.arm64`, built for `aarch64-apple-ios`, or for
+    /// `aarch64-apple-ios-sim` with [`IosLibLayout::Simulator`].
+    ///
+    /// [`IosLibLayout::Simulator`]: crate::IosLibLayout::Simulator
+    IosArm64,
+    /// `iOS.x86_64`, the simulator on Intel Macs, built for
+    /// `x86_64-apple-ios`.
+    IosSimulatorX86_64,
+    /// `HTML5.wasm32`, built for `wasm32-unknown-emscripten`.
+    Html5Wasm32,
+    /// `Windows.32`, built for `i686-pc-windows-msvc`.
+    Windows32,
+    /// `Windows.arm64`, built for `aarch64-pc-windows-msvc`.
+    ///
+    /// Declared before [`Platform::Windows64`], so Godot picks this entry on
+    /// ARM64, where both match.
+    WindowsArm64,
+    /// `Windows.64`, built for the host.
+    Windows64,
+}
+
+impl Platform {
+    /// All platforms, in the order their entries are generated.
+    pub const ALL: &'static [Platform] = &[
+        Platform::AndroidArmv7,
+        Platform::AndroidAarch64,
+        Platform::AndroidX86,
+        Platform::AndroidX86_64,
+        Platform::X11Armv7,
+        Platform::X11Arm64,
+        Platform::X11_32,
+        Platform::X11_64,
+        Platform::Server64,
+        Platform::OsxArm64,
+        Platform::Osx64,
+        Platform::IosArmv7,
+        Platform::IosArm64,
+        Platform::IosSimulatorX86_64,
+        Platform::Html5Wasm32,
+        Platform::Windows32,
+        Platform::WindowsArm64,
+        Platform::Windows64,
+    ];
+
+    /// The key of this platform in the `[entry]` and `[dependencies]`
+    /// sections of a `.gdnlib` file.
+    pub fn gdnlib_key(self) -> &'static str {
+        match self {
+            Platform::AndroidArmv7 => "Android.armeabi-v7a",
+            Platform::AndroidAarch64 => "Android.arm64-v8a",
+            Platform::AndroidX86 => "Android.x86",
+            Platform::AndroidX86_64 => "Android.x86_64",
+            Platform::X11Armv7 => "X11.armv7",
+            Platform::X11Arm64 => "X11.arm64",
+            Platform::X11_32 => "X11.32",
+            Platform::X11_64 => "X11.64",
+            Platform::Server64 => "Server.64",
+            Platform::OsxArm64 => "OSX.arm64",
+            Platform::Osx64 => "OSX.64",
+            Platform::IosArmv7 => "iOS.armv7",
+            Platform::IosArm64 => "iOS.arm64",
+            Platform::IosSimulatorX86_64 => "iOS.x86_64",
+            Platform::Html5Wasm32 => "HTML5.wasm32",
+            Platform::Windows32 => "Windows.32",
+            Platform::WindowsArm64 => "Windows.arm64",
+            Platform::Windows64 => "Windows.64",
+        }
+    }
+
+    /// The key of this platform in the `[libraries]` section of a
+    /// `.gdextension` file, or `None` if Godot 4 does not support it.
+    pub fn gdextension_key(self) -> Option<&'static str> {
+        match self {
+            Platform::AndroidArmv7 => Some("android.arm32"),
+            Platform::AndroidAarch64 => Some("android.arm64"),
+            Platform::AndroidX86 => Some("android.x86_32"),
+            Platform::AndroidX86_64 => Some("android.x86_64"),
+            Platform::X11Armv7 => Some("linux.arm32"),
+            Platform::X11Arm64 => Some("linux.arm64"),
+            Platform::X11_32 => Some("linux.x86_32"),
+            Platform::X11_64 => Some("linux.x86_64"),
+            Platform::Server64 => None,
+            Platform::OsxArm64 => Some("macos.arm64"),
+            Platform::Osx64 => Some("macos"),
+            Platform::IosArmv7 => None,
+            Platform::IosArm64 => Some("ios"),
+            // Godot 4 loads a single xcframework for the devices and the
+            // simulator from the `ios` entry.
+            Platform::IosSimulatorX86_64 => None,
+            Platform::Html5Wasm32 => Some("web.wasm32"),
+            Platform::Windows32 => Some("windows.x86_32"),
+            Platform::WindowsArm64 => Some("windows.arm64"),
+            Platform::Windows64 => Some("windows.x86_64"),
+        }
+    }
+
+    /// The target triple this platform is cross-compiled for, or `None` if
+    /// the artifacts are expected directly inside the build mode directory.
+    pub fn target_triple(self) -> Option<&'static str> {
+        match self {
+            Platform::AndroidArmv7 => Some("armv7-linux-androideabi"),
+            Platform::AndroidAarch64 => Some("aarch64-linux-android"),
+            Platform::AndroidX86 => Some("i686-linux-android"),
+            Platform::AndroidX86_64 => Some("x86_64-linux-android"),
+            Platform::X11Armv7 => Some("armv7-unknown-linux-gnueabihf"),
+            Platform::X11Arm64 => Some("aarch64-unknown-linux-gnu"),
+            Platform::X11_32 => Some("i686-unknown-linux-gnu"),
+            Platform::IosArmv7 => Some("armv7-apple-ios"),
+            Platform::IosArm64 => Some("aarch64-apple-ios"),
+            Platform::IosSimulatorX86_64 => Some("x86_64-apple-ios"),
+            Platform::OsxArm64 => Some("aarch64-apple-darwin"),
+            Platform::Html5Wasm32 => Some("wasm32-unknown-emscripten"),
+            Platform::Windows32 => Some("i686-pc-windows-msvc"),
+            Platform::WindowsArm64 => Some("aarch64-pc-windows-msvc"),
+            Platform::X11_64 | Platform::Server64 | Platform::Osx64 | Platform::Windows64 => None,
+        }
+    }
+
+    /// The Android ABI of this platform, as used for the directories of
+    /// `jniLibs` and by `cargo ndk`, or `None` if it is not an Android
+    /// platform.
+    pub fn android_abi(self) -> Option<&'static str> {
+        match self {
+            Platform::AndroidArmv7 => Some("armeabi-v7a"),
+            Platform::AndroidAarch64 => Some("arm64-v8a"),
+            Platform::AndroidX86 => Some("x86"),
+            Platform::AndroidX86_64 => Some("x86_64"),
+            _ => None,
+        }
+    }
+
+    /// The Android platform of the ABI `abi`, like `"arm64-v8a"`, or `None`
+    /// if the ABI is not known.
+    ///
+    /// ```
+    /// # use gdnative_project_utils::Platform;
+    /// assert_eq!(Platform::from_android_abi("arm64-v8a"), Some(Platform::AndroidAarch64));
+    /// ```
+    pub fn from_android_abi(abi: &str) -> Option<Platform> {
+        Platform::ALL
+            .iter()
+            .copied()
+            .find(|platform| platform.android_abi() == Some(abi))
+    }
+
+    /// The platform that cargo builds for when targeting `triple`, or `None`
+    /// if Godot has no matching platform.
+    ///
+    /// Besides the triples returned by [`target_triple`](Self::target_triple),
+    /// this recognizes the other triples of the same architecture and
+    /// operating system, e.g. `x86_64-pc-windows-gnu` for
+    /// [`Platform::Windows64`]. [`Platform::Server64`] is never returned, it
+    /// shares its library with [`Platform::X11_64`].
+    pub fn from_target_triple(triple: &str) -> Option<Platform> {
+        let arch = triple.split('-').next()?;
+
+        let platform = if triple.contains("-android") {
+            match arch {
+                "armv7" | "thumbv7neon" => Platform::AndroidArmv7,
+                "aarch64" => Platform::AndroidAarch64,
+                "i686" => Platform::AndroidX86,
+                "x86_64" => Platform::AndroidX86_64,
+                _ => return None,
+            }
+        } else if triple.contains("-linux") {
+            match arch {
+                "armv7" => Platform::X11Armv7,
+                "aarch64" => Platform::X11Arm64,
+                "i586" | "i686" => Platform::X11_32,
+                "x86_64" => Platform::X11_64,
+                _ => return None,
+            }
+        } else if triple.contains("-apple-darwin") {
+            match arch {
+                "aarch64" => Platform::OsxArm64,
+                "x86_64" => Platform::Osx64,
+                _ => return None,
+            }
+        } else if triple.contains("-apple-ios") {
+            match arch {
+                "armv7" => Platform::IosArmv7,
+                "aarch64" => Platform::IosArm64,
+                "x86_64" => Platform::IosSimulatorX86_64,
+                _ => return None,
+            }
+        } else if triple.contains("-windows") {
+            match arch {
+                "i586" | "i686" => Platform::Windows32,
+                "aarch64" => Platform::WindowsArm64,
+                "x86_64" => Platform::Windows64,
+                _ => return None,
+            }
+        } else if triple.contains("-emscripten") && arch == "wasm32" {
+            Platform::Html5Wasm32
+        } else {
+            return None;
+        };
+
+        Some(platform)
+    }
+
+    /// The platform whose entry points at libraries built for the host
+    /// without a `--target`, or `None` if the host is not a desktop platform
+    /// Godot supports.
+    ///
+    /// Both the operating system and the architecture are taken into account,
+    /// so an Apple Silicon Mac is [`OsxArm64`](Self::OsxArm64).
+    pub fn host() -> Option<Platform> {
+        let arch = match std::env::consts::ARCH {
+            "x86" => "i686",
+            "arm" => "armv7",
+            arch => arch,
+        };
+        let vendor_os = match std::env::consts::OS {
+            "linux" => "unknown-linux-gnu",
+            "macos" => "apple-darwin",
+            "windows" => "pc-windows-msvc",
+            _ => return None,
+        };
+
+        Platform::from_target_triple(&format!("{}-{}", arch, vendor_os))
+    }
+
+    /// The file name of the dynamic library called `name` on this platform.
+    ///
+    /// Hyphens in `name` are replaced with underscores, like cargo does.
+    pub fn lib_file_name(self, name: &str) -> String {
+        // cargo derives the crate name of a library from the package name by
+        // replacing hyphens with underscores on every platform, and
+        // `[lib] name` may not contain hyphens at all. Only the prefix and
+        // extension depend on the platform: `lib` for everything but Windows
+        // (both the MSVC and GNU toolchains) and WebAssembly.
+        let name = name.replace('-', "_");
+
+        match self {
+            Platform::Windows32 | Platform::WindowsArm64 | Platform::Windows64 => {
+                format!("{}.dll", name)
+            }
+            Platform::OsxArm64 | Platform::Osx64 => format!("lib{}.dylib", name),
+            Platform::IosArmv7 | Platform::IosArm64 | Platform::IosSimulatorX86_64 => {
+                format!("lib{}.a", name)
+            }
+            Platform::Html5Wasm32 => format!("{}.wasm", name),
+            Platform::AndroidArmv7
+            | Platform::AndroidAarch64
+            | Platform::AndroidX86
+            | Platform::AndroidX86_64
+            | Platform::X11Armv7
+            | Platform::X11Arm64
+            | Platform::X11_32
+            | Platform::X11_64
+            | Platform::Server64 => format!("lib{}.so", name),
+        }
+    }
+}
+
+/// A set of platforms for which entries are generated.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Platforms(BTreeSet<Platform>);
+
+impl Platforms {
+    /// An empty set of platforms.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every known platform.
+    pub fn all() -> Self {
+        Platform::ALL.iter().copied().collect()
+    }
+
+    /// The platforms generated when no selection is made: 64 bit desktop and
+    /// Android.
+    ///
+    /// iOS has to be selected explicitly, e.g. with
+    /// `Platforms::common().union(&Platforms::ios())`.
+    pub fn common() -> Self {
+        Self::desktop().union(&Self::android())
+    }
+
+    /// The 64 bit desktop platforms: X11, OSX and Windows.
+    pub fn desktop() -> Self {
+        vec![Platform::X11_64, Platform::Osx64, Platform::Windows64]
+            .into_iter()
+            .collect()
+    }
+
+    /// All Linux architectures.
+    pub fn linux() -> Self {
+        vec![
+            Platform::X11Armv7,
+            Platform::X11Arm64,
+            Platform::X11_32,
+            Platform::X11_64,
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    /// Both macOS architectures.
+    pub fn macos() -> Self {
+        vec![Platform::OsxArm64, Platform::Osx64]
+            .into_iter()
+            .collect()
+    }
+
+    /// All Windows architectures.
+    pub fn windows() -> Self {
+        vec![
+            Platform::Windows32,
+            Platform::WindowsArm64,
+            Platform::Windows64,
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    /// All Android ABIs.
+    pub fn android() -> Self {
+        vec![
+            Platform::AndroidArmv7,
+            Platform::AndroidAarch64,
+            Platform::AndroidX86,
+            Platform::AndroidX86_64,
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    /// All iOS device architectures.
+    pub fn ios() -> Self {
+        vec![Platform::IosArmv7, Platform::IosArm64]
+            .into_iter()
+            .collect()
+    }
+
+    /// The iOS simulator architectures, for use with
+    /// [`IosLibLayout::Simulator`](crate::IosLibLayout::Simulator).
+    pub fn ios_simulator() -> Self {
+        vec![Platform::IosArm64, Platform::IosSimulatorX86_64]
+            .into_iter()
+            .collect()
+    }
+
+    /// Add a platform to the set.
+    pub fn with(mut self, platform: Platform) -> Self {
+        self.insert(platform);
+        self
+    }
+
+    /// Remove a platform from the set.
+    pub fn without(mut self, platform: Platform) -> Self {
+        self.remove(platform);
+        self
+    }
+
+    /// Add a platform to the set.
+    pub fn insert(&mut self, platform: Platform) {
+        self.0.insert(platform);
+    }
+
+    /// Remove a platform from the set.
+    pub fn remove(&mut self, platform: Platform) {
+        self.0.remove(&platform);
+    }
+
+    /// Returns `true` if the set contains `platform`.
+    pub fn contains(&self, platform: Platform) -> bool {
+        self.0.contains(&platform)
+    }
+
+    /// Returns `true` if the set contains no platforms.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The platforms contained in either set.
+    pub fn union(&self, other: &Platforms) -> Platforms {
+        Platforms(self.0.union(&other.0).copied().collect())
+    }
+
+    /// Iterate over the platforms in generation order.
+    pub fn iter(&self) -> impl Iterator<Item = Platform> + '_ {
+        self.0.iter().copied()
+    }
+}
+
+impl From<Platform> for Platforms {
+    fn from(platform: Platform) -> Self {
+        Platforms::new().with(platform)
+    }
+}
+
+impl std::iter::FromIterator<Platform> for Platforms {
+    fn from_iter<I: IntoIterator<Item = Platform>>(iter: I) -> Self {
+        Platforms(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for Platforms {
+    type Item = Platform;
+    type IntoIter = std::collections::btree_set::IntoIter<Platform>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
@@ -0,0 +1,495 @@
+//! Generator settings declared outside of the build script.
+//!
+//! The settings are read from a `gdnative.toml` next to the crate's
+//! `Cargo.toml`, or from the `[package.metadata.gdnative]` table of the
+//! `Cargo.toml` itself. The keys are named after the [`Builder`] methods:
+//!
+//! ```toml
+//! godot_project_dir = "../godot"
+//! godot_resource_output_dir = "../godot/native"
+//! platforms = ["desktop", "Android.arm64-v8a"]
+//! overwrite_policy = "if_generated_by_us"
+//! naming_convention = "snake_case"
+//! prune_stale = true
+//! ```
+//!
+//! Each key can also be set with an environment variable, `GDNATIVE_` followed
+//! by the upper-cased key, e.g. `GDNATIVE_PLATFORMS=desktop,Android.arm64-v8a`.
+//! The project directories use the shorter `GODOT_PROJECT_DIR` and
+//! `GDNATIVE_RESOURCE_DIR`.
+//!
+//! The settings in `Cargo.toml` may also be written as dotted keys or an
+//! inline table, like any other TOML table.
+
+use std::path::{Path, PathBuf};
+
+use crate::cargo_config::{toml_document, toml_string_array};
+use crate::generate::{
+    AndroidLibLayout, BuildMode, Builder, GenerateError, LibFormat, NamingConvention, OutputFormat,
+    OutputLayout, OverwritePolicy, PathResolution, PathStyle, StagingMode,
+};
+use crate::godot_cfg::ParseError;
+use crate::godot_version::GodotVersion;
+use crate::platform::{Platform, Platforms};
+
+/// File name of the settings file inside the crate directory.
+const CONFIG_FILE_NAME: &str = "gdnative.toml";
+
+/// The table of the settings inside `Cargo.toml`.
+const METADATA_TABLE: [&str; 3] = ["package", "metadata", "gdnative"];
+
+/// Every setting, in the order the environment variables are applied.
+const KEYS: &[&str] = &[
+    "godot_project_dir",
+    "godot_resource_output_dir",
+    "target_dir",
+    "stage_artifacts",
+    "lib_name",
+    "target_triple",
+    "symbol_prefix",
+    "entry_symbol",
+    "editor_plugin",
+    "build_mode",
+    "lib_format",
+    "output_format",
+    "godot_version",
+    "layout",
+    "naming_convention",
+    "naming_prefix",
+    "naming_suffix",
+    "overwrite_policy",
+    "staging_mode",
+    "path_style",
+    "path_resolution",
+    "platforms",
+    "android_abis",
+    "android_lib_dir",
+    "host_only",
+    "detect_installed_targets",
+    "installed_targets",
+    "include_classes",
+    "exclude_classes",
+    "auto_detect_project",
+    "prune_stale",
+    "update_existing_lib",
+    "reloadable",
+    "singleton",
+    "load_once",
+    "gdscript_stubs",
+    "scene_templates",
+    "global_script_classes",
+    "migrate_renames",
+    "update_export_presets",
+    "gdignore",
+    "backups",
+    "sync_writes",
+    "include_debug_symbols",
+    "stage_dependencies",
+];
+
+impl Builder {
+    /// Construct a new Builder with the settings declared by the crate in
+    /// `CARGO_MANIFEST_DIR`, see [`from_config_in`](Self::from_config_in).
+    pub fn from_config() -> Result<Self, GenerateError> {
+        let crate_dir = std::env::var_os("CARGO_MANIFEST_DIR")
+            .ok_or(GenerateError::MissingField("crate_dir"))?;
+
+        Self::from_config_in(crate_dir)
+    }
+
+    /// Construct a new Builder with the settings declared by the crate in
+    /// `crate_dir`.
+    ///
+    /// The settings are read from `gdnative.toml` in `crate_dir`, or, if it
+    /// does not exist, from the `[package.metadata.gdnative]` table of its
+    /// `Cargo.toml`. The keys are named after the Builder methods, and
+    /// relative paths are resolved from `crate_dir`. Settings that are not
+    /// declared keep their defaults and can still be set on the returned
+    /// Builder, or overridden from the environment with
+    /// [`env_overrides`](Self::env_overrides).
+    ///
+    /// The supported keys are `godot_project_dir`, `godot_resource_output_dir`,
+    /// `target_dir`, `stage_artifacts` (paths), `lib_name`, `target_triple`,
+    /// `symbol_prefix`, `entry_symbol`, `editor_plugin` (strings),
+    /// `build_mode`, `lib_format`, `output_format`, `godot_version`, `layout`,
+    /// `naming_convention`, `overwrite_policy`, `staging_mode`, `path_style`,
+    /// `path_resolution` (the snake case name of a variant), `naming_prefix`,
+    /// `naming_suffix` (strings added to the naming convention), `platforms`
+    /// (an array of `.gdnlib` keys like `"X11.64"` and the groups `"all"`,
+    /// `"common"`, `"desktop"`, `"linux"`, `"macos"`, `"windows"`, `"android"`,
+    /// `"ios"` and `"ios_simulator"`), `android_abis` (an array of ABIs like
+    /// `"arm64-v8a"`), `android_lib_dir` (the path `cargo ndk -o` writes to),
+    /// `include_classes`, `exclude_classes` (arrays of class name patterns),
+    /// `installed_targets` (an array of target triples), and the flags
+    /// `host_only`, `detect_installed_targets`, `auto_detect_project`,
+    /// `prune_stale`, `update_existing_lib`, `reloadable`, `singleton`,
+    /// `load_once`, `gdscript_stubs`, `scene_templates`,
+    /// `global_script_classes`, `migrate_renames`, `update_export_presets`,
+    /// `gdignore`, `backups`, `sync_writes`, `include_debug_symbols` and
+    /// `stage_dependencies`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the settings can not be read, or if a key is
+    /// unknown or has an invalid value.
+    pub fn from_config_in(crate_dir: impl AsRef<Path>) -> Result<Self, GenerateError> {
+        Builder::new().config_in(crate_dir)
+    }
+
+    /// Apply the settings declared by the crate in `crate_dir`, like
+    /// [`from_config_in`](Self::from_config_in) does, and set it as the
+    /// crate directory.
+    ///
+    /// Settings are applied in order, so the declared settings take
+    /// precedence over the ones set before, e.g. defaults of a tool.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the settings can not be read, or if a key is
+    /// unknown or has an invalid value.
+    pub fn with_config_in(&mut self, crate_dir: impl AsRef<Path>) -> Result<(), GenerateError> {
+        let crate_dir = crate_dir.as_ref();
+        self.with_crate_dir(crate_dir);
+
+        let (path, in_manifest) = match crate_dir.join(CONFIG_FILE_NAME) {
+            path if path.is_file() => (path, false),
+            _ => (crate_dir.join("Cargo.toml"), true),
+        };
+        if !path.is_file() {
+            return Ok(());
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        let cfg = toml_document(&content).map_err(|error| GenerateError::InvalidToml {
+            path: path.clone(),
+            error: Box::new(error),
+        })?;
+
+        let settings = if in_manifest {
+            let metadata = cfg
+                .get(METADATA_TABLE[0])
+                .and_then(|package| package.get(METADATA_TABLE[1]))
+                .and_then(|metadata| metadata.get(METADATA_TABLE[2]));
+            match metadata {
+                Some(toml::Value::Table(settings)) => settings,
+                Some(_) => {
+                    return Err(GenerateError::InvalidFile {
+                        path,
+                        error: ParseError::InvalidValue {
+                            key: METADATA_TABLE.join("."),
+                        },
+                    })
+                }
+                None => return Ok(()),
+            }
+        } else {
+            &cfg
+        };
+
+        for (key, value) in settings {
+            let key = key.replace('-', "_");
+
+            apply(self, &key, Value::Toml(value), Some(crate_dir)).ok_or_else(|| {
+                GenerateError::InvalidFile {
+                    path: path.clone(),
+                    error: ParseError::InvalidValue { key },
+                }
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Apply the settings declared by the crate in `crate_dir`, like
+    /// [`from_config_in`](Self::from_config_in) does, and set it as the
+    /// crate directory.
+    ///
+    /// Settings are applied in order, so the declared settings take
+    /// precedence over the ones set before, e.g. defaults of a tool.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the settings can not be read, or if a key is
+    /// unknown or has an invalid value.
+    pub fn config_in(mut self, crate_dir: impl AsRef<Path>) -> Result<Self, GenerateError> {
+        self.with_config_in(crate_dir)?;
+        Ok(self)
+    }
+
+    /// Construct a new Builder with the settings given by environment
+    /// variables, see [`with_env_overrides`](Self::with_env_overrides).
+    pub fn from_env() -> Result<Self, GenerateError> {
+        Builder::new().env_overrides()
+    }
+
+    /// Override settings with the values of environment variables.
+    ///
+    /// Every key supported by [`from_config_in`](Self::from_config_in) has a
+    /// variable named `GDNATIVE_` followed by the upper-cased key, e.g.
+    /// `GDNATIVE_BUILD_MODE=release` or `GDNATIVE_PRUNE_STALE=true`, except
+    /// for the project directories, which are set with `GODOT_PROJECT_DIR`
+    /// and `GDNATIVE_RESOURCE_DIR`. `GDNATIVE_PLATFORMS` is a comma separated
+    /// list like `desktop,Android.arm64-v8a`, and flags also accept `1` and
+    /// `0`. Relative paths are resolved from the working directory.
+    ///
+    /// Settings are applied in order, so variables take precedence over the
+    /// defaults and a configuration file loaded before, and methods called
+    /// afterwards take precedence over the variables. Variables that are not
+    /// set or empty are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a variable has an invalid value.
+    pub fn with_env_overrides(&mut self) -> Result<(), GenerateError> {
+        for key in KEYS {
+            let var = env_var_name(key);
+            let value = match std::env::var(&var) {
+                Ok(value) if !value.is_empty() => value,
+                _ => continue,
+            };
+
+            apply(self, key, Value::Env(&value), None)
+                .ok_or(GenerateError::InvalidEnvVar { var, value })?;
+        }
+
+        Ok(())
+    }
+
+    /// Override settings with the values of environment variables.
+    ///
+    /// Every key supported by [`from_config_in`](Self::from_config_in) has a
+    /// variable named `GDNATIVE_` followed by the upper-cased key, e.g.
+    /// `GDNATIVE_BUILD_MODE=release` or `GDNATIVE_PRUNE_STALE=true`, except
+    /// for the project directories, which are set with `GODOT_PROJECT_DIR`
+    /// and `GDNATIVE_RESOURCE_DIR`. `GDNATIVE_PLATFORMS` is a comma separated
+    /// list like `desktop,Android.arm64-v8a`, and flags also accept `1` and
+    /// `0`. Relative paths are resolved from the working directory.
+    ///
+    /// Settings are applied in order, so variables take precedence over the
+    /// defaults and a configuration file loaded before, and methods called
+    /// afterwards take precedence over the variables. Variables that are not
+    /// set or empty are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a variable has an invalid value.
+    pub fn env_overrides(mut self) -> Result<Self, GenerateError> {
+        self.with_env_overrides()?;
+        Ok(self)
+    }
+}
+
+/// The environment variable of the setting `key`.
+fn env_var_name(key: &str) -> String {
+    match key {
+        "godot_project_dir" => "GODOT_PROJECT_DIR".to_string(),
+        "godot_resource_output_dir" => "GDNATIVE_RESOURCE_DIR".to_string(),
+        key => format!("GDNATIVE_{}", key.to_uppercase()),
+    }
+}
+
+/// The raw value of a setting.
+#[derive(Copy, Clone)]
+enum Value<'a> {
+    /// A TOML value, e.g. `"release"` or `["X11.64", "Windows.64"]`.
+    Toml(&'a toml::Value),
+    /// The value of an environment variable, e.g. `release` or
+    /// `X11.64,Windows.64`.
+    Env(&'a str),
+}
+
+impl Value<'_> {
+    fn string(self) -> Option<String> {
+        match self {
+            Value::Toml(value) => value.as_str().map(String::from),
+            Value::Env(value) => Some(value.to_string()),
+        }
+    }
+
+    fn flag(self) -> Option<bool> {
+        match self {
+            Value::Toml(value) => value.as_bool(),
+            Value::Env(value) => match value.trim() {
+                "true" | "1" => Some(true),
+                "false" | "0" => Some(false),
+                _ => None,
+            },
+        }
+    }
+
+    fn list(self) -> Option<Vec<String>> {
+        match self {
+            Value::Toml(value) => toml_string_array(value),
+            Value::Env(value) => Some(
+                value
+                    .split(',')
+                    .map(|item| item.trim().to_string())
+                    .filter(|item| !item.is_empty())
+                    .collect(),
+            ),
+        }
+    }
+
+    /// The snake case name of an enum variant.
+    fn variant(self) -> Option<String> {
+        self.string().map(|variant| variant.replace('-', "_"))
+    }
+}
+
+/// Apply the setting `key` to `builder`, resolving relative paths from
+/// `base_dir`.
+///
+/// Returns `None` if the key is unknown or the value is invalid.
+fn apply(builder: &mut Builder, key: &str, value: Value, base_dir: Option<&Path>) -> Option<()> {
+    let path = || {
+        let path = PathBuf::from(value.string()?);
+        Some(match base_dir {
+            Some(base_dir) => base_dir.join(path),
+            None => path,
+        })
+    };
+
+    match key {
+        "godot_project_dir" => builder.with_godot_project_dir(path()?),
+        "godot_resource_output_dir" => builder.with_godot_resource_output_dir(path()?),
+        "target_dir" => builder.with_target_dir(path()?),
+        "stage_artifacts" => builder.with_stage_artifacts(path()?),
+        "lib_name" => builder.with_lib_name(value.string()?),
+        "target_triple" => builder.with_target_triple(value.string()?),
+        "symbol_prefix" => builder.with_symbol_prefix(value.string()?),
+        "entry_symbol" => builder.with_entry_symbol(value.string()?),
+        "editor_plugin" => builder.with_editor_plugin(value.string()?),
+        "build_mode" => builder.with_build_mode(BuildMode::from_profile(&value.string()?)),
+        "lib_format" => builder.with_lib_format(match value.variant()?.as_str() {
+            "gdnlib" => LibFormat::Gdnlib,
+            "tres" => LibFormat::Tres,
+            _ => return None,
+        }),
+        "output_format" => builder.with_output_format(match value.variant()?.as_str() {
+            "gdnative" => OutputFormat::Gdnative,
+            "gd_extension" | "gdextension" => OutputFormat::GdExtension,
+            "dual" => OutputFormat::Dual,
+            _ => return None,
+        }),
+        "godot_version" => builder.with_godot_version(match value.variant()?.as_str() {
+            "godot3" | "3" => GodotVersion::Godot3,
+            "godot4" | "4" => GodotVersion::Godot4,
+            _ => return None,
+        }),
+        "layout" => builder.with_layout(match value.variant()?.as_str() {
+            "flat" => OutputLayout::Flat,
+            "module_tree" => OutputLayout::ModuleTree,
+            _ => return None,
+        }),
+        "naming_convention" => {
+            let convention = match value.variant()?.as_str() {
+                "pascal_case" => NamingConvention::PascalCase,
+                "snake_case" => NamingConvention::SnakeCase,
+                _ => return None,
+            };
+
+            // Keep the prefix and suffix that may have been set before.
+            let naming = match builder.current_naming_convention() {
+                Some(NamingConvention::Affixed { prefix, suffix, .. }) => convention
+                    .with_prefix(prefix.clone())
+                    .with_suffix(suffix.clone()),
+                _ => convention,
+            };
+            builder.with_naming_convention(naming);
+        }
+        "naming_prefix" | "naming_suffix" => {
+            let affix = value.string()?;
+            let naming = builder
+                .current_naming_convention()
+                .cloned()
+                .unwrap_or(NamingConvention::PascalCase);
+
+            builder.with_naming_convention(match key {
+                "naming_prefix" => naming.with_prefix(affix),
+                _ => naming.with_suffix(affix),
+            });
+        }
+        "overwrite_policy" => builder.with_overwrite_policy(match value.variant()?.as_str() {
+            "never" => OverwritePolicy::Never,
+            "if_generated_by_us" => OverwritePolicy::IfGeneratedByUs,
+            "if_content_differs" => OverwritePolicy::IfContentDiffers,
+            _ => return None,
+        }),
+        "staging_mode" => builder.with_staging_mode(match value.variant()?.as_str() {
+            "copy" => StagingMode::Copy,
+            "symlink" => StagingMode::Symlink,
+            "versioned" => StagingMode::Versioned,
+            _ => return None,
+        }),
+        "path_style" => builder.with_path_style(match value.variant()?.as_str() {
+            "auto" => PathStyle::Auto,
+            "force_res" => PathStyle::ForceRes,
+            "force_absolute" => PathStyle::ForceAbsolute,
+            _ => return None,
+        }),
+        "path_resolution" => builder.with_path_resolution(match value.variant()?.as_str() {
+            "canonical" => PathResolution::Canonical,
+            "logical" => PathResolution::Logical,
+            _ => return None,
+        }),
+        "platforms" => builder.with_platforms(parse_platforms(&value.list()?)?),
+        "android_abis" => builder.with_android_abis(
+            value
+                .list()?
+                .iter()
+                .map(|abi| Platform::from_android_abi(abi))
+                .collect::<Option<Platforms>>()?,
+        ),
+        "android_lib_dir" => {
+            builder.with_android_lib_layout(AndroidLibLayout::AbiDirectories(path()?))
+        }
+        "host_only" => builder.with_host_only(value.flag()?),
+        "detect_installed_targets" => builder.with_detect_installed_targets(value.flag()?),
+        "installed_targets" => builder.with_installed_targets(value.list()?),
+        "include_classes" => builder.with_include_classes(value.list()?),
+        "exclude_classes" => builder.with_exclude_classes(value.list()?),
+        "auto_detect_project" => builder.with_auto_detect_project(value.flag()?),
+        "prune_stale" => builder.with_prune_stale(value.flag()?),
+        "update_existing_lib" => builder.with_update_existing_lib(value.flag()?),
+        "reloadable" => builder.with_reloadable(value.flag()?),
+        "singleton" => builder.with_singleton(value.flag()?),
+        "load_once" => builder.with_load_once(value.flag()?),
+        "gdscript_stubs" => builder.with_gdscript_stubs(value.flag()?),
+        "scene_templates" => builder.with_scene_templates(value.flag()?),
+        "global_script_classes" => builder.with_global_script_classes(value.flag()?),
+        "migrate_renames" => builder.with_migrate_renames(value.flag()?),
+        "update_export_presets" => builder.with_update_export_presets(value.flag()?),
+        "gdignore" => builder.with_gdignore(value.flag()?),
+        "backups" => builder.with_backups(value.flag()?),
+        "sync_writes" => builder.with_sync_writes(value.flag()?),
+        "include_debug_symbols" => builder.with_include_debug_symbols(value.flag()?),
+        "stage_dependencies" => builder.with_stage_dependencies(value.flag()?),
+        _ => return None,
+    }
+
+    Some(())
+}
+
+/// Parse an array of platform keys like `"X11.64"` and groups like
+/// `"desktop"`.
+fn parse_platforms(names: &[String]) -> Option<Platforms> {
+    names.iter().try_fold(Platforms::new(), |platforms, name| {
+        let group = match name.as_str() {
+            "all" => Platforms::all(),
+            "common" => Platforms::common(),
+            "desktop" => Platforms::desktop(),
+            "linux" => Platforms::linux(),
+            "macos" => Platforms::macos(),
+            "windows" => Platforms::windows(),
+            "android" => Platforms::android(),
+            "ios" => Platforms::ios(),
+            "ios_simulator" => Platforms::ios_simulator(),
+            key => Platforms::new().with(
+                *Platform::ALL
+                    .iter()
+                    .find(|platform| platform.gdnlib_key() == key)?,
+            ),
+        };
+        Some(platforms.union(&group))
+    })
+}
@@ -0,0 +1,69 @@
+//! Discovery of the Godot project directory from the crate directory.
+//!
+//! The project is either around the crate, as in a Godot project with a
+//! `rust/` directory, or next to it, as in a repository with `godot/` and
+//! `rust/` directories. The ancestors of the crate directory are searched
+//! first, nearest first, then the directories one or two levels below the
+//! crate directory and its parent.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use crate::generate::GenerateError;
+
+/// How many levels below the crate directory and its parent are searched.
+const MAX_DEPTH: usize = 2;
+
+/// Find the directory of the Godot project that belongs to the crate in
+/// `crate_dir`.
+pub(crate) fn find(crate_dir: &Path) -> Result<PathBuf, GenerateError> {
+    if let Some(dir) = crate_dir
+        .ancestors()
+        .find(|dir| dir.join("project.godot").is_file())
+    {
+        return Ok(dir.to_path_buf());
+    }
+
+    let mut candidates = BTreeSet::new();
+    for base in std::iter::once(crate_dir).chain(crate_dir.parent()) {
+        search_below(base, MAX_DEPTH, &mut candidates);
+    }
+
+    let mut candidates = candidates.into_iter();
+    match (candidates.next(), candidates.len()) {
+        (Some(dir), 0) => Ok(dir),
+        (Some(first), _) => Err(GenerateError::AmbiguousProject {
+            candidates: std::iter::once(first).chain(candidates).collect(),
+        }),
+        (None, _) => Err(GenerateError::MissingField("godot_project_dir")),
+    }
+}
+
+/// Add the directories up to `depth` levels below `dir` that hold a
+/// `project.godot` file to `candidates`.
+fn search_below(dir: &Path, depth: usize, candidates: &mut BTreeSet<PathBuf>) {
+    if depth == 0 {
+        return;
+    }
+
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        // Hidden directories and build output never hold the project.
+        if name.starts_with('.') || name == "target" || !path.is_dir() {
+            continue;
+        }
+
+        if path.join("project.godot").is_file() {
+            candidates.insert(path);
+        } else {
+            search_below(&path, depth - 1, candidates);
+        }
+    }
+}
@@ -0,0 +1,308 @@
+//! Summaries of what the generator did.
+
+use std::path::{Path, PathBuf};
+
+use crate::diagnostics::DiagnosticSink;
+use crate::generate::{Binaries, BuildMode, OutputFormat};
+use crate::godot_version::GodotVersion;
+use crate::platform::Platform;
+
+/// What happened to a generated file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FileAction {
+    /// The file did not exist and was created.
+    Created,
+    /// The file existed and was overwritten or updated.
+    Updated,
+    /// The file already had the generated content.
+    Unchanged,
+    /// The file existed with different content and was left untouched.
+    Skipped,
+    /// The file was removed because it is stale.
+    Removed,
+}
+
+/// A summary of a [`Generator::build`](crate::Generator::build) run.
+#[derive(Clone, Debug)]
+pub struct Report {
+    /// The resolved root of the Godot project.
+    pub godot_project_dir: PathBuf,
+    /// The resolved directory the resources were generated into.
+    pub godot_resource_output_dir: PathBuf,
+    /// The resolved cargo target directory.
+    pub target_dir: PathBuf,
+    /// The target triple the crate is built for, if it is not built for the
+    /// host.
+    pub target_triple: Option<String>,
+    /// The name of the library, which the file names of the libraries and of
+    /// the library resource are derived from.
+    pub lib_name: String,
+    /// The build mode the library entries point at.
+    pub build_mode: BuildMode,
+    /// The Godot version of the project, as given or detected from
+    /// `project.godot`, or `None` if it is unknown.
+    pub godot_version: Option<GodotVersion>,
+    /// The kind of resources that were generated.
+    pub output_format: OutputFormat,
+    /// The path of the library resource.
+    pub lib_path: PathBuf,
+    /// The path of the `.gdextension` file, if one was generated. With
+    /// [`OutputFormat::Dual`](crate::OutputFormat::Dual) it is generated in
+    /// addition to the GDNative library resource in `lib_path`.
+    pub gdextension_path: Option<PathBuf>,
+    /// The prefix of the library entries, `res://` or empty for absolute
+    /// paths.
+    pub binary_path_prefix: String,
+    /// The libraries the entries of the library resource point at, after
+    /// overrides and staging.
+    pub binaries: Binaries,
+    /// The path of the library resource as referenced by the `.gdns` files,
+    /// or `None` if no `.gdns` files are generated.
+    pub lib_resource_path: Option<String>,
+    /// Every file the generator considered, in the order they were handled.
+    pub files: Vec<(PathBuf, FileAction)>,
+    /// Libraries that were copied into the staging directory, as source and
+    /// destination.
+    pub staged: Vec<(PathBuf, PathBuf)>,
+    /// Files modified by hand that were copied before they were overwritten
+    /// or removed, as the file and its backup.
+    pub backups: Vec<(PathBuf, PathBuf)>,
+    /// Classes that were probably renamed since the last run.
+    pub renames: Vec<ClassRename>,
+    /// Platforms whose entries were left out because no target for them is
+    /// installed, see
+    /// [`Generator::detect_installed_targets`](crate::Generator::detect_installed_targets).
+    pub uninstalled_platforms: Vec<Platform>,
+    /// The sets of symbols Godot looks up in the library, one of which it
+    /// has to export, e.g. the `gdnative_init`, `gdnative_terminate` and
+    /// `nativescript_init` functions with the configured symbol prefix.
+    pub required_symbols: Vec<Vec<String>>,
+}
+
+/// A class that was probably renamed, detected by
+/// [`Generator::build`](crate::Generator::build).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClassRename {
+    pub old_class: String,
+    pub new_class: String,
+    /// The `.gdns` file of the old class.
+    pub old_file: PathBuf,
+    /// The `.gdns` file of the new class.
+    pub new_file: PathBuf,
+}
+
+impl Report {
+    /// Files that were created.
+    pub fn created(&self) -> impl Iterator<Item = &Path> {
+        self.with_action(FileAction::Created)
+    }
+
+    /// Files that were overwritten or updated.
+    pub fn updated(&self) -> impl Iterator<Item = &Path> {
+        self.with_action(FileAction::Updated)
+    }
+
+    /// Files that already had the generated content.
+    pub fn unchanged(&self) -> impl Iterator<Item = &Path> {
+        self.with_action(FileAction::Unchanged)
+    }
+
+    /// Files that exist with different content but were left untouched.
+    pub fn skipped(&self) -> impl Iterator<Item = &Path> {
+        self.with_action(FileAction::Skipped)
+    }
+
+    /// Stale files that were removed.
+    pub fn removed(&self) -> impl Iterator<Item = &Path> {
+        self.with_action(FileAction::Removed)
+    }
+
+    /// Check that the library of the platform being built for exists.
+    ///
+    /// The platform is the one of [`target_triple`](Self::target_triple), or
+    /// the host platform. A missing library usually means that the build
+    /// mode or target directory the entries were generated for is not the
+    /// one the crate is built with. In a build script the library of the
+    /// current build is only created after the build script ran, so it is
+    /// also missing on the very first build.
+    pub fn validate(&self) -> Result<(), MissingLibrary> {
+        match self.built_library() {
+            Some((platform, path)) if !path.exists() => Err(MissingLibrary {
+                platform,
+                path: path.to_path_buf(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that the library of the platform being built for exports the
+    /// [symbols](Self::required_symbols) Godot looks for.
+    ///
+    /// In a build script the library is only built after the build script
+    /// ran, so this is meant to be called once cargo built it, e.g. by a
+    /// tool that runs `cargo build` and then generates the resources. Static
+    /// and WebAssembly libraries, and platforms without a library entry, are
+    /// not checked.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the library can not be read, or if it does not
+    /// export one of the sets of symbols.
+    #[cfg(feature = "object")]
+    pub fn check_symbols(&self) -> Result<(), crate::symbols::SymbolError> {
+        let path = match self.built_library() {
+            // Static and WebAssembly libraries are linked differently.
+            Some((
+                Platform::IosArmv7
+                | Platform::IosArm64
+                | Platform::IosSimulatorX86_64
+                | Platform::Html5Wasm32,
+                _,
+            ))
+            | None => return Ok(()),
+            Some((_, path)) => path,
+        };
+
+        let mut result = Ok(());
+        for symbols in &self.required_symbols {
+            let symbols = symbols.iter().map(String::as_str).collect::<Vec<_>>();
+            result = crate::symbols::check_exported_symbols(path, &symbols);
+            if result.is_ok() {
+                break;
+            }
+        }
+        result
+    }
+
+    /// Pass a warning to `sink` if [`validate`](Self::validate) fails, for
+    /// every platform whose entry was left out because its target is not
+    /// [installed](Self::uninstalled_platforms), and for every file that was
+    /// [skipped](FileAction::Skipped).
+    pub fn emit_diagnostics(&self, sink: &mut impl DiagnosticSink) {
+        if let Err(missing) = self.validate() {
+            sink.warning(&missing.to_string());
+        }
+
+        for platform in &self.uninstalled_platforms {
+            sink.warning(&format!(
+                "The `{}` library entry was left out, no target for it is installed",
+                platform.gdnlib_key()
+            ));
+        }
+
+        for path in self.skipped() {
+            sink.warning(&format!(
+                "`{}` was modified and is not overwritten, remove it or use \
+                 `OverwritePolicy::IfContentDiffers` to generate it again",
+                path.display()
+            ));
+        }
+    }
+
+    /// Print the [diagnostics](Self::emit_diagnostics) as `cargo:warning`
+    /// directives, so cargo shows them when building the crate.
+    #[cfg(feature = "build_script")]
+    pub fn print_warnings(&self) {
+        self.emit_diagnostics(&mut crate::diagnostics::CargoWarnings);
+    }
+
+    /// The platform being built for and the library its entry points at.
+    pub(crate) fn built_library(&self) -> Option<(Platform, &Path)> {
+        let platform = match &self.target_triple {
+            Some(triple) => Platform::from_target_triple(triple),
+            None => Platform::host(),
+        }?;
+
+        Some((platform, self.binaries.get(platform)?))
+    }
+
+    fn with_action(&self, action: FileAction) -> impl Iterator<Item = &Path> {
+        self.files
+            .iter()
+            .filter(move |(_, a)| *a == action)
+            .map(|(path, _)| path.as_path())
+    }
+}
+
+/// A library entry that points at a file that does not exist, found by
+/// [`Report::validate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MissingLibrary {
+    pub platform: Platform,
+    /// The path the entry points at.
+    pub path: PathBuf,
+}
+
+impl std::fmt::Display for MissingLibrary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "The `{}` library entry points at `{}`, which does not exist",
+            self.platform.gdnlib_key(),
+            self.path.display()
+        ))
+    }
+}
+
+impl std::error::Error for MissingLibrary {}
+
+/// The result of [`Generator::check`](crate::Generator::check).
+#[derive(Clone, Debug, Default)]
+pub struct CheckReport {
+    /// Every difference between the generated files and the files on disk.
+    pub issues: Vec<CheckIssue>,
+}
+
+/// A difference between a generated file and the file on disk.
+#[derive(Clone, Debug)]
+pub enum CheckIssue {
+    /// The file does not exist.
+    Missing(PathBuf),
+    /// The file exists, but its content differs from the generated content.
+    OutOfDate {
+        path: PathBuf,
+        expected: String,
+        actual: String,
+    },
+    /// The file was generated for a class that no longer exists.
+    Orphaned(PathBuf),
+}
+
+impl CheckReport {
+    /// Returns `true` if all generated files are up to date.
+    pub fn is_up_to_date(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl std::fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for issue in &self.issues {
+            match issue {
+                CheckIssue::Missing(path) => writeln!(f, "missing: {}", path.display())?,
+                CheckIssue::OutOfDate {
+                    path,
+                    expected,
+                    actual,
+                } => {
+                    writeln!(f, "out of date: {}", path.display())?;
+
+                    for line in actual
+                        .lines()
+                        .filter(|l| !expected.lines().any(|e| e == *l))
+                    {
+                        writeln!(f, "  - {}", line)?;
+                    }
+                    for line in expected
+                        .lines()
+                        .filter(|l| !actual.lines().any(|a| a == *l))
+                    {
+                        writeln!(f, "  + {}", line)?;
+                    }
+                }
+                CheckIssue::Orphaned(path) => writeln!(f, "orphaned: {}", path.display())?,
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,59 +1,994 @@
 //! Scanning of project sources.
 
 use std::collections::HashSet;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use proc_macro2::TokenTree;
+use crate::api::ClassApi;
+use crate::cfg::TargetCfg;
+use crate::class::{Class, Classes};
+use crate::diagnostics::{CargoWarnings, DiagnosticSink};
+use crate::manifest::content_hash;
+use crate::scan_cache::{modified_stamp, CachedFile, ScanCache};
 
-/// Type-alias for a set of classes that were found from the scan.
-pub type Classes = HashSet<String>;
+/// The result of a scan, including the files that contributed to it.
+///
+/// With the `serde` feature, the report can be serialized, see
+/// [`scan_crate_to_writer`] for its JSON representation.
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScanReport {
+    /// The classes that were found.
+    pub classes: Classes,
+    /// The directory that was scanned.
+    pub dir: PathBuf,
+    /// All `*.rs` files that were scanned.
+    pub files: Vec<PathBuf>,
+    /// The files that were skipped because they could not be scanned, with
+    /// [`ScanOptions::lenient`].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub errors: Vec<FileScanError>,
+    /// How much work the scan did.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub stats: ScanStats,
+}
+
+/// Metrics of a scan, to find out why it is slow.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScanStats {
+    /// The number of `*.rs` files that were found.
+    pub files_walked: usize,
+    /// The number of files that were parsed.
+    pub files_parsed: usize,
+    /// The number of files whose results were taken from the scan cache.
+    pub cache_hits: usize,
+    /// The time spent parsing files, summed over all threads.
+    pub parse_time: Duration,
+    /// The time the whole scan took.
+    pub total_time: Duration,
+}
+
+/// Displays the metrics in one line, e.g. for a `cargo:warning` directive.
+impl std::fmt::Display for ScanStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "Scanned {} files ({} parsed, {} cached) in {:.1?}, {:.1?} spent parsing",
+            self.files_walked, self.files_parsed, self.cache_hits, self.total_time, self.parse_time
+        ))
+    }
+}
+
+impl ScanReport {
+    /// Print `cargo:rerun-if-changed` directives for the scanned directory and
+    /// every scanned file, so cargo re-runs the build script when classes are
+    /// added or removed.
+    pub fn print_rerun_if_changed(&self) {
+        println!("cargo:rerun-if-changed={}", self.dir.display());
+
+        for file in &self.files {
+            println!("cargo:rerun-if-changed={}", file.display());
+        }
+    }
+
+    /// Pass a warning to `sink` for every file that was skipped by a lenient
+    /// scan.
+    pub fn emit_diagnostics(&self, sink: &mut impl DiagnosticSink) {
+        for error in &self.errors {
+            sink.warning(&error.to_string().replace('\n', " "));
+        }
+    }
+
+    /// Print a `cargo:warning` directive for every file that was skipped, so
+    /// cargo shows them when building the crate.
+    pub fn print_warnings(&self) {
+        self.emit_diagnostics(&mut CargoWarnings);
+    }
+}
+
+/// A source of the classes to generate `.gdns` files for.
+///
+/// Besides [`SynScanner`], which scans the crate's sources, this is
+/// implemented for a fixed set of [`Classes`] and for closures returning them,
+/// e.g. to read a class list from a file or as test doubles.
+pub trait Scanner {
+    /// Find the classes.
+    fn scan(&self) -> Result<Classes, ScanError>;
+}
+
+impl Scanner for Classes {
+    fn scan(&self) -> Result<Classes, ScanError> {
+        Ok(self.clone())
+    }
+}
+
+impl<F: Fn() -> Result<Classes, ScanError>> Scanner for F {
+    fn scan(&self) -> Result<Classes, ScanError> {
+        self()
+    }
+}
+
+/// A [`Scanner`] that parses the `*.rs` files in a directory, like
+/// [`scan_crate`].
+#[derive(Clone, Debug)]
+pub struct SynScanner {
+    dir: PathBuf,
+    options: ScanOptions,
+}
+
+impl SynScanner {
+    /// Construct a new SynScanner for the sources in `dir`.
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        SynScanner {
+            dir: dir.as_ref().to_path_buf(),
+            options: ScanOptions::default(),
+        }
+    }
+
+    /// Set the options of the scan.
+    pub fn with_options(&mut self, options: ScanOptions) {
+        self.options = options;
+    }
+
+    /// Set the options of the scan.
+    pub fn options(mut self, options: ScanOptions) -> Self {
+        self.with_options(options);
+        self
+    }
+}
+
+/// Prints `cargo:rerun-if-changed` directives like [`scan_crate`], and
+/// warnings about the files a lenient scan skipped.
+impl Scanner for SynScanner {
+    fn scan(&self) -> Result<Classes, ScanError> {
+        let report = scan_crate_with_options(&self.dir, &self.options)?;
+
+        report.print_rerun_if_changed();
+        if cfg!(feature = "build_script") {
+            report.print_warnings();
+        }
+
+        Ok(report.classes)
+    }
+}
+
+/// Settings that change which declarations a scan considers.
+#[derive(Clone, Debug, Default)]
+pub struct ScanOptions {
+    features: Option<HashSet<String>>,
+    target: Option<TargetCfg>,
+    derives: Vec<String>,
+    follow_modules: bool,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    include_tests: bool,
+    lenient: bool,
+    cache_file: Option<PathBuf>,
+}
+
+impl ScanOptions {
+    /// Construct new ScanOptions, under which every conditional declaration
+    /// except test code is considered.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the cargo features the crate is built with.
+    ///
+    /// Types and modules behind `#[cfg(feature = "...")]` gates, and
+    /// `#[cfg_attr(feature = "...", derive(NativeClass))]` attributes, are
+    /// only considered if their predicate holds for these features. If no
+    /// features are set, all of them are considered.
+    pub fn with_features<S: AsRef<str>>(&mut self, features: impl IntoIterator<Item = S>) {
+        self.features = Some(
+            features
+                .into_iter()
+                .map(|feature| feature.as_ref().to_string())
+                .collect(),
+        );
+    }
+
+    /// Set the cargo features the crate is built with.
+    ///
+    /// Types and modules behind `#[cfg(feature = "...")]` gates, and
+    /// `#[cfg_attr(feature = "...", derive(NativeClass))]` attributes, are
+    /// only considered if their predicate holds for these features. If no
+    /// features are set, all of them are considered.
+    ///
+    /// ```
+    /// # use gdnative_project_utils::ScanOptions;
+    /// let options = ScanOptions::new().features(["godot"]);
+    /// ```
+    pub fn features<S: AsRef<str>>(mut self, features: impl IntoIterator<Item = S>) -> Self {
+        self.with_features(features);
+        self
+    }
+
+    /// Set the target triple the crate is built for, e.g.
+    /// `x86_64-pc-windows-msvc`.
+    ///
+    /// Types and modules behind `#[cfg(...)]` gates on the target, such as
+    /// `target_os = "windows"` or `unix`, are only scanned if the gate holds
+    /// for this target. If no target is set, all of them are scanned.
+    pub fn with_target(&mut self, triple: impl AsRef<str>) {
+        self.target = Some(TargetCfg::from_triple(triple.as_ref()));
+    }
+
+    /// Set the target triple the crate is built for, e.g.
+    /// `x86_64-pc-windows-msvc`.
+    ///
+    /// Types and modules behind `#[cfg(...)]` gates on the target, such as
+    /// `target_os = "windows"` or `unix`, are only scanned if the gate holds
+    /// for this target. If no target is set, all of them are scanned.
+    pub fn target(mut self, triple: impl AsRef<str>) -> Self {
+        self.with_target(triple);
+        self
+    }
+
+    /// Accept `#[derive(...)]` of the macro at `path` as implementing
+    /// `NativeClass`, e.g. for a macro that wraps the original derive.
+    ///
+    /// `NativeClass` is always accepted. A derive matches if its path, as
+    /// written, ends with all segments of `path`, so `my_macros::GodotClass`
+    /// matches `::my_macros::GodotClass` but not `GodotClass` on its own.
+    /// Names it is imported under with `use`, such as `GodotClass` after
+    /// `use my_macros::GodotClass;`, are matched as well. Manual `impl`
+    /// blocks only count for the `NativeClass` trait itself.
+    pub fn with_derive(&mut self, path: impl Into<String>) {
+        self.derives.push(path.into());
+    }
+
+    /// Accept `#[derive(...)]` of the macro at `path` as implementing
+    /// `NativeClass`, e.g. for a macro that wraps the original derive.
+    ///
+    /// `NativeClass` is always accepted. A derive matches if its path, as
+    /// written, ends with all segments of `path`, so `my_macros::GodotClass`
+    /// matches `::my_macros::GodotClass` but not `GodotClass` on its own.
+    /// Names it is imported under with `use`, such as `GodotClass` after
+    /// `use my_macros::GodotClass;`, are matched as well. Manual `impl`
+    /// blocks only count for the `NativeClass` trait itself.
+    ///
+    /// ```
+    /// # use gdnative_project_utils::ScanOptions;
+    /// let options = ScanOptions::new().derive("my_macros::GodotClass");
+    /// ```
+    pub fn derive(mut self, path: impl Into<String>) -> Self {
+        self.with_derive(path);
+        self
+    }
+
+    /// Set whether to scan only the files that are part of the crate's module
+    /// tree, instead of every `*.rs` file in the directory.
+    ///
+    /// The scan then starts at `lib.rs`, or `main.rs`, in the scanned
+    /// directory and follows the `mod` declarations whose `#[cfg]` gates hold,
+    /// including their `#[path]` attributes. Files that are not compiled into
+    /// the library, such as examples or scratch files, are skipped.
+    ///
+    /// Defaults to `false`.
+    pub fn with_follow_modules(&mut self, follow: bool) {
+        self.follow_modules = follow;
+    }
+
+    /// Set whether to scan only the files that are part of the crate's module
+    /// tree, instead of every `*.rs` file in the directory.
+    ///
+    /// The scan then starts at `lib.rs`, or `main.rs`, in the scanned
+    /// directory and follows the `mod` declarations whose `#[cfg]` gates hold,
+    /// including their `#[path]` attributes. Files that are not compiled into
+    /// the library, such as examples or scratch files, are skipped.
+    ///
+    /// Defaults to `false`.
+    pub fn follow_modules(mut self, follow: bool) -> Self {
+        self.with_follow_modules(follow);
+        self
+    }
+
+    /// Only scan the files matching one of the glob `patterns`, e.g.
+    /// `"src/**"`.
+    ///
+    /// Patterns use `.gitignore` syntax and are relative to the scanned
+    /// directory. By default all files are scanned.
+    pub fn with_include<S: AsRef<str>>(&mut self, patterns: impl IntoIterator<Item = S>) {
+        self.include.extend(
+            patterns
+                .into_iter()
+                .map(|pattern| pattern.as_ref().to_string()),
+        );
+    }
+
+    /// Only scan the files matching one of the glob `patterns`, e.g.
+    /// `"src/**"`.
+    ///
+    /// Patterns use `.gitignore` syntax and are relative to the scanned
+    /// directory. By default all files are scanned.
+    pub fn include<S: AsRef<str>>(mut self, patterns: impl IntoIterator<Item = S>) -> Self {
+        self.with_include(patterns);
+        self
+    }
+
+    /// Skip the files matching one of the glob `patterns`, even if they match
+    /// an include pattern.
+    ///
+    /// Patterns use `.gitignore` syntax and are relative to the scanned
+    /// directory. With [`follow_modules`](Self::follow_modules), the modules
+    /// declared in skipped files are still followed.
+    pub fn with_exclude<S: AsRef<str>>(&mut self, patterns: impl IntoIterator<Item = S>) {
+        self.exclude.extend(
+            patterns
+                .into_iter()
+                .map(|pattern| pattern.as_ref().to_string()),
+        );
+    }
+
+    /// Skip the files matching one of the glob `patterns`, even if they match
+    /// an include pattern.
+    ///
+    /// Patterns use `.gitignore` syntax and are relative to the scanned
+    /// directory. With [`follow_modules`](Self::follow_modules), the modules
+    /// declared in skipped files are still followed.
+    ///
+    /// ```
+    /// # use gdnative_project_utils::ScanOptions;
+    /// let options = ScanOptions::new().exclude(["tests/**", "examples/**"]);
+    /// ```
+    pub fn exclude<S: AsRef<str>>(mut self, patterns: impl IntoIterator<Item = S>) -> Self {
+        self.with_exclude(patterns);
+        self
+    }
+
+    /// Set whether to scan test code, which is not part of the library.
+    ///
+    /// Test code is everything behind `#[cfg(test)]`, such as the usual
+    /// `mod tests`, and the `tests` and `benches` directories of cargo's
+    /// test and benchmark targets in the scanned directory. Code behind
+    /// `#[cfg(not(test))]` is scanned either way.
+    ///
+    /// Defaults to `false`.
+    pub fn with_include_tests(&mut self, include: bool) {
+        self.include_tests = include;
+    }
+
+    /// Set whether to scan test code, which is not part of the library.
+    ///
+    /// Test code is everything behind `#[cfg(test)]`, such as the usual
+    /// `mod tests`, and the `tests` and `benches` directories of cargo's
+    /// test and benchmark targets in the scanned directory. Code behind
+    /// `#[cfg(not(test))]` is scanned either way.
+    ///
+    /// Defaults to `false`.
+    pub fn include_tests(mut self, include: bool) -> Self {
+        self.with_include_tests(include);
+        self
+    }
+
+    /// Set whether to skip files that can not be read or parsed, instead of
+    /// failing the whole scan.
+    ///
+    /// The skipped files are listed in [`ScanReport::errors`]. Classes that
+    /// are declared twice are still an error.
+    ///
+    /// Defaults to `false`.
+    pub fn with_lenient(&mut self, lenient: bool) {
+        self.lenient = lenient;
+    }
+
+    /// Set whether to skip files that can not be read or parsed, instead of
+    /// failing the whole scan.
+    ///
+    /// The skipped files are listed in [`ScanReport::errors`]. Classes that
+    /// are declared twice are still an error.
+    ///
+    /// Defaults to `false`.
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.with_lenient(lenient);
+        self
+    }
+
+    /// Cache the results of the scan in the file at `path`, and reuse the
+    /// results of the files that did not change since the last scan.
+    ///
+    /// Build scripts can keep the cache in `OUT_DIR`. Files are considered
+    /// unchanged if their modification time and size, or else their content,
+    /// are the same. The cache is discarded if the options of the scan
+    /// changed.
+    pub fn with_cache_file(&mut self, path: impl AsRef<Path>) {
+        self.cache_file = Some(path.as_ref().to_path_buf());
+    }
+
+    /// Cache the results of the scan in the file at `path`, and reuse the
+    /// results of the files that did not change since the last scan.
+    ///
+    /// Build scripts can keep the cache in `OUT_DIR`. Files are considered
+    /// unchanged if their modification time and size, or else their content,
+    /// are the same. The cache is discarded if the options of the scan
+    /// changed.
+    ///
+    /// ```no_run
+    /// # use gdnative_project_utils::ScanOptions;
+    /// # use std::path::Path;
+    /// let out_dir = std::env::var("OUT_DIR").unwrap();
+    /// let options = ScanOptions::new().cache_file(Path::new(&out_dir).join("scan-cache.cfg"));
+    /// ```
+    pub fn cache_file(mut self, path: impl AsRef<Path>) -> Self {
+        self.with_cache_file(path);
+        self
+    }
+
+    /// A description of the options that change what is found in a file, to
+    /// tell whether cached results apply.
+    fn cache_key(&self) -> String {
+        let mut features = self.features.as_ref().map(|features| {
+            features
+                .iter()
+                .map(|f| normalize_feature(f))
+                .collect::<Vec<_>>()
+        });
+        if let Some(features) = &mut features {
+            features.sort();
+        }
+
+        format!(
+            "{:?}|{:?}|{:?}|{}",
+            features,
+            self.target.as_ref().map(TargetCfg::triple),
+            self.derives,
+            self.include_tests
+        )
+    }
+
+    /// Whether `#[cfg(test)]` holds, or `None` if test code is scanned along
+    /// with the library, so both `test` and `not(test)` code is kept.
+    pub(crate) fn test_cfg(&self) -> Option<bool> {
+        Some(false).filter(|_| !self.include_tests)
+    }
+
+    /// The include and exclude patterns as overrides of the walker in `dir`.
+    fn overrides(&self, dir: &Path) -> Result<ignore::overrides::Override, ScanError> {
+        let mut builder = ignore::overrides::OverrideBuilder::new(dir);
+
+        // Later patterns take precedence, so excludes win over includes.
+        for pattern in &self.include {
+            builder.add(pattern).map_err(ScanError::InvalidPattern)?;
+        }
+        for pattern in &self.exclude {
+            builder
+                .add(&format!("!{}", pattern))
+                .map_err(ScanError::InvalidPattern)?;
+        }
+
+        builder.build().map_err(ScanError::InvalidPattern)
+    }
+
+    /// Whether `feature` is enabled, or `None` if the features are not known.
+    pub(crate) fn feature_enabled(&self, feature: &str) -> Option<bool> {
+        self.features.as_ref().map(|features| {
+            let feature = normalize_feature(feature);
+            features
+                .iter()
+                .any(|enabled| normalize_feature(enabled) == feature)
+        })
+    }
+
+    /// The features set with [`ScanOptions::features`].
+    #[cfg(feature = "cargo_expand")]
+    pub(crate) fn enabled_features(&self) -> Option<&HashSet<String>> {
+        self.features.as_ref()
+    }
+
+    /// The target triple set with [`ScanOptions::target`].
+    #[cfg(feature = "cargo_expand")]
+    pub(crate) fn target_triple(&self) -> Option<&str> {
+        self.target.as_ref().map(TargetCfg::triple)
+    }
+
+    /// Whether the target option `key`, or `key = "value"`, is set, or `None`
+    /// if the target is not known or `key` is not a target option.
+    pub(crate) fn target_cfg(&self, key: &str, value: Option<&str>) -> Option<bool> {
+        self.target.as_ref()?.get(key, value)
+    }
+}
+
+/// Cargo treats `-` and `_` in feature names alike in `CARGO_FEATURE_*`
+/// variables, so compare names in one spelling.
+fn normalize_feature(feature: &str) -> String {
+    feature.replace('-', "_").to_lowercase()
+}
 
 /// Scan the directory at path `dir` for all `*.rs` files and find types which implement `NativeClass`.
+///
+/// Prints `cargo:rerun-if-changed` directives for the directory and every
+/// scanned file, so a build script calling this re-runs when classes are
+/// added or removed.
 pub fn scan_crate(dir: impl AsRef<Path>) -> Result<Classes, ScanError> {
-    let rs_extension = std::ffi::OsString::from("rs");
-    let mut paths = vec![];
+    let report = scan_crate_with_report(dir)?;
+
+    report.print_rerun_if_changed();
+
+    Ok(report.classes)
+}
+
+/// Scan the directory at path `dir` like [`scan_crate`], but also return the
+/// list of scanned files.
+///
+/// Unlike [`scan_crate`], this never prints `cargo:rerun-if-changed`
+/// directives itself, use [`ScanReport::print_rerun_if_changed`] for that.
+pub fn scan_crate_with_report(dir: impl AsRef<Path>) -> Result<ScanReport, ScanError> {
+    scan_crate_with_options(dir, &ScanOptions::default())
+}
+
+/// Scan the directory at path `dir` like [`scan_crate_with_report`], using
+/// `options` to decide which conditional derives apply.
+pub fn scan_crate_with_options(
+    dir: impl AsRef<Path>,
+    options: &ScanOptions,
+) -> Result<ScanReport, ScanError> {
+    let start = Instant::now();
+    let cache_key = options.cache_key();
+    let previous_cache = match &options.cache_file {
+        Some(path) => ScanCache::load(path, &cache_key),
+        None => ScanCache::new(&cache_key),
+    };
+    let mut cache = ScanCache::new(&cache_key);
+
+    let mut sources = if options.follow_modules {
+        module_sources(dir.as_ref(), options, &previous_cache)?
+    } else {
+        directory_sources(dir.as_ref(), options, &previous_cache)?
+    };
+
+    // Files are scanned in no particular order, sort them to report the same
+    // duplicate classes every time.
+    sources.sort_by(|a, b| source_path(a).cmp(source_path(b)));
+
+    let mut report = ScanReport {
+        dir: dir.as_ref().to_path_buf(),
+        ..ScanReport::default()
+    };
+
+    report.stats.files_walked = sources.len();
+
+    // Impl blocks may be in other files than their classes, so their methods
+    // and signals are added once all classes are known.
+    let mut apis = vec![];
 
-    for file in ignore::Walk::new(dir.as_ref()) {
-        let file = file.map_err(ScanError::WalkDir)?;
+    for source in sources {
+        let classes = match source {
+            Ok(source) => {
+                match source.parse_time {
+                    Some(parse_time) => {
+                        report.stats.files_parsed += 1;
+                        report.stats.parse_time += parse_time;
+                    }
+                    None => report.stats.cache_hits += 1,
+                }
 
-        let path = file.into_path();
+                let classes = source.classes();
+                trace!(
+                    "Scanned `{}`{}: {} classes",
+                    source.path.display(),
+                    if source.parse_time.is_none() {
+                        " (cached)"
+                    } else {
+                        ""
+                    },
+                    classes.len()
+                );
+                apis.extend(source.scanned.apis.iter().cloned());
+                report.files.push(source.path.clone());
+                cache.insert(source.path, source.scanned);
+                classes
+            }
+            Err(error) if options.lenient => {
+                warn!("Skipped `{}`: {}", error.path.display(), error.error);
+                if !report.files.contains(&error.path) {
+                    report.files.push(error.path.clone());
+                }
+                report.errors.push(error);
+                continue;
+            }
+            Err(error) => return Err(error.error),
+        };
 
-        if path.extension() == Some(&rs_extension) {
-            rerun_if_changed(&path);
-            paths.push(path);
+        for class in classes {
+            if let Some(first) = report.classes.get(&class.name) {
+                return Err(ScanError::DuplicateClass {
+                    first: Box::new(first.clone()),
+                    second: Box::new(class),
+                });
+            }
+            report.classes.insert(class);
         }
     }
 
-    let classes = paths
-        .into_iter()
-        .map(|path| -> Result<_, ScanError> {
-            let contents = std::fs::read_to_string(&path).map_err(ScanError::ReadFile)?;
+    crate::api::merge(&mut report.classes, apis);
 
-            let file = syn::parse_file(&contents).map_err(ScanError::Parse)?;
+    if let Some(path) = &options.cache_file {
+        cache.save(path).map_err(ScanError::Cache)?;
+    }
 
-            find_classes(&file).map_err(ScanError::Parse)
-        })
-        .try_fold(
-            HashSet::new(),
-            |mut acc, classes_res| -> Result<_, ScanError> {
-                let classes = classes_res?;
-                acc.extend(classes.into_iter().map(|x| x.to_string()));
-                Ok(acc)
+    report.stats.total_time = start.elapsed();
+    debug!("{}", report.stats);
+
+    Ok(report)
+}
+
+/// A `mod name;` declaration, as the inline modules it is in, the name of
+/// the module and its `#[path]`.
+pub(crate) type Declaration = (Vec<String>, String, Option<String>);
+
+/// A scanned source file and the classes declared in it.
+struct SourceFile {
+    path: PathBuf,
+    module_path: Vec<String>,
+    /// What was found in the file, as it is stored in the scan cache.
+    scanned: CachedFile,
+    /// How long parsing the file took, or `None` if it was cached.
+    parse_time: Option<Duration>,
+}
+
+impl SourceFile {
+    /// Scan the file at `path`, which is the module `module_path`, unless
+    /// `cache` holds the results for its current content.
+    fn scan(
+        path: PathBuf,
+        module_path: Vec<String>,
+        options: &ScanOptions,
+        cache: &ScanCache,
+    ) -> Result<Self, FileScanError> {
+        match scan_file(&path, options, cache) {
+            Ok((scanned, parse_time)) => Ok(SourceFile {
+                path,
+                module_path,
+                scanned,
+                parse_time,
+            }),
+            Err(error) => Err(FileScanError { path, error }),
+        }
+    }
+
+    fn classes(&self) -> Vec<Class> {
+        self.scanned
+            .classes
+            .iter()
+            .map(|class| Class {
+                module_path: self
+                    .module_path
+                    .iter()
+                    .chain(&class.module_path)
+                    .cloned()
+                    .collect(),
+                source_file: Some(self.path.clone()),
+                ..class.clone()
+            })
+            .collect()
+    }
+}
+
+/// Find the classes and module declarations in the file at `path`, and
+/// return how long parsing took, if it was not cached.
+///
+/// The results in `cache` are used if the file's modification time and size,
+/// or else the hash of its content, did not change.
+fn scan_file(
+    path: &Path,
+    options: &ScanOptions,
+    cache: &ScanCache,
+) -> Result<(CachedFile, Option<Duration>), ScanError> {
+    let metadata = std::fs::metadata(path).map_err(ScanError::ReadFile)?;
+    let modified = modified_stamp(&metadata);
+    let size = metadata.len();
+
+    let cached = cache.get(path);
+
+    if let Some(cached) = cached
+        .filter(|cached| !modified.is_empty() && cached.modified == modified && cached.size == size)
+    {
+        return Ok((cached.clone(), None));
+    }
+
+    let contents = std::fs::read_to_string(path).map_err(ScanError::ReadFile)?;
+    let hash = content_hash(&contents);
+
+    if let Some(cached) = cached.filter(|cached| cached.hash == hash) {
+        return Ok((
+            CachedFile {
+                modified,
+                size,
+                ..cached.clone()
             },
-        )?;
+            None,
+        ));
+    }
+
+    let start = Instant::now();
+    let file = syn::parse_file(&contents).map_err(ScanError::Parse)?;
+
+    let (classes, apis) = find_classes(&file, options).map_err(ScanError::Parse)?;
+
+    let mut declarations = vec![];
+    module_declarations(&file.items, options, &mut vec![], &mut declarations);
+
+    Ok((
+        CachedFile {
+            modified,
+            size,
+            hash,
+            classes,
+            apis,
+            declarations,
+        },
+        Some(start.elapsed()),
+    ))
+}
+
+/// The path of the file `source` is about.
+fn source_path(source: &Result<SourceFile, FileScanError>) -> &Path {
+    match source {
+        Ok(source) => &source.path,
+        Err(error) => &error.path,
+    }
+}
 
-    Ok(classes)
+/// Scan every `*.rs` file in `dir` on several threads, guessing their modules
+/// from their paths.
+fn directory_sources(
+    dir: &Path,
+    options: &ScanOptions,
+    cache: &ScanCache,
+) -> Result<Vec<Result<SourceFile, FileScanError>>, ScanError> {
+    let rs_extension = std::ffi::OsString::from("rs");
+    let sources = std::sync::Mutex::new(vec![]);
+    let walk_errors = std::sync::Mutex::new(vec![]);
+
+    let include_tests = options.include_tests;
+    let walk = ignore::WalkBuilder::new(dir)
+        .overrides(options.overrides(dir)?)
+        .filter_entry(move |entry| {
+            include_tests
+                || entry.depth() != 1
+                || !entry.file_type().is_some_and(|ty| ty.is_dir())
+                || !matches!(entry.file_name().to_str(), Some("tests" | "benches"))
+        })
+        .build_parallel();
+
+    walk.run(|| {
+        Box::new(|file| {
+            let path = match file {
+                Ok(file) => file.into_path(),
+                Err(err) => {
+                    walk_errors.lock().unwrap().push(err);
+                    return ignore::WalkState::Quit;
+                }
+            };
+
+            if path.extension() == Some(&rs_extension) {
+                let module_path = file_module_path(dir, &path);
+                let source = SourceFile::scan(path, module_path, options, cache);
+                sources.lock().unwrap().push(source);
+            }
+
+            ignore::WalkState::Continue
+        })
+    });
+
+    if let Some(err) = walk_errors.into_inner().unwrap().pop() {
+        return Err(ScanError::WalkDir(err));
+    }
+
+    Ok(sources.into_inner().unwrap())
+}
+
+/// Scan the crate root in `dir`, `lib.rs` or else `main.rs`, and the files
+/// of all modules declared in it, recursively.
+fn module_sources(
+    dir: &Path,
+    options: &ScanOptions,
+    cache: &ScanCache,
+) -> Result<Vec<Result<SourceFile, FileScanError>>, ScanError> {
+    let root = ["lib.rs", "main.rs"]
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+        .ok_or_else(|| ScanError::MissingModule {
+            name: "crate".to_string(),
+            declared_in: dir.to_path_buf(),
+        })?;
+
+    let overrides = options.overrides(dir)?;
+    let mut sources = vec![];
+    // The files that were already scanned, so `#[path]` cycles end.
+    let mut visited = HashSet::new();
+    visited.insert(dunce::canonicalize(&root).unwrap_or_else(|_| root.clone()));
+    let mut pending = vec![SourceFile::scan(root, vec![], options, cache)];
+
+    while let Some(source) = pending.pop() {
+        let source = match source {
+            Ok(source) => source,
+            Err(error) => {
+                sources.push(Err(error));
+                continue;
+            }
+        };
+
+        let declarations = source.scanned.declarations.clone();
+
+        let file_dir = source.path.parent().unwrap_or(Path::new(""));
+        let owns_dir = source.module_path.is_empty()
+            || source.path.file_stem().is_some_and(|stem| stem == "mod");
+        // The directory of the modules declared in this file, unless they are
+        // inside inline modules.
+        let child_dir = if owns_dir {
+            file_dir.to_path_buf()
+        } else {
+            file_dir.join(source.path.file_stem().unwrap_or_default())
+        };
+
+        for (inline_modules, name, path_attr) in declarations {
+            let module_dir = inline_modules
+                .iter()
+                .fold(child_dir.clone(), |dir, module| dir.join(module));
+
+            let path = match path_attr {
+                // Outside of inline modules, paths are relative to the
+                // directory of the declaring file.
+                Some(path) if inline_modules.is_empty() => file_dir.join(path),
+                Some(path) => module_dir.join(path),
+                None => match [
+                    module_dir.join(format!("{}.rs", name)),
+                    module_dir.join(&name).join("mod.rs"),
+                ]
+                .iter()
+                .find(|path| path.is_file())
+                {
+                    Some(path) => path.clone(),
+                    None => {
+                        sources.push(Err(FileScanError {
+                            path: source.path.clone(),
+                            error: ScanError::MissingModule {
+                                name,
+                                declared_in: source.path.clone(),
+                            },
+                        }));
+                        continue;
+                    }
+                },
+            };
+
+            if !visited.insert(dunce::canonicalize(&path).unwrap_or_else(|_| path.clone())) {
+                continue;
+            }
+
+            let module_path = source
+                .module_path
+                .iter()
+                .cloned()
+                .chain(inline_modules)
+                .chain(std::iter::once(name))
+                .collect();
+
+            pending.push(SourceFile::scan(path, module_path, options, cache));
+        }
+
+        if !overrides.matched(&source.path, false).is_ignore() {
+            sources.push(Ok(source));
+        }
+    }
+
+    Ok(sources)
+}
+
+/// Collect the `mod name;` declarations in `items` whose `#[cfg]` gates hold,
+/// as the inline modules they are in, their name and their `#[path]`.
+fn module_declarations(
+    items: &[syn::Item],
+    options: &ScanOptions,
+    inline_modules: &mut Vec<String>,
+    declarations: &mut Vec<Declaration>,
+) {
+    for item in items {
+        let module = match item {
+            syn::Item::Mod(module) if crate::cfg::enabled(&module.attrs, options) => module,
+            _ => continue,
+        };
+
+        match &module.content {
+            Some((_, items)) => {
+                inline_modules.push(module.ident.to_string());
+                module_declarations(items, options, inline_modules, declarations);
+                inline_modules.pop();
+            }
+            None => {
+                let path_attr = module.attrs.iter().find_map(|attr| {
+                    if !attr.path.is_ident("path") {
+                        return None;
+                    }
+                    match attr.parse_meta().ok()? {
+                        syn::Meta::NameValue(syn::MetaNameValue {
+                            lit: syn::Lit::Str(path),
+                            ..
+                        }) => Some(path.value()),
+                        _ => None,
+                    }
+                });
+
+                declarations.push((inline_modules.clone(), module.ident.to_string(), path_attr));
+            }
+        }
+    }
+}
+
+/// Scan the directory at path `dir` like [`scan_crate_with_options`] and
+/// write the report as JSON to `writer`, e.g. for an external asset
+/// pipeline.
+///
+/// The JSON document has the following form, with the classes ordered by
+/// name, the modules of a class relative to the crate root and `span` being
+/// the location of the class name, with lines starting at 1 and columns at 0:
+///
+/// ```json
+/// {
+///   "classes": [
+///     {
+///       "name": "Player",
+///       "module_path": ["entities"],
+///       "source_file": "src/entities/player.rs",
+///       "span": { "start_line": 4, "start_column": 11, "end_line": 4, "end_column": 17 },
+///       "base": "KinematicBody2D",
+///       "methods": [{ "name": "jump", "args": 1 }],
+///       "signals": [{ "name": "hit", "args": 2 }]
+///     }
+///   ],
+///   "dir": "src",
+///   "files": ["src/entities/player.rs", "src/lib.rs"]
+/// }
+/// ```
+///
+/// `source_file` and `span` are `null` for classes without source
+/// information, `base` is `null` if the class does not name a base class.
+/// Files skipped by a lenient scan and the scan statistics are not included.
+#[cfg(feature = "serde")]
+pub fn scan_crate_to_writer(
+    dir: impl AsRef<Path>,
+    options: &ScanOptions,
+    writer: impl std::io::Write,
+) -> Result<ScanReport, ScanError> {
+    let report = scan_crate_with_options(dir, options)?;
+    serde_json::to_writer_pretty(writer, &report).map_err(ScanError::Serialize)?;
+    Ok(report)
 }
 
 /// Error type for errors that can occur during scanning.
+///
+/// Some variants only exist with the features that can cause them, so the
+/// enum is not exhaustive.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum ScanError {
     /// An error was encountered when exploring all the files.
     WalkDir(ignore::Error),
+    /// The scan cache could not be written.
+    Cache(std::io::Error),
+    /// An include or exclude pattern is not a valid glob.
+    InvalidPattern(ignore::Error),
     /// An error was encountered when reading in a Rust source file.
     ReadFile(std::io::Error),
     /// An error was encountered when parsing a Rust source file.
     Parse(syn::Error),
+    /// Two types with the same name derive `NativeClass`, so their `.gdns`
+    /// files would collide.
+    DuplicateClass {
+        first: Box<Class>,
+        second: Box<Class>,
+    },
+    /// The file of the module `name`, declared in the file `declared_in`,
+    /// does not exist.
+    MissingModule { name: String, declared_in: PathBuf },
+    /// The scan report could not be serialized.
+    #[cfg(feature = "serde")]
+    Serialize(serde_json::Error),
+    /// An external command used for scanning failed.
+    #[cfg(feature = "cargo_expand")]
+    CommandFailed { command: String, stderr: String },
 }
 
 impl std::fmt::Display for ScanError {
@@ -62,8 +997,29 @@ impl std::fmt::Display for ScanError {
             ScanError::WalkDir(err) => {
                 f.write_fmt(format_args!("Directory walking error: {}", err))
             }
+            ScanError::Cache(err) => f.write_fmt(format_args!("Cache writing error: {}", err)),
+            ScanError::InvalidPattern(err) => f.write_fmt(format_args!("Invalid pattern: {}", err)),
             ScanError::ReadFile(err) => f.write_fmt(format_args!("File reading error: {}", err)),
             ScanError::Parse(err) => f.write_fmt(format_args!("Parsing error: {}", err)),
+            ScanError::DuplicateClass { first, second } => f.write_fmt(format_args!(
+                "Class `{}` is declared twice, as `{}`{} and as `{}`{}",
+                first.name,
+                first.rust_path(),
+                location(first),
+                second.rust_path(),
+                location(second),
+            )),
+            ScanError::MissingModule { name, declared_in } => f.write_fmt(format_args!(
+                "The file of module `{}`, declared in `{}`, could not be found",
+                name,
+                declared_in.display()
+            )),
+            #[cfg(feature = "serde")]
+            ScanError::Serialize(err) => f.write_fmt(format_args!("Serialization error: {}", err)),
+            #[cfg(feature = "cargo_expand")]
+            ScanError::CommandFailed { command, stderr } => {
+                f.write_fmt(format_args!("`{}` failed: {}", command, stderr.trim()))
+            }
         }
     }
 }
@@ -72,35 +1028,119 @@ impl std::error::Error for ScanError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             ScanError::WalkDir(err) => Some(err),
+            ScanError::Cache(err) => Some(err),
+            ScanError::InvalidPattern(err) => Some(err),
             ScanError::ReadFile(err) => Some(err),
             ScanError::Parse(err) => Some(err),
+            ScanError::DuplicateClass { .. } | ScanError::MissingModule { .. } => None,
+            #[cfg(feature = "serde")]
+            ScanError::Serialize(err) => Some(err),
+            #[cfg(feature = "cargo_expand")]
+            ScanError::CommandFailed { .. } => None,
         }
     }
 }
 
-fn find_classes(file: &syn::File) -> Result<HashSet<syn::Ident>, syn::Error> {
-    fn derives_nativeclass(attrs: &[syn::Attribute]) -> Result<bool, syn::Error> {
+/// An error in a single file, which was skipped by a lenient scan.
+#[derive(Debug)]
+pub struct FileScanError {
+    /// The file that could not be scanned.
+    pub path: PathBuf,
+    /// What went wrong.
+    pub error: ScanError,
+}
+
+impl std::fmt::Display for FileScanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}: {}", self.path.display(), self.error))
+    }
+}
+
+impl std::error::Error for FileScanError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// The location of `class` as ` (file:line:column)`, or nothing if it is not
+/// known.
+fn location(class: &Class) -> String {
+    match (&class.source_file, class.span) {
+        (Some(file), Some(span)) => format!(
+            " ({}:{}:{})",
+            file.display(),
+            span.start_line,
+            span.start_column + 1
+        ),
+        (Some(file), None) => format!(" ({})", file.display()),
+        (None, _) => String::new(),
+    }
+}
+
+/// The module path of the source file at `path` inside the crate's source
+/// directory `dir`, assuming the usual file layout: `lib.rs`, `main.rs` and
+/// `mod.rs` belong to their directory, any other file is a module of its own.
+fn file_module_path(dir: &Path, path: &Path) -> Vec<String> {
+    let rel_path = path.strip_prefix(dir).unwrap_or(path);
+
+    let mut module_path = rel_path
+        .parent()
+        .into_iter()
+        .flat_map(|parent| parent.components())
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+
+    match rel_path.file_stem().and_then(|stem| stem.to_str()) {
+        Some("mod") => {}
+        Some("lib" | "main") if module_path.is_empty() => {}
+        Some(stem) => module_path.push(stem.to_string()),
+        None => {}
+    }
+
+    module_path
+}
+
+/// Find the types deriving or manually implementing `NativeClass` in `file`,
+/// and the methods and signals exported in its impl blocks.
+///
+/// The module path of the classes are the inline modules they are declared
+/// in, and they have no source file.
+pub(crate) fn find_classes(
+    file: &syn::File,
+    options: &ScanOptions,
+) -> Result<(Vec<Class>, Vec<ClassApi>), syn::Error> {
+    fn derives_nativeclass(
+        attrs: &[syn::Attribute],
+        derives: &Derives,
+        options: &ScanOptions,
+    ) -> Result<bool, syn::Error> {
         let mut res = false;
 
+        if !crate::cfg::enabled(attrs, options) {
+            return Ok(false);
+        }
+
         for attr in attrs {
-            if !attr.path.is_ident("derive") {
+            if attr.path.is_ident("cfg_attr") {
+                // a `cfg_attr` that is not meta-shaped, e.g. with a macro
+                // call, can not apply a derive
+                if let Ok(syn::Meta::List(list)) = attr.parse_meta() {
+                    res |= cfg_attr_derives_nativeclass(&list, derives, options);
+                }
                 continue;
             }
 
-            for t in attr.tokens.clone() {
-                if let TokenTree::Group(g) = &t {
-                    let s = g.stream();
+            if !attr.path.is_ident("derive") {
+                continue;
+            }
 
-                    for tt in s {
-                        if let TokenTree::Ident(i) = tt {
-                            if i == "NativeClass" {
-                                res = true;
-                                break;
-                            }
-                        }
-                    }
-                } else {
-                    return Err(syn::Error::new(t.span(), "Unexpected #[derive attribute]"));
+            match attr.parse_meta()? {
+                syn::Meta::List(list) => res |= derives.any_nested(&list.nested),
+                meta => {
+                    return Err(syn::Error::new_spanned(
+                        meta,
+                        "Unexpected #[derive attribute]",
+                    ))
                 }
             }
         }
@@ -108,19 +1148,181 @@ fn find_classes(file: &syn::File) -> Result<HashSet<syn::Ident>, syn::Error> {
         Ok(res)
     }
 
-    struct Visitor {
-        classes: HashSet<syn::Ident>,
+    /// Whether `#[cfg_attr(predicate, attrs...)]` applies a derive of
+    /// `NativeClass`. Predicates that cannot be decided count as true.
+    fn cfg_attr_derives_nativeclass(
+        list: &syn::MetaList,
+        derives: &Derives,
+        options: &ScanOptions,
+    ) -> bool {
+        let mut nested = list.nested.iter();
+
+        match nested.next() {
+            Some(predicate) if crate::cfg::evaluate(predicate, options) != Some(false) => {}
+            _ => return false,
+        }
+
+        nested.any(|attr| match attr {
+            syn::NestedMeta::Meta(syn::Meta::List(list)) if list.path.is_ident("derive") => {
+                derives.any_nested(&list.nested)
+            }
+            syn::NestedMeta::Meta(syn::Meta::List(list)) if list.path.is_ident("cfg_attr") => {
+                cfg_attr_derives_nativeclass(list, derives, options)
+            }
+            _ => false,
+        })
+    }
+
+    /// The type `item` implements `NativeClass` for, if it is a manual
+    /// implementation of it.
+    fn implements_nativeclass(
+        item: &syn::ItemImpl,
+        derives: &Derives,
+        options: &ScanOptions,
+    ) -> Option<syn::Ident> {
+        let trait_path = match &item.trait_ {
+            Some((None, path, _)) => path,
+            _ => return None,
+        };
+
+        if !crate::cfg::enabled(&item.attrs, options) {
+            return None;
+        }
+
+        let trait_path = trait_path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect::<Vec<_>>();
+
+        if !derives.matches_trait(&trait_path) {
+            return None;
+        }
+
+        match &*item.self_ty {
+            syn::Type::Path(ty) if ty.qself.is_none() => {
+                ty.path.segments.last().map(|segment| segment.ident.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// The base class named by `#[inherit(...)]` in `attrs`.
+    fn inherited_base(attrs: &[syn::Attribute]) -> Option<String> {
+        attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("inherit"))
+            .find_map(|attr| match attr.parse_meta().ok()? {
+                syn::Meta::List(list) => match list.nested.first()? {
+                    syn::NestedMeta::Meta(syn::Meta::Path(path)) => {
+                        Some(path.segments.last()?.ident.to_string())
+                    }
+                    _ => None,
+                },
+                _ => None,
+            })
+    }
+
+    /// The `.gdns` path given by a `gdns: <path>` line in the doc comment in
+    /// `attrs`.
+    ///
+    /// A doc comment is used instead of an attribute of its own, since no
+    /// derive macro declares such an attribute and rustc would reject it.
+    /// The path has to be relative and stay inside the resource output
+    /// directory. `.gdns` is appended if it is missing.
+    fn gdns_path(attrs: &[syn::Attribute]) -> Result<Option<String>, syn::Error> {
+        for attr in attrs {
+            let (path, lit) = match attr.parse_meta() {
+                Ok(syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(lit),
+                    ..
+                })) if path.is_ident("doc") => match lit.value().trim().strip_prefix("gdns:") {
+                    Some(path) => (path.trim().to_string(), lit),
+                    None => continue,
+                },
+                _ => continue,
+            };
+
+            let path = path.replace('\\', "/");
+            let components = path
+                .split('/')
+                .filter(|component| !component.is_empty() && *component != ".")
+                .collect::<Vec<_>>();
+            if path.starts_with('/') || components.is_empty() || components.contains(&"..") {
+                return Err(syn::Error::new_spanned(
+                    lit,
+                    format!(
+                        "The .gdns path `{}` must be relative to the resource output directory",
+                        path
+                    ),
+                ));
+            }
+
+            let path = components.join("/");
+            return Ok(Some(match path.ends_with(".gdns") {
+                true => path,
+                false => format!("{}.gdns", path),
+            }));
+        }
+
+        Ok(None)
+    }
+
+    /// The base class named by `type Base = ...;` in a manual implementation.
+    fn impl_base(item: &syn::ItemImpl) -> Option<String> {
+        item.items.iter().find_map(|item| match item {
+            syn::ImplItem::Type(ty) if ty.ident == "Base" => match &ty.ty {
+                syn::Type::Path(path) => Some(path.path.segments.last()?.ident.to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+
+    struct Visitor<'a> {
+        derives: &'a Derives,
+        options: &'a ScanOptions,
+        classes: Vec<Class>,
+        apis: Vec<ClassApi>,
+        modules: Vec<String>,
         errors: Vec<syn::Error>,
     }
 
-    impl<'ast> syn::visit::Visit<'ast> for Visitor {
+    impl Visitor<'_> {
+        fn push_class(
+            &mut self,
+            ident: &syn::Ident,
+            base: Option<String>,
+            attrs: &[syn::Attribute],
+        ) {
+            let gdns_path = gdns_path(attrs).unwrap_or_else(|err| {
+                self.errors.push(err);
+                None
+            });
+
+            self.classes.push(Class {
+                name: ident.to_string(),
+                module_path: self.modules.clone(),
+                source_file: None,
+                span: Some(ident.span().into()),
+                base,
+                methods: vec![],
+                signals: vec![],
+                gdns_path,
+            });
+        }
+    }
+
+    impl<'ast> syn::visit::Visit<'ast> for Visitor<'_> {
         fn visit_item_struct(&mut self, s: &'ast syn::ItemStruct) {
-            match derives_nativeclass(&s.attrs) {
+            match derives_nativeclass(&s.attrs, self.derives, self.options) {
                 Err(err) => {
                     self.errors.push(err);
                 }
                 Ok(true) => {
-                    self.classes.insert(s.ident.clone());
+                    let base = inherited_base(&s.attrs);
+                    self.push_class(&s.ident, base, &s.attrs);
                 }
                 Ok(false) => {}
             }
@@ -128,28 +1330,60 @@ fn find_classes(file: &syn::File) -> Result<HashSet<syn::Ident>, syn::Error> {
         }
 
         fn visit_item_enum(&mut self, i: &'ast syn::ItemEnum) {
-            match derives_nativeclass(&i.attrs) {
+            match derives_nativeclass(&i.attrs, self.derives, self.options) {
                 Err(err) => {
                     self.errors.push(err);
                 }
                 Ok(true) => {
-                    self.classes.insert(i.ident.clone());
+                    let base = inherited_base(&i.attrs);
+                    self.push_class(&i.ident, base, &i.attrs);
                 }
                 Ok(false) => {}
             }
             syn::visit::visit_item_enum(self, i)
         }
+
+        fn visit_item_impl(&mut self, i: &'ast syn::ItemImpl) {
+            if let Some(ident) = implements_nativeclass(i, self.derives, self.options) {
+                self.push_class(&ident, impl_base(i), &i.attrs);
+            }
+            self.apis.extend(crate::api::impl_api(i, self.options));
+            syn::visit::visit_item_impl(self, i)
+        }
+
+        fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+            self.apis.extend(crate::api::fn_api(i, self.options));
+            syn::visit::visit_item_fn(self, i)
+        }
+
+        fn visit_item_mod(&mut self, m: &'ast syn::ItemMod) {
+            if !crate::cfg::enabled(&m.attrs, self.options) {
+                return;
+            }
+
+            self.modules.push(m.ident.to_string());
+            syn::visit::visit_item_mod(self, m);
+            self.modules.pop();
+        }
     }
 
+    let derives = Derives::for_file(file, options);
+
     let mut vis = Visitor {
-        classes: HashSet::new(),
+        derives: &derives,
+        options,
+        classes: vec![],
+        apis: vec![],
+        modules: vec![],
         errors: vec![],
     };
 
-    syn::visit::visit_file(&mut vis, file);
+    if crate::cfg::enabled(&file.attrs, options) {
+        syn::visit::visit_file(&mut vis, file);
+    }
 
     if vis.errors.is_empty() {
-        Ok(vis.classes)
+        Ok((vis.classes, vis.apis))
     } else {
         let mut err = vis.errors.pop().unwrap();
 
@@ -161,8 +1395,137 @@ fn find_classes(file: &syn::File) -> Result<HashSet<syn::Ident>, syn::Error> {
     }
 }
 
-fn rerun_if_changed(path: &Path) {
-    if cfg!(feature = "build_script") {
-        println!("cargo:rerun-if-changed={}", path.display());
+/// The paths of the derive macros that implement `NativeClass` in one file,
+/// and the paths of the `NativeClass` trait itself.
+///
+/// The names they are imported under are local to the crate, so these only
+/// match a path that names the import, e.g. `NC` or `super::NC`.
+struct Derives {
+    derives: Vec<Vec<String>>,
+    traits: Vec<Vec<String>>,
+    imported_derives: Vec<String>,
+    imported_traits: Vec<String>,
+}
+
+impl Derives {
+    /// The derives accepted by `options`, plus the names they and the
+    /// `NativeClass` trait are imported under with `use` in `file`.
+    fn for_file(file: &syn::File, options: &ScanOptions) -> Self {
+        struct UseVisitor {
+            derives: Derives,
+            imported_derives: Vec<String>,
+            imported_traits: Vec<String>,
+        }
+
+        impl UseVisitor {
+            fn visit_tree(&mut self, prefix: &mut Vec<String>, tree: &syn::UseTree) {
+                let (ident, name) = match tree {
+                    syn::UseTree::Path(path) => {
+                        prefix.push(path.ident.to_string());
+                        self.visit_tree(prefix, &path.tree);
+                        prefix.pop();
+                        return;
+                    }
+                    syn::UseTree::Group(group) => {
+                        for tree in &group.items {
+                            self.visit_tree(prefix, tree);
+                        }
+                        return;
+                    }
+                    syn::UseTree::Rename(rename) => (&rename.ident, &rename.rename),
+                    syn::UseTree::Name(name) => (&name.ident, &name.ident),
+                    syn::UseTree::Glob(_) => return,
+                };
+
+                let mut path = prefix.clone();
+                path.push(ident.to_string());
+                let name = name.to_string();
+
+                if self.derives.matches_trait(&path) {
+                    self.imported_traits.push(name.clone());
+                }
+                if self.derives.matches(&path) {
+                    self.imported_derives.push(name);
+                }
+            }
+        }
+
+        impl<'ast> syn::visit::Visit<'ast> for UseVisitor {
+            fn visit_item_use(&mut self, item: &'ast syn::ItemUse) {
+                self.visit_tree(&mut vec![], &item.tree);
+            }
+        }
+
+        let native_class = vec!["NativeClass".to_string()];
+        let derives = std::iter::once(native_class.clone())
+            .chain(options.derives.iter().map(|path| {
+                path.trim_start_matches("::")
+                    .split("::")
+                    .map(|segment| segment.trim().to_string())
+                    .collect()
+            }))
+            .collect();
+
+        let mut visitor = UseVisitor {
+            derives: Derives {
+                derives,
+                traits: vec![native_class],
+                imported_derives: vec![],
+                imported_traits: vec![],
+            },
+            imported_derives: vec![],
+            imported_traits: vec![],
+        };
+        syn::visit::visit_file(&mut visitor, file);
+
+        Derives {
+            imported_derives: visitor.imported_derives,
+            imported_traits: visitor.imported_traits,
+            ..visitor.derives
+        }
+    }
+
+    /// Whether the derive `path` ends with all segments of one of the
+    /// derives, e.g. `gdnative::prelude::NativeClass` or, after importing
+    /// it, `GodotClass` for `my_macros::GodotClass`.
+    fn matches(&self, path: &[String]) -> bool {
+        self.derives.iter().any(|derive| path.ends_with(derive))
+            || names_import(path, &self.imported_derives)
+    }
+
+    /// Whether the trait `path` of an `impl` block ends with all segments of
+    /// the `NativeClass` trait or a name it is imported under.
+    fn matches_trait(&self, path: &[String]) -> bool {
+        self.traits.iter().any(|name| path.ends_with(name))
+            || names_import(path, &self.imported_traits)
+    }
+
+    /// Whether any of the `#[derive(...)]` arguments `nested` is one of the
+    /// derives.
+    fn any_nested<'a>(&self, nested: impl IntoIterator<Item = &'a syn::NestedMeta>) -> bool {
+        nested.into_iter().any(|derive| match derive {
+            syn::NestedMeta::Meta(syn::Meta::Path(path)) => {
+                let path = path
+                    .segments
+                    .iter()
+                    .map(|segment| segment.ident.to_string())
+                    .collect::<Vec<_>>();
+                self.matches(&path)
+            }
+            _ => false,
+        })
+    }
+}
+
+/// Whether `path` is one of the `imports`, possibly reached through
+/// `crate`, `self` or `super`.
+fn names_import(path: &[String], imports: &[String]) -> bool {
+    match path
+        .iter()
+        .skip_while(|segment| matches!(segment.as_str(), "crate" | "self" | "super"))
+        .collect::<Vec<_>>()[..]
+    {
+        [name] => imports.contains(name),
+        _ => false,
     }
 }
@@ -0,0 +1,299 @@
+//! Caching of scan results between builds.
+//!
+//! The cache is a `ConfigFile` with one section per scanned file, holding what
+//! is needed to tell whether the file changed and what was found in it:
+//!
+//! ```ini
+//! ; Scan cache of gdnative-project-utils, do not edit.
+//!
+//! [cache]
+//!
+//! options="fnv1a64:0123456789abcdef"
+//!
+//! [src/player.rs]
+//!
+//! modified="1700000000.123456789"
+//! size=412
+//! hash="fnv1a64:8c1f2a3b4c5d6e7f"
+//! class="Player||4:11:4:17|Node2D|"
+//! method="Player|jump|1"
+//! signal="Player|hit|2"
+//! mod="|weapon|"
+//! ```
+//!
+//! A class is stored as its name, the inline modules it is declared in, its
+//! span, its base class and its `.gdns` path. Exported methods and signals
+//! are stored as the name of the class they were found for, their name and
+//! their number of arguments. A module declaration is stored as the inline
+//! modules it is in, its name and its `#[path]`. The cache is discarded as a
+//! whole if the scan options it was created with differ.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::api::ClassApi;
+use crate::class::{Class, Method, Signal, SourceSpan};
+use crate::godot_cfg::ConfigFile;
+use crate::manifest::content_hash;
+use crate::scan::Declaration;
+
+/// Increased whenever the format or the meaning of the cache changes.
+const CACHE_VERSION: u32 = 4;
+
+/// The results of a previous scan.
+#[derive(Debug, Default)]
+pub(crate) struct ScanCache {
+    options: String,
+    files: HashMap<PathBuf, CachedFile>,
+}
+
+/// What was found in a file, and how to tell whether it changed since.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct CachedFile {
+    /// The modification time, as seconds and nanoseconds since the Unix
+    /// epoch.
+    pub(crate) modified: String,
+    pub(crate) size: u64,
+    pub(crate) hash: String,
+    /// The classes, with the inline modules they are declared in as their
+    /// module path.
+    pub(crate) classes: Vec<Class>,
+    /// The methods and signals exported in the file's impl blocks.
+    pub(crate) apis: Vec<ClassApi>,
+    pub(crate) declarations: Vec<Declaration>,
+}
+
+impl ScanCache {
+    /// An empty cache for a scan with the options described by `options`.
+    pub(crate) fn new(options: &str) -> Self {
+        ScanCache {
+            options: content_hash(&format!("{}:{}", CACHE_VERSION, options)),
+            files: HashMap::new(),
+        }
+    }
+
+    /// Load the cache at `path`, or return an empty cache if it does not
+    /// exist, can not be read or was created with other options.
+    pub(crate) fn load(path: &Path, options: &str) -> Self {
+        let mut cache = ScanCache::new(options);
+
+        let cfg = match std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| ConfigFile::parse(&content).ok())
+        {
+            Some(cfg) => cfg,
+            None => return cache,
+        };
+
+        let cached_options = cfg
+            .entries("cache")
+            .find(|(key, _)| *key == "options")
+            .map(|(_, value)| unquote(value));
+        if cached_options != Some(cache.options.as_str()) {
+            return cache;
+        }
+
+        for section in cfg.section_names() {
+            if section.is_empty() || section == "cache" {
+                continue;
+            }
+
+            if let Some(file) = CachedFile::parse(cfg.entries(section)) {
+                cache.files.insert(PathBuf::from(section), file);
+            }
+        }
+
+        cache
+    }
+
+    /// Write the cache to `path`, unless it is unchanged.
+    pub(crate) fn save(&self, path: &Path) -> std::io::Result<()> {
+        let mut content = format!(
+            "; Scan cache of gdnative-project-utils, do not edit.\n\n[cache]\n\noptions=\"{}\"\n",
+            self.options
+        );
+
+        let mut files = self.files.iter().collect::<Vec<_>>();
+        files.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (path, file) in files {
+            content.push_str(&format!("\n[{}]\n\n", path.display()));
+            file.render(&mut content);
+        }
+
+        if std::fs::read_to_string(path).ok().as_ref() == Some(&content) {
+            return Ok(());
+        }
+
+        crate::file_sink::write_atomic(path, content.as_bytes(), false)
+    }
+
+    /// The cached results of the file at `path`.
+    pub(crate) fn get(&self, path: &Path) -> Option<&CachedFile> {
+        self.files.get(path)
+    }
+
+    pub(crate) fn insert(&mut self, path: PathBuf, file: CachedFile) {
+        self.files.insert(path, file);
+    }
+}
+
+impl CachedFile {
+    fn parse<'a>(entries: impl Iterator<Item = (&'a str, &'a str)>) -> Option<Self> {
+        let mut file = CachedFile {
+            modified: String::new(),
+            size: 0,
+            hash: String::new(),
+            classes: vec![],
+            apis: vec![],
+            declarations: vec![],
+        };
+
+        for (key, value) in entries {
+            let value = unquote(value);
+            match key {
+                "modified" => file.modified = value.to_string(),
+                "size" => file.size = value.parse().ok()?,
+                "hash" => file.hash = value.to_string(),
+                "class" => {
+                    let mut parts = value.split('|');
+                    let name = parts.next()?.to_string();
+                    let modules = split_modules(parts.next()?);
+                    let span = parse_span(parts.next()?);
+                    let base = Some(parts.next()?)
+                        .filter(|base| !base.is_empty())
+                        .map(String::from);
+                    let gdns_path = Some(parts.next()?)
+                        .filter(|path| !path.is_empty())
+                        .map(String::from);
+                    file.classes.push(Class {
+                        module_path: modules,
+                        span,
+                        base,
+                        gdns_path,
+                        ..Class::new(name)
+                    });
+                }
+                "method" | "signal" => {
+                    let mut parts = value.split('|');
+                    let class = parts.next()?;
+                    let name = parts.next()?.to_string();
+                    let args = parts.next()?.parse().ok()?;
+
+                    let api = match file.apis.iter_mut().find(|api| api.class == class) {
+                        Some(api) => api,
+                        None => {
+                            file.apis.push(ClassApi {
+                                class: class.to_string(),
+                                ..ClassApi::default()
+                            });
+                            file.apis.last_mut()?
+                        }
+                    };
+
+                    if key == "method" {
+                        api.methods.push(Method { name, args });
+                    } else {
+                        api.signals.push(Signal { name, args });
+                    }
+                }
+                "mod" => {
+                    let mut parts = value.split('|');
+                    let modules = split_modules(parts.next()?);
+                    let name = parts.next()?.to_string();
+                    let path = Some(parts.next()?)
+                        .filter(|path| !path.is_empty())
+                        .map(String::from);
+                    file.declarations.push((modules, name, path));
+                }
+                _ => {}
+            }
+        }
+
+        Some(file).filter(|file| !file.hash.is_empty())
+    }
+
+    fn render(&self, content: &mut String) {
+        content.push_str(&format!("modified=\"{}\"\n", self.modified));
+        content.push_str(&format!("size={}\n", self.size));
+        content.push_str(&format!("hash=\"{}\"\n", self.hash));
+
+        for class in &self.classes {
+            let span = class
+                .span
+                .map(|span| {
+                    format!(
+                        "{}:{}:{}:{}",
+                        span.start_line, span.start_column, span.end_line, span.end_column
+                    )
+                })
+                .unwrap_or_default();
+            content.push_str(&format!(
+                "class=\"{}|{}|{}|{}|{}\"\n",
+                class.name,
+                class.module_path.join("::"),
+                span,
+                class.base.as_deref().unwrap_or_default(),
+                class.gdns_path.as_deref().unwrap_or_default()
+            ));
+        }
+
+        for api in &self.apis {
+            for method in &api.methods {
+                content.push_str(&format!(
+                    "method=\"{}|{}|{}\"\n",
+                    api.class, method.name, method.args
+                ));
+            }
+
+            for signal in &api.signals {
+                content.push_str(&format!(
+                    "signal=\"{}|{}|{}\"\n",
+                    api.class, signal.name, signal.args
+                ));
+            }
+        }
+
+        for (modules, name, path) in &self.declarations {
+            content.push_str(&format!(
+                "mod=\"{}|{}|{}\"\n",
+                modules.join("::"),
+                name,
+                path.as_deref().unwrap_or_default()
+            ));
+        }
+    }
+}
+
+/// The modification time of a file, as stored in the cache.
+pub(crate) fn modified_stamp(metadata: &std::fs::Metadata) -> String {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|since| format!("{}.{:09}", since.as_secs(), since.subsec_nanos()))
+        .unwrap_or_default()
+}
+
+fn unquote(value: &str) -> &str {
+    value.trim_matches('"')
+}
+
+fn split_modules(modules: &str) -> Vec<String> {
+    modules
+        .split("::")
+        .filter(|module| !module.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn parse_span(span: &str) -> Option<SourceSpan> {
+    let mut numbers = span.split(':').map(|number| number.parse().ok());
+
+    Some(SourceSpan {
+        start_line: numbers.next()??,
+        start_column: numbers.next()??,
+        end_line: numbers.next()??,
+        end_column: numbers.next()??,
+    })
+}
@@ -0,0 +1,113 @@
+//! Checking the symbols exported by a built library.
+
+use std::path::{Path, PathBuf};
+
+use object::{Object, ObjectSymbol};
+
+/// Check that the dynamic library at `library` exports all of `symbols`.
+///
+/// ELF, Mach-O and PE libraries are supported. The leading underscore Mach-O
+/// adds to symbol names is ignored.
+///
+/// ```no_run
+/// gdnative_project_utils::check_exported_symbols(
+///     "target/debug/libmy_game.so",
+///     &["godot_gdnative_init", "godot_nativescript_init"],
+/// )?;
+/// # Ok::<(), gdnative_project_utils::SymbolError>(())
+/// ```
+pub fn check_exported_symbols(
+    library: impl AsRef<Path>,
+    symbols: &[&str],
+) -> Result<(), SymbolError> {
+    let library = library.as_ref();
+
+    let data = std::fs::read(library).map_err(SymbolError::Read)?;
+    let file = object::File::parse(&*data).map_err(|error| SymbolError::Parse {
+        path: library.to_path_buf(),
+        error,
+    })?;
+
+    let macho = matches!(file.format(), object::BinaryFormat::MachO);
+    let exported = file
+        .exports()
+        .map_err(|error| SymbolError::Parse {
+            path: library.to_path_buf(),
+            error,
+        })?
+        .into_iter()
+        .map(|export| export.name().to_vec())
+        .chain(
+            file.dynamic_symbols()
+                .filter(|symbol| symbol.is_definition() && symbol.is_global())
+                .filter_map(|symbol| symbol.name_bytes().ok().map(<[u8]>::to_vec)),
+        )
+        .map(|name| match name.strip_prefix(b"_") {
+            Some(stripped) if macho => stripped.to_vec(),
+            _ => name,
+        })
+        .collect::<std::collections::HashSet<_>>();
+
+    let missing = symbols
+        .iter()
+        .filter(|symbol| !exported.contains(symbol.as_bytes()))
+        .map(|symbol| symbol.to_string())
+        .collect::<Vec<_>>();
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(SymbolError::Missing {
+            path: library.to_path_buf(),
+            symbols: missing,
+        })
+    }
+}
+
+/// Error type for errors that can occur when checking the symbols of a
+/// library.
+#[derive(Debug)]
+pub enum SymbolError {
+    /// The library could not be read.
+    Read(std::io::Error),
+    /// The library is not an object file in a supported format.
+    Parse {
+        path: PathBuf,
+        error: object::read::Error,
+    },
+    /// The library does not export `symbols`, usually because the init
+    /// functions are not `#[no_mangle]` or the symbol prefix is wrong.
+    Missing { path: PathBuf, symbols: Vec<String> },
+}
+
+impl std::fmt::Display for SymbolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SymbolError::Read(err) => f.write_fmt(format_args!("Unable to read library: {}", err)),
+            SymbolError::Parse { path, error } => f.write_fmt(format_args!(
+                "Unable to parse `{}`: {}",
+                path.display(),
+                error
+            )),
+            SymbolError::Missing { path, symbols } => f.write_fmt(format_args!(
+                "`{}` does not export {}",
+                path.display(),
+                symbols
+                    .iter()
+                    .map(|symbol| format!("`{}`", symbol))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+impl std::error::Error for SymbolError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SymbolError::Read(err) => Some(err),
+            SymbolError::Parse { error, .. } => Some(error),
+            SymbolError::Missing { .. } => None,
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! Templates replacing the default content of generated files.
+//!
+//! A template is the content of the file with `{name}` placeholders, which
+//! are replaced by the values the generator knows about. `{{` and `}}` stand
+//! for literal braces.
+
+/// Replace the placeholders in `template` with `value(name)`.
+///
+/// Returns the first placeholder `value` does not know as an error.
+pub(crate) fn render(
+    template: &str,
+    value: impl Fn(&str) -> Option<String>,
+) -> Result<String, String> {
+    let mut content = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find(['{', '}']) {
+        content.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            content.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+
+        let end = match rest.find('}') {
+            Some(end) if rest.starts_with('{') => end,
+            // A lone closing brace, or an opening brace that is never closed.
+            _ => return Err(rest.lines().next().unwrap_or_default().to_string()),
+        };
+        let name = &rest[1..end];
+        content.push_str(&value(name).ok_or_else(|| name.to_string())?);
+        rest = &rest[end + 1..];
+    }
+
+    content.push_str(rest);
+    Ok(content)
+}
@@ -0,0 +1,160 @@
+//! Regenerating the resources while the crate's sources are edited.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use notify::Watcher;
+
+use crate::class::Classes;
+use crate::generate::{Builder, GenerateError};
+use crate::report::Report;
+use crate::scan::{scan_crate_with_options, ScanOptions};
+
+/// What [`watch`] watches and how it generates the resources.
+#[derive(Clone)]
+pub struct WatchOptions {
+    crate_dir: PathBuf,
+    generator: Builder,
+    scan_options: ScanOptions,
+    debounce: Duration,
+}
+
+impl WatchOptions {
+    /// Construct new WatchOptions that watch the `src` directory of the crate
+    /// in `crate_dir` and generate the resources with `generator`.
+    pub fn new(crate_dir: impl AsRef<Path>, generator: Builder) -> Self {
+        WatchOptions {
+            crate_dir: crate_dir.as_ref().to_path_buf(),
+            generator,
+            scan_options: ScanOptions::default(),
+            debounce: Duration::from_millis(200),
+        }
+    }
+
+    /// Set the options of the scans.
+    pub fn with_scan_options(&mut self, options: ScanOptions) {
+        self.scan_options = options;
+    }
+
+    /// Set the options of the scans.
+    pub fn scan_options(mut self, options: ScanOptions) -> Self {
+        self.with_scan_options(options);
+        self
+    }
+
+    /// Set how long the sources have to stay unchanged before they are
+    /// scanned, so saving several files at once causes a single scan.
+    ///
+    /// Defaults to 200 milliseconds.
+    pub fn with_debounce(&mut self, debounce: Duration) {
+        self.debounce = debounce;
+    }
+
+    /// Set how long the sources have to stay unchanged before they are
+    /// scanned, so saving several files at once causes a single scan.
+    ///
+    /// Defaults to 200 milliseconds.
+    pub fn debounce(mut self, debounce: Duration) -> Self {
+        self.with_debounce(debounce);
+        self
+    }
+}
+
+/// Generate the resources of a crate, and generate them again whenever its
+/// classes change, until `callback` returns `false`.
+///
+/// The crate is scanned whenever a `*.rs` file in its `src` directory
+/// changes. The resources are only generated again if the classes are
+/// different from the last successful generation, so edits that do not add,
+/// remove or change a class are ignored. `callback` is called with the result
+/// of every generation, and with the errors of scans that failed, e.g.
+/// because a file is saved in the middle of an edit. Watching continues after
+/// errors.
+///
+/// ```no_run
+/// # use gdnative_project_utils::*;
+/// let generator = Generator::new().godot_project_dir("../godot");
+///
+/// watch(WatchOptions::new(".", generator), |result| {
+///     match result {
+///         Ok(report) => println!("{} files created", report.created().count()),
+///         Err(err) => eprintln!("{}", err),
+///     }
+///     true
+/// })?;
+/// # Ok::<(), GenerateError>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the sources can not be watched.
+pub fn watch(
+    options: WatchOptions,
+    mut callback: impl FnMut(Result<Report, GenerateError>) -> bool,
+) -> Result<(), GenerateError> {
+    let src_dir = options.crate_dir.join("src");
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = sender.send(event);
+    })?;
+    watcher.watch(&src_dir, notify::RecursiveMode::Recursive)?;
+
+    let mut generated: Option<Classes> = None;
+
+    loop {
+        let result = match scan_crate_with_options(&src_dir, &options.scan_options) {
+            Ok(report)
+                if generated.as_ref().map(without_locations)
+                    == Some(without_locations(&report.classes)) =>
+            {
+                None
+            }
+            Ok(report) => {
+                let result = options.generator.clone().build(report.classes.clone());
+                generated = result.as_ref().ok().map(|_| report.classes);
+                Some(result)
+            }
+            Err(err) => Some(Err(err.into())),
+        };
+
+        if let Some(result) = result {
+            if !callback(result) {
+                return Ok(());
+            }
+        }
+
+        // Wait for a change to a source file.
+        loop {
+            let event: notify::Event = match receiver.recv() {
+                Ok(event) => event?,
+                Err(_) => return Ok(()),
+            };
+
+            let is_source = event
+                .paths
+                .iter()
+                .any(|path| path.extension().is_some_and(|ext| ext == "rs"));
+            if is_source && !event.kind.is_access() {
+                break;
+            }
+        }
+
+        // Wait for the other files saved at the same time.
+        while receiver.recv_timeout(options.debounce).is_ok() {}
+    }
+}
+
+/// `classes` without their source locations, which change with every edit
+/// above the declarations but do not affect the resources.
+fn without_locations(classes: &Classes) -> Classes {
+    classes
+        .iter()
+        .cloned()
+        .map(|mut class| {
+            class.source_file = None;
+            class.span = None;
+            class
+        })
+        .collect()
+}
@@ -0,0 +1,210 @@
+//! Generation of the resources of every library crate in a cargo workspace.
+
+use std::path::{Path, PathBuf};
+
+use cargo_metadata::MetadataCommand;
+
+use crate::generate::{Builder, GenerateError};
+use crate::report::Report;
+use crate::scan::{scan_crate, ScanError};
+
+/// A builder type that generates one library resource, plus its `.gdns`
+/// files, for every `cdylib` crate of a cargo workspace.
+///
+/// The resources of each crate are generated into a subdirectory of the
+/// resource output directory named after its library, e.g.
+/// `native/game/game.gdnlib` and `native/game/Player.gdns`. Members that do
+/// not build a `cdylib` are skipped.
+#[derive(Default)]
+pub struct WorkspaceGenerator {
+    workspace_dir: Option<PathBuf>,
+    godot_project_dir: Option<PathBuf>,
+    godot_resource_output_dir: Option<PathBuf>,
+    generator: Builder,
+}
+
+impl WorkspaceGenerator {
+    /// Construct a new WorkspaceGenerator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the path to the workspace root, or any of its members.
+    ///
+    /// Defaults to `CARGO_MANIFEST_DIR`.
+    pub fn with_workspace_dir(&mut self, dir: impl AsRef<Path>) {
+        self.workspace_dir = Some(dir.as_ref().to_path_buf());
+    }
+
+    /// Set the path to the workspace root, or any of its members.
+    ///
+    /// Defaults to `CARGO_MANIFEST_DIR`.
+    pub fn workspace_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.with_workspace_dir(dir);
+        self
+    }
+
+    /// **REQUIRED** Set the path to the root of the Godot project.
+    pub fn with_godot_project_dir(&mut self, dir: impl AsRef<Path>) {
+        self.godot_project_dir = Some(dir.as_ref().to_path_buf());
+    }
+
+    /// **REQUIRED** Set the path to the root of the Godot project.
+    pub fn godot_project_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.with_godot_project_dir(dir);
+        self
+    }
+
+    /// Set the path to the directory that holds the subdirectories of the
+    /// crates.
+    ///
+    /// Defaults to `{godot_project_dir}/native`.
+    pub fn with_godot_resource_output_dir(&mut self, dir: impl AsRef<Path>) {
+        self.godot_resource_output_dir = Some(dir.as_ref().to_path_buf());
+    }
+
+    /// Set the path to the directory that holds the subdirectories of the
+    /// crates.
+    ///
+    /// Defaults to `{godot_project_dir}/native`.
+    pub fn godot_resource_output_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.with_godot_resource_output_dir(dir);
+        self
+    }
+
+    /// Set the generator whose settings are used for every crate.
+    ///
+    /// The crate directory, library name, Godot project directory and
+    /// resource output directory are set for each crate, and the target
+    /// directory is found with `cargo metadata`.
+    pub fn with_generator(&mut self, generator: Builder) {
+        self.generator = generator;
+    }
+
+    /// Set the generator whose settings are used for every crate.
+    ///
+    /// The crate directory, library name, Godot project directory and
+    /// resource output directory are set for each crate, and the target
+    /// directory is found with `cargo metadata`.
+    pub fn generator(mut self, generator: Builder) -> Self {
+        self.with_generator(generator);
+        self
+    }
+
+    /// Scan every `cdylib` crate of the workspace and generate its resources.
+    ///
+    /// Returns the reports of the crates in the order of the workspace
+    /// members.
+    pub fn build(self) -> Result<Vec<Report>, WorkspaceError> {
+        let workspace_dir = self
+            .workspace_dir
+            .or_else(|| std::env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from))
+            .ok_or(GenerateError::MissingField("workspace_dir"))?;
+        let godot_project_dir = self
+            .godot_project_dir
+            .ok_or(GenerateError::MissingField("godot_project_dir"))?;
+        let godot_resource_output_dir = self
+            .godot_resource_output_dir
+            .unwrap_or_else(|| godot_project_dir.join("native"));
+
+        let metadata = MetadataCommand::new()
+            .manifest_path(workspace_dir.join("Cargo.toml"))
+            .no_deps()
+            .exec()
+            .map_err(GenerateError::from)?;
+
+        let mut reports = vec![];
+
+        for package in metadata.workspace_packages() {
+            let lib = package
+                .targets
+                .iter()
+                .find(|target| target.crate_types.iter().any(|ty| ty == "cdylib"));
+
+            let lib = match lib {
+                Some(lib) => lib,
+                None => continue,
+            };
+
+            let crate_dir = package
+                .manifest_path
+                .parent()
+                .expect("Manifests are inside a directory")
+                .as_std_path();
+            let source_dir = lib
+                .src_path
+                .parent()
+                .expect("Sources are inside a directory")
+                .as_std_path();
+
+            let classes = scan_crate(source_dir).map_err(|error| WorkspaceError::Scan {
+                package: package.name.clone(),
+                error,
+            })?;
+
+            let report = self
+                .generator
+                .clone()
+                .crate_dir(crate_dir)
+                .cargo_metadata(true)
+                .lib_name(&lib.name)
+                .godot_project_dir(&godot_project_dir)
+                .godot_resource_output_dir(godot_resource_output_dir.join(&lib.name))
+                .build(classes)
+                .map_err(|error| WorkspaceError::Generate {
+                    package: package.name.clone(),
+                    error,
+                })?;
+
+            reports.push(report);
+        }
+
+        Ok(reports)
+    }
+}
+
+/// Error type for errors that can occur when generating the resources of a
+/// workspace.
+#[derive(Debug)]
+pub enum WorkspaceError {
+    /// The workspace could not be inspected.
+    Workspace(GenerateError),
+    /// An error was encountered when scanning the crate `package`.
+    Scan { package: String, error: ScanError },
+    /// An error was encountered when generating the resources of the crate
+    /// `package`.
+    Generate {
+        package: String,
+        error: GenerateError,
+    },
+}
+
+impl std::fmt::Display for WorkspaceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorkspaceError::Workspace(err) => f.write_fmt(format_args!("Workspace error: {}", err)),
+            WorkspaceError::Scan { package, error } => {
+                f.write_fmt(format_args!("Scanning error in `{}`: {}", package, error))
+            }
+            WorkspaceError::Generate { package, error } => {
+                f.write_fmt(format_args!("Generation error in `{}`: {}", package, error))
+            }
+        }
+    }
+}
+
+impl std::error::Error for WorkspaceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WorkspaceError::Workspace(err) => Some(err),
+            WorkspaceError::Scan { error, .. } => Some(error),
+            WorkspaceError::Generate { error, .. } => Some(error),
+        }
+    }
+}
+
+impl From<GenerateError> for WorkspaceError {
+    fn from(err: GenerateError) -> Self {
+        WorkspaceError::Workspace(err)
+    }
+}
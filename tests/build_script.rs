@@ -0,0 +1,23 @@
+#![cfg(feature = "build_script")]
+
+use gdnative_project_utils::*;
+
+#[test]
+fn build_script_generate() {
+    let crate_dir = dunce::canonicalize("tests/build_script_stub").unwrap();
+    let target_dir = tempfile::tempdir().unwrap();
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+
+    std::env::set_var("CARGO_MANIFEST_DIR", &crate_dir);
+    std::env::set_var("CARGO_PKG_NAME", "build_script_test");
+    std::env::set_var("PROFILE", "release");
+    std::env::set_var("CARGO_TARGET_DIR", target_dir.path());
+
+    build_script::generate(godot_proj_dir.path()).expect("Should generate resources");
+
+    let asset_dir = godot_proj_dir.path().join("native");
+
+    let content = std::fs::read_to_string(asset_dir.join("build_script_test.gdnlib")).unwrap();
+    assert!(content.contains("/release/libbuild_script_test.so\""));
+    assert!(asset_dir.join("Test.gdns").is_file());
+}
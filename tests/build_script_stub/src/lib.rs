@@ -0,0 +1,4 @@
+#[derive(NativeClass)]
+struct Test {}
+
+struct NonScriptType {}
\ No newline at end of file
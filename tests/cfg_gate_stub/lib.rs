@@ -0,0 +1,21 @@
+#[cfg(target_os = "windows")]
+#[derive(NativeClass)]
+struct WindowsOnly;
+
+#[cfg(all(target_os = "android", target_env = ""))]
+#[derive(NativeClass)]
+struct AndroidOnly;
+
+#[cfg(all(unix, target_pointer_width = "64"))]
+#[derive(NativeClass)]
+struct Unix64;
+
+#[cfg(feature = "editor")]
+mod editor {
+    #[derive(NativeClass)]
+    struct Inspector;
+}
+
+#[cfg(debug_assertions)]
+#[derive(NativeClass)]
+struct Undecided;
@@ -0,0 +1,15 @@
+#[cfg_attr(feature = "godot", derive(NativeClass))]
+struct Gated;
+
+#[cfg_attr(all(feature = "godot", feature = "editor"), derive(Debug, NativeClass))]
+struct EditorOnly;
+
+#[cfg_attr(not(feature = "godot"), derive(NativeClass))]
+struct Fallback;
+
+#[derive(NativeClass)]
+struct Always;
+
+#[cfg_attr(docsrs, doc = include_str!("documented.md"))]
+#[derive(NativeClass)]
+struct Documented;
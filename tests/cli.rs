@@ -0,0 +1,114 @@
+#![cfg(feature = "cli")]
+
+use std::path::Path;
+use std::process::Command;
+
+fn write(path: std::path::PathBuf, content: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+fn run(mut command: Command) -> (bool, String) {
+    let output = command
+        .env_remove("CARGO_PKG_NAME")
+        .env_remove("CARGO_MANIFEST_DIR")
+        .env_remove("CARGO_TARGET_DIR")
+        .env_remove("PROFILE")
+        .env_remove("OUT_DIR")
+        .output()
+        .unwrap();
+
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+fn cli(crate_dir: &Path, args: &[&str]) -> (bool, String) {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_gdnative-project-utils"));
+    command.args(args).arg("--crate-dir").arg(crate_dir);
+    run(command)
+}
+
+/// A crate with a Godot project in `godot`.
+fn crate_with_project() -> tempfile::TempDir {
+    let crate_dir = tempfile::tempdir().unwrap();
+    let dir = crate_dir.path();
+
+    write(
+        dir.join("Cargo.toml"),
+        "[package]\nname = \"cli-test\"\nversion = \"0.1.0\"\n\n\
+         [package.metadata.gdnative]\ngodot_project_dir = \"godot\"\n\n\
+         [lib]\ncrate-type = [\"cdylib\"]\n",
+    );
+    write(
+        dir.join("src/lib.rs"),
+        "mod enemies;\n\n#[derive(NativeClass)]\nstruct Player;\n",
+    );
+    write(
+        dir.join("src/enemies.rs"),
+        "#[derive(NativeClass)]\nstruct Slime;\n",
+    );
+    std::fs::create_dir_all(dir.join("godot")).unwrap();
+
+    crate_dir
+}
+
+#[test]
+fn commands() {
+    let crate_dir = crate_with_project();
+    let dir = crate_dir.path();
+
+    let (success, out) = cli(dir, &["scan"]);
+    assert!(success);
+    assert!(out.contains("crate::Player ("));
+    assert!(out.contains("crate::enemies::Slime ("));
+
+    let (success, out) = cli(dir, &["check"]);
+    assert!(!success);
+    assert!(out.contains("missing: "));
+
+    let (success, out) = cli(dir, &["generate"]);
+    assert!(success);
+    assert!(out.contains("Created: "));
+    let gdnlib = std::fs::read_to_string(dir.join("godot/native/cli_test.gdnlib")).unwrap();
+    assert!(gdnlib.contains("target/debug/libcli_test.so\""));
+
+    let (success, _) = cli(dir, &["generate", "--release"]);
+    assert!(success);
+    let gdnlib = std::fs::read_to_string(dir.join("godot/native/cli_test.gdnlib")).unwrap();
+    // the generated file follows the build mode
+    assert!(gdnlib.contains("target/release/libcli_test.so\""));
+
+    let (success, _) = cli(dir, &["check", "--release"]);
+    assert!(success);
+
+    let (success, out) = cli(dir, &["clean"]);
+    assert!(success);
+    assert!(out.contains("Removed: "));
+    assert!(!dir.join("godot/native/Player.gdns").exists());
+    assert!(!dir.join("godot/native/.gdnative-utils.cfg").exists());
+
+    let (success, _) = cli(dir, &["frobnicate"]);
+    assert!(!success);
+}
+
+#[test]
+fn cargo_subcommand() {
+    let crate_dir = crate_with_project();
+    let dir = crate_dir.path();
+
+    // cargo passes the name of the subcommand on, and the crate is found
+    // from the working directory
+    let mut command = Command::new(env!("CARGO_BIN_EXE_cargo-gdnative"));
+    command
+        .args(["gdnative", "sync"])
+        .current_dir(dir.join("src"));
+    let (success, out) = run(command);
+    assert!(success);
+    assert!(out.contains("Created: "));
+
+    let gdnlib = std::fs::read_to_string(dir.join("godot/native/cli_test.gdnlib")).unwrap();
+    assert!(gdnlib.contains("target/debug/libcli_test.so\""));
+    assert!(dir.join("godot/native/Slime.gdns").is_file());
+}
@@ -0,0 +1,223 @@
+use gdnative_project_utils::*;
+
+fn write(path: std::path::PathBuf, content: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+#[test]
+fn gdnative_toml() {
+    let crate_dir = tempfile::tempdir().unwrap();
+    let dir = crate_dir.path();
+
+    std::fs::create_dir_all(dir.join("godot")).unwrap();
+    std::fs::create_dir_all(dir.join("target")).unwrap();
+    write(
+        dir.join("gdnative.toml"),
+        r#"# shared by the whole team
+godot_project_dir = "godot"
+godot_resource_output_dir = "godot/scripts"
+target_dir = "target"
+lib_name = "config_test"
+build_mode = "release"
+platforms = ["linux", 'Windows.64'] # no macOS builds
+naming_convention = "snake_case"
+naming_prefix = "rs_"
+overwrite_policy = "if_generated_by_us"
+prune_stale = true # see [docs
+"#,
+    );
+
+    let report = Generator::from_config_in(dir)
+        .unwrap()
+        .build(vec![Class::new("PlayerController")].into_iter().collect())
+        .expect("Should generate resources");
+
+    assert_eq!(report.build_mode, BuildMode::Release);
+    assert!(dir
+        .join("godot/scripts/rs_player_controller.gdns")
+        .is_file());
+
+    let gdnlib = std::fs::read_to_string(dir.join("godot/scripts/config_test.gdnlib")).unwrap();
+    assert!(gdnlib.contains("target/release/libconfig_test.so\""));
+    assert!(gdnlib.contains("X11.arm64="));
+    assert!(gdnlib.contains("Windows.64="));
+    assert!(!gdnlib.contains("OSX.64="));
+}
+
+#[test]
+fn cargo_metadata_table() {
+    let crate_dir = tempfile::tempdir().unwrap();
+    let dir = crate_dir.path();
+
+    std::fs::create_dir_all(dir.join("godot")).unwrap();
+    std::fs::create_dir_all(dir.join("target")).unwrap();
+    write(
+        dir.join("Cargo.toml"),
+        r#"[package]
+name = "metadata_test" # the "name
+version = "0.1.0"
+
+[package.metadata.gdnative]
+godot-project-dir = "godot"
+target-dir = "target"
+build-mode = "debug"
+platforms = [
+    "X11.64",
+]
+
+[lib]
+crate-type = ["cdylib"]
+"#,
+    );
+
+    let report = Generator::from_config_in(dir)
+        .unwrap()
+        // settings of the file can be overridden
+        .lib_name("overridden")
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    assert_eq!(report.binaries.iter().count(), 1);
+    assert!(dir.join("godot/native/overridden.gdnlib").is_file());
+}
+
+#[test]
+fn invalid_config() {
+    let crate_dir = tempfile::tempdir().unwrap();
+    let dir = crate_dir.path();
+
+    for (content, key) in &[
+        ("godot_dir = \"godot\"", "godot_dir"),
+        ("prune_stale = \"yes\"", "prune_stale"),
+        ("platforms = [\"Amiga\"]", "platforms"),
+        ("platforms = [1]", "platforms"),
+        ("android_abis = [\"mips\"]", "android_abis"),
+        ("overwrite_policy = \"always\"", "overwrite_policy"),
+    ] {
+        write(dir.join("gdnative.toml"), content);
+
+        match Generator::from_config_in(dir) {
+            Err(GenerateError::InvalidFile {
+                error: ConfigParseError::InvalidValue { key: invalid },
+                ..
+            }) => assert_eq!(&invalid, key),
+            other => panic!("Expected an invalid value, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    write(dir.join("gdnative.toml"), "platforms = [ü]");
+    assert!(matches!(
+        Generator::from_config_in(dir),
+        Err(GenerateError::InvalidToml { .. })
+    ));
+}
+
+#[test]
+fn cargo_metadata_forms() {
+    let crate_dir = tempfile::tempdir().unwrap();
+    let dir = crate_dir.path();
+
+    for (content, key) in &[
+        (
+            "[package.metadata]\ngdnative.build_mode = 1\n",
+            "build_mode",
+        ),
+        (
+            "[package.metadata]\ngdnative = { build_mode = 1 }\n",
+            "build_mode",
+        ),
+        (
+            "[package]\nmetadata.gdnative.build_mode = 1\n",
+            "build_mode",
+        ),
+        (
+            "[package.metadata]\ngdnative = true\n",
+            "package.metadata.gdnative",
+        ),
+        (
+            "[package.metadata.gdnative.platforms]\nlinux = true\n",
+            "platforms",
+        ),
+    ] {
+        write(dir.join("Cargo.toml"), content);
+
+        match Generator::from_config_in(dir) {
+            Err(GenerateError::InvalidFile {
+                error: ConfigParseError::InvalidValue { key: invalid },
+                ..
+            }) => assert_eq!(&invalid, key),
+            other => panic!("Expected an invalid value, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    // other metadata is not ours to read
+    write(
+        dir.join("Cargo.toml"),
+        "[package.metadata]\ndocs.rs = { all-features = true }\n",
+    );
+    assert!(Generator::from_config_in(dir).is_ok());
+}
+
+// The only test that sets environment variables, so the other tests are not
+// affected by them.
+#[test]
+fn env_overrides() {
+    let crate_dir = tempfile::tempdir().unwrap();
+    let dir = crate_dir.path();
+
+    std::fs::create_dir_all(dir.join("godot")).unwrap();
+    std::fs::create_dir_all(dir.join("ci-godot")).unwrap();
+    std::fs::create_dir_all(dir.join("target")).unwrap();
+    write(
+        dir.join("gdnative.toml"),
+        "godot_project_dir = \"godot\"\nnaming_convention = \"snake_case\"\nplatforms = [\"all\"]\n",
+    );
+
+    std::env::set_var("GODOT_PROJECT_DIR", dir.join("ci-godot"));
+    std::env::set_var("GDNATIVE_TARGET_DIR", dir.join("target"));
+    std::env::set_var("GDNATIVE_LIB_NAME", "env_test");
+    std::env::set_var("GDNATIVE_BUILD_MODE", "release");
+    std::env::set_var("GDNATIVE_PLATFORMS", "linux, Windows.64");
+    std::env::set_var("GDNATIVE_NAMING_PREFIX", "rs_");
+    std::env::set_var("GDNATIVE_PRUNE_STALE", "1");
+
+    let classes = || vec![Class::new("PlayerController")].into_iter().collect();
+
+    let report = Generator::from_env()
+        .unwrap()
+        .build(classes())
+        .expect("Should generate resources");
+    assert!(report.godot_project_dir.ends_with("ci-godot"));
+    assert_eq!(report.build_mode, BuildMode::Release);
+    assert!(dir
+        .join("ci-godot/native/rs_PlayerController.gdns")
+        .is_file());
+    assert!(report
+        .binaries
+        .iter()
+        .all(|(platform, _)| platform.gdnlib_key().starts_with("X11")
+            || platform == Platform::Windows64));
+
+    // the variables take precedence over the file, but keep the settings
+    // they do not override
+    let report = Generator::from_config_in(dir)
+        .unwrap()
+        .env_overrides()
+        .unwrap()
+        .build(classes())
+        .expect("Should generate resources");
+    assert!(report.godot_project_dir.ends_with("ci-godot"));
+    assert!(dir
+        .join("ci-godot/native/rs_player_controller.gdns")
+        .is_file());
+
+    std::env::set_var("GDNATIVE_OVERWRITE_POLICY", "always");
+    match Generator::from_env() {
+        Err(GenerateError::InvalidEnvVar { var, value }) => {
+            assert_eq!(var, "GDNATIVE_OVERWRITE_POLICY");
+            assert_eq!(value, "always");
+        }
+        other => panic!("Expected an invalid variable, got {:?}", other.map(|_| ())),
+    }
+}
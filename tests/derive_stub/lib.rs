@@ -0,0 +1,30 @@
+use gdnative::prelude::NativeClass as NC;
+use my_macros::{GodotClass as Wrapped, Other as Unrelated};
+use my_macros::GodotClass;
+
+#[derive(gdnative::prelude::NativeClass)]
+struct Qualified;
+
+#[derive(::gdnative::derive::NativeClass)]
+struct Absolute;
+
+#[derive(NC)]
+struct Renamed;
+
+#[derive(my_macros::GodotClass)]
+struct Custom;
+
+#[derive(Wrapped)]
+struct CustomRenamed;
+
+#[derive(Unrelated)]
+struct NotAClass;
+
+#[derive(GodotClass)]
+struct Imported;
+
+#[derive(other_macros::GodotClass)]
+struct Foreign;
+
+#[derive(NativeClassExt)]
+struct Extended;
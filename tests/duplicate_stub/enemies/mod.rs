@@ -0,0 +1,4 @@
+mod boss {
+    #[derive(NativeClass)]
+    struct Player {}
+}
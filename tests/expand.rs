@@ -0,0 +1,39 @@
+#![cfg(feature = "cargo_expand")]
+
+use gdnative_project_utils::*;
+
+#[test]
+fn expanded_source() {
+    let source = std::fs::read_to_string("tests/expand_stub/expanded.rs").unwrap();
+    let classes = scan_expanded(&source, &ScanOptions::new()).expect("Scanning should work");
+
+    let names = classes.names().collect::<Vec<_>>();
+    assert_eq!(names, ["Bat", "Player", "Slime"]);
+    assert_eq!(
+        classes.get("Slime").unwrap().rust_path(),
+        "crate::entities::Slime"
+    );
+}
+
+// The only test that sets environment variables, so the other tests are not
+// affected by them.
+#[test]
+fn nested_build_script() {
+    let crate_dir = tempfile::tempdir().unwrap();
+    let src_dir = crate_dir.path().join("src");
+
+    std::fs::create_dir_all(&src_dir).unwrap();
+    std::fs::write(
+        src_dir.join("lib.rs"),
+        "#[derive(NativeClass)]\nstruct Player;\n",
+    )
+    .unwrap();
+
+    // the build script run by `cargo expand` scans the sources instead
+    std::env::set_var("GDNATIVE_PROJECT_UTILS_EXPANDING", "1");
+    let report =
+        scan_crate_expanded(crate_dir.path(), &ScanOptions::new()).expect("Scanning should work");
+
+    assert_eq!(report.classes.names().collect::<Vec<_>>(), ["Player"]);
+    assert_eq!(report.files, [src_dir.join("lib.rs")]);
+}
@@ -0,0 +1,23 @@
+#![feature(prelude_import)]
+#[prelude_import]
+use std::prelude::rust_2018::*;
+#[macro_use]
+extern crate std;
+mod entities {
+    use gdnative::prelude::*;
+    pub struct Slime;
+    impl gdnative::export::NativeClass for Slime {
+        type Base = Node;
+        type UserData = gdnative::export::user_data::LocalCellData<Slime>;
+    }
+    pub struct Bat;
+    impl gdnative::export::NativeClass for Bat {
+        type Base = Node;
+        type UserData = gdnative::export::user_data::LocalCellData<Bat>;
+    }
+}
+pub struct Player;
+impl ::gdnative::export::NativeClass for Player {
+    type Base = Node;
+    type UserData = gdnative::export::user_data::LocalCellData<Player>;
+}
@@ -0,0 +1,159 @@
+use gdnative_project_utils::{ConfigParseError, Gdnlib, Platform};
+
+const GDNLIB: &str = r#"[entry]
+
+X11.64="res://bin/libgame.so"
+Haiku.64="res://bin/haiku/libgame.so"
+Windows.64="res://bin/game.dll"
+
+[dependencies]
+
+X11.64=[ "res://bin/libsteam_api.so", "res://bin/libfmod.so" ]
+Windows.64=[  ]
+
+[general]
+
+singleton=true
+load_once=true
+symbol_prefix="game_"
+reloadable=false
+"#;
+
+#[test]
+fn round_trip() {
+    let lib: Gdnlib = GDNLIB.parse().unwrap();
+    assert_eq!(lib.to_string(), GDNLIB);
+}
+
+#[test]
+fn model() {
+    let lib = Gdnlib::parse(GDNLIB).unwrap();
+
+    assert_eq!(lib.entries.len(), 2);
+    assert_eq!(lib.entries[&Platform::Windows64], "res://bin/game.dll");
+    assert_eq!(
+        lib.dependencies[&Platform::X11_64],
+        ["res://bin/libsteam_api.so", "res://bin/libfmod.so"]
+    );
+    assert!(lib.dependencies[&Platform::Windows64].is_empty());
+    assert!(lib.singleton);
+    assert!(lib.load_once);
+    assert_eq!(lib.symbol_prefix, "game_");
+    assert!(!lib.reloadable);
+}
+
+#[test]
+fn edit() {
+    let mut lib = Gdnlib::parse(GDNLIB).unwrap();
+
+    lib.entries.remove(&Platform::Windows64);
+    lib.dependencies.remove(&Platform::Windows64);
+    lib.entries
+        .insert(Platform::Osx64, "res://bin/libgame.dylib".to_string());
+    lib.reloadable = true;
+
+    assert_eq!(
+        lib.to_string(),
+        r#"[entry]
+
+X11.64="res://bin/libgame.so"
+Haiku.64="res://bin/haiku/libgame.so"
+OSX.64="res://bin/libgame.dylib"
+
+[dependencies]
+
+X11.64=[ "res://bin/libsteam_api.so", "res://bin/libfmod.so" ]
+
+[general]
+
+singleton=true
+load_once=true
+symbol_prefix="game_"
+reloadable=true
+"#
+    );
+}
+
+#[test]
+fn merge() {
+    let mut lib = Gdnlib::parse(GDNLIB).unwrap();
+
+    let mut generated = Gdnlib::new();
+    generated.entries.insert(
+        Platform::X11_64,
+        "res://target/debug/libgame.so".to_string(),
+    );
+    generated.entries.insert(
+        Platform::AndroidAarch64,
+        "res://target/libgame.so".to_string(),
+    );
+    generated.singleton = false;
+
+    lib.merge(generated);
+
+    assert_eq!(
+        lib.entries[&Platform::X11_64],
+        "res://target/debug/libgame.so"
+    );
+    assert_eq!(lib.entries[&Platform::Windows64], "res://bin/game.dll");
+    assert!(lib.entries.contains_key(&Platform::AndroidAarch64));
+    // the general settings of the existing resource are kept
+    assert!(lib.singleton);
+    assert_eq!(lib.dependencies.len(), 2);
+}
+
+#[test]
+fn new_resource() {
+    let mut lib = Gdnlib::new();
+    lib.entries
+        .insert(Platform::X11_64, "res://bin/libgame.so".to_string());
+
+    assert_eq!(
+        lib.to_string(),
+        r#"[entry]
+X11.64="res://bin/libgame.so"
+
+[dependencies]
+
+X11.64=[  ]
+
+[general]
+
+singleton=false
+load_once=true
+symbol_prefix="godot_"
+reloadable=true"#
+    );
+
+    // a rendered resource reads back the same
+    let parsed = Gdnlib::parse(&lib.to_string()).unwrap();
+    assert_eq!(parsed.entries, lib.entries);
+    assert_eq!(parsed.to_string(), lib.to_string());
+}
+
+#[test]
+fn defaults_are_not_added() {
+    let mut lib = Gdnlib::parse("[entry]\nX11.64=\"res://libgame.so\"\n").unwrap();
+    assert!(lib.load_once);
+    assert_eq!(lib.to_string(), "[entry]\nX11.64=\"res://libgame.so\"\n");
+
+    lib.load_once = false;
+    assert_eq!(
+        lib.to_string(),
+        "[entry]\nX11.64=\"res://libgame.so\"\n\n[general]\nload_once=false\n"
+    );
+}
+
+#[test]
+fn invalid_values() {
+    for source in &[
+        "[entry]\nX11.64=res://libgame.so\n",
+        "[dependencies]\nX11.64=\"res://libsteam_api.so\"\n",
+        "[general]\nsingleton=yes\n",
+    ] {
+        assert!(matches!(
+            Gdnlib::parse(source),
+            Err(ConfigParseError::InvalidValue { .. })
+        ));
+    }
+}
@@ -0,0 +1,110 @@
+use gdnative_project_utils::{render_gdns, ConfigParseError, Gdns, GdnsConfig};
+
+const GDNS: &str = r#"[gd_resource type="NativeScript" load_steps=3 format=2]
+
+[ext_resource path="res://icons/player.svg" type="Texture" id=1]
+[ext_resource path="res://native/game.gdnlib" type="GDNativeLibrary" id=2]
+
+[resource]
+resource_name = "Player"
+class_name = "Player"
+script_class_name = "Player"
+library = ExtResource( 2 )
+script_class_icon = ExtResource( 1 )
+"#;
+
+#[test]
+fn round_trip() {
+    let gdns: Gdns = GDNS.parse().unwrap();
+    assert_eq!(gdns.to_string(), GDNS);
+}
+
+#[test]
+fn model() {
+    let gdns = Gdns::parse(GDNS).unwrap();
+
+    assert_eq!(gdns.class_name, "Player");
+    assert_eq!(gdns.script_class_name.as_deref(), Some("Player"));
+    assert_eq!(gdns.library, "res://native/game.gdnlib");
+    assert_eq!(
+        gdns.properties,
+        [
+            ("resource_name".to_string(), "\"Player\"".to_string()),
+            (
+                "script_class_icon".to_string(),
+                "ExtResource( 1 )".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn edit() {
+    let mut gdns = Gdns::parse(GDNS).unwrap();
+
+    gdns.library = "res://bin/game.gdnlib".to_string();
+    gdns.class_name = "Hero".to_string();
+    gdns.script_class_name = None;
+    gdns.properties.retain(|(key, _)| key != "resource_name");
+
+    assert_eq!(
+        gdns.to_string(),
+        r#"[gd_resource type="NativeScript" load_steps=3 format=2]
+
+[ext_resource path="res://icons/player.svg" type="Texture" id=1]
+[ext_resource path="res://bin/game.gdnlib" type="GDNativeLibrary" id=2]
+
+[resource]
+class_name = "Hero"
+library = ExtResource( 2 )
+script_class_icon = ExtResource( 1 )
+"#
+    );
+}
+
+#[test]
+fn new_resource() {
+    let gdns = Gdns::new("Player", "res://native/game.gdnlib");
+
+    assert_eq!(
+        gdns.to_string(),
+        render_gdns(&GdnsConfig {
+            class_name: "Player".to_string(),
+            library: "res://native/game.gdnlib".to_string(),
+        })
+    );
+
+    let parsed = Gdns::parse(&gdns.to_string()).unwrap();
+    assert_eq!(parsed.class_name, "Player");
+    assert_eq!(parsed.library, "res://native/game.gdnlib");
+    assert!(parsed.properties.is_empty());
+}
+
+#[test]
+fn godot4_ids() {
+    let gdns = Gdns::parse(
+        "[gd_resource type=\"NativeScript\" load_steps=2 format=3]\n\n\
+         [ext_resource type=\"GDNativeLibrary\" path=\"res://game.gdnlib\" id=\"1_x2k4d\"]\n\n\
+         [resource]\nclass_name = \"Player\"\nlibrary = ExtResource(\"1_x2k4d\")\n",
+    )
+    .unwrap();
+
+    assert_eq!(gdns.library, "res://game.gdnlib");
+}
+
+#[test]
+fn invalid_values() {
+    for source in &[
+        // no class name
+        "[ext_resource path=\"res://game.gdnlib\" type=\"GDNativeLibrary\" id=1]\n\n\
+         [resource]\nlibrary = ExtResource( 1 )\n",
+        // no such external resource
+        "[resource]\nclass_name = \"Player\"\nlibrary = ExtResource( 1 )\n",
+        "[resource]\nclass_name = Player\n",
+    ] {
+        assert!(matches!(
+            Gdns::parse(source),
+            Err(ConfigParseError::InvalidValue { .. })
+        ));
+    }
+}
@@ -0,0 +1,15 @@
+/// A slime.
+///
+/// gdns: enemies/slime.gdns
+#[derive(NativeClass)]
+#[inherit(Node2D)]
+struct Slime;
+
+/// The final boss.
+///
+/// gdns: ./bosses\dragon
+#[derive(NativeClass)]
+struct Dragon;
+
+#[derive(NativeClass)]
+struct Player;
@@ -0,0 +1,84 @@
+use gdnative_project_utils::godot_cfg::{ConfigFile, ParseError};
+
+const EXPORT_PRESETS: &str = r#"[preset.0]
+
+name="Linux/X11"
+platform="Linux/X11"
+runnable=true
+custom_features=""
+export_filter="all_resources"
+include_filter=""
+
+[preset.0.options]
+
+texture_format/bptc=false
+binary_format/64_bits=true
+"#;
+
+#[test]
+fn round_trip() {
+    let cfg: ConfigFile = EXPORT_PRESETS.parse().unwrap();
+    assert_eq!(cfg.to_string(), EXPORT_PRESETS);
+
+    let sections = cfg.section_names().collect::<Vec<_>>();
+    assert_eq!(sections, ["", "preset.0", "preset.0.options"]);
+    assert_eq!(cfg.get("preset.0", "name"), Some("\"Linux/X11\""));
+    assert_eq!(
+        cfg.get("preset.0.options", "binary_format/64_bits"),
+        Some("true")
+    );
+    assert_eq!(cfg.get("preset.0", "missing"), None);
+}
+
+#[test]
+fn edits_keep_the_rest_of_the_document() {
+    let mut cfg = ConfigFile::parse(EXPORT_PRESETS).unwrap();
+
+    cfg.set("preset.0", "runnable", "false");
+    cfg.set("preset.0.options", "texture_format/s3tc", "true");
+    assert_eq!(
+        cfg.remove("preset.0", "include_filter").as_deref(),
+        Some("\"\"")
+    );
+    assert_eq!(cfg.remove("preset.0", "include_filter"), None);
+
+    assert_eq!(
+        cfg.to_string(),
+        r#"[preset.0]
+
+name="Linux/X11"
+platform="Linux/X11"
+runnable=false
+custom_features=""
+export_filter="all_resources"
+
+[preset.0.options]
+
+texture_format/bptc=false
+binary_format/64_bits=true
+texture_format/s3tc=true
+"#
+    );
+}
+
+#[test]
+fn multi_line_values() {
+    let source = "list=[ {\n\"a\": \"]\"\n} ]\nnext=1\n";
+    let cfg = ConfigFile::parse(source).unwrap();
+
+    assert_eq!(cfg.get("", "list"), Some("[ {\n\"a\": \"]\"\n} ]"));
+    assert_eq!(cfg.get("", "next"), Some("1"));
+    assert_eq!(cfg.to_string(), source);
+}
+
+#[test]
+fn parse_errors() {
+    assert!(matches!(
+        ConfigFile::parse("[section\nkey=1\n"),
+        Err(ParseError::InvalidSection { line: 1 })
+    ));
+    assert!(matches!(
+        ConfigFile::parse("a=1\nlist=[ 1,\n2\n"),
+        Err(ParseError::UnterminatedValue { line: 2 })
+    ));
+}
@@ -0,0 +1,120 @@
+use gdnative_project_utils::godot_resource::{ExtResource, TextResource};
+use gdnative_project_utils::ConfigParseError;
+
+const SCENE: &str = r#"[gd_scene load_steps=4 format=2]
+
+[ext_resource path="res://native/Player.gdns" type="Script" id=1]
+[ext_resource path="res://sprites/player.png" type="Texture" id=2]
+[ext_resource path="res://native/Weapon.tscn" type="PackedScene" id=3]
+
+[sub_resource type="RectangleShape2D" id=1]
+extents = Vector2( 8, 16 )
+
+[node name="Player" type="KinematicBody2D"]
+script = ExtResource( 1 )
+metadata = {
+"[not a heading]": true
+}
+
+[node name="Weapon" parent="." instance=ExtResource( 3 )]
+"#;
+
+#[test]
+fn round_trip() {
+    let scene: TextResource = SCENE.parse().unwrap();
+    assert_eq!(scene.to_string(), SCENE);
+}
+
+#[test]
+fn ext_resources() {
+    let scene = TextResource::parse(SCENE).unwrap();
+
+    let resources = scene.ext_resources().collect::<Vec<_>>();
+    assert_eq!(
+        resources,
+        [
+            ExtResource {
+                path: "res://native/Player.gdns".to_string(),
+                type_name: "Script".to_string(),
+                id: "1".to_string(),
+            },
+            ExtResource {
+                path: "res://sprites/player.png".to_string(),
+                type_name: "Texture".to_string(),
+                id: "2".to_string(),
+            },
+            ExtResource {
+                path: "res://native/Weapon.tscn".to_string(),
+                type_name: "PackedScene".to_string(),
+                id: "3".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn godot4_ext_resources() {
+    let scene = TextResource::parse(
+        "[gd_scene load_steps=2 format=3 uid=\"uid://b1\"]\n\n\
+         [ext_resource type=\"Script\" path=\"res://player.gd\" id=\"1_x2y\"]\n",
+    )
+    .unwrap();
+
+    let resource = scene.ext_resources().next().unwrap();
+    assert_eq!(resource.path, "res://player.gd");
+    assert_eq!(resource.id, "1_x2y");
+}
+
+#[test]
+fn rewrite_paths() {
+    let mut scene = TextResource::parse(SCENE).unwrap();
+
+    assert_eq!(
+        scene.rewrite_ext_resource_paths("res://native/Player.gdns", "res://native/Hero.gdns"),
+        1
+    );
+    assert!(scene.set_ext_resource_path("2", "res://sprites/hero.png"));
+    assert!(!scene.set_ext_resource_path("7", "res://missing.png"));
+
+    let expected = SCENE
+        .replace("res://native/Player.gdns", "res://native/Hero.gdns")
+        .replace("res://sprites/player.png", "res://sprites/hero.png");
+    assert_eq!(scene.to_string(), expected);
+}
+
+#[test]
+fn invalid_heading() {
+    assert!(matches!(
+        TextResource::parse("[gd_resource format=2]\n\n[ext_resource path=\"res://a.gd]\n"),
+        Err(ConfigParseError::InvalidSection { line: 3 })
+    ));
+}
+
+#[test]
+fn properties() {
+    let mut scene = TextResource::parse(SCENE).unwrap();
+
+    assert_eq!(scene.property("node", "script"), Some("ExtResource( 1 )"));
+    assert_eq!(
+        scene.properties("sub_resource").collect::<Vec<_>>(),
+        [("extents", "Vector2( 8, 16 )")]
+    );
+    assert_eq!(scene.property("resource", "script"), None);
+
+    assert!(scene.set_property("sub_resource", "extents", "Vector2( 4, 8 )"));
+    assert!(scene.set_property("sub_resource", "custom_solver_bias", "0.5"));
+    assert_eq!(
+        scene.remove_property("node", "metadata").as_deref(),
+        Some("{\n\"[not a heading]\": true\n}")
+    );
+    assert!(!scene.set_property("resource", "script", "null"));
+
+    let content = scene.to_string();
+    assert!(content.contains(
+        "[sub_resource type=\"RectangleShape2D\" id=1]\n\
+         extents = Vector2( 4, 8 )\n\
+         custom_solver_bias = 0.5\n"
+    ));
+    assert!(!content.contains("metadata"));
+    assert!(content.contains("[node name=\"Weapon\" parent=\".\" instance=ExtResource( 3 )]"));
+}
@@ -0,0 +1,37 @@
+use gdnative::prelude::NativeClass as NC;
+
+struct Manual;
+
+impl NativeClass for Manual {
+    type Base = Node;
+}
+
+struct Qualified;
+
+impl gdnative::prelude::NativeClass for Qualified {
+    type Base = Node;
+}
+
+mod generated {
+    struct Renamed;
+
+    impl super::NC for Renamed {
+        type Base = Node;
+    }
+}
+
+struct Other;
+
+impl Clone for Other {
+    fn clone(&self) -> Self {
+        Other
+    }
+}
+
+impl NativeClassMethods for Manual {}
+
+struct Wrapper;
+
+impl my_macros::GodotClass for Wrapper {}
+
+impl ext::NativeClassExt for Wrapper {}
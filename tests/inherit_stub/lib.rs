@@ -0,0 +1,17 @@
+#[derive(NativeClass)]
+#[inherit(Node2D)]
+struct Player;
+
+#[derive(NativeClass)]
+#[inherit(gdnative::api::KinematicBody)]
+struct Enemy;
+
+#[derive(NativeClass)]
+struct Data;
+
+struct Manual;
+
+impl NativeClass for Manual {
+    type Base = gdnative::api::Spatial;
+    type UserData = LocalCellData<Manual>;
+}
@@ -0,0 +1,2 @@
+#[derive(NativeClass)]
+struct Good;
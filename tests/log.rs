@@ -0,0 +1,64 @@
+#![cfg(feature = "log")]
+
+use std::sync::Mutex;
+
+use gdnative_project_utils::*;
+
+static RECORDS: Mutex<Vec<(log::Level, String)>> = Mutex::new(vec![]);
+
+struct TestLogger;
+
+impl log::Log for TestLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        RECORDS
+            .lock()
+            .unwrap()
+            .push((record.level(), record.args().to_string()));
+    }
+
+    fn flush(&self) {}
+}
+
+#[test]
+fn generation_is_logged() {
+    log::set_logger(&TestLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let build = || {
+        Generator::new()
+            .lib_name("log_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .build(vec!["Player".to_string()].into_iter().collect())
+            .expect("Should generate resources")
+    };
+
+    build();
+    std::fs::write(godot_proj_dir.path().join("native/Player.gdns"), "modified").unwrap();
+    build();
+
+    let records = RECORDS.lock().unwrap();
+    let logged = |level: log::Level, text: &str| {
+        records
+            .iter()
+            .any(|(l, message)| *l == level && message.contains(text))
+    };
+
+    assert!(logged(
+        log::Level::Trace,
+        "Target directory given to the generator"
+    ));
+    assert!(logged(log::Level::Debug, "Library name: log_test"));
+    assert!(logged(log::Level::Info, "Created: "));
+    assert!(logged(log::Level::Warn, "Player.gdns`, it was modified"));
+}
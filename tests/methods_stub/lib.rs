@@ -0,0 +1,65 @@
+use gdnative::prelude::*;
+
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[register_with(Self::register_signals)]
+struct Player;
+
+#[methods]
+impl Player {
+    fn new(_owner: &Node) -> Self {
+        Player
+    }
+
+    fn register_signals(builder: &ClassBuilder<Self>) {
+        builder.signal("died").done();
+        builder
+            .signal("hit")
+            .with_param("damage", VariantType::I64)
+            .with_param_default("source", Variant::nil())
+            .done();
+    }
+
+    #[export]
+    fn _ready(&self, _owner: &Node) {}
+
+    #[export]
+    fn jump(&self, _owner: &Node, height: f32) {}
+
+    fn helper(&self) {}
+}
+
+#[derive(NativeClass)]
+#[inherit(Node)]
+#[register_with(register_enemy)]
+struct Enemy;
+
+fn register_enemy(builder: &ClassBuilder<Enemy>) {
+    builder.add_signal(Signal {
+        name: "spotted",
+        args: &[SignalArgument {
+            name: "target",
+            default: Variant::nil(),
+            export_info: ExportInfo::new(VariantType::Object),
+            usage: PropertyUsage::DEFAULT,
+        }],
+    });
+}
+
+#[methods]
+impl Enemy {
+    fn new(_base: &Node) -> Self {
+        Enemy
+    }
+
+    #[method]
+    fn attack(&self, #[base] _base: &Node, target: Ref<Node>, damage: i64) {}
+
+    #[cfg(test)]
+    #[method]
+    fn disabled(&self) {}
+}
+
+impl Enemy {
+    fn not_exported(&self) {}
+}
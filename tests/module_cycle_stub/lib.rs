@@ -0,0 +1,6 @@
+#[path = "lib.rs"]
+mod me;
+mod other;
+
+#[derive(NativeClass)]
+struct Root;
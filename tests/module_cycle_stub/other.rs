@@ -0,0 +1,5 @@
+#[path = "other.rs"]
+mod again;
+
+#[derive(NativeClass)]
+struct Other;
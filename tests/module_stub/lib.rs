@@ -0,0 +1,11 @@
+mod player;
+#[path = "generated/enemy_impl.rs"]
+mod enemy;
+mod world {
+    mod level;
+}
+#[cfg(feature = "editor")]
+mod tools;
+
+#[derive(NativeClass)]
+struct Root;
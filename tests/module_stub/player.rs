@@ -0,0 +1,4 @@
+mod weapon;
+
+#[derive(NativeClass)]
+struct Player;
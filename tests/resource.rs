@@ -1,5 +1,19 @@
 use gdnative_project_utils::*;
 
+/// A class that extends `base`.
+fn derived_class(name: &str, base: &str) -> Class {
+    let mut class = Class::new(name);
+    class.base = Some(base.to_string());
+    class
+}
+
+/// A class declared in the module `module_path`.
+fn nested_class(name: &str, module_path: &[&str]) -> Class {
+    let mut class = Class::new(name);
+    class.module_path = module_path.iter().map(|m| m.to_string()).collect();
+    class
+}
+
 #[test]
 fn gdnlib_target_in_project() {
     let godot_proj_dir = tempfile::tempdir().unwrap();
@@ -77,6 +91,265 @@ fn gdnlib_target_outside_of_project() {
     assert!(content.contains(&format!("=\"{}/debug/generator_test.dll\"", target_dir)));
 }
 
+#[test]
+fn path_style() {
+    use path_slash::PathExt;
+
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let outside_target_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let build = |target_dir: &std::path::Path, style| {
+        Generator::new()
+            .lib_name("style_test")
+            .build_mode(BuildMode::Debug)
+            .platforms(Platform::X11_64)
+            .target_dir(target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .overwrite_policy(OverwritePolicy::IfContentDiffers)
+            .path_style(style)
+            .build(vec!["Player".to_string()].into_iter().collect())
+    };
+    let entry = || {
+        let gdnlib =
+            std::fs::read_to_string(godot_proj_dir.path().join("native/style_test.gdnlib"))
+                .unwrap();
+        ConfigFile::parse(&gdnlib)
+            .unwrap()
+            .get("entry", "X11.64")
+            .unwrap()
+            .to_string()
+    };
+
+    let report = build(&target_dir, PathStyle::Auto).unwrap();
+    assert_eq!(report.binary_path_prefix, "res://");
+    assert_eq!(entry(), "\"res://target/debug/libstyle_test.so\"");
+
+    let report = build(&target_dir, PathStyle::ForceAbsolute).unwrap();
+    assert_eq!(report.binary_path_prefix, "");
+    let project_dir = dunce::canonicalize(godot_proj_dir.path()).unwrap();
+    assert_eq!(
+        entry(),
+        format!(
+            "\"{}\"",
+            project_dir
+                .join("target/debug/libstyle_test.so")
+                .to_slash()
+                .unwrap()
+        )
+    );
+    assert_eq!(
+        report.lib_resource_path,
+        project_dir.join("native/style_test.gdnlib").to_slash()
+    );
+    let gdns = std::fs::read_to_string(godot_proj_dir.path().join("native/Player.gdns")).unwrap();
+    assert!(!gdns.contains("res://"));
+
+    let report = build(&target_dir, PathStyle::ForceRes).unwrap();
+    assert_eq!(report.binary_path_prefix, "res://");
+    assert_eq!(entry(), "\"res://target/debug/libstyle_test.so\"");
+
+    match build(outside_target_dir.path(), PathStyle::ForceRes) {
+        Err(GenerateError::OutsideProject { path }) => {
+            assert!(path.ends_with("debug/libstyle_test.so"))
+        }
+        result => panic!("Expected OutsideProject, got {:?}", result.map(|_| ())),
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinked_target_dir() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let ramdisk = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::os::unix::fs::symlink(ramdisk.path(), &target_dir).unwrap();
+
+    let build = |resolution, style| {
+        Generator::new()
+            .lib_name("symlinked_test")
+            .build_mode(BuildMode::Debug)
+            .platforms(Platform::X11_64)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .overwrite_policy(OverwritePolicy::IfContentDiffers)
+            .path_style(style)
+            .path_resolution(resolution)
+            .build(Classes::new())
+    };
+    let entry = || {
+        let gdnlib =
+            std::fs::read_to_string(godot_proj_dir.path().join("native/symlinked_test.gdnlib"))
+                .unwrap();
+        ConfigFile::parse(&gdnlib)
+            .unwrap()
+            .get("entry", "X11.64")
+            .unwrap()
+            .to_string()
+    };
+
+    // the target directory resolves to outside of the project
+    build(PathResolution::Canonical, PathStyle::Auto).unwrap();
+    assert!(!entry().contains("res://"));
+    assert!(matches!(
+        build(PathResolution::Canonical, PathStyle::ForceRes),
+        Err(GenerateError::OutsideProject { .. })
+    ));
+
+    let report = build(PathResolution::Logical, PathStyle::Auto).unwrap();
+    assert_eq!(report.binary_path_prefix, "res://");
+    assert_eq!(entry(), "\"res://target/debug/libsymlinked_test.so\"");
+
+    build(PathResolution::Logical, PathStyle::ForceRes).unwrap();
+    assert_eq!(entry(), "\"res://target/debug/libsymlinked_test.so\"");
+}
+
+#[cfg(unix)]
+#[test]
+fn logical_path_resolution() {
+    let dir = tempfile::tempdir().unwrap();
+    let ramdisk = tempfile::tempdir().unwrap();
+    let real_proj_dir = dir.path().join("project");
+    let godot_proj_dir = dir.path().join("linked_project");
+    let target_dir = godot_proj_dir.join("target");
+
+    std::fs::create_dir_all(&real_proj_dir).unwrap();
+    std::os::unix::fs::symlink(&real_proj_dir, &godot_proj_dir).unwrap();
+    std::os::unix::fs::symlink(ramdisk.path(), &target_dir).unwrap();
+
+    let report = Generator::new()
+        .lib_name("logical_test")
+        .build_mode(BuildMode::Debug)
+        .platforms(Platform::X11_64)
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .path_resolution(PathResolution::Logical)
+        .build(vec!["Player".to_string()].into_iter().collect())
+        .expect("Should generate resources");
+
+    assert_eq!(report.godot_project_dir, godot_proj_dir);
+    assert_eq!(report.target_dir, target_dir);
+    assert_eq!(report.binary_path_prefix, "res://");
+
+    let gdnlib = std::fs::read_to_string(real_proj_dir.join("native/logical_test.gdnlib")).unwrap();
+    assert_eq!(
+        ConfigFile::parse(&gdnlib).unwrap().get("entry", "X11.64"),
+        Some("\"res://target/debug/liblogical_test.so\"")
+    );
+    assert!(real_proj_dir.join("native/Player.gdns").is_file());
+}
+
+#[cfg(all(unix, feature = "cargo_metadata"))]
+#[test]
+fn logical_cargo_metadata() {
+    let dir = tempfile::tempdir().unwrap();
+    let real_crate_dir = dir.path().join("crate");
+    let crate_dir = dir.path().join("linked_crate");
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+
+    std::fs::create_dir_all(real_crate_dir.join("src")).unwrap();
+    std::fs::write(real_crate_dir.join("src/lib.rs"), "").unwrap();
+    std::fs::write(
+        real_crate_dir.join("Cargo.toml"),
+        "[package]\nname = \"linked-test\"\nversion = \"0.1.0\"\n\n\
+         [lib]\ncrate-type = [\"cdylib\"]\n\n[workspace]\n",
+    )
+    .unwrap();
+    std::os::unix::fs::symlink(&real_crate_dir, &crate_dir).unwrap();
+
+    let report = Generator::new()
+        .crate_dir(&crate_dir)
+        .cargo_metadata(true)
+        .path_resolution(PathResolution::Logical)
+        .godot_project_dir(&godot_proj_dir)
+        .build_mode(BuildMode::Debug)
+        .platforms(Platform::X11_64)
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    assert!(report.lib_path.ends_with("native/linked_test.gdnlib"));
+}
+
+#[cfg(windows)]
+#[test]
+fn verbatim_paths() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = tempfile::tempdir().unwrap();
+    let vendor_dir = tempfile::tempdir().unwrap();
+
+    // `std::fs::canonicalize` returns `\\?\C:\...` paths
+    let verbatim = |path: &std::path::Path| std::fs::canonicalize(path).unwrap();
+    std::fs::write(vendor_dir.path().join("game.dll"), "").unwrap();
+
+    let report = Generator::new()
+        .lib_name("verbatim_test")
+        .target_dir(verbatim(target_dir.path()))
+        .godot_project_dir(verbatim(godot_proj_dir.path()))
+        .override_binary(
+            Platform::Windows64,
+            verbatim(&vendor_dir.path().join("game.dll")),
+        )
+        .build_mode(BuildMode::Debug)
+        .build(vec!["Player".to_string()].into_iter().collect())
+        .expect("Should generate resources");
+
+    assert_eq!(
+        report.lib_resource_path.as_deref(),
+        Some("res://native/verbatim_test.gdnlib")
+    );
+
+    for file in ["verbatim_test.gdnlib", "Player.gdns"] {
+        let content =
+            std::fs::read_to_string(godot_proj_dir.path().join("native").join(file)).unwrap();
+        assert!(!content.contains("//?/"), "{}", content);
+        assert!(!content.contains("\\\\?\\"), "{}", content);
+    }
+}
+
+// Windows does not allow quotes in file names.
+#[cfg(unix)]
+#[test]
+fn special_characters_in_paths() {
+    let dir = tempfile::tempdir().unwrap();
+    let godot_proj_dir = dir.path().join("My \"Game\" – Ünïcödé");
+    let asset_dir = godot_proj_dir.join("native \\ libs");
+    let target_dir = dir.path().join("target \"dir\"");
+
+    std::fs::create_dir_all(&godot_proj_dir).unwrap();
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let report = Generator::new()
+        .lib_name("escape_test")
+        .build_mode(BuildMode::Debug)
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .godot_resource_output_dir(&asset_dir)
+        .build(vec!["Player".to_string()].into_iter().collect())
+        .expect("Should generate resources");
+
+    let gdnlib_path = "res://native \\ libs/escape_test.gdnlib";
+    assert_eq!(report.lib_resource_path.as_deref(), Some(gdnlib_path));
+
+    let gdnlib = std::fs::read_to_string(asset_dir.join("escape_test.gdnlib")).unwrap();
+    let gdnlib = ConfigFile::parse(&gdnlib).expect("Should be a valid ConfigFile");
+    let lib_path = format!(
+        "{}/debug/libescape_test.so",
+        dunce::canonicalize(&target_dir).unwrap().display()
+    );
+    assert_eq!(
+        gdnlib.get("entry", "X11.64"),
+        Some(format!("\"{}\"", lib_path.replace('"', "\\\"")).as_str())
+    );
+
+    let gdns = std::fs::read_to_string(asset_dir.join("Player.gdns")).unwrap();
+    let gdns = TextResource::parse(&gdns).expect("Should be a valid resource");
+    let ext_resource = gdns.ext_resources().next().unwrap();
+    assert_eq!(ext_resource.path, gdnlib_path);
+}
+
 #[test]
 fn gdns() {
     let c: Classes = vec!["Test".to_string(), "AnotherTest".to_string()]
@@ -110,3 +383,3624 @@ fn gdns() {
         assert!(content.contains(&format!("script_class_name = \"{}\"", class)));
     }
 }
+
+#[test]
+fn gdextension() {
+    let c: Classes = vec!["Test".to_string()].into_iter().collect();
+
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&asset_dir).unwrap();
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    Generator::new()
+        .lib_name("gdext_test")
+        .build_mode(BuildMode::Release)
+        .output_format(OutputFormat::GdExtension)
+        .entry_symbol("my_init")
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .godot_resource_output_dir(&asset_dir)
+        .build(c)
+        .expect("Should generate resources");
+
+    let path = asset_dir.join("gdext_test.gdextension");
+    assert!(path.is_file());
+    assert!(!asset_dir.join("gdext_test.gdnlib").exists());
+    assert!(!asset_dir.join("Test.gdns").exists());
+
+    let content = std::fs::read_to_string(&path).unwrap();
+
+    assert!(content.contains("entry_symbol = \"my_init\""));
+    assert!(content.contains("compatibility_minimum = \"4.1\""));
+    assert!(content.contains("linux.x86_64 = \"res://target/release/libgdext_test.so\""));
+    assert!(content.contains("windows.x86_64 = \"res://target/release/gdext_test.dll\""));
+}
+
+#[test]
+fn dual_output() {
+    let c: Classes = vec!["Test".to_string()].into_iter().collect();
+
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&asset_dir).unwrap();
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let report = Generator::new()
+        .lib_name("dual_test")
+        .build_mode(BuildMode::Release)
+        .output_format(OutputFormat::Dual)
+        .compatibility_minimum("4.2.1")
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .godot_resource_output_dir(&asset_dir)
+        .build(c)
+        .expect("Should generate resources");
+
+    assert_eq!(report.lib_path, asset_dir.join("dual_test.gdnlib"));
+    assert_eq!(
+        report.gdextension_path,
+        Some(asset_dir.join("dual_test.gdextension"))
+    );
+
+    let gdnlib = std::fs::read_to_string(asset_dir.join("dual_test.gdnlib")).unwrap();
+    assert!(gdnlib.contains("X11.64=\"res://target/release/libdual_test.so\""));
+
+    let gdextension = std::fs::read_to_string(asset_dir.join("dual_test.gdextension")).unwrap();
+    assert!(gdextension.contains("entry_symbol = \"gdext_rust_init\""));
+    assert!(gdextension.contains("compatibility_minimum = \"4.2.1\""));
+    assert!(gdextension.contains("linux.x86_64 = \"res://target/release/libdual_test.so\""));
+
+    // the script resources are for Godot 3 and use the GDNative library
+    let gdns = std::fs::read_to_string(asset_dir.join("Test.gdns")).unwrap();
+    assert!(gdns.contains("path=\"res://native/dual_test.gdnlib\""));
+}
+
+#[test]
+fn godot_version_detection() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let classes: Classes = vec![derived_class("Player", "Node2D")]
+        .into_iter()
+        .collect();
+
+    let build = |generator: Generator| {
+        generator
+            .lib_name("version_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .scene_templates(true)
+            .build(classes.clone())
+            .expect("Should generate resources")
+    };
+
+    std::fs::write(
+        godot_proj_dir.path().join("project.godot"),
+        "config_version=5\n\n[application]\n\nconfig/features=PackedStringArray(\"4.2\")\n",
+    )
+    .unwrap();
+
+    let report = build(Generator::new());
+    assert_eq!(report.godot_version, Some(GodotVersion::Godot4));
+    assert!(matches!(report.output_format, OutputFormat::GdExtension));
+    assert!(asset_dir.join("version_test.gdextension").is_file());
+    assert!(!asset_dir.join("Player.gdns").exists());
+
+    let scene = std::fs::read_to_string(asset_dir.join("Player.tscn")).unwrap();
+    assert_eq!(
+        scene,
+        "[gd_scene format=3]\n\n[node name=\"Player\" type=\"Player\"]\n"
+    );
+
+    // an explicit output format wins over the detected one
+    let report = build(Generator::new().output_format(OutputFormat::Gdnative));
+    assert!(matches!(report.output_format, OutputFormat::Gdnative));
+    assert!(asset_dir.join("version_test.gdnlib").is_file());
+
+    // and so does an explicit version
+    let report = build(Generator::new().godot_version(GodotVersion::Godot3));
+    assert_eq!(report.godot_version, Some(GodotVersion::Godot3));
+    assert!(matches!(report.output_format, OutputFormat::Gdnative));
+
+    std::fs::write(
+        godot_proj_dir.path().join("project.godot"),
+        "config_version=4\n\n[application]\n\nconfig/name=\"Game\"\n",
+    )
+    .unwrap();
+
+    let report = build(Generator::new());
+    assert_eq!(report.godot_version, Some(GodotVersion::Godot3));
+    assert!(matches!(report.output_format, OutputFormat::Gdnative));
+
+    // without a config version the features are used
+    let project: ConfigFile =
+        "[application]\n\nconfig/features=PackedStringArray(\"4.1\", \"Forward Plus\")\n"
+            .parse()
+            .unwrap();
+    assert_eq!(
+        GodotVersion::from_project(&project),
+        Some(GodotVersion::Godot4)
+    );
+    let project: ConfigFile = "[application]\n\nconfig/name=\"Game\"\n".parse().unwrap();
+    assert_eq!(GodotVersion::from_project(&project), None);
+}
+
+#[test]
+fn auto_detect_project() {
+    let root = tempfile::tempdir().unwrap();
+    let root = dunce::canonicalize(root.path()).unwrap();
+    let godot_proj_dir = root.join("godot");
+    let crate_dir = root.join("rust");
+    let target_dir = crate_dir.join("target");
+
+    std::fs::create_dir_all(&godot_proj_dir).unwrap();
+    std::fs::create_dir_all(&target_dir).unwrap();
+    std::fs::write(godot_proj_dir.join("project.godot"), "config_version=4\n").unwrap();
+
+    let build = |crate_dir: &std::path::Path| {
+        Generator::new()
+            .lib_name("detect_test")
+            .build_mode(BuildMode::Debug)
+            .crate_dir(crate_dir)
+            .target_dir(&target_dir)
+            .auto_detect_project(true)
+            .build(Classes::new())
+    };
+
+    // next to the crate
+    let report = build(&crate_dir).expect("Should find the project");
+    assert_eq!(report.godot_project_dir, godot_proj_dir);
+    assert!(godot_proj_dir.join("native/detect_test.gdnlib").is_file());
+
+    // around the crate, which wins over other projects
+    let inner_crate_dir = godot_proj_dir.join("rust");
+    std::fs::create_dir_all(&inner_crate_dir).unwrap();
+    std::fs::create_dir_all(root.join("demo")).unwrap();
+    std::fs::write(root.join("demo/project.godot"), "config_version=4\n").unwrap();
+    let report = build(&inner_crate_dir).expect("Should find the project");
+    assert_eq!(report.godot_project_dir, godot_proj_dir);
+
+    // several projects next to the crate
+    match build(&crate_dir) {
+        Err(GenerateError::AmbiguousProject { candidates }) => {
+            assert_eq!(candidates, vec![root.join("demo"), godot_proj_dir.clone()]);
+        }
+        other => panic!("Expected an ambiguous project, got {:?}", other.map(|_| ())),
+    }
+
+    // not searched unless enabled
+    let result = Generator::new()
+        .lib_name("detect_test")
+        .build_mode(BuildMode::Debug)
+        .crate_dir(&crate_dir)
+        .target_dir(&target_dir)
+        .build(Classes::new());
+    assert!(matches!(
+        result,
+        Err(GenerateError::MissingField("godot_project_dir"))
+    ));
+}
+
+#[test]
+fn missing_project_dir_is_an_error() {
+    let target_dir = tempfile::tempdir().unwrap();
+
+    let err = Generator::new()
+        .lib_name("error_test")
+        .target_dir(&target_dir)
+        .build_mode(BuildMode::Debug)
+        .build(Classes::new())
+        .expect_err("Should fail without a Godot project dir");
+
+    assert!(matches!(
+        err,
+        GenerateError::MissingField("godot_project_dir")
+    ));
+}
+
+#[test]
+fn nonexistent_project_dir_is_an_error() {
+    let tmp = tempfile::tempdir().unwrap();
+
+    let err = Generator::new()
+        .lib_name("error_test")
+        .target_dir(tmp.path())
+        .godot_project_dir(tmp.path().join("does_not_exist"))
+        .build_mode(BuildMode::Debug)
+        .build(Classes::new())
+        .expect_err("Should fail with a nonexistent Godot project dir");
+
+    assert!(matches!(err, GenerateError::InvalidPath { .. }));
+}
+
+#[test]
+fn gdnlib_ios_entries() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    Generator::new()
+        .lib_name("ios-test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .build_mode(BuildMode::Release)
+        .platforms(Platforms::ios())
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    let content =
+        std::fs::read_to_string(godot_proj_dir.path().join("native/ios-test.gdnlib")).unwrap();
+
+    assert!(content.contains("iOS.armv7=\"res://target/armv7-apple-ios/release/libios_test.a\""));
+    assert!(content.contains("iOS.arm64=\"res://target/aarch64-apple-ios/release/libios_test.a\""));
+    assert!(content.contains("iOS.arm64=[  ]"));
+
+    // iOS is only generated if it is selected
+    Generator::new()
+        .lib_name("ios-test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .build_mode(BuildMode::Release)
+        .overwrite_policy(OverwritePolicy::IfGeneratedByUs)
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    let content =
+        std::fs::read_to_string(godot_proj_dir.path().join("native/ios-test.gdnlib")).unwrap();
+    assert!(!content.contains("iOS."));
+    assert!(content.contains("Android.arm64-v8a="));
+
+    let xcframework_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = xcframework_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    Generator::new()
+        .lib_name("ios-test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&xcframework_proj_dir)
+        .build_mode(BuildMode::Release)
+        .platforms(Platforms::ios())
+        .ios_lib_layout(IosLibLayout::XcFramework)
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    let content =
+        std::fs::read_to_string(xcframework_proj_dir.path().join("native/ios-test.gdnlib"))
+            .unwrap();
+
+    assert!(content.contains("iOS.armv7=\"res://target/release/ios_test.xcframework\""));
+    assert!(content.contains("iOS.arm64=\"res://target/release/ios_test.xcframework\""));
+}
+
+#[test]
+fn gdnlib_ios_simulator_entries() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let build = |layout| {
+        Generator::new()
+            .lib_name("sim_test")
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .build_mode(BuildMode::Debug)
+            .platforms(Platforms::ios_simulator())
+            .ios_lib_layout(layout)
+            .overwrite_policy(OverwritePolicy::IfGeneratedByUs)
+            .build(Classes::new())
+            .expect("Should generate resources");
+
+        std::fs::read_to_string(godot_proj_dir.path().join("native/sim_test.gdnlib")).unwrap()
+    };
+
+    let content = build(IosLibLayout::Simulator);
+    assert!(
+        content.contains("iOS.arm64=\"res://target/aarch64-apple-ios-sim/debug/libsim_test.a\"")
+    );
+    assert!(content.contains("iOS.x86_64=\"res://target/x86_64-apple-ios/debug/libsim_test.a\""));
+
+    // without a device build there is nothing to create the bundle from yet
+    let content = build(IosLibLayout::XcFrameworkCreate);
+    assert!(content.contains("iOS.arm64=\"res://target/debug/sim_test.xcframework\""));
+    assert!(content.contains("iOS.x86_64=\"res://target/debug/sim_test.xcframework\""));
+    assert!(!target_dir.join("debug/sim_test.xcframework").exists());
+}
+
+#[test]
+fn gdnlib_linux_entries() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    Generator::new()
+        .lib_name("linux-test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .build_mode(BuildMode::Release)
+        .platforms(
+            Platforms::new()
+                .with(Platform::X11Armv7)
+                .with(Platform::X11Arm64),
+        )
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    let content =
+        std::fs::read_to_string(godot_proj_dir.path().join("native/linux-test.gdnlib")).unwrap();
+
+    assert!(content.contains(
+        "X11.armv7=\"res://target/armv7-unknown-linux-gnueabihf/release/liblinux_test.so\""
+    ));
+    assert!(content
+        .contains("X11.arm64=\"res://target/aarch64-unknown-linux-gnu/release/liblinux_test.so\""));
+    assert!(!content.contains("X11.64"));
+
+    let gdextension_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = gdextension_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    Generator::new()
+        .lib_name("linux-test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&gdextension_proj_dir)
+        .build_mode(BuildMode::Release)
+        .output_format(OutputFormat::GdExtension)
+        .platforms(Platforms::linux())
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    let content = std::fs::read_to_string(
+        gdextension_proj_dir
+            .path()
+            .join("native/linux-test.gdextension"),
+    )
+    .unwrap();
+
+    let arm64 = content
+        .find("linux.arm64 = ")
+        .expect("Should contain the arm64 entry");
+    let x86_64 = content
+        .find("linux.x86_64 = ")
+        .expect("Should contain the x86_64 entry");
+    assert!(arm64 < x86_64);
+    assert!(content.contains(
+        "linux.x86_32 = \"res://target/i686-unknown-linux-gnu/release/liblinux_test.so\""
+    ));
+}
+
+#[test]
+fn gdnlib_macos_entries() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    Generator::new()
+        .lib_name("macos-test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .build_mode(BuildMode::Release)
+        .platforms(Platforms::macos())
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    let content =
+        std::fs::read_to_string(godot_proj_dir.path().join("native/macos-test.gdnlib")).unwrap();
+
+    let arm64 = content
+        .find("OSX.arm64=\"res://target/aarch64-apple-darwin/release/libmacos_test.dylib\"")
+        .expect("Should contain the arm64 entry");
+    let x86_64 = content
+        .find("OSX.64=\"res://target/release/libmacos_test.dylib\"")
+        .expect("Should contain the x86_64 entry");
+    assert!(arm64 < x86_64);
+
+    let universal_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = universal_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    Generator::new()
+        .lib_name("macos-test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&universal_proj_dir)
+        .build_mode(BuildMode::Release)
+        .platforms(Platforms::macos())
+        .macos_lib_layout(MacosLibLayout::Universal)
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    let content =
+        std::fs::read_to_string(universal_proj_dir.path().join("native/macos-test.gdnlib"))
+            .unwrap();
+
+    assert!(content.contains(
+        "OSX.arm64=\"res://target/universal2-apple-darwin/release/libmacos_test.dylib\""
+    ));
+    assert!(content
+        .contains("OSX.64=\"res://target/universal2-apple-darwin/release/libmacos_test.dylib\""));
+}
+
+#[test]
+fn gdnlib_windows_entries() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    Generator::new()
+        .lib_name("windows-test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .build_mode(BuildMode::Debug)
+        .platforms(Platforms::windows())
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    let content =
+        std::fs::read_to_string(godot_proj_dir.path().join("native/windows-test.gdnlib")).unwrap();
+
+    assert!(
+        content.contains("Windows.32=\"res://target/i686-pc-windows-msvc/debug/windows_test.dll\"")
+    );
+    assert!(content
+        .contains("Windows.arm64=\"res://target/aarch64-pc-windows-msvc/debug/windows_test.dll\""));
+    assert!(content.contains("Windows.64=\"res://target/debug/windows_test.dll\""));
+    assert!(content.contains("Windows.32=[  ]"));
+    assert!(!content.contains("X11.64"));
+
+    let default_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = default_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    Generator::new()
+        .lib_name("windows-test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&default_proj_dir)
+        .build_mode(BuildMode::Debug)
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    let content =
+        std::fs::read_to_string(default_proj_dir.path().join("native/windows-test.gdnlib"))
+            .unwrap();
+
+    assert!(!content.contains("Windows.32"));
+    assert!(!content.contains("Windows.arm64"));
+}
+
+#[test]
+fn gdnlib_html5_entry() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    Generator::new()
+        .lib_name("web-test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .build_mode(BuildMode::Release)
+        .html5(true)
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    let content =
+        std::fs::read_to_string(godot_proj_dir.path().join("native/web-test.gdnlib")).unwrap();
+
+    assert!(content
+        .contains("HTML5.wasm32=\"res://target/wasm32-unknown-emscripten/release/web_test.wasm\""));
+    assert!(content.contains("HTML5.wasm32=[  ]"));
+    assert!(content.contains("X11.64="));
+
+    let disabled_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = disabled_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    Generator::new()
+        .lib_name("web-test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&disabled_proj_dir)
+        .build_mode(BuildMode::Release)
+        .platforms(Platforms::all())
+        .html5(false)
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    let content =
+        std::fs::read_to_string(disabled_proj_dir.path().join("native/web-test.gdnlib")).unwrap();
+
+    assert!(!content.contains("HTML5"));
+}
+
+#[test]
+fn gdnlib_server_entry() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    Generator::new()
+        .lib_name("server-test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .build_mode(BuildMode::Release)
+        .platforms(Platforms::desktop().with(Platform::Server64))
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    let content =
+        std::fs::read_to_string(godot_proj_dir.path().join("native/server-test.gdnlib")).unwrap();
+
+    assert!(content.contains("X11.64=\"res://target/release/libserver_test.so\""));
+    assert!(content.contains("Server.64=\"res://target/release/libserver_test.so\""));
+    assert!(content.contains("Server.64=[  ]"));
+}
+
+#[test]
+fn gdnlib_platform_selection() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    Generator::new()
+        .lib_name("platform_test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .build_mode(BuildMode::Debug)
+        .platforms(Platforms::desktop().without(Platform::Osx64))
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    let content =
+        std::fs::read_to_string(godot_proj_dir.path().join("native/platform_test.gdnlib")).unwrap();
+
+    assert!(content.contains("X11.64=\"res://target/debug/libplatform_test.so\""));
+    assert!(content.contains("Windows.64=\"res://target/debug/platform_test.dll\""));
+    assert!(content.contains("X11.64=[  ]"));
+    assert!(content.contains("Windows.64=[  ]"));
+    assert!(!content.contains("OSX.64"));
+    assert!(!content.contains("Android"));
+    assert!(!content.contains("iOS"));
+}
+
+#[test]
+fn gdnlib_general_settings() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    Generator::new()
+        .lib_name("general_test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .build_mode(BuildMode::Debug)
+        .singleton(true)
+        .load_once(false)
+        .symbol_prefix("mygame_")
+        .reloadable(false)
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    let content =
+        std::fs::read_to_string(godot_proj_dir.path().join("native/general_test.gdnlib")).unwrap();
+
+    assert!(content.contains("singleton=true"));
+    assert!(content.contains("load_once=false"));
+    assert!(content.contains("symbol_prefix=\"mygame_\""));
+    assert!(content.contains("reloadable=false"));
+}
+
+#[test]
+fn gdnlib_general_settings_for_build_mode() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let build = |mode| {
+        Generator::new()
+            .lib_name("general_test")
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .build_mode(mode)
+            .load_once(false)
+            .reloadable_for(BuildMode::Release, false)
+            .load_once_for(BuildMode::Release, true)
+            .singleton_for(BuildMode::Custom("profiling".to_string()), true)
+            .overwrite_policy(OverwritePolicy::IfGeneratedByUs)
+            .build(Classes::new())
+            .expect("Should generate resources");
+
+        std::fs::read_to_string(godot_proj_dir.path().join("native/general_test.gdnlib")).unwrap()
+    };
+
+    let content = build(BuildMode::Debug);
+    assert!(content.contains("singleton=false"));
+    assert!(content.contains("load_once=false"));
+    assert!(content.contains("reloadable=true"));
+
+    let content = build(BuildMode::Release);
+    assert!(content.contains("singleton=false"));
+    assert!(content.contains("load_once=true"));
+    assert!(content.contains("reloadable=false"));
+
+    let content = build(BuildMode::Custom("profiling".to_string()));
+    assert!(content.contains("singleton=true"));
+    assert!(content.contains("load_once=false"));
+    assert!(content.contains("reloadable=true"));
+
+    // an updated library resource follows the build mode as well, but keeps
+    // the settings made in the editor
+    let lib_path = godot_proj_dir.path().join("native/general_test.gdnlib");
+    let edited = std::fs::read_to_string(&lib_path)
+        .unwrap()
+        .replace("symbol_prefix=\"godot_\"", "symbol_prefix=\"edited_\"");
+    std::fs::write(&lib_path, edited).unwrap();
+
+    let update = |mode| {
+        Generator::new()
+            .lib_name("general_test")
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .build_mode(mode)
+            .reloadable_for(BuildMode::Release, false)
+            .update_existing_lib(true)
+            .build(Classes::new())
+            .expect("Should generate resources");
+
+        std::fs::read_to_string(&lib_path).unwrap()
+    };
+
+    let content = update(BuildMode::Release);
+    assert!(content.contains("reloadable=false"));
+    assert!(content.contains("singleton=true"));
+    assert!(content.contains("symbol_prefix=\"edited_\""));
+
+    let content = update(BuildMode::Debug);
+    assert!(content.contains("reloadable=true"));
+    assert!(content.contains("symbol_prefix=\"edited_\""));
+
+    // settings made for every build mode reach the library resource too
+    Generator::new()
+        .lib_name("general_test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .build_mode(BuildMode::Release)
+        .singleton(false)
+        .symbol_prefix("mygame_")
+        .load_once_for(BuildMode::Release, false)
+        .update_existing_lib(true)
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    let content = std::fs::read_to_string(&lib_path).unwrap();
+    assert!(content.contains("singleton=false"));
+    assert!(content.contains("symbol_prefix=\"mygame_\""));
+    assert!(content.contains("load_once=false"));
+}
+
+#[test]
+fn gdnlib_dependencies() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+    let bin_dir = godot_proj_dir.path().join("bin");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+    std::fs::create_dir_all(&bin_dir).unwrap();
+    std::fs::write(bin_dir.join("libsteam_api.so"), "").unwrap();
+    std::fs::write(bin_dir.join("steam_api64.dll"), "").unwrap();
+
+    let build = |output_format| {
+        Generator::new()
+            .lib_name("deps_test")
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .build_mode(BuildMode::Debug)
+            .platforms(Platforms::desktop())
+            .output_format(output_format)
+            .dependency(Platform::X11_64, "bin/libsteam_api.so")
+            .dependency(Platform::Windows64, "bin/steam_api64.dll")
+            // not selected
+            .dependency(Platform::AndroidAarch64, "bin/libsteam_api.so")
+            .build(Classes::new())
+            .expect("Should generate resources")
+    };
+
+    build(OutputFormat::Gdnative);
+    let gdnlib = std::fs::read_to_string(asset_dir.join("deps_test.gdnlib")).unwrap();
+    assert!(gdnlib.contains(
+        "[dependencies]\n\n\
+         X11.64=[ \"res://bin/libsteam_api.so\" ]\n\
+         OSX.64=[  ]\n\
+         Windows.64=[ \"res://bin/steam_api64.dll\" ]\n"
+    ));
+    assert!(!gdnlib.contains("Android"));
+
+    build(OutputFormat::GdExtension);
+    let gdextension = std::fs::read_to_string(asset_dir.join("deps_test.gdextension")).unwrap();
+    assert!(gdextension.contains(
+        "[dependencies]\n\
+         linux.x86_64 = { \"res://bin/libsteam_api.so\": \"\" }\n\
+         windows.x86_64 = { \"res://bin/steam_api64.dll\": \"\" }\n"
+    ));
+
+    // existing resources get the dependencies as well
+    std::fs::write(
+        asset_dir.join("deps_test.gdnlib"),
+        "[entry]\n\n[dependencies]\n\nX11.64=[  ]\nHaiku.64=[ \"res://custom.so\" ]\n",
+    )
+    .unwrap();
+    let report = Generator::new()
+        .lib_name("deps_test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .build_mode(BuildMode::Debug)
+        .platforms(Platforms::linux())
+        .dependency(Platform::X11_64, "bin/libsteam_api.so")
+        .update_existing_lib(true)
+        .build(Classes::new())
+        .expect("Should generate resources");
+    assert_eq!(report.updated().count(), 1);
+    let gdnlib = std::fs::read_to_string(asset_dir.join("deps_test.gdnlib")).unwrap();
+    assert!(gdnlib.contains("X11.64=[ \"res://bin/libsteam_api.so\" ]\n"));
+    assert!(gdnlib.contains("Haiku.64=[ \"res://custom.so\" ]\n"));
+}
+
+#[test]
+fn gdnlib_update_existing() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&asset_dir).unwrap();
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let gdnlib_path = asset_dir.join("update_test.gdnlib");
+    std::fs::write(
+        &gdnlib_path,
+        r#"[entry]
+X11.64="res://old/libupdate_test.so"
+Haiku.64="res://custom/libupdate_test.so"
+
+[dependencies]
+
+X11.64=[ "res://native/libsteam_api.so" ]
+
+[general]
+
+singleton=false
+load_once=true
+symbol_prefix="godot_"
+reloadable=true
+"#,
+    )
+    .unwrap();
+
+    let build = |update| {
+        Generator::new()
+            .lib_name("update_test")
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .build_mode(BuildMode::Debug)
+            .platforms(Platforms::desktop())
+            .update_existing_lib(update)
+            .build(Classes::new())
+            .expect("Should generate resources");
+
+        std::fs::read_to_string(&gdnlib_path).unwrap()
+    };
+
+    // existing files are left untouched by default
+    assert!(build(false).contains("X11.64=\"res://old/libupdate_test.so\""));
+
+    let content = build(true);
+
+    assert_eq!(
+        content,
+        r#"[entry]
+X11.64="res://target/debug/libupdate_test.so"
+Haiku.64="res://custom/libupdate_test.so"
+OSX.64="res://target/debug/libupdate_test.dylib"
+Windows.64="res://target/debug/update_test.dll"
+
+[dependencies]
+
+X11.64=[ "res://native/libsteam_api.so" ]
+
+[general]
+
+singleton=false
+load_once=true
+symbol_prefix="godot_"
+reloadable=true
+"#
+    );
+}
+
+#[test]
+fn gdnlib_installed_targets() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&asset_dir).unwrap();
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let report = Generator::new()
+        .lib_name("installed_test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .build_mode(BuildMode::Debug)
+        .platforms(Platforms::all())
+        .installed_targets(["x86_64-unknown-linux-gnu", "aarch64-linux-android"])
+        .override_binary(Platform::Windows64, "bin/installed_test.dll")
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    let content = std::fs::read_to_string(asset_dir.join("installed_test.gdnlib")).unwrap();
+    assert!(content.contains("X11.64=\"res://target/debug/libinstalled_test.so\""));
+    assert!(content.contains("Server.64=\"res://target/debug/libinstalled_test.so\""));
+    assert!(content.contains(
+        "Android.arm64-v8a=\"res://target/aarch64-linux-android/debug/libinstalled_test.so\""
+    ));
+    assert!(content.contains("Windows.64=\"res://bin/installed_test.dll\""));
+    assert!(!content.contains("Android.armeabi-v7a"));
+    assert!(!content.contains("OSX.64"));
+
+    // the left out entries are reported
+    assert!(report.uninstalled_platforms.contains(&Platform::Osx64));
+    assert!(!report.uninstalled_platforms.contains(&Platform::X11_64));
+    assert!(!report.uninstalled_platforms.contains(&Platform::Windows64));
+    let mut warnings = vec![];
+    report.emit_diagnostics(&mut |message: &str| warnings.push(message.to_string()));
+    assert!(warnings.contains(
+        &"The `OSX.64` library entry was left out, no target for it is installed".to_string()
+    ));
+}
+
+#[test]
+fn gdnlib_android_abis() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&asset_dir).unwrap();
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    Generator::new()
+        .lib_name("android_test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .build_mode(BuildMode::Debug)
+        .platforms(Platforms::common())
+        .android_abis(
+            Platforms::new()
+                .with(Platform::AndroidAarch64)
+                .with(Platform::AndroidX86_64),
+        )
+        .android_lib_layout(AndroidLibLayout::AbiDirectories("android/jniLibs".into()))
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    let content = std::fs::read_to_string(asset_dir.join("android_test.gdnlib")).unwrap();
+    assert!(content
+        .contains("Android.arm64-v8a=\"res://android/jniLibs/arm64-v8a/libandroid_test.so\""));
+    assert!(content.contains("Android.x86_64=\"res://android/jniLibs/x86_64/libandroid_test.so\""));
+    assert!(!content.contains("Android.armeabi-v7a"));
+    assert!(!content.contains("Android.x86="));
+    // the other platforms are not affected
+    assert!(content.contains("X11.64=\"res://target/debug/libandroid_test.so\""));
+}
+
+#[test]
+fn gdnlib_host_only() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&asset_dir).unwrap();
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let gdnlib_path = asset_dir.join("host_test.gdnlib");
+    let build = |update| {
+        Generator::new()
+            .lib_name("host_test")
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .build_mode(BuildMode::Debug)
+            .target_triple("x86_64-unknown-linux-gnu")
+            .host_only(true)
+            .update_existing_lib(update)
+            .build(Classes::new())
+            .expect("Should generate resources");
+
+        std::fs::read_to_string(&gdnlib_path).unwrap()
+    };
+
+    let content = build(false);
+    assert!(
+        content.contains("X11.64=\"res://target/x86_64-unknown-linux-gnu/debug/libhost_test.so\"")
+    );
+    assert!(!content.contains("Windows.64"));
+    assert!(!content.contains("Android"));
+
+    // other entries of an existing library are kept
+    std::fs::write(
+        &gdnlib_path,
+        "[entry]\nX11.64=\"res://old/libhost_test.so\"\nWindows.64=\"res://win/host_test.dll\"\n",
+    )
+    .unwrap();
+    let content = build(true);
+    assert!(
+        content.contains("X11.64=\"res://target/x86_64-unknown-linux-gnu/debug/libhost_test.so\"")
+    );
+    assert!(content.contains("Windows.64=\"res://win/host_test.dll\""));
+}
+
+#[test]
+fn gdns_repair() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+    let levels_dir = godot_proj_dir.path().join("levels");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+    std::fs::create_dir_all(&levels_dir).unwrap();
+
+    let generator = |output_dir: &str, lib_name: &str| {
+        let output_dir = godot_proj_dir.path().join(output_dir);
+        std::fs::create_dir_all(&output_dir).unwrap();
+
+        Generator::new()
+            .lib_name(lib_name)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .godot_resource_output_dir(output_dir)
+            .build_mode(BuildMode::Debug)
+    };
+
+    generator("native", "repair_test")
+        .build(vec!["Player".to_string()].into_iter().collect())
+        .expect("Should generate resources");
+    let gdns = |class: &str, library: &str| {
+        render_gdns(&GdnsConfig {
+            class_name: class.to_string(),
+            library: library.to_string(),
+        })
+    };
+    std::fs::write(
+        levels_dir.join("Door.gdns"),
+        gdns("Door", "res://native/repair_test.gdnlib"),
+    )
+    .unwrap();
+    std::fs::write(
+        levels_dir.join("Other.gdns"),
+        gdns("Other", "res://other/other.gdnlib"),
+    )
+    .unwrap();
+    // an addon with a library of the same name
+    let addon_dir = godot_proj_dir.path().join("addons/thing");
+    std::fs::create_dir_all(&addon_dir).unwrap();
+    std::fs::write(addon_dir.join("repair_test.gdnlib"), "[entry]\n").unwrap();
+    let thing = gdns("Thing", "res://addons/thing/repair_test.gdnlib");
+    std::fs::write(addon_dir.join("Thing.gdns"), &thing).unwrap();
+
+    // the resource output directory moved
+    let repaired = generator("scripts", "repair_test")
+        .repair(RepairScope::Project)
+        .expect("Should repair");
+    assert_eq!(repaired.len(), 2);
+    let thing_content = std::fs::read_to_string(addon_dir.join("Thing.gdns")).unwrap();
+    assert_eq!(thing_content, thing);
+    // files that were not generated are backed up
+    assert!(levels_dir.join("Door.gdns.bak").is_file());
+    assert!(!godot_proj_dir
+        .path()
+        .join("native/Player.gdns.bak")
+        .exists());
+
+    let player = std::fs::read_to_string(godot_proj_dir.path().join("native/Player.gdns")).unwrap();
+    assert_eq!(player, gdns("Player", "res://scripts/repair_test.gdnlib"));
+    let door = std::fs::read_to_string(levels_dir.join("Door.gdns")).unwrap();
+    assert_eq!(door, gdns("Door", "res://scripts/repair_test.gdnlib"));
+    let other = std::fs::read_to_string(levels_dir.join("Other.gdns")).unwrap();
+    assert_eq!(other, gdns("Other", "res://other/other.gdnlib"));
+
+    // the library was renamed, only the output directory is searched
+    std::fs::remove_file(godot_proj_dir.path().join("native/repair_test.gdnlib")).unwrap();
+    std::fs::write(
+        levels_dir.join("Door.gdns"),
+        gdns("Door", "res://native/repair_test.gdnlib"),
+    )
+    .unwrap();
+
+    let repaired = generator("native", "renamed")
+        .repair(RepairScope::OutputDir)
+        .expect("Should repair");
+    assert_eq!(
+        repaired,
+        [(
+            godot_proj_dir
+                .path()
+                .join("native/Player.gdns")
+                .canonicalize()
+                .unwrap(),
+            FileAction::Updated
+        )]
+    );
+    let player = std::fs::read_to_string(godot_proj_dir.path().join("native/Player.gdns")).unwrap();
+    assert_eq!(player, gdns("Player", "res://native/renamed.gdnlib"));
+    let door = std::fs::read_to_string(levels_dir.join("Door.gdns")).unwrap();
+    assert_eq!(door, gdns("Door", "res://native/repair_test.gdnlib"));
+
+    // the repaired file is still recognized as generated
+    let cleaned = generator("native", "renamed")
+        .clean()
+        .expect("Should clean");
+    assert!(cleaned
+        .iter()
+        .any(|(path, action)| path.ends_with("Player.gdns") && *action == FileAction::Removed));
+}
+
+#[test]
+fn gdns_prune_stale() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&asset_dir).unwrap();
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    // hand-made, must never be removed
+    std::fs::write(asset_dir.join("Custom.gdns"), "").unwrap();
+
+    let build = |classes: &[&str]| {
+        Generator::new()
+            .lib_name("prune_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .prune_stale(true)
+            .build(classes.iter().map(|c| c.to_string()).collect())
+            .expect("Should generate resources");
+    };
+
+    build(&["Player", "Enemy"]);
+    assert!(asset_dir.join("Player.gdns").is_file());
+    assert!(asset_dir.join("Enemy.gdns").is_file());
+
+    // `Enemy` was renamed
+    build(&["Player", "Slime"]);
+    assert!(asset_dir.join("Player.gdns").is_file());
+    assert!(asset_dir.join("Slime.gdns").is_file());
+    assert!(!asset_dir.join("Enemy.gdns").exists());
+    assert!(asset_dir.join("Custom.gdns").is_file());
+}
+
+#[test]
+fn class_filters() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let classes: Classes = vec![
+        Class::new("Player"),
+        Class::new("DebugOverlay"),
+        nested_class("Dummy", &["tests"]),
+        Class::new("Slime"),
+    ]
+    .into_iter()
+    .collect();
+
+    let build = |generator: Generator| {
+        generator
+            .lib_name("filter_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .prune_stale(true)
+            .build(classes.clone())
+            .expect("Should generate resources")
+    };
+
+    build(Generator::new().exclude_classes(["Debug*", "crate::tests::*"]));
+    assert!(asset_dir.join("Player.gdns").is_file());
+    assert!(asset_dir.join("Slime.gdns").is_file());
+    assert!(!asset_dir.join("DebugOverlay.gdns").exists());
+    assert!(!asset_dir.join("Dummy.gdns").exists());
+
+    // filtered classes are treated as removed
+    let report = build(
+        Generator::new()
+            .include_classes(["P*", "S?ime"])
+            .filter_classes(|class| class.name != "Slime"),
+    );
+    assert_eq!(report.removed().count(), 1);
+    assert!(asset_dir.join("Player.gdns").is_file());
+    assert!(!asset_dir.join("Slime.gdns").exists());
+    assert!(!asset_dir.join("DebugOverlay.gdns").exists());
+}
+
+#[test]
+fn multiple_libraries() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+    std::fs::write(godot_proj_dir.path().join("project.godot"), "").unwrap();
+
+    let classes = |classes: &[&str]| -> Classes { classes.iter().map(|c| c.to_string()).collect() };
+
+    let build = |game: &[&str], tools: &[&str]| {
+        Generator::new()
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .prune_stale(true)
+            .overwrite_policy(OverwritePolicy::IfGeneratedByUs)
+            .global_script_classes(true)
+            .build_libraries(vec![
+                ("game", classes(game)),
+                ("editor_tools", classes(tools)),
+            ])
+            .expect("Should generate resources")
+    };
+
+    let reports = build(&["Player", "Enemy"], &["Gizmo"]);
+    assert_eq!(reports.len(), 2);
+    assert_eq!(reports[1].lib_path, asset_dir.join("editor_tools.gdnlib"));
+
+    assert!(asset_dir.join("game.gdnlib").is_file());
+    assert!(asset_dir.join("editor_tools.gdnlib").is_file());
+
+    let player = std::fs::read_to_string(asset_dir.join("Player.gdns")).unwrap();
+    assert!(player.contains("path=\"res://native/game.gdnlib\""));
+    let gizmo = std::fs::read_to_string(asset_dir.join("Gizmo.gdns")).unwrap();
+    assert!(gizmo.contains("path=\"res://native/editor_tools.gdnlib\""));
+
+    // `Enemy` moved to the tools, `Gizmo` was removed
+    build(&["Player"], &["Enemy"]);
+    assert!(asset_dir.join("Player.gdns").is_file());
+    assert!(!asset_dir.join("Gizmo.gdns").exists());
+    let enemy = std::fs::read_to_string(asset_dir.join("Enemy.gdns")).unwrap();
+    assert!(enemy.contains("path=\"res://native/editor_tools.gdnlib\""));
+
+    // the classes of both libraries are registered
+    let project = std::fs::read_to_string(godot_proj_dir.path().join("project.godot")).unwrap();
+    assert!(project.contains("\"path\": \"res://native/Player.gdns\""));
+    assert!(project.contains("\"path\": \"res://native/Enemy.gdns\""));
+    assert!(!project.contains("Gizmo"));
+}
+
+#[test]
+fn gdns_module_tree() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let class = nested_class;
+
+    let build = |classes: Classes| {
+        Generator::new()
+            .lib_name("tree_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .layout(OutputLayout::ModuleTree)
+            .prune_stale(true)
+            .build(classes)
+            .expect("Should generate resources")
+    };
+
+    build(
+        vec![
+            class("Player", &[]),
+            class("Slime", &["enemies"]),
+            class("Dragon", &["enemies", "bosses"]),
+        ]
+        .into_iter()
+        .collect(),
+    );
+
+    assert!(asset_dir.join("Player.gdns").is_file());
+    assert!(asset_dir.join("enemies/Slime.gdns").is_file());
+    let dragon = std::fs::read_to_string(asset_dir.join("enemies/bosses/Dragon.gdns")).unwrap();
+    assert!(dragon.contains("path=\"res://native/tree_test.gdnlib\""));
+
+    let report = build(vec![class("Player", &[])].into_iter().collect());
+    assert_eq!(report.removed().count(), 2);
+    assert!(!asset_dir.join("enemies/Slime.gdns").exists());
+}
+
+#[test]
+fn gdns_path_override() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let mut slime = nested_class("Slime", &["enemies"]);
+    slime.gdns_path = Some("mobs/green_slime.gdns".to_string());
+
+    let classes = vec![slime, nested_class("Bat", &["enemies"])]
+        .into_iter()
+        .collect();
+
+    Generator::new()
+        .lib_name("override_test")
+        .build_mode(BuildMode::Debug)
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .layout(OutputLayout::ModuleTree)
+        .naming_convention(NamingConvention::SnakeCase)
+        .gdscript_stubs(true)
+        .build(classes)
+        .expect("Should generate resources");
+
+    // the path is used as it is, everything else follows the convention
+    assert!(asset_dir.join("mobs/green_slime.gdns").is_file());
+    assert!(asset_dir.join("mobs/green_slime.stub.gd").is_file());
+    assert!(!asset_dir.join("enemies/slime.gdns").exists());
+    assert!(asset_dir.join("enemies/bat.gdns").is_file());
+}
+
+#[test]
+fn gdns_naming_convention() {
+    let build = |naming: NamingConvention| {
+        let godot_proj_dir = tempfile::tempdir().unwrap();
+        let target_dir = godot_proj_dir.path().join("target");
+
+        std::fs::create_dir_all(&target_dir).unwrap();
+
+        let report = Generator::new()
+            .lib_name("naming_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .naming_convention(naming)
+            .build(
+                vec!["PlayerController", "HTTPServer", "Node2D"]
+                    .into_iter()
+                    .collect(),
+            )
+            .expect("Should generate resources");
+
+        let mut names = report
+            .created()
+            .filter_map(|path| path.file_name()?.to_str())
+            .filter(|name| name.ends_with(".gdns"))
+            .map(String::from)
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    };
+
+    assert_eq!(
+        build(NamingConvention::PascalCase),
+        ["HTTPServer.gdns", "Node2D.gdns", "PlayerController.gdns"]
+    );
+    assert_eq!(
+        build(NamingConvention::SnakeCase),
+        ["http_server.gdns", "node2d.gdns", "player_controller.gdns"]
+    );
+    assert_eq!(
+        build(
+            NamingConvention::SnakeCase
+                .with_prefix("rs_")
+                .with_suffix("_script")
+        ),
+        [
+            "rs_http_server_script.gdns",
+            "rs_node2d_script.gdns",
+            "rs_player_controller_script.gdns"
+        ]
+    );
+    assert_eq!(
+        build(NamingConvention::custom(|name| name.to_lowercase())),
+        ["httpserver.gdns", "node2d.gdns", "playercontroller.gdns"]
+    );
+}
+
+#[test]
+fn manifest_lists_generated_files() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&asset_dir).unwrap();
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    // hand-made, must not be listed
+    std::fs::write(asset_dir.join("Custom.gdns"), "").unwrap();
+
+    Generator::new()
+        .lib_name("manifest_test")
+        .build_mode(BuildMode::Debug)
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .build(
+            vec!["Player".to_string(), "Custom".to_string()]
+                .into_iter()
+                .collect(),
+        )
+        .expect("Should generate resources");
+
+    let manifest = std::fs::read_to_string(asset_dir.join(".gdnative-utils.cfg")).unwrap();
+
+    assert!(manifest.contains("\nmanifest_test.gdnlib=\"fnv1a64:"));
+    assert!(manifest.contains("\nPlayer.gdns=\"fnv1a64:"));
+    assert!(!manifest.contains("Custom.gdns"));
+}
+
+#[test]
+fn overwrite_policy() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+
+    std::fs::create_dir_all(&asset_dir).unwrap();
+
+    let build = |target: &str, policy| {
+        let target_dir = godot_proj_dir.path().join(target);
+        std::fs::create_dir_all(&target_dir).unwrap();
+
+        Generator::new()
+            .lib_name("policy_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .overwrite_policy(policy)
+            .build(
+                vec!["Player".to_string(), "Enemy".to_string()]
+                    .into_iter()
+                    .collect(),
+            )
+            .expect("Should generate resources");
+    };
+    let read = |file: &str| std::fs::read_to_string(asset_dir.join(file)).unwrap();
+
+    build("target", OverwritePolicy::Never);
+    assert!(read("policy_test.gdnlib").contains("res://target/debug/"));
+
+    // the target dir moved
+    build("build", OverwritePolicy::Never);
+    assert!(read("policy_test.gdnlib").contains("res://target/debug/"));
+
+    std::fs::write(asset_dir.join("Enemy.gdns"), "hand-edited").unwrap();
+
+    build("build", OverwritePolicy::IfGeneratedByUs);
+    assert!(read("policy_test.gdnlib").contains("res://build/debug/"));
+    assert_eq!(read("Enemy.gdns"), "hand-edited");
+
+    build("build", OverwritePolicy::IfContentDiffers);
+    assert!(read("Enemy.gdns").contains("class_name = \"Enemy\""));
+}
+
+#[test]
+fn unchanged_files_are_not_rewritten() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let build = || {
+        Generator::new()
+            .lib_name("unchanged_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .overwrite_policy(OverwritePolicy::IfContentDiffers)
+            .update_existing_lib(true)
+            .build(vec!["Player".to_string()].into_iter().collect())
+            .expect("Should generate resources");
+    };
+
+    build();
+
+    let past = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+    for file in &["unchanged_test.gdnlib", "Player.gdns"] {
+        let file = std::fs::File::options()
+            .write(true)
+            .open(asset_dir.join(file))
+            .unwrap();
+        file.set_modified(past).unwrap();
+    }
+
+    build();
+
+    for file in &["unchanged_test.gdnlib", "Player.gdns"] {
+        let modified = std::fs::metadata(asset_dir.join(file))
+            .unwrap()
+            .modified()
+            .unwrap();
+        assert_eq!(modified, past, "{} should not be rewritten", file);
+    }
+}
+
+#[test]
+fn build_report() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&asset_dir).unwrap();
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    std::fs::write(asset_dir.join("Enemy.gdns"), "hand-made").unwrap();
+
+    let build = || {
+        Generator::new()
+            .lib_name("report_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .build(
+                vec!["Player".to_string(), "Enemy".to_string()]
+                    .into_iter()
+                    .collect(),
+            )
+            .expect("Should generate resources")
+    };
+
+    let report = build();
+    let asset_dir = report.godot_resource_output_dir.clone();
+
+    assert_eq!(report.binary_path_prefix, "res://");
+    assert_eq!(
+        report.lib_resource_path.as_deref(),
+        Some("res://native/report_test.gdnlib")
+    );
+    assert_eq!(report.lib_path, asset_dir.join("report_test.gdnlib"));
+
+    let mut created = report.created().collect::<Vec<_>>();
+    created.sort();
+    assert_eq!(
+        created,
+        vec![
+            asset_dir.join("Player.gdns"),
+            asset_dir.join("report_test.gdnlib")
+        ]
+    );
+    assert_eq!(
+        report.skipped().collect::<Vec<_>>(),
+        vec![asset_dir.join("Enemy.gdns")]
+    );
+
+    let report = build();
+    assert_eq!(report.created().count(), 0);
+    assert_eq!(report.unchanged().count(), 2);
+}
+
+#[test]
+fn dry_run() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let planned = Generator::new()
+        .lib_name("dry_run_test")
+        .build_mode(BuildMode::Debug)
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .dry_run(vec!["Player".to_string()].into_iter().collect())
+        .expect("Should plan resources");
+
+    // nothing was touched, not even the output directory
+    assert!(!godot_proj_dir.path().join("native").exists());
+
+    assert_eq!(planned.len(), 2);
+    assert!(planned
+        .iter()
+        .all(|write| write.action == FileAction::Created));
+
+    let gdns = planned
+        .iter()
+        .find(|write| write.path.ends_with("native/Player.gdns"))
+        .expect("Should plan the gdns file");
+    assert!(gdns
+        .content
+        .contains("path=\"res://native/dry_run_test.gdnlib\""));
+}
+
+#[test]
+fn build_to_map() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+    let asset_dir = godot_proj_dir.path().join("native");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+    std::fs::create_dir_all(&asset_dir).unwrap();
+    // files on disk do not change the rendered content
+    std::fs::write(asset_dir.join("Player.gdns"), "modified").unwrap();
+
+    let files = Generator::new()
+        .lib_name("map_test")
+        .build_mode(BuildMode::Debug)
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .build_to_map(vec!["Player".to_string()].into_iter().collect())
+        .expect("Should render resources");
+
+    assert_eq!(
+        std::fs::read_to_string(asset_dir.join("Player.gdns")).unwrap(),
+        "modified"
+    );
+    assert!(!asset_dir.join("map_test.gdnlib").exists());
+
+    assert_eq!(files.len(), 2);
+    let gdns = files
+        .iter()
+        .find(|(path, _)| path.ends_with("native/Player.gdns"))
+        .map(|(_, content)| content)
+        .expect("Should render the gdns file");
+    assert_eq!(
+        gdns,
+        &render_gdns(&GdnsConfig {
+            class_name: "Player".to_string(),
+            library: "res://native/map_test.gdnlib".to_string(),
+        })
+    );
+}
+
+#[test]
+fn render() {
+    let mut config = GdnlibConfig::default();
+    config
+        .entries
+        .insert(Platform::X11_64, "res://bin/libgame.so".to_string());
+    config.symbol_prefix = "game_".to_string();
+
+    let gdnlib = render_gdnlib(&config);
+    assert!(gdnlib.starts_with("[entry]\n"));
+    assert!(gdnlib.contains("X11.64=\"res://bin/libgame.so\"\n"));
+    assert!(gdnlib.contains("symbol_prefix=\"game_\"\n"));
+    assert!(gdnlib.contains("reloadable=true"));
+
+    let gdns = render_gdns(&GdnsConfig {
+        class_name: "Player".to_string(),
+        library: "res://native/game.gdnlib".to_string(),
+    });
+    assert!(gdns.contains(
+        "[ext_resource path=\"res://native/game.gdnlib\" type=\"GDNativeLibrary\" id=1]"
+    ));
+    assert!(gdns.contains("class_name = \"Player\"\n"));
+}
+
+#[test]
+fn build_with_memory_sink() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let generator = |policy| {
+        Generator::new()
+            .lib_name("sink_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .overwrite_policy(policy)
+    };
+    let classes = || -> Classes { vec!["Player".to_string()].into_iter().collect() };
+
+    let mut sink = MemorySink::new();
+    let report = generator(OverwritePolicy::IfGeneratedByUs)
+        .build_with_sink(classes(), &mut sink)
+        .expect("Should generate resources");
+
+    // nothing was written to the filesystem
+    assert!(!godot_proj_dir.path().join("native").exists());
+
+    let output_dir = report.godot_resource_output_dir;
+    let gdns_path = output_dir.join("Player.gdns");
+    assert!(sink
+        .get(&gdns_path)
+        .unwrap()
+        .contains("class_name = \"Player\""));
+    assert!(sink.get(output_dir.join("sink_test.gdnlib")).is_some());
+    assert!(sink.get(output_dir.join(".gdnative-utils.cfg")).is_some());
+    assert_eq!(sink.len(), 3);
+
+    // files modified by the user are kept
+    sink.insert(&gdns_path, "modified");
+    let report = generator(OverwritePolicy::IfGeneratedByUs)
+        .build_with_sink(classes(), &mut sink)
+        .unwrap();
+    assert!(report.skipped().any(|path| path == gdns_path));
+    assert_eq!(sink.get(&gdns_path), Some("modified"));
+
+    let report = generator(OverwritePolicy::IfContentDiffers)
+        .build_with_sink(classes(), &mut sink)
+        .unwrap();
+    assert!(report.updated().any(|path| path == gdns_path));
+    assert_ne!(sink.get(&gdns_path), Some("modified"));
+}
+
+#[test]
+fn templates() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let generator = || {
+        Generator::new()
+            .lib_name("template_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .platforms(Platforms::new().with(Platform::X11_64))
+            .symbol_prefix("game_")
+    };
+
+    let files = generator()
+        .gdnlib_template(
+            "[entry]\n{entries}\n[general]\n\nsymbol_prefix=\"{symbol_prefix}\"\n\
+             ; linux: {entry.X11.64}, windows: {entry.Windows.64}\n",
+        )
+        .gdns_template(
+            "{class_name} extends {base} in {gdnlib_path} of {lib_name}\n\
+             [editor]\nmetadata={{ }}\n",
+        )
+        .build_to_map(
+            vec![derived_class("Player", "Node2D")]
+                .into_iter()
+                .collect(),
+        )
+        .expect("Should render resources");
+
+    let file = |name: &str| {
+        files
+            .iter()
+            .find(|(path, _)| path.ends_with(name))
+            .map(|(_, content)| content.as_str())
+            .unwrap()
+    };
+
+    assert_eq!(
+        file("native/template_test.gdnlib"),
+        "[entry]\nX11.64=\"res://target/debug/libtemplate_test.so\"\n\n\
+         [general]\n\nsymbol_prefix=\"game_\"\n\
+         ; linux: res://target/debug/libtemplate_test.so, windows: \n"
+    );
+    assert_eq!(
+        file("native/Player.gdns"),
+        "Player extends Node2D in res://native/template_test.gdnlib of template_test\n\
+         [editor]\nmetadata={ }\n"
+    );
+
+    for template in &["{unknown}", "{entry.Nintendo64}", "{class_name"] {
+        let err = generator()
+            .gdns_template(template)
+            .gdnlib_template(template)
+            .build_to_map(vec!["Player".to_string()].into_iter().collect())
+            .unwrap_err();
+        assert!(matches!(err, GenerateError::InvalidTemplate { .. }));
+    }
+}
+
+#[test]
+fn hooks() {
+    use std::sync::{Arc, Mutex};
+
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let written = Arc::new(Mutex::new(vec![]));
+    let skipped = Arc::new(Mutex::new(vec![]));
+
+    let build = |classes: &[&str]| {
+        let (written, skipped) = (written.clone(), skipped.clone());
+        Generator::new()
+            .lib_name("hook_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .prune_stale(true)
+            .on_file_written(move |path, action| {
+                let name = path.file_name().unwrap().to_string_lossy().into_owned();
+                written.lock().unwrap().push((name, action));
+            })
+            .on_skip(move |path| skipped.lock().unwrap().push(path.to_path_buf()))
+            .build(classes.iter().map(|class| class.to_string()).collect())
+            .expect("Should generate resources")
+    };
+
+    build(&["Player", "Slime"]);
+    assert_eq!(
+        written.lock().unwrap().drain(..).collect::<Vec<_>>(),
+        vec![
+            ("hook_test.gdnlib".to_string(), FileAction::Created),
+            ("Player.gdns".to_string(), FileAction::Created),
+            ("Slime.gdns".to_string(), FileAction::Created),
+        ]
+    );
+
+    let slime = godot_proj_dir.path().join("native/Slime.gdns");
+    std::fs::write(&slime, "modified").unwrap();
+
+    // unchanged files are not reported
+    build(&["Player", "Slime"]);
+    assert!(written.lock().unwrap().is_empty());
+    assert_eq!(skipped.lock().unwrap().len(), 1);
+    assert!(skipped.lock().unwrap()[0].ends_with("native/Slime.gdns"));
+
+    build(&["Player"]);
+    assert_eq!(
+        *written.lock().unwrap(),
+        vec![("Slime.gdns".to_string(), FileAction::Removed)]
+    );
+}
+
+#[test]
+fn diagnostics() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let build = || {
+        Generator::new()
+            .lib_name("diagnostics_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .platforms(Platforms::all())
+            .build(vec!["Player".to_string()].into_iter().collect())
+            .expect("Should generate resources")
+    };
+    let warnings = |report: &Report| {
+        let mut warnings = vec![];
+        report.emit_diagnostics(&mut |message: &str| warnings.push(message.to_string()));
+        warnings
+    };
+
+    // the library has not been built
+    let report = build();
+    let warnings_before = warnings(&report);
+    assert_eq!(warnings_before.len(), 1);
+    assert!(warnings_before[0].contains("diagnostics_test"));
+
+    let gdns_path = godot_proj_dir.path().join("native/Player.gdns");
+    std::fs::write(&gdns_path, "modified").unwrap();
+
+    let report = build();
+    let warnings = warnings(&report);
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[1].contains("Player.gdns` was modified"));
+}
+
+#[test]
+fn deterministic_output() {
+    let build = |names: &[&str]| {
+        let godot_proj_dir = tempfile::tempdir().unwrap();
+        let target_dir = godot_proj_dir.path().join("target");
+
+        std::fs::create_dir_all(&target_dir).unwrap();
+        std::fs::write(
+            godot_proj_dir.path().join("project.godot"),
+            "config_version=4\n",
+        )
+        .unwrap();
+
+        let report = Generator::new()
+            .lib_name("deterministic_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .platforms(Platforms::all())
+            .gdscript_stubs(true)
+            .global_script_classes(true)
+            .build(names.iter().map(|name| name.to_string()).collect())
+            .expect("Should generate resources");
+
+        report
+            .files
+            .iter()
+            .map(|(path, _)| {
+                let rel_path = path.strip_prefix(&report.godot_project_dir).unwrap();
+                (rel_path.to_path_buf(), std::fs::read(path).unwrap())
+            })
+            .chain(std::iter::once((
+                ".gdnative-utils.cfg".into(),
+                std::fs::read(report.godot_resource_output_dir.join(".gdnative-utils.cfg"))
+                    .unwrap(),
+            )))
+            .collect::<Vec<_>>()
+    };
+
+    let first = build(&["Slime", "Player", "Bat"]);
+    let second = build(&["Bat", "Slime", "Player"]);
+
+    assert_eq!(first, second);
+    let names = first
+        .iter()
+        .map(|(path, _)| path.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "native/deterministic_test.gdnlib",
+            "native/Bat.stub.gd",
+            "native/Bat.gdns",
+            "native/Player.stub.gd",
+            "native/Player.gdns",
+            "native/Slime.stub.gd",
+            "native/Slime.gdns",
+            "project.godot",
+            ".gdnative-utils.cfg",
+        ]
+    );
+}
+
+#[test]
+fn atomic_writes() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let build = |classes: &[&str]| {
+        Generator::new()
+            .lib_name("atomic_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .overwrite_policy(OverwritePolicy::IfContentDiffers)
+            .sync_writes(true)
+            .backups(false)
+            .build(classes.iter().map(|class| class.to_string()).collect())
+    };
+    let files = || {
+        let mut files = std::fs::read_dir(&asset_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    };
+
+    build(&["Player"]).expect("Should generate resources");
+    std::fs::write(asset_dir.join("Player.gdns"), "modified").unwrap();
+    build(&["Player"]).expect("Should generate resources");
+
+    // no temporary files are left behind
+    assert_eq!(
+        files(),
+        [".gdnative-utils.cfg", "Player.gdns", "atomic_test.gdnlib"]
+    );
+    assert!(std::fs::read_to_string(asset_dir.join("Player.gdns"))
+        .unwrap()
+        .contains("class_name = \"Player\""));
+
+    // a failed write keeps neither the new nor a temporary file
+    std::fs::create_dir(asset_dir.join("Slime.gdns")).unwrap();
+    std::fs::write(asset_dir.join("Slime.gdns/keep"), "").unwrap();
+    assert!(matches!(
+        build(&["Player", "Slime"]),
+        Err(GenerateError::Io(_))
+    ));
+    assert_eq!(
+        files(),
+        [
+            ".gdnative-utils.cfg",
+            "Player.gdns",
+            "Slime.gdns",
+            "atomic_test.gdnlib"
+        ]
+    );
+}
+
+#[test]
+fn backups() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let build = |classes: &[&str], backups: bool| {
+        Generator::new()
+            .lib_name("backup_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .overwrite_policy(OverwritePolicy::IfContentDiffers)
+            .prune_stale(true)
+            .backups(backups)
+            .build(classes.iter().map(|class| class.to_string()).collect())
+            .expect("Should generate resources")
+    };
+
+    let report = build(&["Player", "Slime"], true);
+    assert!(report.backups.is_empty());
+
+    // unmodified files are overwritten without a backup
+    let report = Generator::new()
+        .lib_name("backup_test")
+        .build_mode(BuildMode::Release)
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .overwrite_policy(OverwritePolicy::IfContentDiffers)
+        .build(
+            vec!["Player".to_string(), "Slime".to_string()]
+                .into_iter()
+                .collect(),
+        )
+        .unwrap();
+    assert!(report.updated().count() > 0);
+    assert!(report.backups.is_empty());
+
+    std::fs::write(asset_dir.join("Player.gdns"), "player edits").unwrap();
+    std::fs::write(asset_dir.join("Slime.gdns"), "slime edits").unwrap();
+
+    let report = build(&["Player"], true);
+    assert_eq!(report.backups.len(), 2);
+    assert_eq!(
+        std::fs::read_to_string(asset_dir.join("Player.gdns.bak")).unwrap(),
+        "player edits"
+    );
+    assert_eq!(
+        std::fs::read_to_string(asset_dir.join("Slime.gdns.bak")).unwrap(),
+        "slime edits"
+    );
+    assert!(!asset_dir.join("Slime.gdns").exists());
+
+    std::fs::write(asset_dir.join("Player.gdns"), "more edits").unwrap();
+    let report = build(&["Player"], false);
+    assert!(report.backups.is_empty());
+    assert_eq!(
+        std::fs::read_to_string(asset_dir.join("Player.gdns.bak")).unwrap(),
+        "player edits"
+    );
+
+    // updated library resources are backed up if they were edited by hand
+    let gdnlib = asset_dir.join("backup_test.gdnlib");
+    let edited = std::fs::read_to_string(&gdnlib).unwrap() + "\n[custom]\n\nkey=1\n";
+    std::fs::write(&gdnlib, &edited).unwrap();
+
+    let report = Generator::new()
+        .lib_name("backup_test")
+        .build_mode(BuildMode::Release)
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .update_existing_lib(true)
+        .build(vec!["Player".to_string()].into_iter().collect())
+        .unwrap();
+    assert_eq!(
+        report.backups,
+        [(gdnlib.clone(), asset_dir.join("backup_test.gdnlib.bak"))]
+    );
+    assert_eq!(
+        std::fs::read_to_string(asset_dir.join("backup_test.gdnlib.bak")).unwrap(),
+        edited
+    );
+    assert!(std::fs::read_to_string(&gdnlib).unwrap().contains("key=1"));
+}
+
+#[test]
+fn check() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let generator = |mode| {
+        Generator::new()
+            .lib_name("check_test")
+            .build_mode(mode)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+    };
+    let classes = |names: &[&str]| names.iter().map(|c| c.to_string()).collect::<Classes>();
+
+    generator(BuildMode::Debug)
+        .build(classes(&["Player", "Enemy"]))
+        .expect("Should generate resources");
+
+    let report = generator(BuildMode::Debug)
+        .check(classes(&["Player", "Enemy"]))
+        .expect("Should check resources");
+    assert!(report.is_up_to_date());
+
+    let report = generator(BuildMode::Release)
+        .check(classes(&["Player", "Slime"]))
+        .expect("Should check resources");
+    assert!(!report.is_up_to_date());
+
+    let asset_dir = dunce::canonicalize(asset_dir).unwrap();
+    let mut missing = vec![];
+    let mut out_of_date = vec![];
+    let mut orphaned = vec![];
+    for issue in &report.issues {
+        match issue {
+            CheckIssue::Missing(path) => missing.push(path.clone()),
+            CheckIssue::OutOfDate { path, .. } => out_of_date.push(path.clone()),
+            CheckIssue::Orphaned(path) => orphaned.push(path.clone()),
+        }
+    }
+    assert_eq!(missing, vec![asset_dir.join("Slime.gdns")]);
+    assert_eq!(out_of_date, vec![asset_dir.join("check_test.gdnlib")]);
+    assert_eq!(orphaned, vec![asset_dir.join("Enemy.gdns")]);
+
+    let diff = report.to_string();
+    assert!(diff.contains("  - X11.64=\"res://target/debug/libcheck_test.so\""));
+    assert!(diff.contains("  + X11.64=\"res://target/release/libcheck_test.so\""));
+
+    // nothing was modified
+    assert!(asset_dir.join("Enemy.gdns").is_file());
+    assert!(!asset_dir.join("Slime.gdns").exists());
+}
+
+#[test]
+fn gdnlib_custom_profile() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    Generator::new()
+        .lib_name("profile_test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .build_mode(BuildMode::from_profile("release-lto"))
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    let content =
+        std::fs::read_to_string(godot_proj_dir.path().join("native/profile_test.gdnlib")).unwrap();
+
+    assert!(content.contains("X11.64=\"res://target/release-lto/libprofile_test.so\""));
+    assert!(content.contains(
+        "Android.arm64-v8a=\"res://target/aarch64-linux-android/release-lto/libprofile_test.so\""
+    ));
+
+    assert_eq!(BuildMode::from_profile("dev"), BuildMode::Debug);
+    assert_eq!(BuildMode::from_profile("bench"), BuildMode::Release);
+}
+
+#[test]
+fn gdnlib_build_mode_per_platform() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    Generator::new()
+        .lib_name("mode_test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .build_mode(BuildMode::Debug)
+        .platforms(Platforms::common().union(&Platforms::ios()))
+        .build_mode_for(Platforms::android(), BuildMode::Release)
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    let content =
+        std::fs::read_to_string(godot_proj_dir.path().join("native/mode_test.gdnlib")).unwrap();
+
+    assert!(content.contains("X11.64=\"res://target/debug/libmode_test.so\""));
+    assert!(content.contains(
+        "Android.arm64-v8a=\"res://target/aarch64-linux-android/release/libmode_test.so\""
+    ));
+    assert!(content.contains("iOS.arm64=\"res://target/aarch64-apple-ios/debug/libmode_test.a\""));
+}
+
+#[test]
+fn gdnlib_target_triple() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let report = Generator::new()
+        .lib_name("cross-test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .build_mode(BuildMode::Release)
+        .platforms(Platforms::desktop().with(Platform::X11Arm64))
+        .target_triple("x86_64-pc-windows-gnu")
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    assert_eq!(
+        report.target_triple.as_deref(),
+        Some("x86_64-pc-windows-gnu")
+    );
+
+    let content =
+        std::fs::read_to_string(godot_proj_dir.path().join("native/cross-test.gdnlib")).unwrap();
+
+    assert!(content
+        .contains("Windows.64=\"res://target/x86_64-pc-windows-gnu/release/cross_test.dll\""));
+    assert!(content.contains("X11.64=\"res://target/release/libcross_test.so\""));
+    assert!(content
+        .contains("X11.arm64=\"res://target/aarch64-unknown-linux-gnu/release/libcross_test.so\""));
+
+    let musl_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = musl_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    Generator::new()
+        .lib_name("cross-test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&musl_proj_dir)
+        .build_mode(BuildMode::Release)
+        .platforms(Platform::X11Arm64)
+        .target_triple("aarch64-unknown-linux-musl")
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    let content =
+        std::fs::read_to_string(musl_proj_dir.path().join("native/cross-test.gdnlib")).unwrap();
+
+    assert!(content.contains(
+        "X11.arm64=\"res://target/aarch64-unknown-linux-musl/release/libcross_test.so\""
+    ));
+}
+
+#[test]
+fn host_platform() {
+    let expected = if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+        Some(Platform::X11_64)
+    } else if cfg!(all(target_os = "linux", target_arch = "aarch64")) {
+        Some(Platform::X11Arm64)
+    } else if cfg!(all(target_os = "macos", target_arch = "x86_64")) {
+        Some(Platform::Osx64)
+    } else if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+        Some(Platform::OsxArm64)
+    } else if cfg!(all(target_os = "windows", target_arch = "x86_64")) {
+        Some(Platform::Windows64)
+    } else if cfg!(all(target_os = "windows", target_arch = "aarch64")) {
+        Some(Platform::WindowsArm64)
+    } else {
+        return;
+    };
+
+    assert_eq!(Platform::host(), expected);
+}
+
+#[test]
+fn cargo_config() {
+    let workspace_dir = tempfile::tempdir().unwrap();
+    let member_dir = workspace_dir.path().join("member");
+
+    std::fs::create_dir_all(workspace_dir.path().join(".cargo")).unwrap();
+    std::fs::create_dir_all(member_dir.join(".cargo")).unwrap();
+
+    std::fs::write(
+        workspace_dir.path().join(".cargo/config.toml"),
+        "[build]\ntarget-dir = \"build/cargo\" # shared by all members\njobs = 4 # see [docs\n\
+         target = 'x86_64-pc-windows-gnu'\n",
+    )
+    .unwrap();
+    // closer configuration files take precedence
+    std::fs::write(
+        member_dir.join(".cargo/config"),
+        "build.target = \"x86_64-unknown-linux-gnu\"\n",
+    )
+    .unwrap();
+
+    let report = Generator::new()
+        .lib_name("cfg_test")
+        .crate_dir(&member_dir)
+        .godot_project_dir(&workspace_dir)
+        .build_mode(BuildMode::Release)
+        .platforms(Platforms::desktop())
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    assert_eq!(
+        report.target_dir,
+        dunce::canonicalize(workspace_dir.path())
+            .unwrap()
+            .join("build/cargo")
+    );
+    assert_eq!(
+        report.target_triple.as_deref(),
+        Some("x86_64-unknown-linux-gnu")
+    );
+
+    let content =
+        std::fs::read_to_string(workspace_dir.path().join("native/cfg_test.gdnlib")).unwrap();
+
+    assert!(content
+        .contains("X11.64=\"res://build/cargo/x86_64-unknown-linux-gnu/release/libcfg_test.so\""));
+    assert!(content.contains("Windows.64=\"res://build/cargo/release/cfg_test.dll\""));
+
+    // a configuration that can not be read has no settings
+    std::fs::write(
+        member_dir.join(".cargo/config"),
+        "build.target = \"x86_64-unknown-linux-gnu\"\nrustflags = [\n",
+    )
+    .unwrap();
+
+    let report = Generator::new()
+        .lib_name("cfg_test")
+        .crate_dir(&member_dir)
+        .godot_project_dir(&workspace_dir)
+        .build_mode(BuildMode::Release)
+        .platforms(Platforms::desktop())
+        .build(Classes::new())
+        .expect("Should generate resources");
+    assert_eq!(
+        report.target_triple.as_deref(),
+        Some("x86_64-pc-windows-gnu")
+    );
+}
+
+#[test]
+fn finish() {
+    let crate_dir = tempfile::tempdir().unwrap();
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+    std::fs::write(
+        crate_dir.path().join("Cargo.toml"),
+        "[package]\nname = \"finish_test\"\n\n[lib]\ncrate-type = [\"cdylib\"]\n",
+    )
+    .unwrap();
+
+    let generator = || {
+        Generator::new()
+            .crate_dir(&crate_dir)
+            .target_dir(&target_dir)
+            .build_mode(BuildMode::Debug)
+    };
+
+    // the missing setting is named before anything is generated
+    match generator().finish() {
+        Err(GenerateError::MissingField(field)) => assert_eq!(field, "godot_project_dir"),
+        other => panic!("Expected a missing field, got {:?}", other.map(|_| ())),
+    }
+    assert!(!godot_proj_dir.path().join("native").exists());
+
+    let generator = generator()
+        .godot_project_dir(&godot_proj_dir)
+        .finish()
+        .expect("Should be configured");
+    assert!(!godot_proj_dir.path().join("native").exists());
+
+    // the library name found in the manifest is kept
+    std::fs::remove_file(crate_dir.path().join("Cargo.toml")).unwrap();
+    let report = generator
+        .build(Classes::new())
+        .expect("Should generate resources");
+    assert_eq!(report.lib_name, "finish_test");
+    assert!(godot_proj_dir
+        .path()
+        .join("native/finish_test.gdnlib")
+        .is_file());
+
+    // a target directory from the cargo config does not exist before the
+    // first build
+    std::fs::create_dir_all(crate_dir.path().join(".cargo")).unwrap();
+    std::fs::write(
+        crate_dir.path().join(".cargo/config.toml"),
+        "[build]\ntarget-dir = \"tgt\"\n",
+    )
+    .unwrap();
+
+    let report = Generator::new()
+        .lib_name("finish_test")
+        .crate_dir(&crate_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .build_mode(BuildMode::Debug)
+        .finish()
+        .expect("Should be configured")
+        .build(Classes::new())
+        .expect("Should generate resources");
+    assert_eq!(
+        report.target_dir,
+        dunce::canonicalize(crate_dir.path()).unwrap().join("tgt")
+    );
+    assert!(!crate_dir.path().join("tgt").exists());
+}
+
+#[test]
+fn lib_name_from_manifest() {
+    let crate_dir = tempfile::tempdir().unwrap();
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let build = |manifest: &str| {
+        std::fs::write(crate_dir.path().join("Cargo.toml"), manifest).unwrap();
+
+        Generator::new()
+            .crate_dir(&crate_dir)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .build_mode(BuildMode::Debug)
+            .platforms(
+                Platforms::new()
+                    .with(Platform::X11_64)
+                    .with(Platform::Windows64),
+            )
+            .build(Classes::new())
+    };
+
+    let report = build(
+        "[package]\nname = \"my-game\"\nversion = \"0.1.0\"\n\n\
+         [lib]\nname = \"game_core\" # renamed\ncrate-type = [\"cdylib\"]\n",
+    )
+    .expect("Should generate resources");
+    assert!(report.lib_path.ends_with("native/game_core.gdnlib"));
+    let content = std::fs::read_to_string(&report.lib_path).unwrap();
+    assert!(content.contains("X11.64=\"res://target/debug/libgame_core.so\""));
+    assert!(content.contains("Windows.64=\"res://target/debug/game_core.dll\""));
+
+    // without `[lib] name`, the hyphens of the package name become underscores
+    let report = build(
+        "[package]\nname = \"my-game\"\nversion = \"0.1.0\"\n\n\
+         [lib]\ncrate-type = ['cdylib', 'rlib']\n",
+    )
+    .expect("Should generate resources");
+    assert!(report.lib_path.ends_with("native/my-game.gdnlib"));
+    let content = std::fs::read_to_string(&report.lib_path).unwrap();
+    assert!(content.contains("X11.64=\"res://target/debug/libmy_game.so\""));
+    assert!(content.contains("Windows.64=\"res://target/debug/my_game.dll\""));
+
+    // comments may contain brackets and quotes
+    let report = build(
+        "[package]\nname = \"my-game\" # see [lib] \"name\"\nversion = \"0.1.0\"\n\n\
+         [lib]\nname = \"game_core\"\ncrate-type = [\"cdylib\"]\n",
+    )
+    .expect("Should generate resources");
+    assert!(report.lib_path.ends_with("native/game_core.gdnlib"));
+
+    // the crate types may span multiple lines
+    build(
+        "[package]\nname = \"my-game\"\nversion = \"0.1.0\"\n\n\
+         [lib]\ncrate-type = [\n    \"cdylib\", # for godot\n    \"rlib\",\n]\n",
+    )
+    .expect("Should find the cdylib crate type");
+
+    // any TOML is read, including escapes and literal strings
+    let report = build(
+        "[package]\nname = \"my-game\"\nversion = \"0.1.0\"\n\n\
+         [package.metadata.docs]\nopen = '('\n\n\
+         [lib]\nname = \"game_\\u0063ore\"\ncrate-type = [\"rlib\", \"cdy\\\"lib\", 'cdylib']\n",
+    )
+    .expect("Should generate resources");
+    assert!(report.lib_path.ends_with("native/game_core.gdnlib"));
+
+    // a manifest that is not valid TOML is an error
+    let err = build("[package]\nname = \"my-game\nversion = \"0.1.0\"\n")
+        .expect_err("Should reject invalid manifests");
+    assert!(matches!(err, GenerateError::InvalidToml { .. }));
+
+    // a library Godot can not load is rejected before anything is written
+    std::fs::remove_dir_all(godot_proj_dir.path().join("native")).unwrap();
+    let err = build("[package]\nname = \"my-game\"\nversion = \"0.1.0\"\n")
+        .expect_err("Should reject crates without a cdylib");
+    match err {
+        GenerateError::InvalidCrateType {
+            package,
+            crate_types,
+        } => {
+            assert_eq!(package, "my-game");
+            assert_eq!(crate_types, ["lib"]);
+        }
+        err => panic!("Expected InvalidCrateType, got {:?}", err),
+    }
+    assert!(!godot_proj_dir.path().join("native").exists());
+}
+
+#[cfg(feature = "cargo_metadata")]
+#[test]
+fn cargo_metadata() {
+    let crate_dir = tempfile::tempdir().unwrap();
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+
+    std::fs::create_dir_all(crate_dir.path().join("src")).unwrap();
+    std::fs::write(crate_dir.path().join("src/lib.rs"), "").unwrap();
+
+    let write_manifest = |crate_type: &str| {
+        std::fs::write(
+            crate_dir.path().join("Cargo.toml"),
+            format!(
+                "[package]\nname = \"meta-test\"\nversion = \"0.1.0\"\n\n\
+                 [lib]\nname = \"renamed\"\ncrate-type = [\"{}\"]\n\n[workspace]\n",
+                crate_type
+            ),
+        )
+        .unwrap();
+    };
+
+    write_manifest("cdylib");
+
+    let report = Generator::new()
+        .crate_dir(&crate_dir)
+        .cargo_metadata(true)
+        .godot_project_dir(&godot_proj_dir)
+        .build_mode(BuildMode::Debug)
+        .platforms(Platform::X11_64)
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    assert_eq!(
+        report.target_dir,
+        dunce::canonicalize(crate_dir.path())
+            .unwrap()
+            .join("target")
+    );
+    assert!(report.lib_path.ends_with("native/renamed.gdnlib"));
+    let content = std::fs::read_to_string(&report.lib_path).unwrap();
+    assert!(content.contains("/target/debug/librenamed.so\""));
+
+    write_manifest("rlib");
+
+    let err = Generator::new()
+        .crate_dir(&crate_dir)
+        .cargo_metadata(true)
+        .godot_project_dir(&godot_proj_dir)
+        .build_mode(BuildMode::Debug)
+        .build(Classes::new())
+        .expect_err("Should reject crates without a cdylib");
+
+    assert!(matches!(err, GenerateError::InvalidCrateType { .. }));
+}
+
+#[test]
+fn override_binary() {
+    use path_slash::PathExt;
+
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = tempfile::tempdir().unwrap();
+    let vendor_dir = tempfile::tempdir().unwrap();
+
+    let report = Generator::new()
+        .lib_name("override_test")
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .build_mode(BuildMode::Release)
+        .platforms(Platforms::desktop())
+        .override_binary(Platform::X11Arm64, "vendor/libprebuilt.so")
+        .override_binary(Platform::Windows64, vendor_dir.path().join("renamed.dll"))
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    let target_dir = dunce::canonicalize(target_dir.path()).unwrap();
+    let vendor_dir = dunce::canonicalize(vendor_dir.path()).unwrap();
+
+    let content =
+        std::fs::read_to_string(godot_proj_dir.path().join("native/override_test.gdnlib")).unwrap();
+
+    assert!(content.contains("X11.arm64=\"res://vendor/libprebuilt.so\""));
+    assert!(content.contains(&format!(
+        "Windows.64=\"{}\"",
+        vendor_dir.join("renamed.dll").to_slash_lossy()
+    )));
+    assert!(content.contains(&format!(
+        "X11.64=\"{}\"",
+        target_dir
+            .join("release/liboverride_test.so")
+            .to_slash_lossy()
+    )));
+
+    assert_eq!(
+        report.binaries.get(Platform::Windows64),
+        Some(vendor_dir.join("renamed.dll").as_path())
+    );
+    assert_eq!(report.binaries.iter().count(), 4);
+}
+
+#[test]
+fn stage_artifacts() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = tempfile::tempdir().unwrap();
+
+    let built = target_dir.path().join("debug/libstage_test.so");
+    std::fs::create_dir_all(built.parent().unwrap()).unwrap();
+    std::fs::write(&built, "elf").unwrap();
+
+    let build = |mode| {
+        Generator::new()
+            .lib_name("stage_test")
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .build_mode(mode)
+            .platforms(Platforms::desktop())
+            .stage_artifacts("native/bin")
+            .overwrite_policy(OverwritePolicy::IfGeneratedByUs)
+            .build(Classes::new())
+            .expect("Should generate resources")
+    };
+
+    let report = build(BuildMode::Debug);
+
+    let staged = godot_proj_dir
+        .path()
+        .join("native/bin/x11.64/libstage_test.so");
+    assert_eq!(std::fs::read_to_string(&staged).unwrap(), "elf");
+    // the other desktop libraries have not been built
+    assert_eq!(report.staged.len(), 1);
+
+    let content =
+        std::fs::read_to_string(godot_proj_dir.path().join("native/stage_test.gdnlib")).unwrap();
+    assert!(content.contains("X11.64=\"res://native/bin/x11.64/libstage_test.so\""));
+    assert!(content.contains("Windows.64=\"res://native/bin/windows.64/stage_test.dll\""));
+
+    // the resources do not depend on the build mode
+    let report = build(BuildMode::Release);
+    assert_eq!(report.updated().count(), 0);
+}
+
+#[test]
+fn stage_dependencies() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = tempfile::tempdir().unwrap();
+    let sdk_dir = tempfile::tempdir().unwrap();
+
+    let built = target_dir.path().join("debug/libdeps_test.so");
+    std::fs::create_dir_all(built.parent().unwrap()).unwrap();
+    std::fs::write(&built, "elf").unwrap();
+    let steam = sdk_dir.path().join("libsteam_api.so");
+    std::fs::write(&steam, "steam").unwrap();
+
+    let build = |mode| {
+        Generator::new()
+            .lib_name("deps_test")
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .build_mode(BuildMode::Debug)
+            .platforms(Platforms::linux())
+            .dependency(Platform::X11_64, &steam)
+            .stage_artifacts("native/bin")
+            .staging_mode(mode)
+            .stage_dependencies(true)
+            .overwrite_policy(OverwritePolicy::IfGeneratedByUs)
+            .build(Classes::new())
+            .expect("Should generate resources")
+    };
+
+    let report = build(StagingMode::Copy);
+    let staged = godot_proj_dir.path().join("native/bin/x11.64");
+    assert_eq!(report.staged.len(), 2);
+    assert_eq!(
+        std::fs::read_to_string(staged.join("libsteam_api.so")).unwrap(),
+        "steam"
+    );
+
+    let content =
+        std::fs::read_to_string(godot_proj_dir.path().join("native/deps_test.gdnlib")).unwrap();
+    assert!(content.contains("X11.64=[ \"res://native/bin/x11.64/libsteam_api.so\" ]"));
+
+    // versioned copies of the library still find the dependency by its name
+    build(StagingMode::Versioned);
+    assert!(staged.join("libsteam_api.so").is_file());
+    let content =
+        std::fs::read_to_string(godot_proj_dir.path().join("native/deps_test.gdnlib")).unwrap();
+    assert!(content.contains("X11.64=[ \"res://native/bin/x11.64/libsteam_api.so\" ]"));
+}
+
+#[test]
+fn stage_debug_symbols() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = tempfile::tempdir().unwrap();
+    let debug_dir = target_dir.path().join("debug");
+
+    std::fs::create_dir_all(debug_dir.join("libsymbols_test.dylib.dSYM/Contents")).unwrap();
+    std::fs::write(debug_dir.join("symbols_test.dll"), "pe").unwrap();
+    std::fs::write(debug_dir.join("symbols_test.pdb"), "pdb").unwrap();
+    std::fs::write(debug_dir.join("libsymbols_test.dylib"), "mach-o").unwrap();
+    std::fs::write(
+        debug_dir.join("libsymbols_test.dylib.dSYM/Contents/Info.plist"),
+        "plist",
+    )
+    .unwrap();
+
+    let build = |include| {
+        Generator::new()
+            .lib_name("symbols_test")
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .build_mode(BuildMode::Debug)
+            .platforms(Platforms::desktop())
+            .stage_artifacts("native/bin")
+            .include_debug_symbols(include)
+            .build(Classes::new())
+            .expect("Should generate resources")
+    };
+
+    let staged = godot_proj_dir.path().join("native/bin");
+
+    let report = build(false);
+    assert_eq!(report.staged.len(), 2);
+    assert!(!staged.join("windows.64/symbols_test.pdb").exists());
+
+    let report = build(true);
+    // the libraries are already up to date
+    assert_eq!(report.staged.len(), 2);
+    assert_eq!(
+        std::fs::read_to_string(staged.join("windows.64/symbols_test.pdb")).unwrap(),
+        "pdb"
+    );
+    assert_eq!(
+        std::fs::read_to_string(
+            staged.join("osx.64/libsymbols_test.dylib.dSYM/Contents/Info.plist")
+        )
+        .unwrap(),
+        "plist"
+    );
+
+    // a rebuilt file inside the bundle is staged again
+    let plist = debug_dir.join("libsymbols_test.dylib.dSYM/Contents/Info.plist");
+    std::fs::write(&plist, "rebuilt").unwrap();
+    std::fs::File::options()
+        .write(true)
+        .open(&plist)
+        .unwrap()
+        .set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(60))
+        .unwrap();
+    let report = build(true);
+    assert_eq!(report.staged.len(), 1);
+    assert_eq!(
+        std::fs::read_to_string(
+            staged.join("osx.64/libsymbols_test.dylib.dSYM/Contents/Info.plist")
+        )
+        .unwrap(),
+        "rebuilt"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn stage_artifacts_symlink() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = tempfile::tempdir().unwrap();
+
+    let built = dunce::canonicalize(target_dir.path())
+        .unwrap()
+        .join("debug/libsymlink_test.so");
+    std::fs::create_dir_all(built.parent().unwrap()).unwrap();
+    std::fs::write(&built, "elf").unwrap();
+
+    let build = |mode| {
+        Generator::new()
+            .lib_name("symlink_test")
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .build_mode(BuildMode::Debug)
+            .platforms(Platform::X11_64)
+            .stage_artifacts("native/bin")
+            .staging_mode(mode)
+            .build(Classes::new())
+            .expect("Should generate resources")
+    };
+
+    let staged = godot_proj_dir
+        .path()
+        .join("native/bin/x11.64/libsymlink_test.so");
+
+    build(StagingMode::Copy);
+    assert!(!std::fs::symlink_metadata(&staged)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+
+    let report = build(StagingMode::Symlink);
+    assert_eq!(report.staged.len(), 1);
+    assert_eq!(std::fs::read_link(&staged).unwrap(), built);
+
+    // a rebuilt library is visible without staging it again
+    std::fs::write(&built, "rebuilt elf").unwrap();
+    assert_eq!(std::fs::read_to_string(&staged).unwrap(), "rebuilt elf");
+    assert_eq!(build(StagingMode::Symlink).staged.len(), 0);
+}
+
+#[test]
+fn stage_artifacts_versioned() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = tempfile::tempdir().unwrap();
+
+    let built = target_dir.path().join("debug/versioned_test.dll");
+    std::fs::create_dir_all(built.parent().unwrap()).unwrap();
+
+    let build = || {
+        Generator::new()
+            .lib_name("versioned_test")
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .build_mode(BuildMode::Debug)
+            .platforms(Platforms::new().with(Platform::Windows64))
+            .stage_artifacts("native/bin")
+            .staging_mode(StagingMode::Versioned)
+            .build(Classes::new())
+            .expect("Should generate resources")
+    };
+    let staged_files = || {
+        let mut files = std::fs::read_dir(godot_proj_dir.path().join("native/bin/windows.64"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    };
+    let gdnlib = || {
+        std::fs::read_to_string(godot_proj_dir.path().join("native/versioned_test.gdnlib")).unwrap()
+    };
+
+    std::fs::write(&built, "pe").unwrap();
+    let report = build();
+    assert_eq!(report.staged.len(), 1);
+
+    let first = staged_files();
+    assert_eq!(first.len(), 1);
+    assert!(first[0].starts_with("versioned_test_"));
+    assert!(first[0].ends_with(".dll"));
+    assert!(gdnlib().contains(&format!(
+        "Windows.64=\"res://native/bin/windows.64/{}\"",
+        first[0]
+    )));
+
+    // an unchanged library keeps its name
+    assert_eq!(build().staged.len(), 0);
+    assert_eq!(staged_files(), first);
+
+    // a rebuilt library gets a new name, and the old copy is removed
+    std::fs::write(&built, "rebuilt pe").unwrap();
+    build();
+    let second = staged_files();
+    assert_eq!(second.len(), 1);
+    assert_ne!(second, first);
+    assert!(gdnlib().contains(&second[0]));
+}
+
+#[test]
+fn build_with_scanner() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let generator = Generator::new()
+        .lib_name("scanner_test")
+        .build_mode(BuildMode::Debug)
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir);
+
+    let report = generator
+        .clone()
+        .build_with_scanner(SynScanner::new("tests/project_stub"))
+        .expect("Should generate resources");
+    assert!(report
+        .created()
+        .any(|path| path.ends_with("EvenMoreTest.gdns")));
+
+    let fixed = vec!["Listed"].into_iter().collect::<Classes>();
+    let report = generator
+        .clone()
+        .build_with_scanner(fixed)
+        .expect("Should generate resources");
+    assert!(report.created().any(|path| path.ends_with("Listed.gdns")));
+
+    let failing = || -> Result<Classes, ScanError> {
+        Err(ScanError::ReadFile(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "classes.txt",
+        )))
+    };
+    let err = generator.build_with_scanner(failing).unwrap_err();
+    assert!(matches!(err, GenerateError::Scan(_)));
+}
+
+#[test]
+fn gdscript_stubs() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&asset_dir).unwrap();
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let build = |classes: Classes| {
+        Generator::new()
+            .lib_name("stub_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .gdscript_stubs(true)
+            .prune_stale(true)
+            .build(classes)
+            .expect("Should generate resources");
+    };
+
+    let mut player = derived_class("Player", "KinematicBody2D");
+    player.module_path = vec!["entities".to_string()];
+    player.methods = vec![Method::new("_ready", 0), Method::new("jump", 2)];
+    player.signals = vec![Signal::new("died", 0), Signal::new("hit", 1)];
+
+    build(
+        vec![player.clone(), Class::new("Enemy")]
+            .into_iter()
+            .collect(),
+    );
+
+    let content = std::fs::read_to_string(asset_dir.join("Player.stub.gd")).unwrap();
+    assert_eq!(
+        content,
+        "# Generated by gdnative-project-utils, do not edit.\n\
+         # API of the native class `Player` (`crate::entities::Player`), for autocompletion only.\n\
+         extends KinematicBody2D\n\
+         \n\
+         signal died\n\
+         signal hit(arg0)\n\
+         \n\
+         func _ready():\n\
+         \tpass\n\
+         \n\
+         func jump(arg0, arg1):\n\
+         \tpass\n"
+    );
+
+    let content = std::fs::read_to_string(asset_dir.join("Enemy.stub.gd")).unwrap();
+    assert!(content.ends_with("extends Reference\n"));
+
+    // stubs follow the methods of their class
+    let mut enemy = Class::new("Enemy");
+    enemy.methods = vec![Method::new("attack", 0)];
+    build(vec![player.clone(), enemy].into_iter().collect());
+    let content = std::fs::read_to_string(asset_dir.join("Enemy.stub.gd")).unwrap();
+    assert!(content.ends_with("func attack():\n\tpass\n"));
+
+    // stubs of removed classes are pruned with their `.gdns` files
+    build(vec![Class::new("Enemy")].into_iter().collect());
+    assert!(!asset_dir.join("Player.stub.gd").exists());
+    assert!(asset_dir.join("Enemy.stub.gd").is_file());
+}
+
+#[test]
+fn scene_templates() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&asset_dir).unwrap();
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let build = || {
+        let mut player = derived_class("Player", "KinematicBody2D");
+        player.module_path = vec!["entities".to_string()];
+        let classes = vec![player, Class::new("Data")];
+
+        Generator::new()
+            .lib_name("scene_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .layout(OutputLayout::ModuleTree)
+            .overwrite_policy(OverwritePolicy::IfContentDiffers)
+            .scene_templates(true)
+            .build(classes.into_iter().collect())
+            .expect("Should generate resources")
+    };
+
+    build();
+
+    let scene_path = asset_dir.join("entities/Player.tscn");
+    let content = std::fs::read_to_string(&scene_path).unwrap();
+    assert_eq!(
+        content,
+        r#"[gd_scene load_steps=2 format=2]
+
+[ext_resource path="res://native/entities/Player.gdns" type="Script" id=1]
+
+[node name="Player" type="KinematicBody2D"]
+script = ExtResource( 1 )
+"#
+    );
+
+    // without a known base class there is no scene
+    assert!(asset_dir.join("Data.gdns").is_file());
+    assert!(!asset_dir.join("Data.tscn").exists());
+
+    // edited scenes are kept
+    std::fs::write(&scene_path, "edited").unwrap();
+    let report = build();
+    assert_eq!(std::fs::read_to_string(&scene_path).unwrap(), "edited");
+    assert!(report.skipped().any(|path| path == scene_path));
+}
+
+#[test]
+fn editor_plugin() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&asset_dir).unwrap();
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let classes = vec![
+        derived_class("Player", "KinematicBody2D"),
+        derived_class("Stats", "Resource"),
+        Class::new("Data"),
+    ];
+
+    let build = |classes: Vec<Class>| {
+        Generator::new()
+            .lib_name("plugin_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .editor_plugin("native_classes")
+            .class_icon("Player", "icons/player.svg")
+            .build(classes.into_iter().collect())
+            .expect("Should generate resources");
+    };
+    build(classes.clone());
+
+    let plugin_dir = godot_proj_dir.path().join("addons/native_classes");
+
+    let cfg = std::fs::read_to_string(plugin_dir.join("plugin.cfg")).unwrap();
+    assert!(cfg.contains("name=\"native_classes\"\n"));
+    assert!(cfg.contains("script=\"plugin.gd\"\n"));
+
+    let script = std::fs::read_to_string(plugin_dir.join("plugin.gd")).unwrap();
+    assert_eq!(
+        script,
+        "# Generated by gdnative-project-utils, do not edit.\n\
+         tool\n\
+         extends EditorPlugin\n\
+         \n\
+         \n\
+         func _enter_tree():\n\
+         \tadd_custom_type(\"Player\", \"KinematicBody2D\", preload(\"res://native/Player.gdns\"), preload(\"res://icons/player.svg\"))\n\
+         \tadd_custom_type(\"Stats\", \"Resource\", preload(\"res://native/Stats.gdns\"), null)\n\
+         \n\
+         \n\
+         func _exit_tree():\n\
+         \tremove_custom_type(\"Player\")\n\
+         \tremove_custom_type(\"Stats\")\n"
+    );
+
+    // the plugin files are tracked like the other generated files
+    let manifest = std::fs::read_to_string(asset_dir.join(".gdnative-utils.cfg")).unwrap();
+    assert!(manifest.contains("../addons/native_classes/plugin.gd="));
+
+    // and follow the classes
+    build(classes[1..].to_vec());
+    let script = std::fs::read_to_string(plugin_dir.join("plugin.gd")).unwrap();
+    assert!(!script.contains("Player"));
+    assert!(script.contains("add_custom_type(\"Stats\""));
+}
+
+#[test]
+fn global_script_classes() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+    let project_path = godot_proj_dir.path().join("project.godot");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+    std::fs::write(
+        &project_path,
+        r#"; Engine configuration file.
+
+config_version=4
+
+_global_script_classes=[ {
+"base": "Node",
+"class": "Spawner",
+"language": "GDScript",
+"path": "res://spawner.gd"
+} ]
+_global_script_class_icons={
+"Spawner": ""
+}
+
+[application]
+
+config/name="Test"
+"#,
+    )
+    .unwrap();
+
+    let build = |classes: Vec<Class>| {
+        Generator::new()
+            .lib_name("global_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .overwrite_policy(OverwritePolicy::IfGeneratedByUs)
+            .global_script_classes(true)
+            .class_icon("Player", "icons/player.svg")
+            .build(classes.into_iter().collect())
+            .expect("Should generate resources")
+    };
+
+    build(vec![
+        derived_class("Player", "KinematicBody2D"),
+        Class::new("Enemy"),
+    ]);
+
+    let content = std::fs::read_to_string(&project_path).unwrap();
+    assert_eq!(
+        content,
+        r#"; Engine configuration file.
+
+config_version=4
+
+_global_script_classes=[ {
+"base": "Reference",
+"class": "Enemy",
+"language": "NativeScript",
+"path": "res://native/Enemy.gdns"
+}, {
+"base": "KinematicBody2D",
+"class": "Player",
+"language": "NativeScript",
+"path": "res://native/Player.gdns"
+}, {
+"base": "Node",
+"class": "Spawner",
+"language": "GDScript",
+"path": "res://spawner.gd"
+} ]
+_global_script_class_icons={
+"Enemy": "",
+"Player": "res://icons/player.svg",
+"Spawner": ""
+}
+
+[application]
+
+config/name="Test"
+"#
+    );
+    let backup = std::fs::read_to_string(godot_proj_dir.path().join("project.godot.bak")).unwrap();
+    assert!(backup.contains("\"class\": \"Spawner\""));
+    assert!(!backup.contains("\"class\": \"Player\""));
+
+    // `Enemy` was renamed, its old entry is removed
+    build(vec![Class::new("Player"), Class::new("Slime")]);
+
+    let content = std::fs::read_to_string(&project_path).unwrap();
+    assert!(!content.contains("\"Enemy\""));
+    assert!(content.contains("\"class\": \"Slime\""));
+    assert!(content.contains("\"class\": \"Spawner\""));
+
+    // a `project.godot` changed by hand since is left alone
+    let edited = content.replace("config/name=\"Test\"", "config/name=\"Edited\"");
+    std::fs::write(&project_path, &edited).unwrap();
+    let report = build(vec![Class::new("Player")]);
+    assert!(report.skipped().any(|path| path == project_path));
+    assert_eq!(std::fs::read_to_string(&project_path).unwrap(), edited);
+
+    std::fs::remove_file(&project_path).unwrap();
+    let err = Generator::new()
+        .lib_name("global_test")
+        .build_mode(BuildMode::Debug)
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .global_script_classes(true)
+        .build(Classes::new())
+        .expect_err("Should require `project.godot`");
+    assert!(matches!(err, GenerateError::InvalidPath { path, .. } if path == project_path));
+}
+
+#[test]
+fn migrate_renames() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let asset_dir = godot_proj_dir.path().join("native");
+    let target_dir = godot_proj_dir.path().join("target");
+    let scene_path = godot_proj_dir.path().join("levels/Level.tscn");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+    std::fs::create_dir_all(scene_path.parent().unwrap()).unwrap();
+
+    let class = |name: &str, file: &str, line: usize| {
+        let mut class = Class::new(name);
+        class.source_file = Some(file.into());
+        class.span = Some(SourceSpan::new(line, 11, line, 11 + name.len()));
+        class
+    };
+
+    let build_with_policy = |classes: Vec<Class>, migrate: bool, policy| {
+        Generator::new()
+            .lib_name("rename_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .migrate_renames(migrate)
+            .overwrite_policy(policy)
+            .build(classes.into_iter().collect())
+            .expect("Should generate resources")
+    };
+    let build = |classes: Vec<Class>, migrate: bool| {
+        build_with_policy(classes, migrate, OverwritePolicy::IfGeneratedByUs)
+    };
+
+    build(
+        vec![
+            class("Player", "src/player.rs", 4),
+            class("Enemy", "src/enemy.rs", 4),
+            class("Boss", "src/enemy.rs", 20),
+        ],
+        false,
+    );
+
+    std::fs::write(
+        &scene_path,
+        r#"[gd_scene load_steps=3 format=2]
+
+[ext_resource path="res://native/Player.gdns" type="Script" id=1]
+[ext_resource path="res://native/Enemy.gdns" type="Script" id=2]
+
+[node name="Level" type="Node2D"]
+"#,
+    )
+    .unwrap();
+
+    // `Player` became `Hero`, `Enemy` became `Slime` in the same file
+    let classes = vec![
+        class("Hero", "src/player.rs", 4),
+        class("Slime", "src/enemy.rs", 4),
+        class("Boss", "src/enemy.rs", 20),
+    ];
+
+    // without migrating, renames are only reported
+    let report = build(classes.clone(), false);
+    let renames = report
+        .renames
+        .iter()
+        .map(|rename| (rename.old_class.as_str(), rename.new_class.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(renames, [("Enemy", "Slime"), ("Player", "Hero")]);
+    assert!(asset_dir.join("Player.gdns").is_file());
+    assert!(std::fs::read_to_string(&scene_path)
+        .unwrap()
+        .contains("res://native/Player.gdns"));
+
+    // the new `.gdns` files exist now, so start over to see them created
+    let start_over = || {
+        std::fs::remove_file(asset_dir.join("Hero.gdns")).unwrap();
+        std::fs::remove_file(asset_dir.join("Slime.gdns")).unwrap();
+    };
+    start_over();
+
+    // nothing is migrated if no file may be overwritten
+    let report = build_with_policy(classes.clone(), true, OverwritePolicy::Never);
+    assert_eq!(report.renames.len(), 2);
+    assert!(asset_dir.join("Player.gdns").is_file());
+    assert!(report.skipped().any(|path| path == scene_path));
+    assert!(std::fs::read_to_string(&scene_path)
+        .unwrap()
+        .contains("res://native/Player.gdns"));
+
+    start_over();
+    let edited_enemy = std::fs::read_to_string(asset_dir.join("Enemy.gdns")).unwrap() + "\n";
+    std::fs::write(asset_dir.join("Enemy.gdns"), &edited_enemy).unwrap();
+
+    let report = build(classes, true);
+    assert_eq!(report.renames.len(), 2);
+    assert!(!asset_dir.join("Player.gdns").exists());
+    assert!(!asset_dir.join("Enemy.gdns").exists());
+    assert!(asset_dir.join("Hero.gdns").is_file());
+
+    // the modified `.gdns` file and the scene are backed up
+    assert!(!asset_dir.join("Player.gdns.bak").exists());
+    assert_eq!(
+        std::fs::read_to_string(asset_dir.join("Enemy.gdns.bak")).unwrap(),
+        edited_enemy
+    );
+    assert!(
+        std::fs::read_to_string(scene_path.with_extension("tscn.bak"))
+            .unwrap()
+            .contains("res://native/Player.gdns")
+    );
+
+    let scene = std::fs::read_to_string(&scene_path).unwrap();
+    assert!(scene.contains(r#"[ext_resource path="res://native/Hero.gdns" type="Script" id=1]"#));
+    assert!(scene.contains(r#"[ext_resource path="res://native/Slime.gdns" type="Script" id=2]"#));
+    assert!(report.updated().any(|path| path == scene_path));
+
+    // replacing the only class of a file with one elsewhere is not a rename
+    let classes = vec![
+        class("Hero", "src/player.rs", 4),
+        class("Slime", "src/enemy.rs", 4),
+        class("Boss", "src/enemy.rs", 20),
+        class("Coin", "src/items.rs", 4),
+    ];
+    build(classes, true);
+
+    let report = build(
+        vec![
+            class("Hero", "src/player.rs", 4),
+            class("Slime", "src/enemy.rs", 4),
+            class("Boss", "src/enemy.rs", 20),
+            class("Camera", "src/camera.rs", 8),
+        ],
+        true,
+    );
+    assert!(report.renames.is_empty());
+    assert!(asset_dir.join("Camera.gdns").is_file());
+}
+
+#[test]
+fn update_export_presets() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+    let presets_path = godot_proj_dir.path().join("export_presets.cfg");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+    std::fs::write(
+        &presets_path,
+        r#"[preset.0]
+
+name="Linux/X11"
+platform="Linux/X11"
+export_filter="all_resources"
+include_filter="*.json"
+exclude_filter=""
+
+[preset.0.options]
+
+binary_format/64_bits=true
+
+[preset.1]
+
+name="Windows Desktop"
+platform="Windows Desktop"
+export_filter="all_resources"
+exclude_filter=""
+"#,
+    )
+    .unwrap();
+
+    let build_with = |staging: bool, policy: OverwritePolicy| {
+        let mut generator = Generator::new()
+            .lib_name("export_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .platforms(
+                Platforms::new()
+                    .with(Platform::X11_64)
+                    .with(Platform::Windows64),
+            )
+            .overwrite_policy(policy)
+            .update_export_presets(true);
+        if staging {
+            generator.with_stage_artifacts("native/bin");
+        }
+        generator
+            .build(Classes::new())
+            .expect("Should generate resources")
+    };
+    let build = |staging: bool| build_with(staging, OverwritePolicy::IfGeneratedByUs);
+
+    // the presets belong to the user
+    let report = build_with(false, OverwritePolicy::Never);
+    assert!(report.skipped().any(|path| path == presets_path));
+    assert!(!std::fs::read_to_string(&presets_path)
+        .unwrap()
+        .contains("export_test"));
+
+    build(false);
+
+    let content = std::fs::read_to_string(&presets_path).unwrap();
+    assert!(content.contains(
+        "include_filter=\"*.json, target/debug/libexport_test.so, target/debug/export_test.dll\"\n"
+    ));
+    assert!(content.contains(
+        "exclude_filter=\"\"\ninclude_filter=\"target/debug/libexport_test.so, target/debug/export_test.dll\"\n"
+    ));
+    assert!(content.contains("[preset.0.options]\n\nbinary_format/64_bits=true\n"));
+    let backup =
+        std::fs::read_to_string(godot_proj_dir.path().join("export_presets.cfg.bak")).unwrap();
+    assert!(backup.contains("include_filter=\"*.json\"\n"));
+
+    // existing patterns are not added twice
+    let report = build(false);
+    assert!(report.unchanged().any(|path| path == presets_path));
+
+    build(true);
+    let content = std::fs::read_to_string(&presets_path).unwrap();
+    assert!(content.contains("target/debug/export_test.dll, native/bin/*\"\n"));
+
+    // presets changed by hand since are left alone, unless content may be overwritten
+    let edited = content.replace(", native/bin/*", "");
+    std::fs::write(&presets_path, &edited).unwrap();
+    std::fs::remove_file(godot_proj_dir.path().join("export_presets.cfg.bak")).unwrap();
+    let report = build(true);
+    assert!(report.skipped().any(|path| path == presets_path));
+    assert_eq!(std::fs::read_to_string(&presets_path).unwrap(), edited);
+
+    let report = build_with(true, OverwritePolicy::IfContentDiffers);
+    assert!(report.updated().any(|path| path == presets_path));
+    assert!(godot_proj_dir
+        .path()
+        .join("export_presets.cfg.bak")
+        .is_file());
+}
+
+#[test]
+fn gdignore() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+    let outside_target_dir = tempfile::tempdir().unwrap();
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let build = |target_dir: &std::path::Path| {
+        Generator::new()
+            .lib_name("gdignore_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .stage_artifacts("native/bin")
+            .gdignore(true)
+            .build(Classes::new())
+            .expect("Should generate resources")
+    };
+
+    build(&target_dir);
+    assert!(target_dir.join(".gdignore").is_file());
+    assert!(godot_proj_dir.path().join("native/bin/.gdignore").is_file());
+
+    // a target directory outside of the project is left alone
+    build(outside_target_dir.path());
+    assert!(!outside_target_dir.path().join(".gdignore").exists());
+
+    // an existing `.gdignore` of the user is neither reported nor tracked
+    let user_target_dir = godot_proj_dir.path().join("user_target");
+    std::fs::create_dir_all(&user_target_dir).unwrap();
+    std::fs::write(user_target_dir.join(".gdignore"), "by hand").unwrap();
+    let report = build(&user_target_dir);
+    assert!(!report
+        .files
+        .iter()
+        .any(|(path, _)| path.ends_with(".gdignore")));
+
+    // files outside of the project are never cleaned up
+    let outside_file = outside_target_dir.path().join(".gdignore");
+    std::fs::write(&outside_file, "").unwrap();
+    let manifest_path = godot_proj_dir.path().join("native/.gdnative-utils.cfg");
+    let mut manifest = std::fs::read_to_string(&manifest_path).unwrap();
+    assert!(!manifest.contains("user_target"));
+    manifest = manifest.replacen(
+        "[files]\n\n",
+        &format!(
+            "[files]\n\n../../{}/.gdignore=\"fnv1a64:cbf29ce484222325\"\n",
+            outside_target_dir
+                .path()
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+        ),
+        1,
+    );
+    std::fs::write(&manifest_path, manifest).unwrap();
+
+    Generator::new()
+        .godot_project_dir(&godot_proj_dir)
+        .overwrite_policy(OverwritePolicy::IfContentDiffers)
+        .clean()
+        .expect("Should clean");
+    assert!(outside_file.is_file());
+    assert!(!target_dir.join(".gdignore").exists());
+    assert_eq!(
+        std::fs::read_to_string(user_target_dir.join(".gdignore")).unwrap(),
+        "by hand"
+    );
+}
+
+#[test]
+fn validate() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    let build = |triple: &str| {
+        Generator::new()
+            .lib_name("validate_test")
+            .build_mode(BuildMode::Release)
+            .target_dir(&target_dir)
+            .target_triple(triple)
+            .godot_project_dir(&godot_proj_dir)
+            .platforms(Platforms::android())
+            .build(Classes::new())
+            .expect("Should generate resources")
+    };
+
+    let report = build("aarch64-linux-android");
+    let missing = report.validate().unwrap_err();
+    assert_eq!(missing.platform, Platform::AndroidAarch64);
+    assert_eq!(
+        missing.path,
+        target_dir.join("aarch64-linux-android/release/libvalidate_test.so")
+    );
+    assert!(missing
+        .to_string()
+        .starts_with("The `Android.arm64-v8a` library entry points at `"));
+
+    std::fs::create_dir_all(missing.path.parent().unwrap()).unwrap();
+    std::fs::write(&missing.path, "elf").unwrap();
+    assert!(build("aarch64-linux-android").validate().is_ok());
+
+    // without an entry for the platform, there is nothing to validate
+    assert!(build("x86_64-pc-windows-msvc").validate().is_ok());
+}
+
+#[test]
+#[cfg(unix)]
+fn headless_check() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+    let godot = godot_proj_dir.path().join("fake_godot.sh");
+    let script_copy = godot_proj_dir.path().join("check.gd");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    // Pretends to be Godot 3, failing to load the `Enemy` class.
+    std::fs::write(
+        &godot,
+        format!(
+            r#"#!/bin/sh
+if [ "$1" = "--version" ]; then
+    echo "3.5.2.stable.official"
+    exit 0
+fi
+while [ "$1" != "-s" ]; do shift; done
+cp "$2" "{}"
+if grep -q Enemy "$2"; then
+    echo "gdnative-project-utils: failed to load res://native/Enemy.gdns"
+    exit 1
+fi
+"#,
+            script_copy.display()
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&godot, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let build = |classes: Vec<&str>| {
+        Generator::new()
+            .lib_name("headless_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+            .godot_resource_output_dir(godot_proj_dir.path().join("native"))
+            .build(classes.into_iter().map(Class::new).collect())
+            .expect("Should generate resources")
+    };
+
+    let report = build(vec!["Player"]);
+    HeadlessCheck::new(&godot)
+        .run(&report)
+        .expect("Should load all resources");
+
+    let script = std::fs::read_to_string(&script_copy).unwrap();
+    assert!(script.contains(
+        r#"for path in ["res://native/headless_test.gdnlib", "res://native/Player.gdns"]:"#
+    ));
+    assert!(script.contains("not resource.can_instance()"));
+
+    let report = build(vec!["Player", "Enemy"]);
+    match HeadlessCheck::new(&godot).run(&report) {
+        Err(HeadlessCheckError::Failed { resources, .. }) => {
+            assert_eq!(resources, vec!["res://native/Enemy.gdns".to_string()])
+        }
+        other => panic!("Expected a failed check, got {:?}", other),
+    }
+}
+
+#[test]
+fn clean() {
+    use std::sync::{Arc, Mutex};
+
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+    let native = godot_proj_dir.path().join("native");
+
+    let project_path = godot_proj_dir.path().join("project.godot");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+    std::fs::write(&project_path, "").unwrap();
+
+    let generator = || {
+        Generator::new()
+            .lib_name("clean_test")
+            .build_mode(BuildMode::Debug)
+            .target_dir(&target_dir)
+            .godot_project_dir(&godot_proj_dir)
+    };
+
+    generator()
+        .overwrite_policy(OverwritePolicy::IfGeneratedByUs)
+        .global_script_classes(true)
+        .build(
+            vec![Class::new("Player"), Class::new("Enemy")]
+                .into_iter()
+                .collect(),
+        )
+        .expect("Should generate resources");
+    std::fs::write(native.join("Enemy.gdns"), "edited by hand").unwrap();
+
+    let written = Arc::new(Mutex::new(vec![]));
+    let skipped = Arc::new(Mutex::new(vec![]));
+    let files = {
+        let (written, skipped) = (written.clone(), skipped.clone());
+        generator()
+            .on_file_written(move |path, action| {
+                written.lock().unwrap().push((path.to_path_buf(), action))
+            })
+            .on_skip(move |path| skipped.lock().unwrap().push(path.to_path_buf()))
+            .clean()
+            .expect("Should clean")
+    };
+    assert_eq!(*skipped.lock().unwrap(), vec![native.join("Enemy.gdns")]);
+    assert!(written
+        .lock()
+        .unwrap()
+        .contains(&(native.join("Player.gdns"), FileAction::Removed)));
+    assert!(files.contains(&(native.join("Player.gdns"), FileAction::Removed)));
+    assert!(files.contains(&(native.join("clean_test.gdnlib"), FileAction::Removed)));
+    assert!(files.contains(&(native.join("Enemy.gdns"), FileAction::Skipped)));
+    assert!(!native.join("Player.gdns").exists());
+    assert!(native.join("Enemy.gdns").exists());
+
+    // only the removed classes are unregistered
+    assert!(files.contains(&(project_path.clone(), FileAction::Updated)));
+    let project = std::fs::read_to_string(&project_path).unwrap();
+    assert!(!project.contains("res://native/Player.gdns"));
+    assert!(project.contains("res://native/Enemy.gdns"));
+
+    let files = generator()
+        .overwrite_policy(OverwritePolicy::IfContentDiffers)
+        .clean()
+        .expect("Should clean");
+    assert_eq!(
+        files,
+        vec![
+            (native.join("Enemy.gdns"), FileAction::Removed),
+            (project_path.clone(), FileAction::Updated),
+        ]
+    );
+    assert!(!native.join(".gdnative-utils.cfg").exists());
+    assert!(!std::fs::read_to_string(&project_path)
+        .unwrap()
+        .contains("Enemy"));
+    // the modified file was backed up before removing it
+    assert_eq!(
+        std::fs::read_to_string(native.join("Enemy.gdns.bak")).unwrap(),
+        "edited by hand"
+    );
+}
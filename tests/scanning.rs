@@ -9,3 +9,445 @@ fn scanning() {
     assert!(res.contains("MoreTest"));
     assert!(res.contains("EvenMoreTest"));
 }
+
+#[test]
+fn scanning_with_report() {
+    let report = scan_crate_with_report("tests/project_stub").expect("Scanning should work");
+
+    assert_eq!(report.classes.len(), 3);
+    assert_eq!(report.files.len(), 2);
+    assert!(report
+        .files
+        .iter()
+        .any(|path| path.ends_with("project_stub/more_test.rs")));
+}
+
+#[test]
+fn class_metadata() {
+    let classes = scan_crate("tests/project_stub").expect("Scanning should work");
+
+    let test = classes.get("Test").expect("Should find `Test`");
+    assert_eq!(test.module_path, vec!["test".to_string()]);
+    assert_eq!(test.rust_path(), "crate::test::Test");
+    assert!(test
+        .source_file
+        .as_ref()
+        .unwrap()
+        .ends_with("project_stub/test.rs"));
+
+    let span = test.span.expect("Should have a span");
+    assert_eq!((span.start_line, span.start_column), (2, 7));
+    assert_eq!((span.end_line, span.end_column), (2, 11));
+
+    let names = ["Test", "Other"]
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<std::collections::HashSet<_>>();
+    let classes = Classes::from(names);
+    assert_eq!(classes.names().collect::<Vec<_>>(), vec!["Other", "Test"]);
+    assert_eq!(classes.get("Other"), Some(&Class::new("Other")));
+}
+
+#[test]
+fn duplicate_classes() {
+    let err = scan_crate("tests/duplicate_stub").expect_err("Scanning should fail");
+
+    match &err {
+        ScanError::DuplicateClass { first, second } => {
+            let mut paths = vec![first.rust_path(), second.rust_path()];
+            paths.sort();
+            assert_eq!(
+                paths,
+                ["crate::enemies::boss::Player", "crate::player::Player"]
+            );
+        }
+        err => panic!("Unexpected error: {}", err),
+    }
+
+    let message = err.to_string();
+    assert!(message.contains("enemies/mod.rs:3:12"));
+    assert!(message.contains("player.rs:2:8"));
+}
+
+#[test]
+fn cfg_attr_derives() {
+    let report = scan_crate_with_report("tests/cfg_stub").expect("Scanning should work");
+    let names = report.classes.names().collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        ["Always", "Documented", "EditorOnly", "Fallback", "Gated"]
+    );
+
+    let options = ScanOptions::new().features(["godot"]);
+    let report = scan_crate_with_options("tests/cfg_stub", &options).expect("Scanning should work");
+    let names = report.classes.names().collect::<Vec<_>>();
+    assert_eq!(names, ["Always", "Documented", "Gated"]);
+}
+
+#[test]
+fn cfg_gates() {
+    let report = scan_crate_with_report("tests/cfg_gate_stub").expect("Scanning should work");
+    let names = report.classes.names().collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "AndroidOnly",
+            "Inspector",
+            "Undecided",
+            "Unix64",
+            "WindowsOnly"
+        ]
+    );
+
+    let options = ScanOptions::new()
+        .features(["editor"])
+        .target("x86_64-pc-windows-msvc");
+    let report =
+        scan_crate_with_options("tests/cfg_gate_stub", &options).expect("Scanning should work");
+    let names = report.classes.names().collect::<Vec<_>>();
+    assert_eq!(names, ["Inspector", "Undecided", "WindowsOnly"]);
+
+    let options = ScanOptions::new()
+        .features(Vec::<String>::new())
+        .target("aarch64-linux-android");
+    let report =
+        scan_crate_with_options("tests/cfg_gate_stub", &options).expect("Scanning should work");
+    let names = report.classes.names().collect::<Vec<_>>();
+    assert_eq!(names, ["AndroidOnly", "Undecided", "Unix64"]);
+
+    // 32-bit Android has an ABI suffix and no vendor
+    let options = ScanOptions::new()
+        .features(Vec::<String>::new())
+        .target("armv7-linux-androideabi");
+    let report =
+        scan_crate_with_options("tests/cfg_gate_stub", &options).expect("Scanning should work");
+    let names = report.classes.names().collect::<Vec<_>>();
+    assert_eq!(names, ["AndroidOnly", "Undecided"]);
+
+    let options = ScanOptions::new()
+        .features(Vec::<String>::new())
+        .target("armv7-unknown-linux-gnueabihf");
+    let report =
+        scan_crate_with_options("tests/cfg_gate_stub", &options).expect("Scanning should work");
+    let names = report.classes.names().collect::<Vec<_>>();
+    assert_eq!(names, ["Undecided"]);
+}
+
+#[test]
+fn qualified_and_renamed_derives() {
+    let report = scan_crate_with_report("tests/derive_stub").expect("Scanning should work");
+    let names = report.classes.names().collect::<Vec<_>>();
+    assert_eq!(names, ["Absolute", "Qualified", "Renamed"]);
+
+    let options = ScanOptions::new().derive("my_macros::GodotClass");
+    let report =
+        scan_crate_with_options("tests/derive_stub", &options).expect("Scanning should work");
+    let names = report.classes.names().collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "Absolute",
+            "Custom",
+            "CustomRenamed",
+            "Imported",
+            "Qualified",
+            "Renamed"
+        ]
+    );
+}
+
+#[test]
+fn manual_impls() {
+    let classes = scan_crate("tests/impl_stub").expect("Scanning should work");
+    let names = classes.names().collect::<Vec<_>>();
+    assert_eq!(names, ["Manual", "Qualified", "Renamed"]);
+
+    let renamed = classes.get("Renamed").unwrap();
+    assert_eq!(renamed.rust_path(), "crate::generated::Renamed");
+
+    // custom derives only apply to `#[derive(...)]`, not to trait impls
+    let options = ScanOptions::new().derive("my_macros::GodotClass");
+    let report =
+        scan_crate_with_options("tests/impl_stub", &options).expect("Scanning should work");
+    let names = report.classes.names().collect::<Vec<_>>();
+    assert_eq!(names, ["Manual", "Qualified", "Renamed"]);
+}
+
+#[test]
+fn follow_modules() {
+    let report = scan_crate_with_report("tests/module_stub").expect("Scanning should work");
+    assert!(report.classes.contains("Scratch"));
+
+    let options = ScanOptions::new().follow_modules(true);
+    let report =
+        scan_crate_with_options("tests/module_stub", &options).expect("Scanning should work");
+    let names = report.classes.names().collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        ["Enemy", "Level", "Player", "Root", "Tool", "Weapon"]
+    );
+    assert_eq!(report.files.len(), 6);
+
+    let path = |name: &str| report.classes.get(name).unwrap().rust_path();
+    assert_eq!(path("Enemy"), "crate::enemy::Enemy");
+    assert_eq!(path("Level"), "crate::world::level::Level");
+    assert_eq!(path("Tool"), "crate::tools::Tool");
+    assert_eq!(path("Weapon"), "crate::player::weapon::Weapon");
+
+    let options = options.features(Vec::<String>::new());
+    let report =
+        scan_crate_with_options("tests/module_stub", &options).expect("Scanning should work");
+    assert!(!report.classes.contains("Tool"));
+}
+
+#[test]
+fn follow_module_cycles() {
+    let options = ScanOptions::new().follow_modules(true);
+    let report =
+        scan_crate_with_options("tests/module_cycle_stub", &options).expect("Scanning should work");
+    let names = report.classes.names().collect::<Vec<_>>();
+    assert_eq!(names, ["Other", "Root"]);
+    assert_eq!(report.files.len(), 2);
+}
+
+#[test]
+fn include_and_exclude() {
+    let scan = |options: ScanOptions| {
+        let report =
+            scan_crate_with_options("tests/module_stub", &options).expect("Scanning should work");
+        report.classes.names().map(String::from).collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        scan(ScanOptions::new().exclude(["scratch.rs", "generated/**"])),
+        ["Level", "Player", "Root", "Tool", "Weapon"]
+    );
+    assert_eq!(
+        scan(ScanOptions::new().include(["player.rs", "player/**"])),
+        ["Player", "Weapon"]
+    );
+    assert_eq!(
+        scan(
+            ScanOptions::new()
+                .include(["player.rs", "player/**"])
+                .exclude(["player/weapon.rs"])
+        ),
+        ["Player"]
+    );
+    assert_eq!(
+        scan(
+            ScanOptions::new()
+                .follow_modules(true)
+                .exclude(["player.rs"])
+        ),
+        ["Enemy", "Level", "Root", "Tool", "Weapon"]
+    );
+
+    let err = scan_crate_with_options("tests/module_stub", &ScanOptions::new().include(["{"]))
+        .unwrap_err();
+    assert!(matches!(err, ScanError::InvalidPattern(_)));
+}
+
+#[test]
+fn skip_test_code() {
+    let report = scan_crate_with_report("tests/test_code_stub").expect("Scanning should work");
+    let names = report.classes.names().collect::<Vec<_>>();
+    assert_eq!(names, ["Game", "RealNode"]);
+    assert_eq!(report.files.len(), 1);
+
+    let options = ScanOptions::new().include_tests(true);
+    let report =
+        scan_crate_with_options("tests/test_code_stub", &options).expect("Scanning should work");
+    let names = report.classes.names().collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "Bench",
+            "Fixture",
+            "Game",
+            "MockNode",
+            "RealNode",
+            "TestDouble"
+        ]
+    );
+}
+
+#[test]
+fn gdns_path_override() {
+    let classes = scan_crate("tests/gdns_path_stub").expect("Scanning should work");
+    let path = |name: &str| classes.get(name).unwrap().gdns_path.clone();
+
+    assert_eq!(path("Slime").as_deref(), Some("enemies/slime.gdns"));
+    assert_eq!(path("Dragon").as_deref(), Some("bosses/dragon.gdns"));
+    assert_eq!(path("Player"), None);
+
+    let crate_dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        crate_dir.path().join("lib.rs"),
+        "/// gdns: ../outside.gdns\n#[derive(NativeClass)]\nstruct Escaped;\n",
+    )
+    .unwrap();
+    let err = scan_crate(crate_dir.path()).expect_err("Scanning should fail");
+    assert!(err.to_string().contains("must be relative"));
+}
+
+#[test]
+fn lenient_scanning() {
+    let err = scan_crate_with_report("tests/lenient_stub").unwrap_err();
+    assert!(matches!(err, ScanError::Parse(_)));
+
+    let options = ScanOptions::new().lenient(true);
+    let report =
+        scan_crate_with_options("tests/lenient_stub", &options).expect("Scanning should work");
+    assert_eq!(report.classes.names().collect::<Vec<_>>(), ["Good"]);
+    assert_eq!(report.files.len(), 2);
+    assert_eq!(report.errors.len(), 1);
+    assert!(report.errors[0].path.ends_with("lenient_stub/broken.rs"));
+    assert!(matches!(report.errors[0].error, ScanError::Parse(_)));
+
+    let mut warnings = vec![];
+    report.emit_diagnostics(&mut |message: &str| warnings.push(message.to_string()));
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("broken.rs"));
+}
+
+#[test]
+fn many_files() {
+    let dir = tempfile::tempdir().unwrap();
+
+    for i in 0..200 {
+        std::fs::write(
+            dir.path().join(format!("class_{:03}.rs", i)),
+            format!("#[derive(NativeClass)]\nstruct Class{:03};\n", i),
+        )
+        .unwrap();
+    }
+
+    let report = scan_crate_with_report(dir.path()).expect("Scanning should work");
+    assert_eq!(report.classes.len(), 200);
+    assert_eq!(report.stats.files_walked, 200);
+    assert_eq!(report.stats.files_parsed, 200);
+    assert_eq!(report.stats.cache_hits, 0);
+    assert!(report.stats.parse_time > std::time::Duration::ZERO);
+    assert!(report
+        .stats
+        .to_string()
+        .starts_with("Scanned 200 files (200 parsed, 0 cached)"));
+    assert!(report.files.windows(2).all(|files| files[0] < files[1]));
+
+    let class = report.classes.get("Class042").unwrap();
+    assert_eq!(class.module_path, vec!["class_042".to_string()]);
+}
+
+#[test]
+fn scan_cache() {
+    let dir = tempfile::tempdir().unwrap();
+    let src = dir.path().join("src");
+    let cache_file = dir.path().join("out/scan-cache.cfg");
+    std::fs::create_dir_all(&src).unwrap();
+
+    std::fs::write(
+        src.join("player.rs"),
+        "#[derive(NativeClass)]\nstruct Player;\n",
+    )
+    .unwrap();
+    std::fs::write(
+        src.join("enemy.rs"),
+        "#[derive(GodotClass)]\nstruct Enemy;\n",
+    )
+    .unwrap();
+
+    let scan = |options: ScanOptions| {
+        let report = scan_crate_with_options(&src, &options.cache_file(&cache_file))
+            .expect("Scanning should work");
+        report.classes.names().map(String::from).collect::<Vec<_>>()
+    };
+
+    assert_eq!(scan(ScanOptions::new()), ["Player"]);
+    let cache = std::fs::read_to_string(&cache_file).expect("The cache should be written");
+    assert!(cache.contains("class=\"Player||2:7:2:13||\""));
+
+    // Unchanged files come from the cache with their source information.
+    let report = scan_crate_with_options(&src, &ScanOptions::new().cache_file(&cache_file))
+        .expect("Scanning should work");
+    assert_eq!(report.stats.files_walked, 2);
+    assert_eq!(report.stats.files_parsed, 0);
+    assert_eq!(report.stats.cache_hits, 2);
+    let player = report.classes.get("Player").unwrap();
+    assert_eq!(player.rust_path(), "crate::player::Player");
+    assert_eq!(player.span.unwrap().start_line, 2);
+
+    // Other options discard the cache.
+    assert_eq!(
+        scan(ScanOptions::new().derive("GodotClass")),
+        ["Enemy", "Player"]
+    );
+
+    std::fs::write(
+        src.join("player.rs"),
+        "#[derive(NativeClass)]\nstruct PlayerController;\n",
+    )
+    .unwrap();
+    assert_eq!(scan(ScanOptions::new()), ["PlayerController"]);
+
+    std::fs::remove_file(src.join("player.rs")).unwrap();
+    assert!(scan(ScanOptions::new()).is_empty());
+    let cache = std::fs::read_to_string(&cache_file).unwrap();
+    assert!(!cache.contains("player.rs"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn scan_to_json() {
+    let mut json = vec![];
+    let report = scan_crate_to_writer("tests/project_stub", &ScanOptions::new(), &mut json)
+        .expect("Scanning should work");
+    let json = String::from_utf8(json).unwrap();
+
+    assert_eq!(report.classes.len(), 3);
+    assert!(json.contains("\"name\": \"Test\""));
+    assert!(json.contains("\"module_path\": [\n        \"test\"\n      ]"));
+    assert!(json.contains("\"start_line\": 2"));
+    assert!(json.contains("\"dir\": \"tests/project_stub\""));
+}
+
+#[test]
+fn inherited_base_class() {
+    let classes = scan_crate("tests/inherit_stub").expect("Scanning should work");
+    let base = |name: &str| classes.get(name).unwrap().base.clone();
+
+    assert_eq!(base("Player").as_deref(), Some("Node2D"));
+    assert_eq!(base("Enemy").as_deref(), Some("KinematicBody"));
+    assert_eq!(base("Data"), None);
+    assert_eq!(base("Manual").as_deref(), Some("Spatial"));
+}
+
+#[test]
+fn exported_methods_and_signals() {
+    let classes = scan_crate("tests/methods_stub").expect("Scanning should work");
+
+    let player = classes.get("Player").unwrap();
+    let methods = player
+        .methods
+        .iter()
+        .map(|method| (method.name.as_str(), method.args))
+        .collect::<Vec<_>>();
+    assert_eq!(methods, [("_ready", 0), ("jump", 1)]);
+    let signals = player
+        .signals
+        .iter()
+        .map(|signal| (signal.name.as_str(), signal.args))
+        .collect::<Vec<_>>();
+    assert_eq!(signals, [("died", 0), ("hit", 2)]);
+
+    let enemy = classes.get("Enemy").unwrap();
+    let methods = enemy
+        .methods
+        .iter()
+        .map(|method| (method.name.as_str(), method.args))
+        .collect::<Vec<_>>();
+    assert_eq!(methods, [("attack", 2)]);
+    assert_eq!(enemy.signals.len(), 1);
+    assert_eq!(enemy.signals[0].name, "spotted");
+    assert_eq!(enemy.signals[0].args, 1);
+}
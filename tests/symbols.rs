@@ -0,0 +1,91 @@
+#![cfg(feature = "object")]
+
+use gdnative_project_utils::*;
+
+/// Compile a `cdylib` exporting `symbols` for the host to `path`.
+fn compile_library(path: &std::path::Path, symbols: &[&str]) {
+    let dir = path.parent().unwrap();
+    std::fs::create_dir_all(dir).unwrap();
+
+    let source = symbols
+        .iter()
+        .map(|symbol| format!("#[no_mangle]\npub extern \"C\" fn {}() {{}}\n", symbol))
+        .collect::<String>();
+    let source_path = dir.join("lib.rs");
+    std::fs::write(&source_path, source).unwrap();
+
+    let status =
+        std::process::Command::new(std::env::var("RUSTC").unwrap_or_else(|_| "rustc".into()))
+            .arg("--crate-type=cdylib")
+            .arg("--crate-name=symbols_test")
+            .arg("-o")
+            .arg(path)
+            .arg(&source_path)
+            .status()
+            .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn exported_symbols() {
+    let dir = tempfile::tempdir().unwrap();
+    let library = dir.path().join("libsymbols_test.so");
+
+    compile_library(&library, &["my_gdnative_init", "my_nativescript_init"]);
+
+    assert!(check_exported_symbols(&library, &["my_gdnative_init"]).is_ok());
+
+    match check_exported_symbols(&library, &["my_gdnative_init", "godot_gdnative_init"]) {
+        Err(SymbolError::Missing { path, symbols }) => {
+            assert_eq!(path, library);
+            assert_eq!(symbols, vec!["godot_gdnative_init".to_string()]);
+        }
+        other => panic!("Expected missing symbols, got {:?}", other),
+    }
+
+    std::fs::write(&library, "not a library").unwrap();
+    assert!(matches!(
+        check_exported_symbols(&library, &[]),
+        Err(SymbolError::Parse { .. })
+    ));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn check_symbols() {
+    let godot_proj_dir = tempfile::tempdir().unwrap();
+    let target_dir = godot_proj_dir.path().join("target");
+    let library = target_dir.join("debug/libsymbols_test.so");
+
+    std::fs::create_dir_all(&target_dir).unwrap();
+
+    // the library of the previous build does not keep the resources from
+    // being generated
+    compile_library(&library, &["my_gdnative_init"]);
+    let report = Generator::new()
+        .lib_name("symbols_test")
+        .build_mode(BuildMode::Debug)
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir)
+        .symbol_prefix("my_")
+        .build(Classes::new())
+        .expect("Should generate resources");
+
+    compile_library(&library, &["my_gdnative_init", "my_nativescript_init"]);
+    let err = report.check_symbols().unwrap_err();
+    assert!(matches!(
+        &err,
+        SymbolError::Missing { symbols, .. }
+            if symbols == &["my_gdnative_terminate".to_string()]
+    ));
+
+    compile_library(
+        &library,
+        &[
+            "my_gdnative_init",
+            "my_gdnative_terminate",
+            "my_nativescript_init",
+        ],
+    );
+    report.check_symbols().expect("Should find all symbols");
+}
@@ -0,0 +1,15 @@
+#[derive(NativeClass)]
+struct Game;
+
+#[cfg_attr(test, derive(NativeClass))]
+struct MockNode;
+
+#[cfg(not(test))]
+#[derive(NativeClass)]
+struct RealNode;
+
+#[cfg(test)]
+mod tests {
+    #[derive(NativeClass)]
+    struct TestDouble;
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "notify")]
+
+use std::time::Duration;
+
+use gdnative_project_utils::*;
+
+#[test]
+fn regenerate_on_change() {
+    let crate_dir = tempfile::tempdir().unwrap();
+    let dir = crate_dir.path().to_path_buf();
+    let godot_proj_dir = dir.join("godot");
+    let target_dir = dir.join("target");
+
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::create_dir_all(&godot_proj_dir).unwrap();
+    std::fs::create_dir_all(&target_dir).unwrap();
+    std::fs::write(
+        dir.join("src/lib.rs"),
+        "#[derive(NativeClass)]\nstruct Player;\n",
+    )
+    .unwrap();
+
+    let generator = Generator::new()
+        .lib_name("watch_test")
+        .build_mode(BuildMode::Debug)
+        .target_dir(&target_dir)
+        .godot_project_dir(&godot_proj_dir);
+    let options = WatchOptions::new(&dir, generator).debounce(Duration::from_millis(50));
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let watcher = std::thread::spawn(move || {
+        let mut builds = 0;
+        watch(options, |result| {
+            let report = result.expect("Should generate resources");
+            let created = report
+                .created()
+                .filter_map(|path| path.file_name())
+                .map(|name| name.to_string_lossy().into_owned())
+                .collect::<Vec<_>>();
+            sender.send(created).unwrap();
+            builds += 1;
+            builds < 2
+        })
+    });
+
+    let timeout = Duration::from_secs(10);
+    let created = receiver.recv_timeout(timeout).unwrap();
+    assert!(created.contains(&"Player.gdns".to_string()));
+
+    // edits that do not change the classes are ignored
+    std::fs::write(
+        dir.join("src/lib.rs"),
+        "// The player.\n#[derive(NativeClass)]\nstruct Player;\n",
+    )
+    .unwrap();
+    std::thread::sleep(Duration::from_millis(200));
+
+    std::fs::write(
+        dir.join("src/enemies.rs"),
+        "#[derive(NativeClass)]\nstruct Slime;\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("src/lib.rs"),
+        "mod enemies;\n\n// The player.\n#[derive(NativeClass)]\nstruct Player;\n",
+    )
+    .unwrap();
+
+    let created = receiver.recv_timeout(timeout).unwrap();
+    assert_eq!(created, vec!["Slime.gdns".to_string()]);
+
+    watcher.join().unwrap().expect("Should watch the sources");
+}
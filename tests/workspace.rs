@@ -0,0 +1,63 @@
+#![cfg(feature = "cargo_metadata")]
+
+use gdnative_project_utils::*;
+
+fn write(path: std::path::PathBuf, content: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+#[test]
+fn workspace() {
+    let workspace_dir = tempfile::tempdir().unwrap();
+    let ws = workspace_dir.path();
+
+    write(
+        ws.join("Cargo.toml"),
+        "[workspace]\nmembers = [\"game\", \"tools\", \"common\"]\n",
+    );
+    for (name, crate_type, class) in &[
+        ("game", "cdylib", "Player"),
+        ("tools", "cdylib", "Inspector"),
+        ("common", "rlib", "Shared"),
+    ] {
+        write(
+            ws.join(name).join("Cargo.toml"),
+            &format!(
+                "[package]\nname = \"{}\"\nversion = \"0.1.0\"\n\n[lib]\ncrate-type = [\"{}\"]\n",
+                name, crate_type
+            ),
+        );
+        write(
+            ws.join(name).join("src/lib.rs"),
+            &format!("#[derive(NativeClass)]\nstruct {} {{}}\n", class),
+        );
+    }
+    std::fs::create_dir_all(ws.join("godot")).unwrap();
+
+    let reports = WorkspaceGenerator::new()
+        .workspace_dir(ws)
+        .godot_project_dir(ws.join("godot"))
+        .generator(
+            Generator::new()
+                .build_mode(BuildMode::Debug)
+                .platforms(Platform::X11_64),
+        )
+        .build()
+        .expect("Should generate resources");
+
+    assert_eq!(reports.len(), 2);
+
+    let native = ws.join("godot/native");
+
+    let gdnlib = std::fs::read_to_string(native.join("game/game.gdnlib")).unwrap();
+    assert!(gdnlib.contains("/target/debug/libgame.so\""));
+
+    let gdns = std::fs::read_to_string(native.join("game/Player.gdns")).unwrap();
+    assert!(gdns.contains("path=\"res://native/game/game.gdnlib\""));
+
+    assert!(native.join("tools/tools.gdnlib").is_file());
+    assert!(native.join("tools/Inspector.gdns").is_file());
+    assert!(!native.join("game/Inspector.gdns").exists());
+    assert!(!native.join("common").exists());
+}